@@ -0,0 +1,132 @@
+use std::{
+    fmt::{Debug, Formatter},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use derive_new::new;
+use eyre::Result;
+use hyperlane_base::{
+    db::{HyperlaneRocksDB, DB},
+    CoreMetrics,
+};
+use hyperlane_core::HyperlaneDomain;
+use prometheus::{IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
+use tracing::info;
+
+use crate::processor::ProcessorExt;
+
+/// How often delivered messages are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// The number of nonces scanned in a single pass, so that a pass never holds up
+/// the runtime for long
+const NONCES_PER_PASS: u32 = 1_000;
+
+/// Periodically deletes the data of messages that were confirmed delivered more
+/// than `retention` ago.
+#[derive(new)]
+pub struct DbPruner {
+    db: HyperlaneRocksDB,
+    retention: Duration,
+    metrics: DbPrunerMetrics,
+}
+
+impl Debug for DbPruner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DbPruner {{ retention: {:?} }}", self.retention)
+    }
+}
+
+#[async_trait]
+impl ProcessorExt for DbPruner {
+    /// The domain whose messages this pruner deletes.
+    fn domain(&self) -> &HyperlaneDomain {
+        self.db.domain()
+    }
+
+    /// One round of pruning, extracted from infinite work loop for
+    /// testing purposes.
+    async fn tick(&mut self) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let delivered_before = now.saturating_sub(self.retention.as_secs());
+
+        let (mut messages, mut bytes) = (0, 0);
+        loop {
+            let stats = self
+                .db
+                .prune_delivered_messages(delivered_before, now, NONCES_PER_PASS)?;
+            messages += stats.messages;
+            bytes += stats.bytes;
+            self.metrics.pruned_messages.inc_by(stats.messages.into());
+            self.metrics.pruned_bytes.inc_by(stats.bytes);
+            if stats.reached_latest {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        let db: &DB = self.db.as_ref();
+        let live_data_size = db.estimated_live_data_size()?;
+        if let Some(size) = live_data_size {
+            self.metrics.live_data_size.set(size as i64);
+        }
+        info!(
+            messages,
+            bytes,
+            ?live_data_size,
+            "Pruned data of delivered messages from db"
+        );
+        Ok(())
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        Some(PRUNE_INTERVAL)
+    }
+}
+
+/// Metric vectors shared by the pruners of all origins.
+#[derive(Debug, Clone)]
+pub struct DbPrunerMetricVecs {
+    pruned_messages: IntCounterVec,
+    pruned_bytes: IntCounterVec,
+    live_data_size: IntGaugeVec,
+}
+
+impl DbPrunerMetricVecs {
+    pub fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            pruned_messages: metrics.new_int_counter(
+                "db_pruned_messages",
+                "Number of delivered messages whose data was pruned from the db",
+                &["origin"],
+            )?,
+            pruned_bytes: metrics.new_int_counter(
+                "db_pruned_bytes",
+                "Size in bytes of the keys and values pruned from the db",
+                &["origin"],
+            )?,
+            live_data_size: metrics.new_int_gauge(
+                "db_estimated_live_data_bytes",
+                "RocksDB's estimate of the size of the live data in the db",
+                &[],
+            )?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DbPrunerMetrics {
+    pruned_messages: IntCounter,
+    pruned_bytes: IntCounter,
+    live_data_size: IntGauge,
+}
+
+impl DbPrunerMetrics {
+    pub fn new(vecs: &DbPrunerMetricVecs, origin: &HyperlaneDomain) -> Self {
+        Self {
+            pruned_messages: vecs.pruned_messages.with_label_values(&[origin.name()]),
+            pruned_bytes: vecs.pruned_bytes.with_label_values(&[origin.name()]),
+            live_data_size: vecs.live_data_size.with_label_values(&[]),
+        }
+    }
+}
@@ -0,0 +1,585 @@
+//! Structured events for each step in the delivery of a message, which are
+//! published to pluggable sinks: a JSON lines file, webhooks and a
+//! server-sent events endpoint on the relayer's server.
+
+use std::{
+    convert::Infallible,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    routing, Router,
+};
+use eyre::{eyre, Context, Result};
+use futures::{stream, Stream};
+use hyperlane_base::{
+    server::{is_authorized, ApiToken},
+    CoreMetrics,
+};
+use hyperlane_core::{HyperlaneMessage, H256, H512};
+use prometheus::{IntCounter, IntCounterVec, Opts};
+use reqwest::Url;
+use serde::Serialize;
+use tokio::{
+    fs::File,
+    io::AsyncWriteExt,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, error::TrySendError},
+    },
+    task::JoinHandle,
+};
+use tracing::{error, info_span, instrument::Instrumented, warn, Instrument};
+
+use crate::settings::matching_list::MatchingList;
+
+const MESSAGE_EVENTS_API_BASE: &str = "/message_events";
+/// How many events each sink can fall behind by before it starts missing events
+const EVENTS_CHANNEL_CAPACITY: usize = 10_000;
+/// How many events each webhook can fall behind by before events to it are
+/// dropped
+const WEBHOOK_QUEUE_CAPACITY: usize = 1_000;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+
+/// A step in the delivery of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum MessageLifecycleEventKind {
+    /// The message was indexed and handed to the submitter of its destination
+    Indexed,
+    /// The message hasn't paid enough for gas to be delivered yet
+    GasPaymentRequirementNotMet,
+    /// The metadata needed by the recipient's ISM couldn't be fetched yet
+    MetadataUnavailable,
+    /// A transaction delivering the message was submitted
+    Submitted {
+        #[serde(rename = "txId")]
+        tx_id: H512,
+    },
+    /// The message was confirmed delivered
+    Confirmed {
+        /// The transaction submitted by this relayer, if it delivered the
+        /// message
+        #[serde(rename = "txId")]
+        tx_id: Option<H512>,
+    },
+    /// The relayer gave up on delivering the message
+    Dropped { reason: String },
+}
+
+impl MessageLifecycleEventKind {
+    /// The name of the event, as used by the server-sent events endpoint
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Indexed => "indexed",
+            Self::GasPaymentRequirementNotMet => "gasPaymentRequirementNotMet",
+            Self::MetadataUnavailable => "metadataUnavailable",
+            Self::Submitted { .. } => "submitted",
+            Self::Confirmed { .. } => "confirmed",
+            Self::Dropped { .. } => "dropped",
+        }
+    }
+}
+
+/// An event in the delivery of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageLifecycleEvent {
+    pub message_id: H256,
+    pub nonce: u32,
+    pub origin: u32,
+    pub sender: H256,
+    pub destination: u32,
+    pub recipient: H256,
+    /// Unix timestamp in seconds of when the event happened
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: MessageLifecycleEventKind,
+}
+
+impl MessageLifecycleEvent {
+    fn new(message: &HyperlaneMessage, kind: MessageLifecycleEventKind) -> Self {
+        Self {
+            message_id: message.id(),
+            nonce: message.nonce,
+            origin: message.origin,
+            sender: message.sender,
+            destination: message.destination,
+            recipient: message.recipient,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            kind,
+        }
+    }
+}
+
+/// Publishes message lifecycle events to all sinks. Publishing never blocks
+/// delivery; a sink which falls too far behind misses events instead, which
+/// are counted by `dropped_events`.
+#[derive(Debug, Clone)]
+pub struct MessageLifecycleEvents {
+    tx: broadcast::Sender<MessageLifecycleEvent>,
+    /// Events which a sink missed, by the `sink` they were dropped by
+    dropped_events: IntCounterVec,
+}
+
+impl Default for MessageLifecycleEvents {
+    /// Counts dropped events with a metric which isn't registered anywhere
+    fn default() -> Self {
+        let dropped_events = IntCounterVec::new(
+            Opts::new(
+                "message_lifecycle_events_dropped",
+                "Message lifecycle events which a sink missed",
+            ),
+            &["sink"],
+        )
+        .expect("static metric options are valid");
+        Self::new(dropped_events)
+    }
+}
+
+impl MessageLifecycleEvents {
+    /// Creates the publisher, counting the events dropped by each sink with
+    /// `dropped_events`
+    pub fn new(dropped_events: IntCounterVec) -> Self {
+        let (tx, _) = broadcast::channel(EVENTS_CHANNEL_CAPACITY);
+        Self { tx, dropped_events }
+    }
+
+    /// Creates the publisher with the dropped events metric registered on
+    /// `metrics`
+    pub fn with_metrics(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self::new(metrics.new_int_counter(
+            "message_lifecycle_events_dropped",
+            "Message lifecycle events which a sink missed",
+            &["sink"],
+        )?))
+    }
+
+    /// The counter of events dropped by `sink`
+    pub fn dropped_events(&self, sink: &str) -> IntCounter {
+        self.dropped_events.with_label_values(&[sink])
+    }
+
+    /// Publish an event of `message`
+    pub fn publish(&self, message: &HyperlaneMessage, kind: MessageLifecycleEventKind) {
+        // Sending only fails if there are no sinks, in which case the event
+        // isn't needed
+        let _ = self.tx.send(MessageLifecycleEvent::new(message, kind));
+    }
+
+    /// Write all events published from now on to `sink`.
+    pub fn spawn_sink(
+        &self,
+        mut sink: Box<dyn LifecycleEventSink>,
+    ) -> Instrumented<JoinHandle<()>> {
+        let span = info_span!("LifecycleEventSink", sink=?sink);
+        let mut rx = self.tx.subscribe();
+        let dropped_events = self.dropped_events(sink.name());
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        if let Err(err) = sink.write(&event).await {
+                            error!(?err, ?event, "Failed to write message lifecycle event");
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        dropped_events.inc_by(missed);
+                        warn!(
+                            missed,
+                            "Sink fell behind and missed message lifecycle events"
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+        .instrument(span)
+    }
+
+    /// A route streaming all events published from when a client connects as
+    /// server-sent events, with `GET /message_events`. Clients must
+    /// authenticate with `token` as a bearer token.
+    pub fn get_route(&self, token: ApiToken) -> (&'static str, Router) {
+        (
+            MESSAGE_EVENTS_API_BASE,
+            Router::new()
+                .route("/", routing::get(stream_events))
+                .with_state((self.clone(), Arc::new(token))),
+        )
+    }
+}
+
+async fn stream_events(
+    State((events, token)): State<(MessageLifecycleEvents, Arc<ApiToken>)>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, &'static str)> {
+    if !is_authorized(&headers, &token) {
+        warn!("Rejected unauthorized message events request");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or missing bearer token"));
+    }
+    let dropped_events = events.dropped_events("sse");
+    let state = (events.tx.subscribe(), dropped_events);
+    let stream = stream::unfold(state, |(mut rx, dropped_events)| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let Ok(sse_event) = Event::default().event(event.kind.name()).json_data(&event)
+                    else {
+                        continue;
+                    };
+                    return Some((Ok(sse_event), (rx, dropped_events)));
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    dropped_events.inc_by(missed);
+                    warn!(
+                        missed,
+                        "Event stream client fell behind and missed message lifecycle events"
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// A destination for message lifecycle events
+#[async_trait]
+pub trait LifecycleEventSink: Send + Debug {
+    /// The kind of sink, as used by the dropped events metric
+    fn name(&self) -> &'static str;
+
+    /// Write an event to the sink
+    async fn write(&mut self, event: &MessageLifecycleEvent) -> Result<()>;
+}
+
+/// Appends events to a file as JSON lines
+#[derive(Debug)]
+pub struct JsonLinesFileSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl JsonLinesFileSink {
+    /// Open `path` for appending, creating it if it doesn't exist
+    pub async fn open(path: &Path) -> Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Opening message lifecycle events file {path:?}"))?;
+        Ok(Self {
+            path: path.to_owned(),
+            file,
+        })
+    }
+}
+
+#[async_trait]
+impl LifecycleEventSink for JsonLinesFileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn write(&mut self, event: &MessageLifecycleEvent) -> Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .await
+            .with_context(|| format!("Writing to {:?}", self.path))?;
+        self.file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Posts events of the messages matching `matching_list` to a webhook as JSON,
+/// retrying with an exponential backoff if the webhook fails.
+///
+/// Events are posted by a task of their own from a bounded queue, so a slow
+/// webhook never holds up the other sinks. Events are dropped and counted once
+/// the queue is full.
+#[derive(Debug)]
+pub struct WebhookSink {
+    url: Url,
+    matching_list: MatchingList,
+    queue: mpsc::Sender<MessageLifecycleEvent>,
+    dropped_events: IntCounter,
+}
+
+impl WebhookSink {
+    /// Create the sink and spawn the task posting its events, which stops once
+    /// the sink is dropped and its queue is drained.
+    pub fn new(url: Url, matching_list: MatchingList, dropped_events: IntCounter) -> Result<Self> {
+        Self::with_queue_capacity(url, matching_list, dropped_events, WEBHOOK_QUEUE_CAPACITY)
+    }
+
+    fn with_queue_capacity(
+        url: Url,
+        matching_list: MatchingList,
+        dropped_events: IntCounter,
+        queue_capacity: usize,
+    ) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        let (queue, rx) = mpsc::channel(queue_capacity);
+        tokio::spawn(
+            post_events(client, url.clone(), rx)
+                .instrument(info_span!("WebhookDelivery", url=%url)),
+        );
+        Ok(Self {
+            url,
+            matching_list,
+            queue,
+            dropped_events,
+        })
+    }
+}
+
+async fn post_events(
+    client: reqwest::Client,
+    url: Url,
+    mut rx: mpsc::Receiver<MessageLifecycleEvent>,
+) {
+    while let Some(event) = rx.recv().await {
+        if let Err(err) = post_with_retries(&client, &url, &event).await {
+            error!(?err, ?event, "Failed to post message lifecycle event");
+        }
+    }
+}
+
+async fn post_with_retries(
+    client: &reqwest::Client,
+    url: &Url,
+    event: &MessageLifecycleEvent,
+) -> Result<()> {
+    let mut attempt = 1;
+    loop {
+        match post(client, url, event).await {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < WEBHOOK_MAX_ATTEMPTS => {
+                warn!(?err, attempt, %url, "Failed to post message lifecycle event to webhook, retrying");
+                tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Posting to webhook {url} failed {attempt} times"))
+            }
+        }
+    }
+}
+
+async fn post(client: &reqwest::Client, url: &Url, event: &MessageLifecycleEvent) -> Result<()> {
+    client
+        .post(url.clone())
+        .json(event)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[async_trait]
+impl LifecycleEventSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn write(&mut self, event: &MessageLifecycleEvent) -> Result<()> {
+        if !self.matching_list.event_matches(event, true) {
+            return Ok(());
+        }
+        match self.queue.try_send(event.clone()) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped_events.inc();
+                warn!(url=%self.url, ?event, "Webhook fell behind, dropped message lifecycle event");
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => {
+                Err(eyre!("Webhook {} stopped posting events", self.url))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{extract::Json, routing::post};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Serve a webhook on localhost which forwards the events it receives to
+    /// `received`, and never responds if `hang` is set
+    fn serve_webhook(received: mpsc::UnboundedSender<Value>, hang: bool) -> Url {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(event): Json<Value>| async move {
+                received.send(event).unwrap();
+                if hang {
+                    futures::future::pending::<()>().await;
+                }
+                StatusCode::OK
+            }),
+        );
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        Url::parse(&format!("http://{addr}/")).unwrap()
+    }
+
+    fn dummy_message() -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce: 7,
+            origin: 1,
+            destination: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn serializes_events() {
+        let message = dummy_message();
+        let event = MessageLifecycleEvent::new(
+            &message,
+            MessageLifecycleEventKind::Submitted {
+                tx_id: H512::zero(),
+            },
+        );
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], json!("submitted"));
+        assert_eq!(value["messageId"], json!(format!("{:?}", message.id())));
+        assert_eq!(value["nonce"], json!(7));
+        assert_eq!(value["origin"], json!(1));
+        assert_eq!(value["destination"], json!(2));
+        assert_eq!(value["txId"], json!(format!("{:?}", H512::zero())));
+    }
+
+    #[tokio::test]
+    async fn writes_events_to_file() {
+        let path = std::env::temp_dir().join(format!(
+            "relayer_lifecycle_events_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let events = MessageLifecycleEvents::default();
+        let sink = JsonLinesFileSink::open(&path).await.unwrap();
+        let sink_task = events.spawn_sink(Box::new(sink));
+
+        let message = dummy_message();
+        events.publish(&message, MessageLifecycleEventKind::Indexed);
+        events.publish(
+            &message,
+            MessageLifecycleEventKind::Dropped {
+                reason: "Recipient is not a contract".to_owned(),
+            },
+        );
+        // The sink stops once it has written every published event
+        drop(events);
+        sink_task.await.unwrap();
+
+        let lines = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], json!("indexed"));
+        assert_eq!(lines[1]["event"], json!("dropped"));
+        assert_eq!(lines[1]["reason"], json!("Recipient is not a contract"));
+    }
+
+    #[tokio::test]
+    async fn posts_events_to_webhook() {
+        let (received_tx, mut received) = mpsc::unbounded_channel();
+        let url = serve_webhook(received_tx, false);
+
+        let events = MessageLifecycleEvents::default();
+        let sink = WebhookSink::new(
+            url,
+            MatchingList::default(),
+            events.dropped_events("webhook"),
+        )
+        .unwrap();
+        let _sink_task = events.spawn_sink(Box::new(sink));
+
+        let message = dummy_message();
+        events.publish(&message, MessageLifecycleEventKind::Indexed);
+        events.publish(
+            &message,
+            MessageLifecycleEventKind::Confirmed { tx_id: None },
+        );
+
+        let first = received.recv().await.unwrap();
+        let second = received.recv().await.unwrap();
+        assert_eq!(first["event"], json!("indexed"));
+        assert_eq!(first["messageId"], json!(format!("{:?}", message.id())));
+        assert_eq!(second["event"], json!("confirmed"));
+    }
+
+    #[tokio::test]
+    async fn drops_events_when_webhook_falls_behind() {
+        let (received_tx, mut received) = mpsc::unbounded_channel();
+        // The webhook never responds, so the first event stays in flight
+        let url = serve_webhook(received_tx, true);
+
+        let events = MessageLifecycleEvents::default();
+        let dropped_events = events.dropped_events("webhook");
+        let mut sink = WebhookSink::with_queue_capacity(
+            url,
+            MatchingList::default(),
+            dropped_events.clone(),
+            1,
+        )
+        .unwrap();
+
+        let event =
+            MessageLifecycleEvent::new(&dummy_message(), MessageLifecycleEventKind::Indexed);
+        sink.write(&event).await.unwrap();
+        // Wait for the first event to be taken off the queue and posted
+        received.recv().await.unwrap();
+        // The second event fills the queue and the third is dropped
+        sink.write(&event).await.unwrap();
+        sink.write(&event).await.unwrap();
+        assert_eq!(dropped_events.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthorized_event_streams() {
+        let events = MessageLifecycleEvents::default();
+        let (path, router) = events.get_route(ApiToken::new("secret".to_owned()));
+        let app = Router::new().nest(path, router);
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}{MESSAGE_EVENTS_API_BASE}");
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response = client.get(&url).bearer_auth("wrong").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response = client.get(&url).bearer_auth("secret").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
@@ -13,6 +13,9 @@ use hyperlane_base::agent_main;
 
 use crate::relayer::Relayer;
 
+mod db_pruner;
+mod lifecycle;
+mod manual_relay;
 mod merkle_tree;
 mod msg;
 mod processor;
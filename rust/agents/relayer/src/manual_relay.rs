@@ -0,0 +1,559 @@
+//! Manual relays deliver specific messages regardless of the relayer's
+//! whitelist, blacklist and gas payment enforcement, e.g. for support cases or
+//! unpaid governance messages. Requests are authenticated with a bearer token
+//! and each one, including those rejected as unauthorized, is recorded in an
+//! audit log before any message is relayed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::{HeaderMap, StatusCode},
+    routing, Json, Router,
+};
+use derive_new::new;
+use eyre::{Context, Result};
+use hyperlane_base::{
+    db::HyperlaneRocksDB,
+    server::{is_authorized, ApiToken},
+};
+use hyperlane_core::{ChainCommunicationError, HyperlaneMessage, SequenceAwareIndexer, H256, H512};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    fs::File,
+    io::AsyncWriteExt,
+    sync::{broadcast::Sender, mpsc::UnboundedSender, Mutex},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    msg::{
+        gas_payment::GasPaymentEnforcer,
+        op_queue::{OperationStage, OperationStates},
+    },
+    server::{error_response, parse_tx_id, MessageRetryRequest, StatusResponse},
+};
+
+const MANUAL_RELAY_API_BASE: &str = "/manual_relay";
+
+/// What manual relays need of an origin chain
+#[derive(new)]
+pub struct ManualRelayOrigin {
+    db: HyperlaneRocksDB,
+    /// Looks up the messages dispatched by transactions which haven't been
+    /// indexed yet
+    indexer: Arc<dyn SequenceAwareIndexer<HyperlaneMessage>>,
+    gas_payment_enforcer: Arc<GasPaymentEnforcer>,
+    /// Sends messages to the origin's `MessageProcessor`, which hands them to
+    /// the submitters of their destinations
+    sender: UnboundedSender<HyperlaneMessage>,
+}
+
+/// `POST /manual_relay` with a JSON body of an origin domain, plus either a
+/// message id or the hash of the transaction which dispatched the messages.
+#[derive(Clone)]
+pub struct ManualRelayApi {
+    token: Arc<ApiToken>,
+    origins: Arc<HashMap<u32, ManualRelayOrigin>>,
+    /// The domain ids of the chains the relayer delivers to
+    destinations: Arc<HashSet<u32>>,
+    op_states: OperationStates,
+    retry_tx: Sender<MessageRetryRequest>,
+    audit_log: Arc<ManualRelayAuditLog>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManualRelayRequest {
+    origin: u32,
+    message_id: Option<String>,
+    origin_tx_hash: Option<String>,
+    /// Why the messages are relayed manually, for the audit log
+    reason: Option<String>,
+}
+
+/// The messages a manual relay request is for
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ManualRelayTarget {
+    MessageId(H256),
+    /// All messages dispatched by the transaction
+    #[serde(rename = "originTxHash")]
+    OriginTx(H512),
+}
+
+/// What a manual relay did with a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ManualRelayOutcome {
+    /// Sent to a submitter of the message's destination
+    Submitted,
+    /// Already in a submitter's queue, so moved to the front of it
+    Requeued,
+    /// Already delivered, so nothing was done
+    AlreadyDelivered,
+    /// The relayer doesn't deliver to the message's destination
+    UnknownDestination,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManualRelayedMessage {
+    id: H256,
+    nonce: u32,
+    destination: u32,
+    outcome: ManualRelayOutcome,
+}
+
+/// A record of a manual relay in the audit log
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManualRelayAuditEntry<'a> {
+    /// Unix timestamp in seconds
+    timestamp: u64,
+    origin: u32,
+    #[serde(flatten)]
+    target: ManualRelayTarget,
+    reason: Option<&'a str>,
+    messages: &'a [ManualRelayedMessage],
+}
+
+/// A record of a rejected manual relay request in the audit log
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManualRelayRejectionEntry {
+    /// Unix timestamp in seconds
+    timestamp: u64,
+    /// The origin of the request, if its body could be parsed
+    origin: Option<u32>,
+    rejected: &'static str,
+}
+
+/// Appends a record of each manual relay to a file as JSON lines
+#[derive(Debug)]
+pub struct ManualRelayAuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl ManualRelayAuditLog {
+    /// Open `path` for appending, creating it if it doesn't exist
+    pub async fn open(path: &Path) -> Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Opening manual relay audit log {path:?}"))?;
+        Ok(Self {
+            path: path.to_owned(),
+            file: Mutex::new(file),
+        })
+    }
+
+    async fn record(&self, entry: &impl Serialize) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line)
+            .await
+            .with_context(|| format!("Writing to {:?}", self.path))?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// `POST /manual_relay`
+async fn manual_relay(
+    State(api): State<ManualRelayApi>,
+    headers: HeaderMap,
+    request: Result<Json<ManualRelayRequest>, JsonRejection>,
+) -> StatusResponse {
+    if !is_authorized(&headers, &api.token) {
+        let entry = ManualRelayRejectionEntry {
+            timestamp: unix_timestamp(),
+            origin: request.as_ref().ok().map(|Json(request)| request.origin),
+            rejected: "unauthorized",
+        };
+        warn!(?entry, "Rejected unauthorized manual relay request");
+        if let Err(err) = api.audit_log.record(&entry).await {
+            error!(error=?err, "Failed to record unauthorized manual relay request");
+        }
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid or missing bearer token");
+    }
+    let Json(request) = match request {
+        Ok(request) => request,
+        Err(err) => return error_response(StatusCode::BAD_REQUEST, err),
+    };
+    api.relay(request).await
+}
+
+impl ManualRelayApi {
+    pub fn new(
+        token: ApiToken,
+        origins: HashMap<u32, ManualRelayOrigin>,
+        destinations: HashSet<u32>,
+        op_states: OperationStates,
+        retry_tx: Sender<MessageRetryRequest>,
+        audit_log: ManualRelayAuditLog,
+    ) -> Self {
+        Self {
+            token: Arc::new(token),
+            origins: Arc::new(origins),
+            destinations: Arc::new(destinations),
+            op_states,
+            retry_tx,
+            audit_log: Arc::new(audit_log),
+        }
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::post(manual_relay))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (MANUAL_RELAY_API_BASE, self.router())
+    }
+
+    async fn relay(&self, request: ManualRelayRequest) -> StatusResponse {
+        let Some(origin) = self.origins.get(&request.origin) else {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                format!("Not relaying from domain {}", request.origin),
+            );
+        };
+        let target = match (&request.message_id, &request.origin_tx_hash) {
+            (Some(id), None) => match id.parse::<H256>() {
+                Ok(id) => ManualRelayTarget::MessageId(id),
+                Err(err) => {
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid message id `{id}`: {err}"),
+                    )
+                }
+            },
+            (None, Some(hash)) => match parse_tx_id(hash) {
+                Some(tx_id) => ManualRelayTarget::OriginTx(tx_id),
+                None => {
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid transaction hash `{hash}`"),
+                    )
+                }
+            },
+            _ => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Expected exactly one of messageId or originTxHash",
+                )
+            }
+        };
+
+        let lookup = match target {
+            ManualRelayTarget::MessageId(id) => origin
+                .db
+                .retrieve_message_by_id(&id)
+                .map(|message| message.into_iter().collect())
+                .map_err(eyre::Report::from),
+            ManualRelayTarget::OriginTx(tx_id) => self.messages_by_origin_tx(origin, tx_id).await,
+        };
+        let messages = match lookup {
+            Ok(messages) => messages,
+            Err(err) => {
+                let status = match err.downcast_ref::<ChainCommunicationError>() {
+                    Some(ChainCommunicationError::Unsupported(_)) => StatusCode::NOT_IMPLEMENTED,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                return error_response(status, err);
+            }
+        };
+
+        let mut relayed = Vec::with_capacity(messages.len());
+        for message in &messages {
+            match self.outcome(origin, message).await {
+                Ok(outcome) => relayed.push(ManualRelayedMessage {
+                    id: message.id(),
+                    nonce: message.nonce,
+                    destination: message.destination,
+                    outcome,
+                }),
+                Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+            }
+        }
+
+        // Record the relay before acting on it, so no relay goes unaudited
+        let entry = ManualRelayAuditEntry {
+            timestamp: unix_timestamp(),
+            origin: request.origin,
+            target,
+            reason: request.reason.as_deref(),
+            messages: &relayed,
+        };
+        if let Err(err) = self.audit_log.record(&entry).await {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, err);
+        }
+        info!(?entry, "Manually relaying messages");
+
+        if messages.is_empty() {
+            return error_response(StatusCode::NOT_FOUND, "No messages found");
+        }
+        for (message, relayed) in messages.into_iter().zip(&relayed) {
+            let sent = match relayed.outcome {
+                ManualRelayOutcome::Submitted => {
+                    origin.gas_payment_enforcer.exempt(relayed.id);
+                    origin.sender.send(message).map_err(|err| err.to_string())
+                }
+                ManualRelayOutcome::Requeued => {
+                    origin.gas_payment_enforcer.exempt(relayed.id);
+                    self.retry_tx
+                        .send(MessageRetryRequest::MessageId(relayed.id))
+                        .map(|_| ())
+                        .map_err(|err| err.to_string())
+                }
+                ManualRelayOutcome::AlreadyDelivered | ManualRelayOutcome::UnknownDestination => {
+                    Ok(())
+                }
+            };
+            if let Err(err) = sent {
+                return error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    format!("Relayer is shutting down: {err}"),
+                );
+            }
+        }
+        (StatusCode::ACCEPTED, Json(json!({ "messages": relayed })))
+    }
+
+    /// The messages dispatched by `tx_id`, from the db or else from the chain
+    async fn messages_by_origin_tx(
+        &self,
+        origin: &ManualRelayOrigin,
+        tx_id: H512,
+    ) -> Result<Vec<HyperlaneMessage>> {
+        let mut messages = vec![];
+        for nonce in origin.db.retrieve_message_nonces_by_dispatch_tx(&tx_id)? {
+            if let Some(message) = origin.db.retrieve_message_by_nonce(nonce)? {
+                messages.push(message);
+            }
+        }
+        if messages.is_empty() {
+            messages = origin
+                .indexer
+                .fetch_logs_by_tx_hash(tx_id)
+                .await?
+                .into_iter()
+                .map(|(message, _)| message.inner().clone())
+                .collect();
+        }
+        Ok(messages)
+    }
+
+    async fn outcome(
+        &self,
+        origin: &ManualRelayOrigin,
+        message: &HyperlaneMessage,
+    ) -> Result<ManualRelayOutcome> {
+        if !self.destinations.contains(&message.destination)
+            || message.destination == message.origin
+        {
+            return Ok(ManualRelayOutcome::UnknownDestination);
+        }
+        if origin
+            .db
+            .retrieve_processed_by_nonce(&message.nonce)?
+            .unwrap_or(false)
+        {
+            return Ok(ManualRelayOutcome::AlreadyDelivered);
+        }
+        let queued = self
+            .op_states
+            .get(&message.id())
+            .await
+            .map_or(false, |state| state.stage != OperationStage::Dropped);
+        Ok(if queued {
+            ManualRelayOutcome::Requeued
+        } else {
+            ManualRelayOutcome::Submitted
+        })
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, ops::RangeInclusive};
+
+    use async_trait::async_trait;
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        ChainResult, HyperlaneDomain, HyperlaneLogStore, Indexed, Indexer, LogMeta, MpmcChannel,
+        TxCostEstimate, U256,
+    };
+    use serde_json::Value;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    use super::*;
+    use crate::{
+        server::ENDPOINT_MESSAGES_QUEUE_SIZE,
+        settings::{GasPaymentEnforcementConf, GasPaymentEnforcementPolicy},
+    };
+
+    const TOKEN: &str = "secret";
+
+    /// Finds no messages on chain
+    #[derive(Debug)]
+    struct EmptyIndexer;
+
+    #[async_trait]
+    impl Indexer<HyperlaneMessage> for EmptyIndexer {
+        async fn fetch_logs(
+            &self,
+            _range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
+            Ok(vec![])
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(0)
+        }
+    }
+
+    #[async_trait]
+    impl SequenceAwareIndexer<HyperlaneMessage> for EmptyIndexer {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            Ok((None, 0))
+        }
+    }
+
+    async fn setup_test_server(
+        db: HyperlaneRocksDB,
+        enforcer: Arc<GasPaymentEnforcer>,
+        audit_log: &Path,
+    ) -> (SocketAddr, UnboundedReceiver<HyperlaneMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let origin = ManualRelayOrigin::new(db, Arc::new(EmptyIndexer), enforcer, sender);
+        let api = ManualRelayApi::new(
+            ApiToken::new(TOKEN.to_owned()),
+            HashMap::from([(1, origin)]),
+            HashSet::from([2]),
+            OperationStates::default(),
+            MpmcChannel::<MessageRetryRequest>::new(ENDPOINT_MESSAGES_QUEUE_SIZE).sender(),
+            ManualRelayAuditLog::open(audit_log).await.unwrap(),
+        );
+        let (path, router) = api.get_route();
+        let app = Router::new().nest(path, router);
+
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        (addr, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_manual_relay() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let message = HyperlaneMessage {
+                nonce: 3,
+                origin: 1,
+                destination: 2,
+                ..Default::default()
+            };
+            db.store_logs(&[(Indexed::new(message.clone()), LogMeta::default())])
+                .await
+                .unwrap();
+            // Require a payment, which the message hasn't made
+            let enforcer = Arc::new(GasPaymentEnforcer::new(
+                vec![GasPaymentEnforcementConf {
+                    policy: GasPaymentEnforcementPolicy::Minimum {
+                        payment: U256::one(),
+                    },
+                    matching_list: Default::default(),
+                }],
+                db.clone(),
+            ));
+            let audit_log = std::env::temp_dir().join(format!(
+                "relayer_manual_relay_audit_{}.jsonl",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&audit_log);
+            let (addr, mut receiver) = setup_test_server(db, enforcer.clone(), &audit_log).await;
+            let url = format!("http://{addr}{MANUAL_RELAY_API_BASE}");
+            let body = json!({
+                "origin": 1,
+                "messageId": format!("{:?}", message.id()),
+                "reason": "Support case",
+            });
+            let client = reqwest::Client::new();
+
+            let response = client
+                .post(&url)
+                .bearer_auth("wrong")
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert!(receiver.try_recv().is_err());
+
+            // The indexer can't look up transactions which haven't been indexed
+            let response = client
+                .post(&url)
+                .bearer_auth(TOKEN)
+                .json(&json!({ "origin": 1, "originTxHash": format!("{:?}", H256::zero()) }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+
+            let response = client
+                .post(&url)
+                .bearer_auth(TOKEN)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+            let result: Value = response.json().await.unwrap();
+            assert_eq!(result["messages"][0]["outcome"], json!("submitted"));
+            assert_eq!(receiver.try_recv().unwrap(), message);
+            assert!(enforcer
+                .message_meets_gas_payment_requirement(&message, &TxCostEstimate::default())
+                .await
+                .unwrap()
+                .is_some());
+
+            let lines = std::fs::read_to_string(&audit_log)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .collect::<Vec<_>>();
+            std::fs::remove_file(&audit_log).unwrap();
+            assert_eq!(lines.len(), 2);
+            assert_eq!(lines[0]["origin"], json!(1));
+            assert_eq!(lines[0]["rejected"], json!("unauthorized"));
+            assert_eq!(lines[1]["origin"], json!(1));
+            assert_eq!(lines[1]["reason"], json!("Support case"));
+            assert_eq!(
+                lines[1]["messages"][0]["id"],
+                json!(format!("{:?}", message.id()))
+            );
+        })
+        .await;
+    }
+}
@@ -11,7 +11,7 @@ use hyperlane_base::db::HyperlaneRocksDB;
 use hyperlane_core::{HyperlaneDomain, MerkleTreeInsertion};
 use prometheus::IntGauge;
 use tokio::sync::RwLock;
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::processor::ProcessorExt;
 
@@ -47,6 +47,20 @@ impl ProcessorExt for MerkleTreeProcessor {
     /// One round of processing, extracted from infinite work loop for
     /// testing purposes.
     async fn tick(&mut self) -> Result<()> {
+        // Leaves can't be removed from the tree, so if insertions it already ingested
+        // were rolled back after a reorg, rebuild it from scratch.
+        if let Some(leaf_index) = self.db.take_rolled_back_leaf_index()? {
+            if leaf_index < self.leaf_index {
+                warn!(
+                    leaf_index,
+                    processed_leaf_index = self.leaf_index,
+                    "Merkle tree insertions were rolled back after a reorg, rebuilding tree"
+                );
+                *self.prover_sync.write().await = MerkleTreeBuilder::new();
+                self.leaf_index = 0;
+            }
+        }
+
         if let Some(insertion) = self.next_unprocessed_leaf()? {
             // Feed the message to the prover sync
             self.prover_sync
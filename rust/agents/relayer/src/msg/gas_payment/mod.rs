@@ -1,13 +1,13 @@
-use std::fmt::Debug;
+use std::{collections::HashSet, fmt::Debug, sync::RwLock};
 
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_base::db::HyperlaneRocksDB;
 use hyperlane_core::{
     FixedPointNumber, GasPaymentKey, HyperlaneMessage, InterchainGasExpenditure,
-    InterchainGasPayment, TxCostEstimate, TxOutcome, U256,
+    InterchainGasPayment, TxCostEstimate, TxOutcome, H256, U256,
 };
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info, trace};
 
 use self::policies::{GasPaymentPolicyMinimum, GasPaymentPolicyNone};
 use crate::{
@@ -41,6 +41,10 @@ pub struct GasPaymentEnforcer {
     /// whitelists, then whichever is first in the list will be used.
     policies: Vec<(Box<dyn GasPaymentPolicy>, MatchingList)>,
     db: HyperlaneRocksDB,
+    /// Ids of the messages which are delivered without enforcing a gas
+    /// payment, because they were relayed manually. Not persisted, so a
+    /// manual relay must be repeated if the relayer restarts before delivery.
+    exemptions: RwLock<HashSet<H256>>,
 }
 
 impl GasPaymentEnforcer {
@@ -67,7 +71,36 @@ impl GasPaymentEnforcer {
             })
             .collect();
 
-        Self { policies, db }
+        Self {
+            policies,
+            db,
+            exemptions: Default::default(),
+        }
+    }
+
+    /// Deliver the message with id `message_id` without enforcing a gas
+    /// payment
+    pub fn exempt(&self, message_id: H256) {
+        self.exemptions
+            .write()
+            .expect("Gas payment exemptions lock poisoned")
+            .insert(message_id);
+    }
+
+    /// Forget the exemption of the message with id `message_id`, once it has
+    /// been delivered or dropped
+    pub fn remove_exemption(&self, message_id: &H256) {
+        self.exemptions
+            .write()
+            .expect("Gas payment exemptions lock poisoned")
+            .remove(message_id);
+    }
+
+    fn is_exempt(&self, message_id: &H256) -> bool {
+        self.exemptions
+            .read()
+            .expect("Gas payment exemptions lock poisoned")
+            .contains(message_id)
     }
 }
 
@@ -80,6 +113,10 @@ impl GasPaymentEnforcer {
         tx_cost_estimate: &TxCostEstimate,
     ) -> Result<Option<U256>> {
         let msg_id = message.id();
+        if self.is_exempt(&msg_id) {
+            info!(msg=%message, "Message was relayed manually, skipping gas payment enforcement");
+            return Ok(Some(tx_cost_estimate.gas_limit));
+        }
         let gas_payment_key = GasPaymentKey {
             message_id: msg_id,
             destination: message.destination,
@@ -131,12 +168,47 @@ impl GasPaymentEnforcer {
         Ok(None)
     }
 
+    /// How many times the gas paid for `message` and not spent yet covers its
+    /// estimated gas limit, or None if the estimate is zero.
+    pub fn gas_overpayment_ratio(
+        &self,
+        message: &HyperlaneMessage,
+        tx_cost_estimate: &TxCostEstimate,
+    ) -> Result<Option<f64>> {
+        let msg_id = message.id();
+        let gas_payment_key = GasPaymentKey {
+            message_id: msg_id,
+            destination: message.destination,
+        };
+        let current_payment = self
+            .db
+            .retrieve_gas_payment_by_gas_payment_key(gas_payment_key)?;
+        let current_expenditure = self.db.retrieve_gas_expenditure_by_message_id(msg_id)?;
+
+        let estimated_gas = tx_cost_estimate.enforceable_gas_limit();
+        if estimated_gas.is_zero() {
+            return Ok(None);
+        }
+        let gas_amount = current_payment
+            .gas_amount
+            .saturating_sub(current_expenditure.gas_used);
+        Ok(Some(
+            gas_amount.to_f64_lossy() / estimated_gas.to_f64_lossy(),
+        ))
+    }
+
     pub fn record_tx_outcome(&self, message: &HyperlaneMessage, outcome: TxOutcome) -> Result<()> {
+        // The L1 data fee is accounted as gas so that it's deducted from the
+        // gas amount paid for, like it's included in the enforceable gas limit
+        let gas_used = outcome
+            .gas_used
+            .saturating_add(outcome.l1_data_fee_as_gas());
+        let gas_tokens_used: U256 =
+            (FixedPointNumber::try_from(outcome.gas_used)? * outcome.gas_price).try_into()?;
         self.db.process_gas_expenditure(InterchainGasExpenditure {
             message_id: message.id(),
-            gas_used: outcome.gas_used,
-            tokens_used: (FixedPointNumber::try_from(outcome.gas_used)? * outcome.gas_price)
-                .try_into()?,
+            gas_used,
+            tokens_used: gas_tokens_used.saturating_add(outcome.l1_data_fee.unwrap_or_default()),
         })?;
         Ok(())
     }
@@ -148,8 +220,8 @@ mod test {
 
     use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
     use hyperlane_core::{
-        HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, LogMeta, TxCostEstimate, H160,
-        H256, U256,
+        HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, LogMeta, TxCostEstimate,
+        TxOutcome, H160, H256, H512, U256,
     };
 
     use super::GasPaymentEnforcer;
@@ -192,6 +264,85 @@ mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_exempt_message() {
+        test_utils::run_test_db(|db| async move {
+            let hyperlane_db =
+                HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test_exempt_message"), db);
+
+            let enforcer = GasPaymentEnforcer::new(
+                // Require a payment
+                vec![GasPaymentEnforcementConf {
+                    policy: GasPaymentEnforcementPolicy::Minimum {
+                        payment: U256::one(),
+                    },
+                    matching_list: Default::default(),
+                }],
+                hyperlane_db,
+            );
+            let message = HyperlaneMessage::default();
+            let tx_cost_estimate = TxCostEstimate {
+                gas_limit: U256::from(100),
+                ..Default::default()
+            };
+
+            // Messages relayed manually are approved without any payment
+            enforcer.exempt(message.id());
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&message, &tx_cost_estimate)
+                    .await
+                    .unwrap(),
+                Some(U256::from(100))
+            );
+
+            // Once the exemption is removed, the payment is enforced again
+            enforcer.remove_exemption(&message.id());
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&message, &tx_cost_estimate)
+                    .await
+                    .unwrap(),
+                None
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_record_tx_outcome_with_l1_data_fee() {
+        test_utils::run_test_db(|db| async move {
+            let hyperlane_db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_record_tx_outcome_with_l1_data_fee"),
+                db,
+            );
+            let enforcer = GasPaymentEnforcer::new(vec![], hyperlane_db);
+            let message = HyperlaneMessage::default();
+
+            enforcer
+                .record_tx_outcome(
+                    &message,
+                    TxOutcome {
+                        transaction_id: H512::zero(),
+                        executed: true,
+                        gas_used: U256::from(1000),
+                        gas_price: U256::from(10).try_into().unwrap(),
+                        l1_data_fee: Some(U256::from(5005)),
+                    },
+                )
+                .unwrap();
+
+            let expenditure = enforcer
+                .db
+                .retrieve_gas_expenditure_by_message_id(message.id())
+                .unwrap();
+            // The L1 data fee is rounded up to 501 gas at the gas price
+            assert_eq!(expenditure.gas_used, U256::from(1501));
+            assert_eq!(expenditure.tokens_used, U256::from(15005));
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_no_match() {
         #[allow(unused_must_use)]
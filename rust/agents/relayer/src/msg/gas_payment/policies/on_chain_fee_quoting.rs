@@ -90,6 +90,7 @@ mod test {
         gas_limit: U256([2000, 0, 0, 0]), // MIN * 2
         gas_price: U256([100001, 0, 0, 0]).try_into().unwrap(),
         l2_gas_limit: None,
+        l1_data_fee: None,
     });
 
     #[test]
@@ -206,6 +207,7 @@ mod test {
             gas_limit: MIN * 100, // Large gas limit
             gas_price: COST_ESTIMATE.gas_price.clone(),
             l2_gas_limit: Some(MIN * 2),
+            l1_data_fee: None,
         };
 
         // First ensure that if l2_gas_limit is None, because of the high gas limit,
@@ -239,4 +241,44 @@ mod test {
             Some(tx_cost_estimate.gas_limit),
         );
     }
+
+    #[tokio::test]
+    async fn test_l1_data_fee() {
+        let policy = GasPaymentPolicyOnChainFeeQuoting::default();
+        let message = HyperlaneMessage::default();
+
+        // An L1 data fee worth MIN gas at the gas price
+        let tx_cost_estimate = TxCostEstimate {
+            l1_data_fee: Some(MIN * 100001),
+            ..COST_ESTIMATE.clone()
+        };
+        assert_eq!(tx_cost_estimate.enforceable_gas_limit(), MIN * 3);
+
+        // A payment for half of the gas limit alone is no longer enough
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message,
+                    &current_payment(MIN),
+                    &current_expenditure(0),
+                    &tx_cost_estimate,
+                )
+                .await
+                .unwrap(),
+            None
+        );
+        // But a payment covering half of the gas limit and the L1 data fee is
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message,
+                    &current_payment(MIN * 2),
+                    &current_expenditure(0),
+                    &tx_cost_estimate,
+                )
+                .await
+                .unwrap(),
+            Some(MIN * 2)
+        );
+    }
 }
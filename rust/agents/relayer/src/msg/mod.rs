@@ -15,11 +15,13 @@
 //! allows us to re-run the operation if it succeeded but did not actually end
 //! up getting included.
 //!
-//! Right now there is one strategy: serial.
+//! Right now there is one strategy: serial. A destination can have a pool of
+//! wallets, each with its own serial submitter, and messages are sharded
+//! across the wallets of their destination by app. The queues of submitters
+//! are split into priority lanes, which share the submitter by weight.
 //!
 //! In the future it could make sense for there to be more, some ideas are:
 //!   - BatchingMessagesSubmitter
-//!   - ShardedWalletSubmitter (to get parallelism / nonce)
 //!   - SpeculativeSerializedSubmitter (batches with higher optimistic nonces,
 //!     recovery behavior)
 //!   - FallbackProviderSubmitter (Serialized, but if some RPC provider sucks,
@@ -31,4 +33,6 @@ pub(crate) mod op_queue;
 pub(crate) mod op_submitter;
 pub(crate) mod pending_message;
 pub(crate) mod pending_operation;
+pub(crate) mod priority_lanes;
 pub(crate) mod processor;
+pub(crate) mod wallet_pool;
@@ -1,36 +1,274 @@
-use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
-
-use derive_new::new;
-use hyperlane_core::MpmcReceiver;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use hyperlane_core::{HyperlaneDomain, MpmcReceiver, H256};
 use prometheus::{IntGauge, IntGaugeVec};
-use tokio::sync::Mutex;
-use tracing::{info, instrument};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, instrument, trace};
 
 use crate::server::MessageRetryRequest;
 
-use super::pending_operation::PendingOperation;
+use super::{
+    pending_operation::{OperationStatus, PendingOperation},
+    priority_lanes::PriorityLanes,
+};
 
 pub type QueueOperation = Box<dyn PendingOperation>;
 
+/// The stage of the submission pipeline an operation is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationStage {
+    Prepare,
+    Submit,
+    Confirm,
+    /// The operation was dropped and won't be retried
+    Dropped,
+}
+
+impl OperationStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prepare => "prepare",
+            Self::Submit => "submit",
+            Self::Confirm => "confirm",
+            Self::Dropped => "dropped",
+        }
+    }
+}
+
+/// The live state of an operation which hasn't been confirmed yet
+#[derive(Debug, Clone)]
+pub struct OperationState {
+    pub stage: OperationStage,
+    pub destination: HyperlaneDomain,
+    pub status: OperationStatus,
+    /// When the operation entered `stage`
+    pub since: Instant,
+}
+
+/// How long the state of a dropped operation is kept for
+const DROPPED_OPERATION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The live state of all operations of the relayer which haven't been
+/// confirmed yet, by operation id. Shared by the queues of all submitters so
+/// the server can look it up.
+///
+/// Dropped operations never leave the queues through confirmation, so their
+/// state is evicted once it's older than the dropped ttl instead.
+#[derive(Debug, Clone)]
+pub struct OperationStates {
+    inner: Arc<RwLock<OperationStatesInner>>,
+    dropped_ttl: Duration,
+}
+
+#[derive(Debug, Default)]
+struct OperationStatesInner {
+    states: HashMap<H256, OperationState>,
+    /// When each operation was dropped, oldest first
+    dropped_at: VecDeque<(Instant, H256)>,
+}
+
+impl Default for OperationStates {
+    fn default() -> Self {
+        Self::new(DROPPED_OPERATION_TTL)
+    }
+}
+
+impl OperationStates {
+    pub fn new(dropped_ttl: Duration) -> Self {
+        Self {
+            inner: Default::default(),
+            dropped_ttl,
+        }
+    }
+
+    /// Record that `op` is in `stage`
+    pub async fn update(&self, op: &dyn PendingOperation, stage: OperationStage) {
+        let state = OperationState {
+            stage,
+            destination: op.destination_domain().clone(),
+            status: op.status(),
+            since: Instant::now(),
+        };
+        let mut inner = self.inner.write().await;
+        inner.evict_dropped(self.dropped_ttl);
+        if stage == OperationStage::Dropped {
+            inner.dropped_at.push_back((state.since, op.id()));
+        }
+        inner.states.insert(op.id(), state);
+    }
+
+    /// Forget a confirmed operation; from then on its outcome is in the db
+    pub async fn remove(&self, id: &H256) {
+        let mut inner = self.inner.write().await;
+        inner.evict_dropped(self.dropped_ttl);
+        inner.states.remove(id);
+    }
+
+    pub async fn get(&self, id: &H256) -> Option<OperationState> {
+        self.inner.read().await.states.get(id).cloned()
+    }
+}
+
+impl OperationStatesInner {
+    /// Forget the operations dropped more than `ttl` ago, unless they were
+    /// requeued since
+    fn evict_dropped(&mut self, ttl: Duration) {
+        while let Some((dropped_at, id)) = self.dropped_at.front() {
+            if dropped_at.elapsed() < ttl {
+                break;
+            }
+            // An operation requeued or dropped again since has a newer state
+            let still_dropped = self.states.get(id).map_or(false, |state| {
+                state.stage == OperationStage::Dropped && state.since == *dropped_at
+            });
+            if still_dropped {
+                self.states.remove(id);
+            }
+            self.dropped_at.pop_front();
+        }
+    }
+}
+
+/// A priority lane of a queue
+#[derive(Debug)]
+struct Lane {
+    weight: i64,
+    /// How far the lane is owed pops, for smooth weighted round-robin
+    credit: i64,
+    heap: BinaryHeap<Reverse<QueueOperation>>,
+}
+
+/// The operations of a queue, split by priority lane. Lanes are picked by
+/// smooth weighted round-robin, so that each lane with pending operations gets
+/// a share of the pops proportional to its weight and none is starved. Within
+/// a lane, operations are popped in order.
+#[derive(Debug)]
+struct LaneQueues {
+    lanes: Vec<Lane>,
+}
+
+impl LaneQueues {
+    fn new(weights: Vec<u32>) -> Self {
+        debug_assert!(!weights.is_empty(), "Queues need at least one lane");
+        let lanes = weights
+            .into_iter()
+            .map(|weight| Lane {
+                weight: weight.into(),
+                credit: 0,
+                heap: BinaryHeap::new(),
+            })
+            .collect();
+        Self { lanes }
+    }
+
+    /// Push `op` onto its lane, or onto the last lane if its lane doesn't exist
+    fn push(&mut self, lane: usize, op: QueueOperation) {
+        let lane = lane.min(self.lanes.len() - 1);
+        self.lanes[lane].heap.push(Reverse(op));
+    }
+
+    fn pop(&mut self) -> Option<QueueOperation> {
+        // Only lanes with an operation ready to be attempted compete, so that a
+        // lane of operations which are backing off doesn't hold the others up
+        let now = Instant::now();
+        let mut candidates = (0..self.lanes.len())
+            .filter(|&index| {
+                self.lanes[index].heap.peek().map_or(false, |Reverse(op)| {
+                    op.next_attempt_after().map_or(true, |at| at <= now)
+                })
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            candidates = (0..self.lanes.len())
+                .filter(|&index| !self.lanes[index].heap.is_empty())
+                .collect();
+        }
+
+        let total_weight = candidates
+            .iter()
+            .map(|&index| self.lanes[index].weight)
+            .sum::<i64>();
+        for &index in &candidates {
+            self.lanes[index].credit += self.lanes[index].weight;
+        }
+        // On ties, the lane configured first wins
+        let picked = candidates
+            .into_iter()
+            .min_by_key(|&index| Reverse(self.lanes[index].credit))?;
+        let lane = &mut self.lanes[picked];
+        lane.credit -= total_weight;
+        let op = lane.heap.pop().map(|Reverse(op)| op);
+        // An empty lane stops competing, so it mustn't keep its credit around to
+        // burst with once it has operations again
+        if lane.heap.is_empty() {
+            lane.credit = 0;
+        }
+        op
+    }
+}
+
 /// Queue of generic operations that can be submitted to a destination chain.
 /// Includes logic for maintaining queue metrics by the destination and `app_context` of an operation
-#[derive(Debug, Clone, new)]
+#[derive(Debug, Clone)]
 pub struct OpQueue {
     metrics: IntGaugeVec,
     queue_metrics_label: String,
     retry_rx: MpmcReceiver<MessageRetryRequest>,
-    #[new(default)]
-    queue: Arc<Mutex<BinaryHeap<Reverse<QueueOperation>>>>,
+    op_states: OperationStates,
+    stage: OperationStage,
+    priority_lanes: Arc<PriorityLanes>,
+    queue: Arc<Mutex<LaneQueues>>,
 }
 
 impl OpQueue {
+    pub fn new(
+        metrics: IntGaugeVec,
+        queue_metrics_label: String,
+        retry_rx: MpmcReceiver<MessageRetryRequest>,
+        op_states: OperationStates,
+        stage: OperationStage,
+        priority_lanes: Arc<PriorityLanes>,
+    ) -> Self {
+        let queue = Arc::new(Mutex::new(LaneQueues::new(priority_lanes.weights())));
+        Self {
+            metrics,
+            queue_metrics_label,
+            retry_rx,
+            op_states,
+            stage,
+            priority_lanes,
+            queue,
+        }
+    }
+
     /// Push an element onto the queue and update metrics
     #[instrument(skip(self), ret, fields(queue_label=%self.queue_metrics_label), level = "debug")]
     pub async fn push(&self, op: QueueOperation) {
         // increment the metric before pushing onto the queue, because we lose ownership afterwards
         self.get_operation_metric(op.as_ref()).inc();
+        self.op_states.update(op.as_ref(), self.stage).await;
+
+        let lane = op.priority_lane();
+        trace!(
+            lane = self.priority_lanes.name(lane),
+            "Pushing operation onto lane"
+        );
+        self.queue.lock().await.push(lane, op);
+    }
 
-        self.queue.lock().await.push(Reverse(op));
+    /// Record that `op` was dropped instead of being pushed back onto a queue
+    pub async fn record_dropped(&self, op: &dyn PendingOperation) {
+        self.op_states.update(op, OperationStage::Dropped).await;
+    }
+
+    /// Record that `op` was confirmed and has left the queues for good
+    pub async fn record_confirmed(&self, op: &dyn PendingOperation) {
+        self.op_states.remove(&op.id()).await;
     }
 
     /// Pop an element from the queue and update metrics
@@ -46,7 +284,7 @@ impl OpQueue {
         self.process_retry_requests().await;
         let mut queue = self.queue.lock().await;
         let mut popped = vec![];
-        while let Some(Reverse(op)) = queue.pop() {
+        while let Some(op) = queue.pop() {
             // even if the metric is decremented here, the operation may fail to process and be re-added to the queue.
             // in those cases, the queue length will look like it has spikes whose sizes are at most `limit`
             self.get_operation_metric(op.as_ref()).dec();
@@ -71,23 +309,26 @@ impl OpQueue {
             return;
         }
         let mut queue = self.queue.lock().await;
-        let mut reprioritized_queue: BinaryHeap<_> = queue
-            .drain()
-            .map(|Reverse(mut op)| {
-                // Can check for equality here because of the PartialEq implementation for MessageRetryRequest,
-                // but can't use `contains` because the types are different
-                if message_retry_requests.iter().any(|r| r == op) {
-                    info!(
-                        operation = %op,
-                        queue_label = %self.queue_metrics_label,
-                        "Retrying OpQueue operation"
-                    );
-                    op.reset_attempts()
-                }
-                Reverse(op)
-            })
-            .collect();
-        queue.append(&mut reprioritized_queue);
+        for lane in queue.lanes.iter_mut() {
+            let mut reprioritized_lane: BinaryHeap<_> = lane
+                .heap
+                .drain()
+                .map(|Reverse(mut op)| {
+                    // Can check for equality here because of the PartialEq implementation for MessageRetryRequest,
+                    // but can't use `contains` because the types are different
+                    if message_retry_requests.iter().any(|r| r == op) {
+                        info!(
+                            operation = %op,
+                            queue_label = %self.queue_metrics_label,
+                            "Retrying OpQueue operation"
+                        );
+                        op.reset_attempts()
+                    }
+                    Reverse(op)
+                })
+                .collect();
+            lane.heap.append(&mut reprioritized_lane);
+        }
     }
 
     /// Get the metric associated with this operation
@@ -101,7 +342,7 @@ impl OpQueue {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::msg::pending_operation::PendingOperationResult;
+    use crate::{msg::pending_operation::PendingOperationResult, settings::PriorityLaneConf};
     use hyperlane_core::{
         HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, MpmcChannel, TryBatchAs,
         TxOutcome, H256,
@@ -116,6 +357,7 @@ mod test {
         id: H256,
         seconds_to_next_attempt: u64,
         destination_domain: HyperlaneDomain,
+        lane: usize,
     }
 
     impl MockPendingOperation {
@@ -124,6 +366,7 @@ mod test {
                 id: H256::random(),
                 seconds_to_next_attempt,
                 destination_domain,
+                lane: 0,
             }
         }
     }
@@ -144,6 +387,10 @@ mod test {
             todo!()
         }
 
+        fn priority_lane(&self) -> usize {
+            self.lane
+        }
+
         fn get_operation_labels(&self) -> (String, String) {
             Default::default()
         }
@@ -174,6 +421,10 @@ mod test {
             todo!()
         }
 
+        fn on_batch_submitted(&mut self, _outcome: &TxOutcome) {
+            todo!()
+        }
+
         /// This will be called after the operation has been submitted and is
         /// responsible for checking if the operation has reached a point at
         /// which we consider it safe from reorgs.
@@ -193,9 +444,17 @@ mod test {
             todo!()
         }
 
+        fn confirm_delay(&self) -> Duration {
+            todo!()
+        }
+
         fn set_retries(&mut self, _retries: u32) {
             todo!()
         }
+
+        fn status(&self) -> OperationStatus {
+            Default::default()
+        }
     }
 
     fn dummy_metrics_and_label() -> (IntGaugeVec, String) {
@@ -217,8 +476,18 @@ mod test {
             metrics.clone(),
             queue_metrics_label.clone(),
             mpmc_channel.receiver(),
+            OperationStates::default(),
+            OperationStage::Prepare,
+            Default::default(),
+        );
+        let mut op_queue_2 = OpQueue::new(
+            metrics,
+            queue_metrics_label,
+            mpmc_channel.receiver(),
+            OperationStates::default(),
+            OperationStage::Prepare,
+            Default::default(),
         );
-        let mut op_queue_2 = OpQueue::new(metrics, queue_metrics_label, mpmc_channel.receiver());
 
         // Add some operations to the queue with increasing `next_attempt_after` values
         let destination_domain: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
@@ -283,6 +552,9 @@ mod test {
             metrics.clone(),
             queue_metrics_label.clone(),
             mpmc_channel.receiver(),
+            OperationStates::default(),
+            OperationStage::Prepare,
+            Default::default(),
         );
 
         // Add some operations to the queue with increasing `next_attempt_after` values
@@ -326,4 +598,137 @@ mod test {
         assert_eq!(popped[3], op_ids[0]);
         assert_eq!(popped[4], op_ids[1]);
     }
+
+    #[tokio::test]
+    async fn test_priority_lanes_share_pops_by_weight() {
+        let (metrics, queue_metrics_label) = dummy_metrics_and_label();
+        let mpmc_channel = MpmcChannel::new(100);
+        // A lane with a weight of 3, and the default lane with a weight of 1
+        let priority_lanes = PriorityLanes::new(vec![PriorityLaneConf {
+            name: "critical".to_owned(),
+            weight: 3,
+            matching_list: Default::default(),
+            app_context: None,
+            min_gas_overpayment_ratio: None,
+        }]);
+        let mut op_queue = OpQueue::new(
+            metrics,
+            queue_metrics_label,
+            mpmc_channel.receiver(),
+            OperationStates::default(),
+            OperationStage::Submit,
+            Arc::new(priority_lanes),
+        );
+
+        let destination_domain: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
+        for lane in [0, 1] {
+            for _ in 0..8 {
+                let op = MockPendingOperation {
+                    lane,
+                    ..MockPendingOperation::new(1, destination_domain.clone())
+                };
+                op_queue.push(Box::new(op) as QueueOperation).await;
+            }
+        }
+
+        let popped_lanes = op_queue
+            .pop_many(8)
+            .await
+            .iter()
+            .map(|op| op.priority_lane())
+            .collect::<Vec<_>>();
+        // The default lane gets a quarter of the pops, and isn't starved
+        assert_eq!(popped_lanes.iter().filter(|&&lane| lane == 0).count(), 6);
+        assert_eq!(popped_lanes.iter().filter(|&&lane| lane == 1).count(), 2);
+
+        // Once the critical lane is drained, the default lane gets all pops
+        let remaining_lanes = op_queue
+            .pop_many(8)
+            .await
+            .iter()
+            .map(|op| op.priority_lane())
+            .collect::<Vec<_>>();
+        assert_eq!(remaining_lanes, vec![0, 0, 1, 1, 1, 1, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_emptied_priority_lanes_do_not_keep_credit() {
+        let (metrics, queue_metrics_label) = dummy_metrics_and_label();
+        let mpmc_channel = MpmcChannel::new(100);
+        // A lane with a weight of 3, and the default lane with a weight of 1
+        let priority_lanes = PriorityLanes::new(vec![PriorityLaneConf {
+            name: "critical".to_owned(),
+            weight: 3,
+            matching_list: Default::default(),
+            app_context: None,
+            min_gas_overpayment_ratio: None,
+        }]);
+        let mut op_queue = OpQueue::new(
+            metrics,
+            queue_metrics_label,
+            mpmc_channel.receiver(),
+            OperationStates::default(),
+            OperationStage::Submit,
+            Arc::new(priority_lanes),
+        );
+
+        let destination_domain: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
+        // The critical lane empties first, after which the default lane is
+        // popped on its own until it empties too
+        for (lane, count) in [(0, 2), (1, 3)] {
+            for _ in 0..count {
+                let op = MockPendingOperation {
+                    lane,
+                    ..MockPendingOperation::new(1, destination_domain.clone())
+                };
+                op_queue.push(Box::new(op) as QueueOperation).await;
+            }
+        }
+        let popped_lanes = op_queue
+            .pop_many(5)
+            .await
+            .iter()
+            .map(|op| op.priority_lane())
+            .collect::<Vec<_>>();
+        assert_eq!(popped_lanes, vec![0, 0, 1, 1, 1]);
+
+        // Once both lanes have operations again, they share pops as if neither
+        // had been popped before
+        for lane in [0, 1] {
+            for _ in 0..4 {
+                let op = MockPendingOperation {
+                    lane,
+                    ..MockPendingOperation::new(1, destination_domain.clone())
+                };
+                op_queue.push(Box::new(op) as QueueOperation).await;
+            }
+        }
+        let popped_lanes = op_queue
+            .pop_many(4)
+            .await
+            .iter()
+            .map(|op| op.priority_lane())
+            .collect::<Vec<_>>();
+        assert_eq!(popped_lanes, vec![0, 0, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_operation_states_are_evicted() {
+        let op_states = OperationStates::new(Duration::ZERO);
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let dropped = MockPendingOperation::new(1, domain.clone());
+        let queued = MockPendingOperation::new(1, domain);
+
+        op_states.update(&dropped, OperationStage::Dropped).await;
+        assert!(op_states.get(&dropped.id()).await.is_some());
+
+        // Any later update evicts the expired dropped state, but not live ones
+        op_states.update(&queued, OperationStage::Prepare).await;
+        op_states.update(&queued, OperationStage::Submit).await;
+        assert!(op_states.get(&dropped.id()).await.is_none());
+        assert_eq!(
+            op_states.get(&queued.id()).await.unwrap().stage,
+            OperationStage::Submit
+        );
+    }
 }
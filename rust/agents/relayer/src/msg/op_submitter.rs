@@ -1,27 +1,41 @@
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+use async_trait::async_trait;
 use derive_new::new;
 use futures::future::join_all;
 use futures_util::future::try_join_all;
-use prometheus::{IntCounter, IntGaugeVec};
+use prometheus::{Counter, IntCounter, IntGauge, IntGaugeVec};
+use serde_json::json;
 use tokio::spawn;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tokio::time::sleep;
+use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info_span, instrument, instrument::Instrumented, trace, Instrument};
 use tracing::{info, warn};
 
-use hyperlane_base::CoreMetrics;
+use hyperlane_base::{ComponentHealth, CoreMetrics, HealthCheck};
 use hyperlane_core::{
     BatchItem, ChainCommunicationError, ChainResult, HyperlaneDomain, HyperlaneDomainProtocol,
     HyperlaneMessage, MpmcReceiver, TxOutcome,
 };
 
-use crate::msg::pending_message::CONFIRM_DELAY;
 use crate::server::MessageRetryRequest;
 
-use super::op_queue::{OpQueue, QueueOperation};
+use super::op_queue::{OpQueue, OperationStage, OperationStates, QueueOperation};
 use super::pending_operation::*;
+use super::priority_lanes::PriorityLanes;
+use super::wallet_pool::WalletBalance;
+
+/// How often a paused submitter checks whether its wallet was topped up
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// SerialSubmitter accepts operations over a channel. It is responsible for
 /// executing the right strategy to deliver those messages to the destination
@@ -46,6 +60,14 @@ use super::pending_operation::*;
 /// destination chain reorgs prior to committing delivery status to
 /// HyperlaneRocksDB.
 ///
+/// Once cancelled, the SerialSubmitter stops receiving, preparing and
+/// submitting operations, and finishes once every submitted operation has left
+/// the confirm queue.
+///
+/// While the balance of its wallet is below the chain's minimum signer balance,
+/// the SerialSubmitter pauses submitting operations. It keeps preparing and
+/// confirming them, and resumes submitting once the wallet is topped up.
+///
 ///
 /// Objectives
 /// ----------
@@ -82,6 +104,15 @@ pub struct SerialSubmitter {
     metrics: SerialSubmitterMetrics,
     /// Max batch size for submitting messages
     max_batch_size: u32,
+    /// Live state of the submitter's operations, for status lookups
+    op_states: OperationStates,
+    /// Priority lanes the submitter's queues are split into
+    priority_lanes: Arc<PriorityLanes>,
+    /// The balance state of the submitter's wallet, which pauses submitting
+    /// while retired
+    balance: WalletBalance,
+    /// Stops the submitter taking new work once cancelled
+    cancel: CancellationToken,
 }
 
 impl SerialSubmitter {
@@ -97,21 +128,37 @@ impl SerialSubmitter {
             rx: rx_prepare,
             retry_rx,
             max_batch_size,
+            op_states,
+            priority_lanes,
+            balance,
+            cancel,
         } = self;
+        // Cancelled once the submit task has stopped, so that the confirm task
+        // knows no more operations will be submitted
+        let submitted_all = CancellationToken::new();
         let prepare_queue = OpQueue::new(
             metrics.submitter_queue_length.clone(),
             "prepare_queue".to_string(),
             retry_rx.clone(),
+            op_states.clone(),
+            OperationStage::Prepare,
+            priority_lanes.clone(),
         );
         let submit_queue = OpQueue::new(
             metrics.submitter_queue_length.clone(),
             "submit_queue".to_string(),
             retry_rx.clone(),
+            op_states.clone(),
+            OperationStage::Submit,
+            priority_lanes.clone(),
         );
         let confirm_queue = OpQueue::new(
             metrics.submitter_queue_length.clone(),
             "confirm_queue".to_string(),
             retry_rx,
+            op_states,
+            OperationStage::Confirm,
+            priority_lanes,
         );
 
         let tasks = [
@@ -119,6 +166,7 @@ impl SerialSubmitter {
                 domain.clone(),
                 rx_prepare,
                 prepare_queue.clone(),
+                cancel.clone(),
             )),
             spawn(prepare_task(
                 domain.clone(),
@@ -127,6 +175,7 @@ impl SerialSubmitter {
                 confirm_queue.clone(),
                 max_batch_size,
                 metrics.clone(),
+                cancel.clone(),
             )),
             spawn(submit_task(
                 domain.clone(),
@@ -134,6 +183,9 @@ impl SerialSubmitter {
                 confirm_queue.clone(),
                 max_batch_size,
                 metrics.clone(),
+                balance,
+                cancel,
+                submitted_all.clone(),
             )),
             spawn(confirm_task(
                 domain.clone(),
@@ -141,6 +193,7 @@ impl SerialSubmitter {
                 confirm_queue,
                 max_batch_size,
                 metrics,
+                submitted_all,
             )),
         ];
 
@@ -159,9 +212,17 @@ async fn receive_task(
     domain: HyperlaneDomain,
     mut rx: mpsc::UnboundedReceiver<QueueOperation>,
     prepare_queue: OpQueue,
+    cancel: CancellationToken,
 ) {
     // Pull any messages sent to this submitter
-    while let Some(op) = rx.recv().await {
+    loop {
+        let op = tokio::select! {
+            op = rx.recv() => op,
+            _ = cancel.cancelled() => break,
+        };
+        let Some(op) = op else {
+            break;
+        };
         trace!(?op, "Received new operation");
         // make sure things are getting wired up correctly; if this works in testing it
         // should also be valid in production.
@@ -178,10 +239,11 @@ async fn prepare_task(
     confirm_queue: OpQueue,
     max_batch_size: u32,
     metrics: SerialSubmitterMetrics,
+    cancel: CancellationToken,
 ) {
     // Prepare at most `max_batch_size` ops at a time to avoid getting rate-limited
     let ops_to_prepare = max_batch_size as usize;
-    loop {
+    while !cancel.is_cancelled() {
         // Pop messages here according to the configured batch.
         let mut batch = prepare_queue.pop_many(ops_to_prepare).await;
         if batch.is_empty() {
@@ -224,6 +286,7 @@ async fn prepare_task(
                 }
                 PendingOperationResult::Drop => {
                     metrics.ops_dropped.inc();
+                    prepare_queue.record_dropped(op.as_ref()).await;
                 }
                 PendingOperationResult::Confirm => {
                     confirm_queue.push(op).await;
@@ -244,9 +307,39 @@ async fn submit_task(
     mut confirm_queue: OpQueue,
     max_batch_size: u32,
     metrics: SerialSubmitterMetrics,
+    balance: WalletBalance,
+    cancel: CancellationToken,
+    submitted_all: CancellationToken,
 ) {
+    // Signal the confirm task even if submitting panics
+    let _submitted_all = submitted_all.drop_guard();
     let recv_limit = max_batch_size as usize;
-    loop {
+    let mut paused_since: Option<Instant> = None;
+    while !cancel.is_cancelled() {
+        if balance.retired.load(Ordering::Relaxed) {
+            if paused_since.is_none() {
+                warn!("Pausing submissions because the wallet's balance is too low");
+                metrics.paused.set(1);
+                paused_since = Some(Instant::now());
+            }
+            let poll_start = Instant::now();
+            tokio::select! {
+                _ = sleep(PAUSED_POLL_INTERVAL) => {},
+                _ = cancel.cancelled() => {},
+            }
+            metrics
+                .paused_seconds
+                .inc_by(poll_start.elapsed().as_secs_f64());
+            continue;
+        }
+        if let Some(paused_since) = paused_since.take() {
+            info!(
+                paused_for = ?paused_since.elapsed(),
+                "Resuming submissions because the wallet was topped up"
+            );
+            metrics.paused.set(0);
+        }
+
         let mut batch = submit_queue.pop_many(recv_limit).await;
 
         match batch.len().cmp(&1) {
@@ -265,6 +358,9 @@ async fn submit_task(
                     .await;
             }
         }
+        // Have the wallet's balance checked right away, so the submitter pauses
+        // before submitting again if the wallet ran low
+        balance.spent.notify_one();
     }
 }
 
@@ -277,7 +373,7 @@ async fn submit_single_operation(
     let destination = op.destination_domain().clone();
     op.submit().await;
     debug!(?op, "Operation submitted");
-    op.set_next_attempt_after(CONFIRM_DELAY);
+    op.set_next_attempt_after(op.confirm_delay());
     confirm_queue.push(op).await;
     metrics.ops_submitted.inc();
 
@@ -299,13 +395,21 @@ async fn confirm_task(
     mut confirm_queue: OpQueue,
     max_batch_size: u32,
     metrics: SerialSubmitterMetrics,
+    submitted_all: CancellationToken,
 ) {
     let recv_limit = max_batch_size as usize;
     loop {
+        // Checked before popping, so that an operation submitted right before the
+        // submit task stopped is still seen
+        let draining = submitted_all.is_cancelled();
         // Pick the next message to try confirming.
         let batch = confirm_queue.pop_many(recv_limit).await;
 
         if batch.is_empty() {
+            if draining {
+                info!("Confirmed all submitted operations");
+                break;
+            }
             // queue is empty so give some time before checking again to prevent burning CPU
             sleep(Duration::from_millis(200)).await;
             continue;
@@ -349,6 +453,7 @@ async fn confirm_operation(
         PendingOperationResult::Success => {
             debug!(?op, "Operation confirmed");
             metrics.ops_confirmed.inc();
+            confirm_queue.record_confirmed(op.as_ref()).await;
         }
         PendingOperationResult::NotReady | PendingOperationResult::Confirm => {
             // TODO: push multiple messages at once
@@ -360,6 +465,7 @@ async fn confirm_operation(
         }
         PendingOperationResult::Drop => {
             metrics.ops_dropped.inc();
+            confirm_queue.record_dropped(op.as_ref()).await;
         }
     }
     operation_result
@@ -373,11 +479,14 @@ pub struct SerialSubmitterMetrics {
     ops_confirmed: IntCounter,
     ops_failed: IntCounter,
     ops_dropped: IntCounter,
+    paused: IntGauge,
+    paused_seconds: Counter,
 }
 
 impl SerialSubmitterMetrics {
-    pub fn new(metrics: &CoreMetrics, destination: &HyperlaneDomain) -> Self {
+    pub fn new(metrics: &CoreMetrics, destination: &HyperlaneDomain, wallet: usize) -> Self {
         let destination = destination.name();
+        let wallet = wallet.to_string();
         Self {
             submitter_queue_length: metrics.submitter_queue_length(),
             ops_prepared: metrics
@@ -395,10 +504,66 @@ impl SerialSubmitterMetrics {
             ops_dropped: metrics
                 .operations_processed_count()
                 .with_label_values(&["dropped", destination]),
+            paused: metrics
+                .submitter_paused()
+                .with_label_values(&[destination, &wallet]),
+            paused_seconds: metrics
+                .submitter_paused_seconds()
+                .with_label_values(&[destination, &wallet]),
         }
     }
 }
 
+/// The state of the submitter of a wallet, as seen by health checks
+#[derive(Debug, Clone, new)]
+pub struct SubmitterHandle {
+    /// Cancelled once the submitter has stopped
+    stopped: CancellationToken,
+    /// Set while the submitter is paused because its wallet's balance is too
+    /// low
+    paused: Arc<AtomicBool>,
+}
+
+/// Reports each destination as ready while the submitters of all of its
+/// wallets are running and at least one of them isn't paused. The pool routes
+/// new apps around paused wallets, so the destination is only stalled once all
+/// of them are paused.
+#[derive(Debug, new)]
+pub struct SubmitterHealthCheck {
+    /// The submitter of each wallet of each destination
+    submitters: HashMap<HyperlaneDomain, Vec<SubmitterHandle>>,
+}
+
+#[async_trait]
+impl HealthCheck for SubmitterHealthCheck {
+    async fn check(&self) -> Vec<ComponentHealth> {
+        self.submitters
+            .iter()
+            .map(|(destination, submitters)| {
+                let running_wallets = submitters
+                    .iter()
+                    .filter(|s| !s.stopped.is_cancelled())
+                    .count();
+                let paused_wallets = submitters
+                    .iter()
+                    .filter(|s| s.paused.load(Ordering::Relaxed))
+                    .count();
+                let running = running_wallets == submitters.len();
+                ComponentHealth {
+                    component: format!("submitter:{destination}"),
+                    ready: running && paused_wallets < submitters.len(),
+                    detail: json!({
+                        "running": running,
+                        "runningWallets": running_wallets,
+                        "pausedWallets": paused_wallets,
+                        "wallets": submitters.len(),
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(new, Debug)]
 struct OperationBatch {
     operations: Vec<QueueOperation>,
@@ -415,7 +580,8 @@ impl OperationBatch {
                 // which means we need to add a `set_transaction_outcome` fn to `PendingOperation`
                 info!(outcome=?outcome, batch_size=self.operations.len(), batch=?self.operations, "Submitted transaction batch");
                 for mut op in self.operations {
-                    op.set_next_attempt_after(CONFIRM_DELAY);
+                    op.on_batch_submitted(&outcome);
+                    op.set_next_attempt_after(op.confirm_delay());
                     confirm_queue.push(op).await;
                 }
                 return;
@@ -457,3 +623,163 @@ impl OperationBatch {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{KnownHyperlaneDomain, MpmcChannel, TryBatchAs, H256};
+    use prometheus::Registry;
+
+    use super::*;
+
+    /// Reports its id once submitted
+    #[derive(Debug)]
+    struct MockOperation {
+        id: H256,
+        destination_domain: HyperlaneDomain,
+        submitted: mpsc::UnboundedSender<H256>,
+    }
+
+    impl TryBatchAs<HyperlaneMessage> for MockOperation {}
+
+    #[async_trait]
+    impl PendingOperation for MockOperation {
+        fn id(&self) -> H256 {
+            self.id
+        }
+
+        fn priority(&self) -> u32 {
+            0
+        }
+
+        fn priority_lane(&self) -> usize {
+            0
+        }
+
+        fn origin_domain_id(&self) -> u32 {
+            0
+        }
+
+        fn destination_domain(&self) -> &HyperlaneDomain {
+            &self.destination_domain
+        }
+
+        fn app_context(&self) -> Option<String> {
+            None
+        }
+
+        async fn prepare(&mut self) -> PendingOperationResult {
+            PendingOperationResult::Success
+        }
+
+        async fn submit(&mut self) {
+            self.submitted.send(self.id).unwrap();
+        }
+
+        fn set_submission_outcome(&mut self, _outcome: TxOutcome) {}
+
+        fn on_batch_submitted(&mut self, _outcome: &TxOutcome) {}
+
+        async fn confirm(&mut self) -> PendingOperationResult {
+            PendingOperationResult::Success
+        }
+
+        fn next_attempt_after(&self) -> Option<std::time::Instant> {
+            None
+        }
+
+        fn set_next_attempt_after(&mut self, _delay: Duration) {}
+
+        fn confirm_delay(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn reset_attempts(&mut self) {}
+
+        fn status(&self) -> OperationStatus {
+            Default::default()
+        }
+
+        fn set_retries(&mut self, _retries: u32) {}
+    }
+
+    fn dummy_queue(metrics: &SerialSubmitterMetrics, stage: OperationStage) -> OpQueue {
+        OpQueue::new(
+            metrics.submitter_queue_length.clone(),
+            stage.as_str().to_owned(),
+            MpmcChannel::new(1).receiver(),
+            OperationStates::default(),
+            stage,
+            Default::default(),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_submitter_pauses_while_wallet_is_retired() {
+        let domain: HyperlaneDomain = KnownHyperlaneDomain::Arbitrum.into();
+        let core_metrics = CoreMetrics::new("dummy_relayer", 37583, Registry::new()).unwrap();
+        let metrics = SerialSubmitterMetrics::new(&core_metrics, &domain, 0);
+        let submit_queue = dummy_queue(&metrics, OperationStage::Submit);
+        let confirm_queue = dummy_queue(&metrics, OperationStage::Confirm);
+        let balance = WalletBalance::default();
+        balance.retired.store(true, Ordering::Relaxed);
+        let cancel = CancellationToken::new();
+        let task = spawn(submit_task(
+            domain.clone(),
+            submit_queue.clone(),
+            confirm_queue,
+            1,
+            metrics.clone(),
+            balance.clone(),
+            cancel.clone(),
+            CancellationToken::new(),
+        ));
+
+        let (submitted_tx, mut submitted) = mpsc::unbounded_channel();
+        let op = MockOperation {
+            id: H256::random(),
+            destination_domain: domain,
+            submitted: submitted_tx,
+        };
+        let id = op.id;
+        submit_queue.push(Box::new(op)).await;
+
+        // Nothing is submitted while the wallet is retired
+        sleep(Duration::from_secs(10)).await;
+        assert!(submitted.try_recv().is_err());
+        assert_eq!(metrics.paused.get(), 1);
+        assert!(metrics.paused_seconds.get() >= 9.0);
+
+        // Submitting resumes once the wallet is topped up, and the wallet's
+        // balance is checked again right after
+        balance.retired.store(false, Ordering::Relaxed);
+        assert_eq!(submitted.recv().await, Some(id));
+        assert_eq!(metrics.paused.get(), 0);
+        balance.spent.notified().await;
+        let paused_seconds = metrics.paused_seconds.get();
+        sleep(Duration::from_secs(10)).await;
+        assert_eq!(metrics.paused_seconds.get(), paused_seconds);
+
+        cancel.cancel();
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_destination_is_ready_unless_all_wallets_are_paused() {
+        let domain: HyperlaneDomain = KnownHyperlaneDomain::Arbitrum.into();
+        let paused = [
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        ];
+        let handles = paused
+            .iter()
+            .map(|paused| SubmitterHandle::new(CancellationToken::new(), paused.clone()))
+            .collect();
+        let health_check = SubmitterHealthCheck::new(HashMap::from([(domain, handles)]));
+
+        paused[0].store(true, Ordering::Relaxed);
+        assert!(health_check.check().await[0].ready);
+
+        paused[1].store(true, Ordering::Relaxed);
+        assert!(!health_check.check().await[0].ready);
+    }
+}
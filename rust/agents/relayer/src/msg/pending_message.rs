@@ -1,7 +1,7 @@
 use std::{
     fmt::{Debug, Formatter},
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
@@ -9,8 +9,9 @@ use derive_new::new;
 use eyre::Result;
 use hyperlane_base::{db::HyperlaneRocksDB, CoreMetrics};
 use hyperlane_core::{
-    BatchItem, ChainCommunicationError, ChainResult, HyperlaneChain, HyperlaneDomain,
-    HyperlaneMessage, Mailbox, MessageSubmissionData, TryBatchAs, TxOutcome, H256, U256,
+    BatchItem, BlockFinality, ChainCommunicationError, ChainResult, HyperlaneChain,
+    HyperlaneDomain, HyperlaneMessage, Mailbox, MessageSubmissionData, TryBatchAs, TxOutcome, H256,
+    H512, U256,
 };
 use prometheus::{IntCounter, IntGauge};
 use tracing::{debug, error, info, instrument, trace, warn};
@@ -19,7 +20,10 @@ use super::{
     gas_payment::GasPaymentEnforcer,
     metadata::{BaseMetadataBuilder, MessageMetadataBuilder, MetadataBuilder},
     pending_operation::*,
+    priority_lanes::PriorityLanes,
+    wallet_pool::WalletLease,
 };
+use crate::lifecycle::{MessageLifecycleEventKind, MessageLifecycleEvents};
 
 pub const CONFIRM_DELAY: Duration = if cfg!(any(test, feature = "test-utils")) {
     // Wait 5 seconds after submitting the message before confirming in test mode
@@ -29,10 +33,19 @@ pub const CONFIRM_DELAY: Duration = if cfg!(any(test, feature = "test-utils")) {
     Duration::from_secs(60)
 };
 
+/// How often a delivery is checked until it's final, on destinations with a
+/// delivery finality. Replaces `CONFIRM_DELAY` for them.
+const FINALITY_POLL_INTERVAL: Duration = if cfg!(any(test, feature = "test-utils")) {
+    Duration::from_secs(1)
+} else {
+    Duration::from_secs(10)
+};
+
 /// The message context contains the links needed to submit a message. Each
-/// instance is for a unique origin -> destination pairing.
+/// instance is for a unique origin -> destination pairing and wallet of the
+/// destination's pool.
 pub struct MessageContext {
-    /// Mailbox on the destination chain.
+    /// Mailbox on the destination chain, which submits with the wallet.
     pub destination_mailbox: Arc<dyn Mailbox>,
     /// Origin chain database to verify gas payments.
     pub origin_db: HyperlaneRocksDB,
@@ -45,7 +58,15 @@ pub struct MessageContext {
     /// Hard limit on transaction gas when submitting a transaction to the
     /// destination.
     pub transaction_gas_limit: Option<U256>,
+    /// When deliveries to the destination are considered final. If None,
+    /// deliveries are confirmed at the latest block after `CONFIRM_DELAY`.
+    pub delivery_finality: Option<BlockFinality>,
     pub metrics: MessageSubmissionMetrics,
+    /// Where to publish the lifecycle events of messages.
+    pub lifecycle_events: MessageLifecycleEvents,
+    /// Used to classify messages into the priority lanes of the submitters'
+    /// queues.
+    pub priority_lanes: Arc<PriorityLanes>,
 }
 
 /// A message that the submitter can and should try to submit.
@@ -66,6 +87,25 @@ pub struct PendingMessage {
     next_attempt_after: Option<Instant>,
     #[new(default)]
     submission_outcome: Option<TxOutcome>,
+    /// The transaction of the latest submission of this message, whether it
+    /// was submitted on its own or in a batch
+    #[new(default)]
+    submitted_tx_id: Option<H512>,
+    /// The last event published while preparing this message, so that retries
+    /// which fail for the same reason don't publish it again
+    #[new(default)]
+    last_prepare_event: Option<MessageLifecycleEventKind>,
+    /// Why the latest failed attempt to deliver this message failed
+    #[new(default)]
+    last_error: Option<String>,
+    /// How many times the unspent gas payment of this message covered its
+    /// estimated gas limit, as of its latest gas estimation
+    #[new(default)]
+    gas_overpayment_ratio: Option<f64>,
+    /// Keeps the message's app pinned to the wallet delivering it until the
+    /// message leaves the queues
+    #[new(default)]
+    _wallet_lease: Option<WalletLease>,
 }
 
 impl Debug for PendingMessage {
@@ -124,6 +164,14 @@ impl PendingOperation for PendingMessage {
         self.message.nonce
     }
 
+    fn priority_lane(&self) -> usize {
+        self.ctx.priority_lanes.lane_of(
+            &self.message,
+            self.app_context.as_deref(),
+            self.gas_overpayment_ratio,
+        )
+    }
+
     fn origin_domain_id(&self) -> u32 {
         self.message.origin
     }
@@ -138,7 +186,7 @@ impl PendingOperation for PendingMessage {
 
     #[instrument(skip(self), ret, fields(id=%self.id()), level = "debug")]
     async fn prepare(&mut self) -> PendingOperationResult {
-        make_op_try!(|| self.on_reprepare());
+        make_op_try!(|err| self.on_reprepare(err));
 
         if !self.is_ready() {
             trace!("Message is not ready to be submitted yet");
@@ -158,7 +206,7 @@ impl PendingOperation for PendingMessage {
         if is_already_delivered {
             debug!("Message has already been delivered, marking as submitted.");
             self.submitted = true;
-            self.set_next_attempt_after(CONFIRM_DELAY);
+            self.set_next_attempt_after(self.confirm_delay());
             return PendingOperationResult::Confirm;
         }
 
@@ -174,6 +222,12 @@ impl PendingOperation for PendingMessage {
                 recipient=?self.message.recipient,
                 "Dropping message because recipient is not a contract"
             );
+            let reason = "Recipient is not a contract".to_owned();
+            self.last_error = Some(reason.clone());
+            self.publish_event(MessageLifecycleEventKind::Dropped { reason });
+            self.ctx
+                .origin_gas_payment_enforcer
+                .remove_exemption(&self.message.id());
             return PendingOperationResult::Drop;
         }
 
@@ -202,7 +256,8 @@ impl PendingOperation for PendingMessage {
             "building metadata"
         ) else {
             info!("Could not fetch metadata");
-            return self.on_reprepare();
+            self.publish_prepare_event(MessageLifecycleEventKind::MetadataUnavailable);
+            return self.on_reprepare("Could not fetch metadata");
         };
 
         // Estimate transaction costs for the process call. If there are issues, it's
@@ -226,9 +281,17 @@ impl PendingOperation for PendingMessage {
             "checking if message meets gas payment requirement"
         ) else {
             warn!(?tx_cost_estimate, "Gas payment requirement not met yet");
-            return self.on_reprepare();
+            self.publish_prepare_event(MessageLifecycleEventKind::GasPaymentRequirementNotMet);
+            return self.on_reprepare("Gas payment requirement not met");
         };
 
+        self.gas_overpayment_ratio = op_try!(
+            self.ctx
+                .origin_gas_payment_enforcer
+                .gas_overpayment_ratio(&self.message, &tx_cost_estimate),
+            "computing the gas overpayment ratio"
+        );
+
         // Go ahead and attempt processing of message to destination chain.
         debug!(
             ?gas_limit,
@@ -240,7 +303,7 @@ impl PendingOperation for PendingMessage {
         if let Some(max_limit) = self.ctx.transaction_gas_limit {
             if gas_limit > max_limit {
                 info!("Message delivery estimated gas exceeds max gas limit");
-                return self.on_reprepare();
+                return self.on_reprepare("Estimated gas exceeds the max gas limit");
             }
         }
 
@@ -248,6 +311,7 @@ impl PendingOperation for PendingMessage {
             metadata,
             gas_limit,
         }));
+        self.last_prepare_event = None;
         PendingOperationResult::Success
     }
 
@@ -272,10 +336,12 @@ impl PendingOperation for PendingMessage {
             .await;
         match tx_outcome {
             Ok(outcome) => {
+                self.on_submitted(outcome.transaction_id);
                 self.set_submission_outcome(outcome);
             }
             Err(e) => {
                 error!(error=?e, "Error when processing message");
+                self.last_error = Some(format!("Error when processing message: {e}"));
             }
         }
     }
@@ -284,10 +350,15 @@ impl PendingOperation for PendingMessage {
         self.submission_outcome = Some(outcome);
     }
 
+    fn on_batch_submitted(&mut self, outcome: &TxOutcome) {
+        self.on_submitted(outcome.transaction_id);
+    }
+
     async fn confirm(&mut self) -> PendingOperationResult {
-        make_op_try!(|| {
+        make_op_try!(|err| {
             // Provider error; just try again later
             // Note: this means that we are using `NotReady` for a retryable error case
+            self.last_error = Some(err);
             self.inc_attempts();
             PendingOperationResult::NotReady
         });
@@ -304,6 +375,20 @@ impl PendingOperation for PendingMessage {
             "Confirming message delivery"
         );
         if is_delivered {
+            if let Some(finality) = self.ctx.delivery_finality {
+                let is_final = op_try!(
+                    self.ctx
+                        .destination_mailbox
+                        .delivered_at_finality(self.message.id(), finality)
+                        .await,
+                    "checking the finality of message delivery"
+                );
+                if !is_final {
+                    debug!(?finality, "Message delivery isn't final yet");
+                    self.set_next_attempt_after(FINALITY_POLL_INTERVAL);
+                    return PendingOperationResult::NotReady;
+                }
+            }
             op_try!(
                 critical: self.record_message_process_success(),
                 "recording message process success"
@@ -312,6 +397,9 @@ impl PendingOperation for PendingMessage {
                 submission=?self.submission_outcome,
                 "Message successfully processed"
             );
+            self.publish_event(MessageLifecycleEventKind::Confirmed {
+                tx_id: self.submitted_tx_id,
+            });
             PendingOperationResult::Success
         } else {
             if let Some(outcome) = &self.submission_outcome {
@@ -328,7 +416,7 @@ impl PendingOperation for PendingMessage {
                 message_id=?self.message.id(),
                 "Transaction attempting to process message either reverted or was reorged"
             );
-            self.on_reprepare()
+            self.on_reprepare("Transaction processing the message reverted or was reorged")
         }
     }
 
@@ -336,6 +424,14 @@ impl PendingOperation for PendingMessage {
         self.next_attempt_after
     }
 
+    fn confirm_delay(&self) -> Duration {
+        if self.ctx.delivery_finality.is_some() {
+            FINALITY_POLL_INTERVAL
+        } else {
+            CONFIRM_DELAY
+        }
+    }
+
     fn set_next_attempt_after(&mut self, delay: Duration) {
         self.next_attempt_after = Some(Instant::now() + delay);
     }
@@ -344,6 +440,15 @@ impl PendingOperation for PendingMessage {
         self.reset_attempts();
     }
 
+    fn status(&self) -> OperationStatus {
+        OperationStatus {
+            num_retries: self.num_retries,
+            next_attempt_after: self.next_attempt_after,
+            last_error: self.last_error.clone(),
+            submitted_tx_id: self.submitted_tx_id,
+        }
+    }
+
     #[cfg(test)]
     fn set_retries(&mut self, retries: u32) {
         self.set_retries(retries);
@@ -357,8 +462,10 @@ impl PendingMessage {
         message: HyperlaneMessage,
         ctx: Arc<MessageContext>,
         app_context: Option<String>,
+        wallet_lease: Option<WalletLease>,
     ) -> Self {
         let mut pm = Self::new(message, ctx, app_context);
+        pm._wallet_lease = wallet_lease;
         match pm
             .ctx
             .origin_db
@@ -377,12 +484,32 @@ impl PendingMessage {
         pm
     }
 
-    fn on_reprepare(&mut self) -> PendingOperationResult {
+    fn on_reprepare(&mut self, reason: impl Into<String>) -> PendingOperationResult {
+        self.last_error = Some(reason.into());
         self.inc_attempts();
         self.submitted = false;
+        self.submitted_tx_id = None;
         PendingOperationResult::Reprepare
     }
 
+    fn on_submitted(&mut self, tx_id: H512) {
+        self.submitted_tx_id = Some(tx_id);
+        self.publish_event(MessageLifecycleEventKind::Submitted { tx_id });
+    }
+
+    fn publish_event(&self, kind: MessageLifecycleEventKind) {
+        self.ctx.lifecycle_events.publish(&self.message, kind);
+    }
+
+    /// Publish an event of a failed preparation, unless the previous attempt
+    /// failed for the same reason.
+    fn publish_prepare_event(&mut self, kind: MessageLifecycleEventKind) {
+        if self.last_prepare_event.as_ref() != Some(&kind) {
+            self.publish_event(kind.clone());
+            self.last_prepare_event = Some(kind);
+        }
+    }
+
     fn is_ready(&self) -> bool {
         self.next_attempt_after
             .map(|a| Instant::now() >= a)
@@ -400,6 +527,20 @@ impl PendingMessage {
         self.ctx
             .origin_db
             .store_processed_by_nonce(&self.message.nonce, &true)?;
+        // Record when the message was confirmed delivered, so that its data can be
+        // pruned once it's older than the configured retention period
+        let processed_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.ctx
+            .origin_db
+            .store_processed_at_by_nonce(&self.message.nonce, &processed_at)?;
+        if let Some(tx_id) = &self.submitted_tx_id {
+            self.ctx
+                .origin_db
+                .store_delivery_tx_by_message_id(&self.message.id(), tx_id)?;
+        }
+        self.ctx
+            .origin_gas_payment_enforcer
+            .remove_exemption(&self.message.id());
         self.ctx.metrics.update_nonce(&self.message);
         self.ctx.metrics.messages_processed.inc();
         Ok(())
@@ -453,7 +594,7 @@ impl PendingMessage {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MessageSubmissionMetrics {
     // Fields are public for testing purposes
     pub last_known_nonce: IntGauge,
@@ -488,3 +629,91 @@ impl MessageSubmissionMetrics {
             .set(std::cmp::max(self.last_known_nonce.get(), msg.nonce as i64));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU64;
+
+    use hyperlane_base::db::test_utils;
+    use hyperlane_test::mocks::MockMailboxContract;
+
+    use super::*;
+    use crate::msg::processor::test::{
+        dummy_domain, dummy_metadata_builder, dummy_submission_metrics,
+    };
+
+    fn dummy_ctx(
+        db: &HyperlaneRocksDB,
+        mailbox: MockMailboxContract,
+        finality: BlockFinality,
+    ) -> Arc<MessageContext> {
+        let origin_domain = dummy_domain(0, "dummy_origin_domain");
+        let destination_domain = dummy_domain(1, "dummy_destination_domain");
+        Arc::new(MessageContext {
+            destination_mailbox: Arc::new(mailbox),
+            origin_db: db.clone(),
+            metadata_builder: Arc::new(dummy_metadata_builder(
+                &origin_domain,
+                &destination_domain,
+                db,
+            )),
+            origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
+            transaction_gas_limit: None,
+            delivery_finality: Some(finality),
+            metrics: dummy_submission_metrics(),
+            lifecycle_events: Default::default(),
+            priority_lanes: Default::default(),
+        })
+    }
+
+    /// Confirms a delivered message which only becomes final at `finality` on
+    /// the second check
+    async fn assert_confirmed_once_final(finality: BlockFinality) {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            let message = HyperlaneMessage::default();
+            let mut mailbox = MockMailboxContract::new();
+            mailbox.expect__delivered().returning(|_| Ok(true));
+            let mut checks = 0;
+            mailbox
+                .expect__delivered_at_finality()
+                .withf(move |_, checked_finality| *checked_finality == finality)
+                .times(2)
+                .returning(move |_, _| {
+                    checks += 1;
+                    Ok(checks > 1)
+                });
+            let mut pending_msg =
+                PendingMessage::new(message.clone(), dummy_ctx(&db, mailbox, finality), None);
+
+            let result = pending_msg.confirm().await;
+            assert!(matches!(result, PendingOperationResult::NotReady));
+            assert!(pending_msg.next_attempt_after.is_some());
+            assert_eq!(
+                db.retrieve_processed_by_nonce(&message.nonce).unwrap(),
+                None
+            );
+
+            // Checked again once the finality poll interval has passed
+            pending_msg.next_attempt_after = None;
+            let result = pending_msg.confirm().await;
+            assert!(matches!(result, PendingOperationResult::Success));
+            assert_eq!(
+                db.retrieve_processed_by_nonce(&message.nonce).unwrap(),
+                Some(true)
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_confirm_waits_for_confirmations() {
+        assert_confirmed_once_final(BlockFinality::Confirmations(NonZeroU64::new(3).unwrap()))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_confirm_waits_for_finalized_block() {
+        assert_confirmed_once_final(BlockFinality::Finalized).await;
+    }
+}
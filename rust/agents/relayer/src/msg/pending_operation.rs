@@ -5,7 +5,7 @@ use std::{
 };
 
 use async_trait::async_trait;
-use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, TryBatchAs, TxOutcome, H256};
+use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, TryBatchAs, TxOutcome, H256, H512};
 
 use super::op_queue::QueueOperation;
 
@@ -40,6 +40,10 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// operations when neither of them have a `next_attempt_after`
     fn priority(&self) -> u32;
 
+    /// The priority lane of the queues this operation is pushed onto, as
+    /// classified by `PriorityLanes`.
+    fn priority_lane(&self) -> usize;
+
     /// The domain this originates from.
     fn origin_domain_id(&self) -> u32;
 
@@ -67,6 +71,10 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// Set the outcome of the `submit` call
     fn set_submission_outcome(&mut self, outcome: TxOutcome);
 
+    /// Called once this operation was submitted as part of a batch, with the
+    /// outcome of the whole batch's transaction
+    fn on_batch_submitted(&mut self, outcome: &TxOutcome);
+
     /// This will be called after the operation has been submitted and is
     /// responsible for checking if the operation has reached a point at
     /// which we consider it safe from reorgs.
@@ -81,10 +89,17 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// Set the next time this operation should be attempted.
     fn set_next_attempt_after(&mut self, delay: Duration);
 
+    /// How long to wait after submitting this operation before trying to
+    /// confirm it.
+    fn confirm_delay(&self) -> Duration;
+
     /// Reset the number of attempts this operation has made, causing it to be
     /// retried immediately.
     fn reset_attempts(&mut self);
 
+    /// A snapshot of the state of this operation, for status lookups.
+    fn status(&self) -> OperationStatus;
+
     #[cfg(test)]
     /// Set the number of times this operation has been retried.
     fn set_retries(&mut self, retries: u32);
@@ -138,6 +153,19 @@ impl Ord for QueueOperation {
     }
 }
 
+/// The state of a pending operation, as reported by status lookups
+#[derive(Debug, Clone, Default)]
+pub struct OperationStatus {
+    /// How many times the operation has been retried
+    pub num_retries: u32,
+    /// The earliest instant at which the operation will next be attempted
+    pub next_attempt_after: Option<Instant>,
+    /// Why the latest failed attempt failed
+    pub last_error: Option<String>,
+    /// The transaction of the latest submission of the operation
+    pub submitted_tx_id: Option<H512>,
+}
+
 #[derive(Debug)]
 pub enum PendingOperationResult {
     /// Promote to the next step
@@ -152,7 +180,8 @@ pub enum PendingOperationResult {
     Confirm,
 }
 
-/// create a `op_try!` macro for the `on_retry` handler.
+/// create a `op_try!` macro for the `on_retry` handler, which is called with a
+/// description of the error.
 macro_rules! make_op_try {
     ($on_retry:expr) => {
         /// Handle a result and either return early with retry or a critical failure on
@@ -164,7 +193,7 @@ macro_rules! make_op_try {
                                     Err(e) => {
                                         error!(error=?e, concat!("Critical error when ", $ctx));
                                         #[allow(clippy::redundant_closure_call)]
-                                        return $on_retry();
+                                        return $on_retry(format!(concat!("Critical error when ", $ctx, ": {}"), e));
                                     }
                                 }
                             };
@@ -174,7 +203,7 @@ macro_rules! make_op_try {
                                     Err(e) => {
                                         warn!(error=?e, concat!("Error when ", $ctx));
                                         #[allow(clippy::redundant_closure_call)]
-                                        return $on_retry();
+                                        return $on_retry(format!(concat!("Error when ", $ctx, ": {}"), e));
                                     }
                                 }
                             };
@@ -0,0 +1,145 @@
+//! Priority lanes split the queues of submitters, so that messages of critical
+//! apps or which paid generously for gas are preferred over other messages,
+//! without starving them.
+
+use std::iter::once;
+
+use hyperlane_core::HyperlaneMessage;
+
+use crate::settings::PriorityLaneConf;
+
+/// Name of the lane of messages which match none of the configured lanes
+const DEFAULT_LANE_NAME: &str = "default";
+
+/// Weight of the lane of messages which match none of the configured lanes
+const DEFAULT_LANE_WEIGHT: u32 = 1;
+
+/// Classifies messages into the configured priority lanes. Lanes are
+/// identified by their index, and the default lane comes after all of the
+/// configured ones.
+#[derive(Debug, Default)]
+pub struct PriorityLanes {
+    lanes: Vec<PriorityLaneConf>,
+}
+
+impl PriorityLanes {
+    pub fn new(lanes: Vec<PriorityLaneConf>) -> Self {
+        Self { lanes }
+    }
+
+    /// The lane of messages which match none of the configured lanes
+    pub fn default_lane(&self) -> usize {
+        self.lanes.len()
+    }
+
+    /// The first lane `message` matches, or the default lane.
+    ///
+    /// Lanes with a minimum gas overpayment ratio are only matched once the
+    /// ratio is known, i.e. once the message has had its gas estimated.
+    pub fn lane_of(
+        &self,
+        message: &HyperlaneMessage,
+        app_context: Option<&str>,
+        gas_overpayment_ratio: Option<f64>,
+    ) -> usize {
+        self.lanes
+            .iter()
+            .position(|lane| {
+                lane.matching_list.msg_matches(message, true)
+                    && lane
+                        .app_context
+                        .as_deref()
+                        .map_or(true, |lane_context| app_context == Some(lane_context))
+                    && lane.min_gas_overpayment_ratio.map_or(true, |min| {
+                        gas_overpayment_ratio.map_or(false, |ratio| ratio >= min)
+                    })
+            })
+            .unwrap_or_else(|| self.default_lane())
+    }
+
+    /// The weight of each lane, by index
+    pub fn weights(&self) -> Vec<u32> {
+        self.lanes
+            .iter()
+            .map(|lane| lane.weight)
+            .chain(once(DEFAULT_LANE_WEIGHT))
+            .collect()
+    }
+
+    /// The name of the lane at `index`
+    pub fn name(&self, index: usize) -> &str {
+        self.lanes
+            .get(index)
+            .map_or(DEFAULT_LANE_NAME, |lane| lane.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H256;
+
+    use super::*;
+    use crate::settings::matching_list::MatchingList;
+
+    fn lane(
+        name: &str,
+        matching_list: &str,
+        app_context: Option<&str>,
+        min_gas_overpayment_ratio: Option<f64>,
+    ) -> PriorityLaneConf {
+        PriorityLaneConf {
+            name: name.to_owned(),
+            weight: 5,
+            matching_list: serde_json::from_str::<MatchingList>(matching_list).unwrap(),
+            app_context: app_context.map(str::to_owned),
+            min_gas_overpayment_ratio,
+        }
+    }
+
+    fn dummy_lanes() -> PriorityLanes {
+        PriorityLanes::new(vec![
+            lane("warp", r#"[{"origindomain": 1}]"#, None, None),
+            lane("critical", "[]", Some("critical_app"), None),
+            lane("overpaid", "[]", None, Some(2.0)),
+        ])
+    }
+
+    #[test]
+    fn messages_are_in_the_first_lane_they_match() {
+        let lanes = dummy_lanes();
+        let warp_message = HyperlaneMessage {
+            origin: 1,
+            ..Default::default()
+        };
+        let other_message = HyperlaneMessage {
+            origin: 2,
+            sender: H256::random(),
+            ..Default::default()
+        };
+
+        assert_eq!(lanes.lane_of(&warp_message, Some("critical_app"), None), 0);
+        assert_eq!(lanes.lane_of(&other_message, Some("critical_app"), None), 1);
+        assert_eq!(
+            lanes.lane_of(&other_message, Some("other_app"), Some(3.0)),
+            2
+        );
+        assert_eq!(lanes.name(2), "overpaid");
+    }
+
+    #[test]
+    fn unmatched_messages_are_in_the_default_lane() {
+        let lanes = dummy_lanes();
+        let message = HyperlaneMessage {
+            origin: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(lanes.lane_of(&message, None, None), lanes.default_lane());
+        assert_eq!(
+            lanes.lane_of(&message, None, Some(1.5)),
+            lanes.default_lane()
+        );
+        assert_eq!(lanes.name(lanes.default_lane()), DEFAULT_LANE_NAME);
+        assert_eq!(lanes.weights(), vec![5, 5, 5, DEFAULT_LANE_WEIGHT]);
+    }
+}
@@ -9,13 +9,19 @@ use async_trait::async_trait;
 use derive_new::new;
 use eyre::Result;
 use hyperlane_base::{db::HyperlaneRocksDB, CoreMetrics};
-use hyperlane_core::{HyperlaneDomain, HyperlaneMessage};
+use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, H256};
 use prometheus::IntGauge;
-use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, trace};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{debug, info, trace, warn};
 
-use super::{metadata::AppContextClassifier, op_queue::QueueOperation, pending_message::*};
-use crate::{processor::ProcessorExt, settings::matching_list::MatchingList};
+use super::{
+    metadata::AppContextClassifier, op_queue::QueueOperation, pending_message::*,
+    wallet_pool::WalletPool,
+};
+use crate::{
+    lifecycle::MessageLifecycleEventKind, processor::ProcessorExt,
+    settings::matching_list::MatchingList,
+};
 
 /// Finds unprocessed messages from an origin and submits then through a channel
 /// for to the appropriate destination.
@@ -26,14 +32,22 @@ pub struct MessageProcessor {
     whitelist: Arc<MatchingList>,
     blacklist: Arc<MatchingList>,
     metrics: MessageProcessorMetrics,
-    /// channel for each destination chain to send operations (i.e. message
-    /// submissions) to
-    send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
-    /// Needed context to send a message for each destination chain
-    destination_ctxs: HashMap<u32, Arc<MessageContext>>,
+    /// wallet pool of each destination chain, whose submitters operations
+    /// (i.e. message submissions) are sent to
+    wallet_pools: HashMap<u32, WalletPool>,
+    /// Needed context to send a message for each wallet of each destination
+    /// chain, indexed like the wallets of its pool
+    destination_ctxs: HashMap<u32, Vec<Arc<MessageContext>>>,
     metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Messages relayed manually, which are sent to the submitters regardless
+    /// of the whitelist and blacklist
+    manual_relays: UnboundedReceiver<HyperlaneMessage>,
     #[new(default)]
     message_nonce: u32,
+    /// Nonces of the messages relayed manually by their ids, so they aren't
+    /// sent to the submitters again when the scan reaches them
+    #[new(default)]
+    manually_relayed: HashMap<H256, u32>,
 }
 
 impl Debug for MessageProcessor {
@@ -56,6 +70,39 @@ impl ProcessorExt for MessageProcessor {
     /// One round of processing, extracted from infinite work loop for
     /// testing purposes.
     async fn tick(&mut self) -> Result<()> {
+        // Messages relayed manually skip the whitelist and blacklist, and don't
+        // wait for the scan to reach them.
+        while let Ok(msg) = self.manual_relays.try_recv() {
+            if !self.wallet_pools.contains_key(&msg.destination) {
+                warn!(%msg, "Manually relayed message destined for unknown domain, skipping");
+                continue;
+            }
+            info!(%msg, "Sending manually relayed message to submitter");
+            // Only messages the scan hasn't reached yet need to be skipped by it
+            if msg.nonce >= self.message_nonce {
+                self.manually_relayed.insert(msg.id(), msg.nonce);
+            }
+            self.send_to_submitter(msg).await?;
+        }
+
+        // If messages were rolled back after a reorg, rewind to process the messages
+        // re-indexed in their place.
+        if let Some(nonce) = self.db.take_rolled_back_message_nonce()? {
+            if nonce < self.message_nonce {
+                warn!(
+                    nonce,
+                    message_nonce = self.message_nonce,
+                    "Messages were rolled back after a reorg, rewinding"
+                );
+                self.message_nonce = nonce;
+            }
+        }
+
+        // Forget the manual relays the scan has passed, including those it skipped
+        // over because they were already processed.
+        let message_nonce = self.message_nonce;
+        self.manually_relayed.retain(|_, nonce| *nonce >= message_nonce);
+
         // Forever, scan HyperlaneRocksDB looking for new messages to send. When criteria are
         // satisfied or the message is disqualified, push the message onto
         // self.tx_msg and then continue the scan at the next highest
@@ -65,6 +112,13 @@ impl ProcessorExt for MessageProcessor {
             debug!(?msg, "Processor working on message");
             let destination = msg.destination;
 
+            // Skip if the message was relayed manually, and so already sent
+            if self.manually_relayed.remove(&msg.id()).is_some() {
+                debug!(?msg, "Message already relayed manually, skipping");
+                self.message_nonce += 1;
+                return Ok(());
+            }
+
             // Skip if not whitelisted.
             if !self.whitelist.msg_matches(&msg, true) {
                 debug!(?msg, whitelist=?self.whitelist, "Message not whitelisted, skipping");
@@ -87,25 +141,14 @@ impl ProcessorExt for MessageProcessor {
             }
 
             // Skip if the message is intended for a destination we do not service
-            if !self.send_channels.contains_key(&destination) {
+            if !self.wallet_pools.contains_key(&destination) {
                 debug!(?msg, "Message destined for unknown domain, skipping");
                 self.message_nonce += 1;
                 return Ok(());
             }
 
             debug!(%msg, "Sending message to submitter");
-
-            let app_context_classifier =
-                AppContextClassifier::new(self.metric_app_contexts.clone());
-
-            let app_context = app_context_classifier.get_app_context(&msg).await?;
-            // Finally, build the submit arg and dispatch it to the submitter.
-            let pending_msg = PendingMessage::from_persisted_retries(
-                msg,
-                self.destination_ctxs[&destination].clone(),
-                app_context,
-            );
-            self.send_channels[&destination].send(Box::new(pending_msg) as QueueOperation)?;
+            self.send_to_submitter(msg).await?;
             self.message_nonce += 1;
         } else {
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -115,8 +158,34 @@ impl ProcessorExt for MessageProcessor {
 }
 
 impl MessageProcessor {
+    /// Build the submit arg of `msg` and dispatch it to the submitter of the
+    /// wallet delivering it.
+    async fn send_to_submitter(&self, msg: HyperlaneMessage) -> Result<()> {
+        let destination = msg.destination;
+        let app_context_classifier = AppContextClassifier::new(self.metric_app_contexts.clone());
+
+        let app_context = app_context_classifier.get_app_context(&msg).await?;
+        let wallet_pool = &self.wallet_pools[&destination];
+        let wallet_lease = wallet_pool.assign(&msg);
+        let wallet = wallet_lease.wallet();
+        let ctx = self.destination_ctxs[&destination][wallet].clone();
+        ctx.lifecycle_events
+            .publish(&msg, MessageLifecycleEventKind::Indexed);
+        let pending_msg =
+            PendingMessage::from_persisted_retries(msg, ctx, app_context, Some(wallet_lease));
+        wallet_pool.send(wallet, Box::new(pending_msg) as QueueOperation)?;
+        Ok(())
+    }
+
     fn try_get_unprocessed_message(&mut self) -> Result<Option<HyperlaneMessage>> {
         loop {
+            // Delivered messages may have had their bodies pruned from the db, so
+            // skip over processed nonces before looking for the message itself.
+            if self.db.is_message_pruned(self.message_nonce)? {
+                trace!(nonce=?self.message_nonce, "Message already processed and pruned from DB");
+                self.message_nonce += 1;
+                continue;
+            }
             // First, see if we can find the message so we can update the gauge.
             if let Some(message) = self.db.retrieve_message_by_nonce(self.message_nonce)? {
                 // Update the latest nonce gauges
@@ -183,7 +252,7 @@ impl MessageProcessorMetrics {
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use std::time::Instant;
 
     use crate::{
@@ -191,6 +260,7 @@ mod test {
         msg::{
             gas_payment::GasPaymentEnforcer,
             metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
+            wallet_pool::PoolWallet,
         },
         processor::Processor,
     };
@@ -209,6 +279,7 @@ mod test {
         },
         time::sleep,
     };
+    use tokio_util::sync::CancellationToken;
 
     fn dummy_processor_metrics(domain_id: u32) -> MessageProcessorMetrics {
         MessageProcessorMetrics {
@@ -224,7 +295,7 @@ mod test {
         }
     }
 
-    fn dummy_submission_metrics() -> MessageSubmissionMetrics {
+    pub(crate) fn dummy_submission_metrics() -> MessageSubmissionMetrics {
         MessageSubmissionMetrics {
             last_known_nonce: IntGauge::new("last_known_nonce_gauge", "help string").unwrap(),
             messages_processed: IntCounter::new("message_processed_gauge", "help string").unwrap(),
@@ -235,7 +306,10 @@ mod test {
         ChainConf {
             domain: domain.clone(),
             signer: Default::default(),
+            additional_signers: Default::default(),
+            min_signer_balance: Default::default(),
             reorg_period: Default::default(),
+            delivery_finality: Default::default(),
             addresses: Default::default(),
             connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf {
                 rpc_connection: hyperlane_ethereum::RpcConnectionConf::Http {
@@ -243,13 +317,14 @@ mod test {
                 },
                 transaction_overrides: Default::default(),
                 operation_batch: Default::default(),
+                rate_limiters: Default::default(),
             }),
             metrics_conf: Default::default(),
             index: Default::default(),
         }
     }
 
-    fn dummy_metadata_builder(
+    pub(crate) fn dummy_metadata_builder(
         origin_domain: &HyperlaneDomain,
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
@@ -290,7 +365,10 @@ mod test {
             metadata_builder: Arc::new(base_metadata_builder),
             origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
             transaction_gas_limit: Default::default(),
+            delivery_finality: Default::default(),
             metrics: dummy_submission_metrics(),
+            lifecycle_events: Default::default(),
+            priority_lanes: Default::default(),
         });
 
         let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
@@ -300,9 +378,13 @@ mod test {
                 Default::default(),
                 Default::default(),
                 dummy_processor_metrics(origin_domain.id()),
-                HashMap::from([(destination_domain.id(), send_channel)]),
-                HashMap::from([(destination_domain.id(), message_context)]),
+                HashMap::from([(
+                    destination_domain.id(),
+                    WalletPool::new(vec![PoolWallet::new(send_channel, Default::default())]),
+                )]),
+                HashMap::from([(destination_domain.id(), vec![message_context])]),
                 vec![],
+                mpsc::unbounded_channel().1,
             ),
             receive_channel,
         )
@@ -329,7 +411,7 @@ mod test {
         }
     }
 
-    fn dummy_domain(domain_id: u32, name: &str) -> HyperlaneDomain {
+    pub(crate) fn dummy_domain(domain_id: u32, name: &str) -> HyperlaneDomain {
         let test_domain = HyperlaneDomain::new_test_domain(name);
         HyperlaneDomain::Unknown {
             domain_id,
@@ -367,7 +449,7 @@ mod test {
         let (message_processor, mut receive_channel) =
             dummy_message_processor(origin_domain, destination_domain, db);
 
-        let processor = Processor::new(Box::new(message_processor));
+        let processor = Processor::new(Box::new(message_processor), CancellationToken::new());
         let process_fut = processor.spawn();
         let mut pending_messages = vec![];
         let pending_message_accumulator = async {
@@ -440,4 +522,86 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_rewinds_to_rolled_back_messages() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            persist_retried_messages(&[0, 0], &db, &destination_domain);
+
+            let (mut message_processor, mut receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            message_processor.tick().await.unwrap();
+            message_processor.tick().await.unwrap();
+            assert_eq!(message_processor.message_nonce, 2);
+            receive_channel.recv().await.unwrap();
+            receive_channel.recv().await.unwrap();
+
+            // The second message is reorged out, and a different one is dispatched in its place
+            db.rollback_messages(1..=1).unwrap();
+            let reorged_in_message = HyperlaneMessage {
+                body: vec![1],
+                ..dummy_hyperlane_message(&destination_domain, 1)
+            };
+            add_db_entry(&db, &reorged_in_message, 0);
+
+            message_processor.tick().await.unwrap();
+            assert_eq!(message_processor.message_nonce, 2);
+            let operation = receive_channel.recv().await.unwrap();
+            assert_eq!(operation.id(), reorged_in_message.id());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_forgets_manual_relays_once_scanned() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            persist_retried_messages(&[0, 0], &db, &destination_domain);
+
+            let (mut message_processor, mut receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            let (manual_relays, manual_relays_receiver) = mpsc::unbounded_channel();
+            message_processor.manual_relays = manual_relays_receiver;
+            message_processor.tick().await.unwrap();
+            message_processor.tick().await.unwrap();
+            receive_channel.recv().await.unwrap();
+            receive_channel.recv().await.unwrap();
+
+            // A message the scan already passed, and one it hasn't reached yet
+            let scanned_message = dummy_hyperlane_message(&destination_domain, 0);
+            let unscanned_message = dummy_hyperlane_message(&destination_domain, 3);
+            manual_relays.send(scanned_message.clone()).unwrap();
+            manual_relays.send(unscanned_message.clone()).unwrap();
+            message_processor.tick().await.unwrap();
+            assert_eq!(
+                receive_channel.recv().await.unwrap().id(),
+                scanned_message.id()
+            );
+            assert_eq!(
+                receive_channel.recv().await.unwrap().id(),
+                unscanned_message.id()
+            );
+            assert_eq!(
+                message_processor.manually_relayed,
+                HashMap::from([(unscanned_message.id(), 3)])
+            );
+
+            // The manually relayed message is delivered before the scan reaches it,
+            // so the scan skips over it
+            add_db_entry(&db, &dummy_hyperlane_message(&destination_domain, 2), 0);
+            add_db_entry(&db, &unscanned_message, 0);
+            db.store_processed_by_nonce(&3, &true).unwrap();
+            message_processor.tick().await.unwrap();
+            message_processor.tick().await.unwrap();
+            message_processor.tick().await.unwrap();
+            assert_eq!(message_processor.message_nonce, 4);
+            assert!(message_processor.manually_relayed.is_empty());
+        })
+        .await;
+    }
 }
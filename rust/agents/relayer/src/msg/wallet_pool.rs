@@ -0,0 +1,436 @@
+//! Pools of wallets which deliver to the same destination in parallel. Each
+//! wallet has its own `SerialSubmitter`, and so its own nonce.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ethers::utils::keccak256;
+use eyre::Result;
+use hyperlane_base::CoreMetrics;
+use hyperlane_core::{
+    metrics::agent::u256_as_scaled_f64, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, H256,
+    U256,
+};
+use prometheus::{Gauge, GaugeVec, IntGauge, IntGaugeVec};
+use tokio::{
+    sync::{
+        mpsc::{error::SendError, UnboundedSender},
+        Notify,
+    },
+    task::JoinHandle,
+    time::{sleep, MissedTickBehavior},
+};
+use tracing::{info, info_span, instrument::Instrumented, warn, Instrument};
+
+use super::op_queue::QueueOperation;
+
+/// How often the balance of each wallet is checked, besides after each of its
+/// submissions
+const BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the balance of a retired wallet is checked, so that it returns
+/// to its pool soon after being topped up
+const RETIRED_BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A wallet of a pool, which delivers the operations sent to its submitter
+#[derive(Debug, Clone)]
+pub struct PoolWallet {
+    sender: UnboundedSender<QueueOperation>,
+    /// Set while the wallet's balance is below the chain's minimum signer
+    /// balance
+    retired: Arc<AtomicBool>,
+}
+
+impl PoolWallet {
+    pub fn new(sender: UnboundedSender<QueueOperation>, retired: Arc<AtomicBool>) -> Self {
+        Self { sender, retired }
+    }
+
+    fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Relaxed)
+    }
+}
+
+/// An app, as the sender and recipient of its messages
+type App = (H256, H256);
+
+/// The wallet an app's operations are pinned to, and how many of them are
+/// still in that wallet's queues
+type AppPins = HashMap<App, (usize, usize)>;
+
+/// The wallets which deliver to a destination.
+///
+/// Operations are sharded across the wallets by the sender and recipient of
+/// their message, so that all messages of an app are delivered by the same
+/// wallet, in order. Retired wallets get no new apps: an app stays pinned to
+/// its wallet while it has operations in that wallet's queues, so that its
+/// messages stay in order, and only then moves to the remaining wallets until
+/// its wallet is topped up. The apps of other wallets stay where they are.
+#[derive(Debug, Clone)]
+pub struct WalletPool {
+    wallets: Vec<PoolWallet>,
+    pins: Arc<Mutex<AppPins>>,
+}
+
+/// Pins the app of an operation to the wallet it was sent to for as long as
+/// the operation is alive, i.e. until it's confirmed or dropped.
+pub struct WalletLease {
+    wallet: usize,
+    app: App,
+    pins: Arc<Mutex<AppPins>>,
+}
+
+impl Debug for WalletLease {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WalletLease {{ wallet: {} }}", self.wallet)
+    }
+}
+
+impl WalletLease {
+    /// The index of the wallet the operation was sent to
+    pub fn wallet(&self) -> usize {
+        self.wallet
+    }
+}
+
+impl Drop for WalletLease {
+    fn drop(&mut self) {
+        let mut pins = self.pins.lock().expect("wallet pins lock poisoned");
+        if let Some((_, in_flight)) = pins.get_mut(&self.app) {
+            *in_flight -= 1;
+            if *in_flight == 0 {
+                pins.remove(&self.app);
+            }
+        }
+    }
+}
+
+impl WalletPool {
+    pub fn new(wallets: Vec<PoolWallet>) -> Self {
+        debug_assert!(!wallets.is_empty(), "Wallet pools can't be empty");
+        Self {
+            wallets,
+            pins: Default::default(),
+        }
+    }
+
+    /// Assign `message` to the wallet which should deliver it. The message's
+    /// operation must hold on to the lease until it leaves the queues.
+    ///
+    /// Messages of an app with operations still in flight go to the same
+    /// wallet, even if it was retired since; otherwise the wallet is picked
+    /// by `select`.
+    pub fn assign(&self, message: &HyperlaneMessage) -> WalletLease {
+        let app = (message.sender, message.recipient);
+        let mut pins = self.pins.lock().expect("wallet pins lock poisoned");
+        let (wallet, in_flight) = pins.entry(app).or_insert_with(|| (self.select(message), 0));
+        *in_flight += 1;
+        WalletLease {
+            wallet: *wallet,
+            app,
+            pins: self.pins.clone(),
+        }
+    }
+
+    /// The index of the wallet which should deliver `message`.
+    ///
+    /// Uses rendezvous hashing: each wallet has a score for the message's
+    /// app, and the active wallet with the highest score is picked. If every
+    /// wallet is retired, all of them are considered rather than halting
+    /// delivery.
+    fn select(&self, message: &HyperlaneMessage) -> usize {
+        let active = (0..self.wallets.len())
+            .filter(|&index| !self.wallets[index].is_retired())
+            .collect::<Vec<_>>();
+        let candidates = if active.is_empty() {
+            (0..self.wallets.len()).collect()
+        } else {
+            active
+        };
+        candidates
+            .into_iter()
+            .max_by_key(|&index| app_score(message, index))
+            .unwrap_or_default()
+    }
+
+    /// Send `op` to the submitter of the wallet at `index`
+    pub fn send(&self, index: usize, op: QueueOperation) -> Result<(), SendError<QueueOperation>> {
+        self.wallets[index].sender.send(op)
+    }
+}
+
+/// The score of a wallet for the app of `message`. Uses keccak rather than
+/// std's hasher, whose output may change between Rust versions, so that apps
+/// keep their wallets across relayer upgrades.
+fn app_score(message: &HyperlaneMessage, wallet_index: usize) -> u64 {
+    let mut preimage = Vec::with_capacity(72);
+    preimage.extend_from_slice(message.sender.as_bytes());
+    preimage.extend_from_slice(message.recipient.as_bytes());
+    preimage.extend_from_slice(&(wallet_index as u64).to_be_bytes());
+    let hash = keccak256(preimage);
+    u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes"))
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletPoolMetricVecs {
+    balance: GaugeVec,
+    retired: IntGaugeVec,
+}
+
+impl WalletPoolMetricVecs {
+    pub fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            balance: metrics.new_gauge(
+                "submitter_wallet_balance",
+                "Native token balance of each wallet submitting to a destination",
+                &["chain", "wallet_address"],
+            )?,
+            retired: metrics.new_int_gauge(
+                "submitter_wallet_retired",
+                "Whether a wallet is retired from its pool because its balance is too low",
+                &["chain", "wallet_address"],
+            )?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct WalletMetrics {
+    balance: Gauge,
+    retired: IntGauge,
+}
+
+/// The balance state of a wallet, shared by its monitor, pool and submitter
+#[derive(Debug, Clone, Default)]
+pub struct WalletBalance {
+    /// Set while the wallet's balance is below the chain's minimum signer
+    /// balance
+    pub retired: Arc<AtomicBool>,
+    /// Notified by the wallet's submitter after each submission, which is
+    /// when the balance drops
+    pub spent: Arc<Notify>,
+}
+
+/// Tracks the balance of a wallet of a pool, and retires the wallet while its
+/// balance is below `min_balance`.
+///
+/// The balance is checked right after each submission of the wallet, so it's
+/// retired as soon as it runs low, and frequently while retired, so it returns
+/// soon after being topped up.
+pub struct WalletBalanceMonitor {
+    domain: HyperlaneDomain,
+    address: String,
+    provider: Box<dyn HyperlaneProvider>,
+    min_balance: Option<U256>,
+    balance: WalletBalance,
+    metrics: WalletMetrics,
+}
+
+impl Debug for WalletBalanceMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "WalletBalanceMonitor {{ domain: {}, address: {}, min_balance: {:?} }}",
+            self.domain, self.address, self.min_balance
+        )
+    }
+}
+
+impl WalletBalanceMonitor {
+    pub fn new(
+        domain: HyperlaneDomain,
+        address: String,
+        provider: Box<dyn HyperlaneProvider>,
+        min_balance: Option<U256>,
+        balance: WalletBalance,
+        metric_vecs: &WalletPoolMetricVecs,
+    ) -> Self {
+        let labels = [domain.name(), address.as_str()];
+        let metrics = WalletMetrics {
+            balance: metric_vecs.balance.with_label_values(&labels),
+            retired: metric_vecs.retired.with_label_values(&labels),
+        };
+        Self {
+            domain,
+            address,
+            provider,
+            min_balance,
+            balance,
+            metrics,
+        }
+    }
+
+    async fn check_balance(&self) {
+        let balance = match self.provider.get_balance(self.address.clone()).await {
+            Ok(balance) => balance,
+            Err(err) => {
+                warn!(?err, address = %self.address, "Failed to get the balance of wallet");
+                return;
+            }
+        };
+        self.metrics
+            .balance
+            .set(u256_as_scaled_f64(balance, self.domain.domain_protocol()));
+
+        let retire = self.min_balance.map_or(false, |min| balance < min);
+        let was_retired = self.balance.retired.swap(retire, Ordering::Relaxed);
+        match (was_retired, retire) {
+            (false, true) => warn!(
+                address = %self.address,
+                %balance,
+                min_balance = ?self.min_balance,
+                "Retiring wallet from its pool because its balance is too low"
+            ),
+            (true, false) => info!(
+                address = %self.address,
+                %balance,
+                "Wallet was topped up, returning it to its pool"
+            ),
+            _ => {}
+        }
+        self.metrics.retired.set(retire as i64);
+    }
+
+    pub fn spawn(self) -> Instrumented<JoinHandle<()>> {
+        let span = info_span!("WalletBalanceMonitor", domain=%self.domain, address=%self.address);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BALANCE_CHECK_INTERVAL);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                let retired = self.balance.retired.load(Ordering::Relaxed);
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = self.balance.spent.notified() => {},
+                    _ = sleep(RETIRED_BALANCE_CHECK_INTERVAL), if retired => {},
+                }
+                self.check_balance().await;
+            }
+        })
+        .instrument(span)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H256;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn dummy_pool(size: usize) -> (WalletPool, Vec<Arc<AtomicBool>>) {
+        let retired = (0..size)
+            .map(|_| Arc::new(AtomicBool::new(false)))
+            .collect::<Vec<_>>();
+        let wallets = retired
+            .iter()
+            .map(|retired| PoolWallet::new(mpsc::unbounded_channel().0, retired.clone()))
+            .collect();
+        (WalletPool::new(wallets), retired)
+    }
+
+    fn dummy_messages() -> Vec<HyperlaneMessage> {
+        (0..50)
+            .map(|_| HyperlaneMessage {
+                sender: H256::random(),
+                recipient: H256::random(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn messages_of_an_app_use_the_same_wallet() {
+        let (pool, _) = dummy_pool(4);
+        for message in dummy_messages() {
+            let other_message = HyperlaneMessage {
+                nonce: message.nonce + 1,
+                body: vec![1, 2, 3],
+                ..message.clone()
+            };
+            assert_eq!(pool.select(&message), pool.select(&other_message));
+        }
+    }
+
+    #[test]
+    fn only_apps_of_retired_wallets_move() {
+        let (pool, retired) = dummy_pool(4);
+        let messages = dummy_messages();
+        let before = messages
+            .iter()
+            .map(|message| pool.select(message))
+            .collect::<Vec<_>>();
+
+        retired[1].store(true, Ordering::Relaxed);
+        for (message, wallet_before) in messages.iter().zip(before) {
+            let wallet_after = pool.select(message);
+            assert_ne!(wallet_after, 1);
+            if wallet_before != 1 {
+                assert_eq!(wallet_after, wallet_before);
+            }
+        }
+    }
+
+    #[test]
+    fn all_wallets_are_used_if_all_are_retired() {
+        let (pool, retired) = dummy_pool(2);
+        let messages = dummy_messages();
+        let before = messages
+            .iter()
+            .map(|message| pool.select(message))
+            .collect::<Vec<_>>();
+
+        retired
+            .iter()
+            .for_each(|r| r.store(true, Ordering::Relaxed));
+        let after = messages
+            .iter()
+            .map(|message| pool.select(message))
+            .collect::<Vec<_>>();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn apps_stay_on_retired_wallets_until_their_operations_leave() {
+        let (pool, retired) = dummy_pool(4);
+        let message = dummy_messages().remove(0);
+        let first = pool.assign(&message);
+        let wallet = first.wallet();
+
+        retired[wallet].store(true, Ordering::Relaxed);
+        let second = pool.assign(&message);
+        assert_eq!(second.wallet(), wallet);
+
+        drop(first);
+        let third = pool.assign(&message);
+        assert_eq!(third.wallet(), wallet);
+
+        drop(second);
+        drop(third);
+        assert_ne!(pool.assign(&message).wallet(), wallet);
+    }
+
+    #[test]
+    fn app_scores_are_stable() {
+        let message = HyperlaneMessage {
+            sender: H256::repeat_byte(1),
+            recipient: H256::repeat_byte(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            (0..3)
+                .map(|index| app_score(&message, index))
+                .collect::<Vec<_>>(),
+            vec![
+                8561896314542550509,
+                14571329847942380095,
+                2114711475226138865
+            ]
+        );
+    }
+}
@@ -1,10 +1,11 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use async_trait::async_trait;
 use derive_new::new;
 use eyre::Result;
 use hyperlane_core::HyperlaneDomain;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{instrument, warn};
 
 #[async_trait]
@@ -13,13 +14,22 @@ pub trait ProcessorExt: Send + Debug {
     fn domain(&self) -> &HyperlaneDomain;
 
     /// One round of processing, extracted from infinite work loop for
-    /// testing purposes.
+    /// testing purposes. A tick always runs to completion; cancellation is
+    /// only checked between ticks.
     async fn tick(&mut self) -> Result<()>;
+
+    /// How long to wait after a successful tick before the next one. Unlike a
+    /// tick, the wait is cut short by cancellation.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[derive(new)]
 pub struct Processor {
     ticker: Box<dyn ProcessorExt>,
+    /// Stops the processor after the current tick once cancelled
+    cancel: CancellationToken,
 }
 
 impl Processor {
@@ -29,11 +39,74 @@ impl Processor {
 
     #[instrument(ret, skip(self), level = "info", fields(domain=%self.ticker.domain()))]
     async fn main_loop(mut self) {
-        loop {
-            if let Err(err) = self.ticker.tick().await {
-                warn!(error=%err, "Error in processor tick");
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        while !self.cancel.is_cancelled() {
+            let wait = match self.ticker.tick().await {
+                Ok(()) => self.ticker.interval(),
+                Err(err) => {
+                    warn!(error=%err, "Error in processor tick");
+                    Some(Duration::from_secs(5))
+                }
+            };
+            if let Some(wait) = wait {
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = self.cancel.cancelled() => {}
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct HourlyTicker {
+        domain: HyperlaneDomain,
+        ticks: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl ProcessorExt for HourlyTicker {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.domain
+        }
+
+        async fn tick(&mut self) -> Result<()> {
+            self.ticks.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn interval(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60 * 60))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_cuts_the_interval_short() {
+        let ticks = Arc::new(AtomicU32::new(0));
+        let cancel = CancellationToken::new();
+        let ticker = HourlyTicker {
+            domain: HyperlaneDomain::new_test_domain("test_cancellation_cuts_the_interval_short"),
+            ticks: ticks.clone(),
+        };
+        let handle = Processor::new(Box::new(ticker), cancel.clone()).spawn();
+
+        while ticks.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        cancel.cancel();
+
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("processor didn't stop while waiting for its next tick")
+            .unwrap();
+        assert_eq!(ticks.load(Ordering::SeqCst), 1);
+    }
+}
@@ -2,43 +2,58 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Formatter},
     sync::Arc,
+    time::Duration,
 };
 
 use async_trait::async_trait;
 use derive_more::AsRef;
-use eyre::Result;
-use futures_util::future::try_join_all;
+use eyre::{ensure, Result};
 use hyperlane_base::{
-    db::{HyperlaneRocksDB, DB},
+    db::{DbBackuper, HyperlaneRocksDB, DB},
+    join_agent_tasks_and_flush_db,
     metrics::{AgentMetrics, MetricsUpdater},
+    server::{DbHealthCheck, HealthCheck},
     settings::ChainConf,
     BaseAgent, ChainMetrics, ContractSyncMetrics, ContractSyncer, CoreMetrics, HyperlaneAgentCore,
 };
 use hyperlane_core::{
-    HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion, MpmcChannel,
-    MpmcReceiver, U256,
+    HyperlaneChain, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, InterchainGasPayment,
+    MerkleTreeInsertion, MpmcChannel, MpmcReceiver, U256,
 };
 use tokio::{
     sync::{
-        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        broadcast::Sender,
+        mpsc::{self, UnboundedReceiver},
         RwLock,
     },
     task::JoinHandle,
 };
-use tracing::{info, info_span, instrument::Instrumented, warn, Instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, info_span, instrument::Instrumented, Instrument};
 
 use crate::{
+    db_pruner::{DbPruner, DbPrunerMetricVecs, DbPrunerMetrics},
+    lifecycle::{JsonLinesFileSink, LifecycleEventSink, MessageLifecycleEvents, WebhookSink},
+    manual_relay::{ManualRelayApi, ManualRelayAuditLog, ManualRelayOrigin},
     merkle_tree::builder::MerkleTreeBuilder,
     msg::{
         gas_payment::GasPaymentEnforcer,
         metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
-        op_queue::QueueOperation,
-        op_submitter::{SerialSubmitter, SerialSubmitterMetrics},
+        op_queue::{OperationStates, QueueOperation},
+        op_submitter::{
+            SerialSubmitter, SerialSubmitterMetrics, SubmitterHandle, SubmitterHealthCheck,
+        },
         pending_message::{MessageContext, MessageSubmissionMetrics},
+        priority_lanes::PriorityLanes,
         processor::{MessageProcessor, MessageProcessorMetrics},
+        wallet_pool::{
+            PoolWallet, WalletBalance, WalletBalanceMonitor, WalletPool, WalletPoolMetricVecs,
+        },
     },
     server::{self as relayer_server, MessageRetryRequest},
-    settings::{matching_list::MatchingList, RelayerSettings},
+    settings::{
+        matching_list::MatchingList, LifecycleEventsApiConf, ManualRelayConf, RelayerSettings,
+    },
 };
 use crate::{
     merkle_tree::processor::{MerkleTreeProcessor, MerkleTreeProcessorMetrics},
@@ -63,11 +78,31 @@ pub struct Relayer {
     interchain_gas_payment_syncs:
         HashMap<HyperlaneDomain, Arc<dyn ContractSyncer<InterchainGasPayment>>>,
     /// Context data for each (origin, destination) chain pair a message can be
-    /// sent between
-    msg_ctxs: HashMap<ContextKey, Arc<MessageContext>>,
+    /// sent between, one for each wallet of the destination's pool
+    msg_ctxs: HashMap<ContextKey, Vec<Arc<MessageContext>>>,
+    /// The balance state of each wallet of each destination's pool. The
+    /// submitter of a retired wallet pauses until it is topped up
+    wallet_balances: HashMap<HyperlaneDomain, Vec<WalletBalance>>,
+    wallet_balance_monitors: Vec<WalletBalanceMonitor>,
     prover_syncs: HashMap<HyperlaneDomain, Arc<RwLock<MerkleTreeBuilder>>>,
     merkle_tree_hook_syncs: HashMap<HyperlaneDomain, Arc<dyn ContractSyncer<MerkleTreeInsertion>>>,
+    /// A provider of each origin and destination chain, taken from contracts
+    /// the relayer already has on the chain
+    chain_providers: HashMap<HyperlaneDomain, Box<dyn HyperlaneProvider>>,
+    db: DB,
     dbs: HashMap<HyperlaneDomain, HyperlaneRocksDB>,
+    /// How long the data of delivered messages is kept for, and the metrics of
+    /// the pruners that delete it afterwards
+    db_pruning: Option<(Duration, DbPrunerMetricVecs)>,
+    db_backuper: Option<Arc<DbBackuper>>,
+    lifecycle_events: MessageLifecycleEvents,
+    /// Live state of the operations in the submitters' queues
+    op_states: OperationStates,
+    priority_lanes: Arc<PriorityLanes>,
+    lifecycle_event_sinks: Vec<Box<dyn LifecycleEventSink>>,
+    gas_payment_enforcers: HashMap<HyperlaneDomain, Arc<GasPaymentEnforcer>>,
+    lifecycle_events_api: Option<LifecycleEventsApiConf>,
+    manual_relay: Option<ManualRelayConf>,
     whitelist: Arc<MatchingList>,
     blacklist: Arc<MatchingList>,
     transaction_gas_limit: Option<U256>,
@@ -75,6 +110,7 @@ pub struct Relayer {
     allow_local_checkpoint_syncers: bool,
     metric_app_contexts: Vec<(MatchingList, String)>,
     core_metrics: Arc<CoreMetrics>,
+    contract_sync_metrics: Arc<ContractSyncMetrics>,
     // TODO: decide whether to consolidate `agent_metrics` and `chain_metrics` into a single struct
     // or move them in `core_metrics`, like the validator metrics
     agent_metrics: AgentMetrics,
@@ -120,6 +156,13 @@ impl BaseAgent for Relayer {
             .iter()
             .map(|origin| (origin.clone(), HyperlaneRocksDB::new(origin, db.clone())))
             .collect::<HashMap<_, _>>();
+        let db_backuper = match &settings.db_backup {
+            Some(conf) => Some(Arc::new(
+                conf.build(db.clone(), settings.origin_chains.iter().cloned().collect())
+                    .await?,
+            )),
+            None => None,
+        };
 
         let mailboxes = settings
             .build_mailboxes(settings.destination_chains.iter(), &core_metrics)
@@ -127,6 +170,72 @@ impl BaseAgent for Relayer {
         let validator_announces = settings
             .build_validator_announces(settings.origin_chains.iter(), &core_metrics)
             .await?;
+        let chain_providers = mailboxes
+            .iter()
+            .map(|(domain, mailbox)| (domain.clone(), mailbox.provider()))
+            .chain(
+                validator_announces
+                    .iter()
+                    .map(|(domain, announce)| (domain.clone(), announce.provider())),
+            )
+            .collect::<HashMap<_, _>>();
+
+        // The mailboxes of the wallets of each destination's pool, starting with
+        // the chain's main signer
+        let wallet_pool_metrics = WalletPoolMetricVecs::new(&core_metrics)?;
+        let db_pruning = settings
+            .delivered_message_retention
+            .map(|retention| {
+                DbPrunerMetricVecs::new(&core_metrics).map(|metric_vecs| (retention, metric_vecs))
+            })
+            .transpose()?;
+        let mut wallet_mailboxes = HashMap::new();
+        let mut wallet_balances = HashMap::new();
+        let mut wallet_balance_monitors = vec![];
+        for destination in &settings.destination_chains {
+            let chain_conf = settings.chain_setup(destination)?;
+            let mut wallet_confs = vec![chain_conf.clone()];
+            wallet_confs.extend(
+                chain_conf
+                    .additional_signers
+                    .iter()
+                    .map(|signer| chain_conf.with_signer(signer.clone())),
+            );
+
+            let mut destination_mailboxes = vec![mailboxes[destination].clone()];
+            let mut destination_balances = vec![];
+            let mut wallet_addresses = HashSet::new();
+            for (index, wallet_conf) in wallet_confs.iter().enumerate() {
+                let signer = wallet_conf.chain_signer().await?;
+                if let Some(signer) = &signer {
+                    // Two wallets of a pool sharing a signer would race each
+                    // other for its nonces
+                    ensure!(
+                        wallet_addresses.insert(signer.address_string()),
+                        "Additional signer {} of {destination} duplicates another of its signers",
+                        signer.address_string(),
+                    );
+                }
+                if index > 0 {
+                    destination_mailboxes
+                        .push(wallet_conf.build_mailbox(&core_metrics).await?.into());
+                }
+                let balance = WalletBalance::default();
+                if let Some(signer) = signer {
+                    wallet_balance_monitors.push(WalletBalanceMonitor::new(
+                        destination.clone(),
+                        signer.address_string(),
+                        wallet_conf.build_provider(&core_metrics).await?,
+                        chain_conf.min_signer_balance,
+                        balance.clone(),
+                        &wallet_pool_metrics,
+                    ));
+                }
+                destination_balances.push(balance);
+            }
+            wallet_mailboxes.insert(destination.clone(), destination_mailboxes);
+            wallet_balances.insert(destination.clone(), destination_balances);
+        }
 
         let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&core_metrics));
 
@@ -215,6 +324,21 @@ impl BaseAgent for Relayer {
             })
             .collect();
 
+        let lifecycle_events = MessageLifecycleEvents::with_metrics(&core_metrics)?;
+        let mut lifecycle_event_sinks: Vec<Box<dyn LifecycleEventSink>> = vec![];
+        if let Some(path) = &settings.lifecycle_events_file {
+            lifecycle_event_sinks.push(Box::new(JsonLinesFileSink::open(path).await?));
+        }
+        for webhook in &settings.lifecycle_event_webhooks {
+            lifecycle_event_sinks.push(Box::new(WebhookSink::new(
+                webhook.url.clone(),
+                webhook.matching_list.clone(),
+                lifecycle_events.dropped_events("webhook"),
+            )?));
+        }
+
+        let priority_lanes = Arc::new(PriorityLanes::new(settings.priority_lanes.clone()));
+
         let mut msg_ctxs = HashMap::new();
         let mut destination_chains = HashMap::new();
         for destination in &settings.destination_chains {
@@ -229,7 +353,7 @@ impl BaseAgent for Relayer {
 
             for origin in &settings.origin_chains {
                 let db = dbs.get(origin).unwrap().clone();
-                let metadata_builder = BaseMetadataBuilder::new(
+                let metadata_builder = Arc::new(BaseMetadataBuilder::new(
                     origin.clone(),
                     destination_chain_setup.clone(),
                     prover_syncs[origin].clone(),
@@ -242,35 +366,58 @@ impl BaseAgent for Relayer {
                         mailboxes[destination].clone(),
                         settings.metric_app_contexts.clone(),
                     ),
-                );
-
+                ));
+                let metrics = MessageSubmissionMetrics::new(&core_metrics, origin, destination);
+
+                let wallet_ctxs = wallet_mailboxes[destination]
+                    .iter()
+                    .map(|mailbox| {
+                        Arc::new(MessageContext {
+                            destination_mailbox: mailbox.clone(),
+                            origin_db: dbs.get(origin).unwrap().clone(),
+                            metadata_builder: metadata_builder.clone(),
+                            origin_gas_payment_enforcer: gas_payment_enforcers[origin].clone(),
+                            transaction_gas_limit,
+                            delivery_finality: destination_chain_setup.delivery_finality,
+                            metrics: metrics.clone(),
+                            lifecycle_events: lifecycle_events.clone(),
+                            priority_lanes: priority_lanes.clone(),
+                        })
+                    })
+                    .collect();
                 msg_ctxs.insert(
                     ContextKey {
                         origin: origin.id(),
                         destination: destination.id(),
                     },
-                    Arc::new(MessageContext {
-                        destination_mailbox: mailboxes[destination].clone(),
-                        origin_db: dbs.get(origin).unwrap().clone(),
-                        metadata_builder: Arc::new(metadata_builder),
-                        origin_gas_payment_enforcer: gas_payment_enforcers[origin].clone(),
-                        transaction_gas_limit,
-                        metrics: MessageSubmissionMetrics::new(&core_metrics, origin, destination),
-                    }),
+                    wallet_ctxs,
                 );
             }
         }
 
         Ok(Self {
+            db,
             dbs,
+            db_pruning,
+            db_backuper,
+            lifecycle_events,
+            op_states: OperationStates::default(),
+            priority_lanes,
+            lifecycle_event_sinks,
+            gas_payment_enforcers,
+            lifecycle_events_api: settings.lifecycle_events_api,
+            manual_relay: settings.manual_relay,
             origin_chains: settings.origin_chains,
             destination_chains,
             msg_ctxs,
+            wallet_balances,
+            wallet_balance_monitors,
             core,
             message_syncs,
             interchain_gas_payment_syncs,
             prover_syncs,
             merkle_tree_hook_syncs,
+            chain_providers,
             whitelist,
             blacklist,
             transaction_gas_limit,
@@ -278,48 +425,85 @@ impl BaseAgent for Relayer {
             allow_local_checkpoint_syncers: settings.allow_local_checkpoint_syncers,
             metric_app_contexts: settings.metric_app_contexts,
             core_metrics,
+            contract_sync_metrics,
             agent_metrics,
             chain_metrics,
         })
     }
 
     #[allow(clippy::async_yields_async)]
-    async fn run(self) {
+    async fn run(mut self, cancel: CancellationToken) {
         let mut tasks = vec![];
+        // Tasks which run until the relayer has finished its work in flight
+        let mut background_tasks = vec![];
 
         // run server
         let mpmc_channel = MpmcChannel::<MessageRetryRequest>::new(ENDPOINT_MESSAGES_QUEUE_SIZE);
-        let custom_routes = relayer_server::routes(mpmc_channel.sender());
+        let mut custom_routes = relayer_server::routes(
+            mpmc_channel.sender(),
+            self.dbs.values().cloned().collect(),
+            self.op_states.clone(),
+        );
+        if let Some(db_backuper) = &self.db_backuper {
+            custom_routes.extend(db_backuper.clone().get_route());
+            background_tasks.extend(db_backuper.clone().spawn_scheduled());
+        }
+        if let Some(conf) = &self.lifecycle_events_api {
+            custom_routes.push(self.lifecycle_events.get_route(conf.token.clone()));
+        }
+        for sink in std::mem::take(&mut self.lifecycle_event_sinks) {
+            background_tasks.push(self.lifecycle_events.spawn_sink(sink));
+        }
+        // manual relays of each origin chain, which its message processor receives
+        let mut manual_relay_receivers = HashMap::new();
+        if let Some(conf) = &self.manual_relay {
+            let (manual_relay_api, receivers) = self
+                .build_manual_relay_api(conf, mpmc_channel.sender())
+                .await
+                .expect("Failed to build manual relay api");
+            custom_routes.push(manual_relay_api.get_route());
+            manual_relay_receivers = receivers;
+        }
 
-        let server = self
-            .core
-            .settings
-            .server(self.core_metrics.clone())
-            .expect("Failed to create server");
-        let server_task = server
-            .run_with_custom_routes(custom_routes)
-            .instrument(info_span!("Relayer server"));
-        tasks.push(server_task);
+        for monitor in std::mem::take(&mut self.wallet_balance_monitors) {
+            background_tasks.push(monitor.spawn());
+        }
 
-        // send channels by destination chain
-        let mut send_channels = HashMap::with_capacity(self.destination_chains.len());
+        // wallet pools by destination chain, with a submitter for each wallet
+        let mut wallet_pools = HashMap::with_capacity(self.destination_chains.len());
+        let mut submitters = HashMap::with_capacity(self.destination_chains.len());
         for (dest_domain, dest_conf) in &self.destination_chains {
-            let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
-            send_channels.insert(dest_domain.id(), send_channel);
-
-            tasks.push(
-                self.run_destination_submitter(
-                    dest_domain,
-                    receive_channel,
-                    mpmc_channel.receiver(),
-                    // Default to submitting one message at a time if there is no batch config
-                    self.core.settings.chains[dest_domain.name()]
-                        .connection
-                        .operation_batch_config()
-                        .map(|c| c.max_batch_size)
-                        .unwrap_or(1),
-                ),
-            );
+            let mut wallets = vec![];
+            let mut wallet_submitters = vec![];
+            for (wallet, balance) in self.wallet_balances[dest_domain].iter().enumerate() {
+                let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
+                wallets.push(PoolWallet::new(send_channel, balance.retired.clone()));
+                let stopped = CancellationToken::new();
+                wallet_submitters.push(SubmitterHandle::new(
+                    stopped.clone(),
+                    balance.retired.clone(),
+                ));
+
+                tasks.push(
+                    self.run_destination_submitter(
+                        dest_domain,
+                        wallet,
+                        receive_channel,
+                        mpmc_channel.receiver(),
+                        balance.clone(),
+                        cancel.clone(),
+                        stopped,
+                        // Default to submitting one message at a time if there is no batch config
+                        self.core.settings.chains[dest_domain.name()]
+                            .connection
+                            .operation_batch_config()
+                            .map(|c| c.max_batch_size)
+                            .unwrap_or(1),
+                    ),
+                );
+            }
+            wallet_pools.insert(dest_domain.id(), WalletPool::new(wallets));
+            submitters.insert(dest_domain.clone(), wallet_submitters);
 
             let metrics_updater = MetricsUpdater::new(
                 dest_conf,
@@ -330,22 +514,58 @@ impl BaseAgent for Relayer {
             )
             .await
             .unwrap();
-            tasks.push(metrics_updater.spawn());
+            background_tasks.push(metrics_updater.spawn());
         }
 
+        let health_checks = self
+            .build_health_checks(submitters)
+            .expect("Failed to build health checks");
+        let server = self
+            .core
+            .settings
+            .server(self.core_metrics.clone())
+            .expect("Failed to create server");
+        let server_task = server
+            .run_with_health_checks(custom_routes, health_checks)
+            .instrument(info_span!("Relayer server"));
+        background_tasks.push(server_task);
+
         for origin in &self.origin_chains {
-            tasks.push(self.run_message_sync(origin).await);
-            tasks.push(self.run_interchain_gas_payment_sync(origin).await);
-            tasks.push(self.run_merkle_tree_hook_syncs(origin).await);
+            tasks.push(self.run_message_sync(origin, cancel.clone()).await);
+            tasks.push(
+                self.run_interchain_gas_payment_sync(origin, cancel.clone())
+                    .await,
+            );
+            tasks.push(
+                self.run_merkle_tree_hook_syncs(origin, cancel.clone())
+                    .await,
+            );
         }
 
         // each message process attempts to send messages from a chain
         for origin in &self.origin_chains {
-            tasks.push(self.run_message_processor(origin, send_channels.clone()));
-            tasks.push(self.run_merkle_tree_processor(origin));
+            let manual_relays = manual_relay_receivers
+                .remove(origin)
+                .unwrap_or_else(|| mpsc::unbounded_channel().1);
+            tasks.push(self.run_message_processor(
+                origin,
+                wallet_pools.clone(),
+                manual_relays,
+                cancel.clone(),
+            ));
+            tasks.push(self.run_merkle_tree_processor(origin, cancel.clone()));
+        }
+        // Only the processors hold the wallet pools from now on, so the
+        // submitters stop receiving operations once the processors have stopped
+        drop(wallet_pools);
+
+        if let Some((retention, metric_vecs)) = &self.db_pruning {
+            for origin in &self.origin_chains {
+                tasks.push(self.run_db_pruner(origin, *retention, metric_vecs, cancel.clone()));
+            }
         }
 
-        if let Err(err) = try_join_all(tasks).await {
+        if let Err(err) = join_agent_tasks_and_flush_db(tasks, background_tasks, &self.db).await {
             tracing::error!(
                 error=?err,
                 "Relayer task panicked"
@@ -355,14 +575,88 @@ impl BaseAgent for Relayer {
 }
 
 impl Relayer {
-    async fn run_message_sync(&self, origin: &HyperlaneDomain) -> Instrumented<JoinHandle<()>> {
+    /// Readiness requires the RPCs of all chains to be reachable, the indexers
+    /// of origin chains to be close to the tip, the db to be open and the
+    /// destination submitters to be running.
+    fn build_health_checks(
+        &self,
+        submitters: HashMap<HyperlaneDomain, Vec<SubmitterHandle>>,
+    ) -> Result<Vec<Box<dyn HealthCheck>>> {
+        let mut health_checks: Vec<Box<dyn HealthCheck>> = vec![
+            Box::new(DbHealthCheck::new(self.db.clone())),
+            Box::new(SubmitterHealthCheck::new(submitters)),
+        ];
+        for (chain, provider) in &self.chain_providers {
+            let indexer_labels = if self.origin_chains.contains(chain) {
+                vec!["dispatched_messages", "gas_payments", "merkle_tree_hook"]
+            } else {
+                vec![]
+            };
+            let health_check = self.core.settings.build_chain_health_check(
+                chain,
+                provider.provider(),
+                indexer_labels,
+                &self.contract_sync_metrics,
+            )?;
+            health_checks.push(Box::new(health_check));
+        }
+        Ok(health_checks)
+    }
+
+    /// Builds the manual relay endpoint, and the receivers of the messages it
+    /// relays for the message processor of each origin chain.
+    async fn build_manual_relay_api(
+        &self,
+        conf: &ManualRelayConf,
+        retry_tx: Sender<MessageRetryRequest>,
+    ) -> Result<(
+        ManualRelayApi,
+        HashMap<HyperlaneDomain, UnboundedReceiver<HyperlaneMessage>>,
+    )> {
+        let mut origins = HashMap::with_capacity(self.origin_chains.len());
+        let mut receivers = HashMap::with_capacity(self.origin_chains.len());
+        for origin in &self.origin_chains {
+            let indexer = self
+                .core
+                .settings
+                .chain_setup(origin)?
+                .build_message_indexer(&self.core_metrics)
+                .await?;
+            let (sender, receiver) = mpsc::unbounded_channel();
+            origins.insert(
+                origin.id(),
+                ManualRelayOrigin::new(
+                    self.dbs[origin].clone(),
+                    indexer.into(),
+                    self.gas_payment_enforcers[origin].clone(),
+                    sender,
+                ),
+            );
+            receivers.insert(origin.clone(), receiver);
+        }
+        let api = ManualRelayApi::new(
+            conf.token.clone(),
+            origins,
+            self.destination_chains.keys().map(|d| d.id()).collect(),
+            self.op_states.clone(),
+            retry_tx,
+            ManualRelayAuditLog::open(&conf.audit_log).await?,
+        );
+        Ok((api, receivers))
+    }
+
+    async fn run_message_sync(
+        &self,
+        origin: &HyperlaneDomain,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
         let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
         let contract_sync = self.message_syncs.get(origin).unwrap().clone();
         let cursor = contract_sync.cursor(index_settings).await;
         tokio::spawn(async move {
             contract_sync
                 .clone()
-                .sync("dispatched_messages", cursor)
+                .sync("dispatched_messages", cursor, cancel)
                 .await
         })
         .instrument(info_span!("MessageSync"))
@@ -371,6 +665,7 @@ impl Relayer {
     async fn run_interchain_gas_payment_sync(
         &self,
         origin: &HyperlaneDomain,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
         let contract_sync = self
@@ -379,25 +674,38 @@ impl Relayer {
             .unwrap()
             .clone();
         let cursor = contract_sync.cursor(index_settings).await;
-        tokio::spawn(async move { contract_sync.clone().sync("gas_payments", cursor).await })
-            .instrument(info_span!("IgpSync"))
+        tokio::spawn(async move {
+            contract_sync
+                .clone()
+                .sync("gas_payments", cursor, cancel)
+                .await
+        })
+        .instrument(info_span!("IgpSync"))
     }
 
     async fn run_merkle_tree_hook_syncs(
         &self,
         origin: &HyperlaneDomain,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let index_settings = self.as_ref().settings.chains[origin.name()].index.clone();
         let contract_sync = self.merkle_tree_hook_syncs.get(origin).unwrap().clone();
         let cursor = contract_sync.cursor(index_settings).await;
-        tokio::spawn(async move { contract_sync.clone().sync("merkle_tree_hook", cursor).await })
-            .instrument(info_span!("MerkleTreeHookSync"))
+        tokio::spawn(async move {
+            contract_sync
+                .clone()
+                .sync("merkle_tree_hook", cursor, cancel)
+                .await
+        })
+        .instrument(info_span!("MerkleTreeHookSync"))
     }
 
     fn run_message_processor(
         &self,
         origin: &HyperlaneDomain,
-        send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
+        wallet_pools: HashMap<u32, WalletPool>,
+        manual_relays: UnboundedReceiver<HyperlaneMessage>,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let metrics = MessageProcessorMetrics::new(
             &self.core.metrics,
@@ -425,18 +733,23 @@ impl Relayer {
             self.whitelist.clone(),
             self.blacklist.clone(),
             metrics,
-            send_channels,
+            wallet_pools,
             destination_ctxs,
             self.metric_app_contexts.clone(),
+            manual_relays,
         );
 
         let span = info_span!("MessageProcessor", origin=%message_processor.domain());
-        let processor = Processor::new(Box::new(message_processor));
+        let processor = Processor::new(Box::new(message_processor), cancel);
 
         processor.spawn().instrument(span)
     }
 
-    fn run_merkle_tree_processor(&self, origin: &HyperlaneDomain) -> Instrumented<JoinHandle<()>> {
+    fn run_merkle_tree_processor(
+        &self,
+        origin: &HyperlaneDomain,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
         let metrics = MerkleTreeProcessorMetrics::new();
         let merkle_tree_processor = MerkleTreeProcessor::new(
             self.dbs.get(origin).unwrap().clone(),
@@ -445,34 +758,62 @@ impl Relayer {
         );
 
         let span = info_span!("MerkleTreeProcessor", origin=%merkle_tree_processor.domain());
-        let processor = Processor::new(Box::new(merkle_tree_processor));
+        let processor = Processor::new(Box::new(merkle_tree_processor), cancel);
+        processor.spawn().instrument(span)
+    }
+
+    fn run_db_pruner(
+        &self,
+        origin: &HyperlaneDomain,
+        retention: Duration,
+        metric_vecs: &DbPrunerMetricVecs,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
+        let db_pruner = DbPruner::new(
+            self.dbs.get(origin).unwrap().clone(),
+            retention,
+            DbPrunerMetrics::new(metric_vecs, origin),
+        );
+
+        let span = info_span!("DbPruner", origin=%db_pruner.domain());
+        let processor = Processor::new(Box::new(db_pruner), cancel);
         processor.spawn().instrument(span)
     }
 
     #[allow(clippy::too_many_arguments)]
-    #[tracing::instrument(skip(self, receiver))]
+    #[tracing::instrument(skip(self, receiver, balance, cancel, stopped))]
     fn run_destination_submitter(
         &self,
         destination: &HyperlaneDomain,
+        wallet: usize,
         receiver: UnboundedReceiver<QueueOperation>,
         retry_receiver_channel: MpmcReceiver<MessageRetryRequest>,
+        balance: WalletBalance,
+        cancel: CancellationToken,
+        stopped: CancellationToken,
         batch_size: u32,
     ) -> Instrumented<JoinHandle<()>> {
         let serial_submitter = SerialSubmitter::new(
             destination.clone(),
             receiver,
             retry_receiver_channel,
-            SerialSubmitterMetrics::new(&self.core.metrics, destination),
+            SerialSubmitterMetrics::new(&self.core.metrics, destination, wallet),
             batch_size,
+            self.op_states.clone(),
+            self.priority_lanes.clone(),
+            balance,
+            cancel,
         );
-        let span = info_span!("SerialSubmitter", destination=%destination);
+        let span = info_span!("SerialSubmitter", destination=%destination, wallet);
         let destination = destination.clone();
         tokio::spawn(async move {
+            // Reports the submitter as stopped however it finishes
+            let _stopped = stopped.drop_guard();
             // Propagate task panics
             serial_submitter.spawn().await.unwrap_or_else(|err| {
                 panic!(
-                    "destination submitter panicked for destination {}: {:?}",
-                    destination, err
+                    "destination submitter panicked for destination {} and wallet {}: {:?}",
+                    destination, wallet, err
                 )
             });
         })
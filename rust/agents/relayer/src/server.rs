@@ -1,24 +1,37 @@
 use axum::{
-    extract::{Query, State},
-    routing, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing, Json, Router,
 };
 use derive_new::new;
-use hyperlane_core::{ChainCommunicationError, H256};
+use ethers::utils::hex;
+use hyperlane_base::db::{DbError, HyperlaneRocksDB};
+use hyperlane_core::{ChainCommunicationError, GasPaymentKey, HyperlaneMessage, H256, H512};
 use serde::Deserialize;
-use std::str::FromStr;
+use serde_json::{json, Value};
+use std::{str::FromStr, time::Instant};
 use tokio::sync::broadcast::Sender;
 
-use crate::msg::op_queue::QueueOperation;
+use crate::msg::op_queue::{OperationStates, QueueOperation};
 
 const MESSAGE_RETRY_API_BASE: &str = "/message_retry";
+const MESSAGES_API_BASE: &str = "/messages";
 pub const ENDPOINT_MESSAGES_QUEUE_SIZE: usize = 1_000;
 
 /// Returns a vector of agent-specific endpoint routes to be served.
 /// Can be extended with additional routes and feature flags to enable/disable individually.
-pub fn routes(tx: Sender<MessageRetryRequest>) -> Vec<(&'static str, Router)> {
+pub fn routes(
+    tx: Sender<MessageRetryRequest>,
+    dbs: Vec<HyperlaneRocksDB>,
+    op_states: OperationStates,
+) -> Vec<(&'static str, Router)> {
     let message_retry_api = MessageRetryApi::new(tx);
+    let message_status_api = MessageStatusApi::new(dbs, op_states);
 
-    vec![message_retry_api.get_route()]
+    vec![
+        message_retry_api.get_route(),
+        message_status_api.get_route(),
+    ]
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -104,14 +117,202 @@ impl MessageRetryApi {
     }
 }
 
+/// Looks up the delivery status of messages, combining what the origin
+/// chains' dbs know about them with the live state of the submitters' queues.
+///
+/// Messages are only indexed by their origin transaction when they're first
+/// stored, so messages indexed by a relayer version without that index can't
+/// be looked up by their origin transaction, only by their id.
+#[derive(new, Clone)]
+pub struct MessageStatusApi {
+    /// The dbs of all origin chains
+    dbs: Vec<HyperlaneRocksDB>,
+    op_states: OperationStates,
+}
+
+#[derive(Deserialize)]
+struct MessagesByOriginTxQuery {
+    origin_tx_hash: String,
+}
+
+pub(crate) type StatusResponse = (StatusCode, Json<Value>);
+
+pub(crate) fn error_response(status: StatusCode, error: impl ToString) -> StatusResponse {
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+/// `GET /messages/:id`
+async fn get_message(
+    State(api): State<MessageStatusApi>,
+    Path(id): Path<String>,
+) -> StatusResponse {
+    let id = match H256::from_str(&id) {
+        Ok(id) => id,
+        Err(err) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid message id `{id}`: {err}"),
+            )
+        }
+    };
+    match api.message_status_by_id(id).await {
+        Ok(Some(status)) => (StatusCode::OK, Json(status)),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("No message with id {id:?}")),
+        Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+    }
+}
+
+/// `GET /messages?origin_tx_hash=0x..`
+///
+/// Returns an empty list for messages indexed before the relayer indexed
+/// messages by their origin transaction; those can still be looked up by id.
+async fn get_messages_by_origin_tx(
+    State(api): State<MessageStatusApi>,
+    Query(query): Query<MessagesByOriginTxQuery>,
+) -> StatusResponse {
+    let Some(tx_id) = parse_tx_id(&query.origin_tx_hash) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid transaction hash `{}`", query.origin_tx_hash),
+        );
+    };
+    match api.message_statuses_by_origin_tx(tx_id).await {
+        Ok(statuses) => (StatusCode::OK, Json(Value::Array(statuses))),
+        Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+    }
+}
+
+/// Parses a transaction hash, which is either 32 bytes (e.g. EVM chains) or
+/// 64 bytes (e.g. Sealevel signatures) of hex, or a base58 Sealevel signature
+pub(crate) fn parse_tx_id(hash: &str) -> Option<H512> {
+    if let Ok(bytes) = hex::decode(hash.trim_start_matches("0x")) {
+        return match bytes.len() {
+            32 => Some(H256::from_slice(&bytes).into()),
+            64 => Some(H512::from_slice(&bytes)),
+            _ => None,
+        };
+    }
+    let bytes = bs58::decode(hash).into_vec().ok()?;
+    (bytes.len() == 64).then(|| H512::from_slice(&bytes))
+}
+
+impl MessageStatusApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(get_messages_by_origin_tx))
+            .route("/:id", routing::get(get_message))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (MESSAGES_API_BASE, self.router())
+    }
+
+    async fn message_status_by_id(&self, id: H256) -> Result<Option<Value>, DbError> {
+        for db in &self.dbs {
+            if let Some(message) = db.retrieve_message_by_id(&id)? {
+                return Ok(Some(self.message_status(db, &message).await?));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn message_statuses_by_origin_tx(&self, tx_id: H512) -> Result<Vec<Value>, DbError> {
+        let mut statuses = vec![];
+        for db in &self.dbs {
+            for nonce in db.retrieve_message_nonces_by_dispatch_tx(&tx_id)? {
+                if let Some(message) = db.retrieve_message_by_nonce(nonce)? {
+                    statuses.push(self.message_status(db, &message).await?);
+                }
+            }
+        }
+        Ok(statuses)
+    }
+
+    async fn message_status(
+        &self,
+        db: &HyperlaneRocksDB,
+        message: &HyperlaneMessage,
+    ) -> Result<Value, DbError> {
+        let id = message.id();
+        let nonce = message.nonce;
+        let origin_tx = db.retrieve_dispatch_tx_by_nonce(&nonce)?;
+        let gas_payment = db.retrieve_gas_payment_by_gas_payment_key(GasPaymentKey {
+            message_id: id,
+            destination: message.destination,
+        })?;
+        let gas_expenditure = db.retrieve_gas_expenditure_by_message_id(id)?;
+        let state = self.op_states.get(&id).await;
+        // Delivery transactions are only stored once confirmed, so fall back
+        // to the latest submission of an operation still in the queues
+        let delivery_tx = match db.retrieve_delivery_tx_by_message_id(&id)? {
+            Some(tx_id) => Some(tx_id),
+            None => state
+                .as_ref()
+                .and_then(|state| state.status.submitted_tx_id),
+        };
+
+        Ok(json!({
+            "id": format!("{id:?}"),
+            "nonce": nonce,
+            "origin": message.origin,
+            "sender": format!("{:?}", message.sender),
+            "destination": message.destination,
+            "recipient": format!("{:?}", message.recipient),
+            "originTxHash": origin_tx.map(|tx| format!("{tx:?}")),
+            "dispatchedBlockNumber": db.retrieve_dispatched_block_number_by_nonce(&nonce)?,
+            "processed": db.retrieve_processed_by_nonce(&nonce)?.unwrap_or(false),
+            "processedAt": db.retrieve_processed_at_by_nonce(&nonce)?,
+            "retryCount": db.retrieve_pending_message_retry_count_by_message_id(&id)?,
+            "gasPayment": {
+                "payment": gas_payment.payment.to_string(),
+                "gasAmount": gas_payment.gas_amount.to_string(),
+            },
+            "gasExpenditure": {
+                "tokensUsed": gas_expenditure.tokens_used.to_string(),
+                "gasUsed": gas_expenditure.gas_used.to_string(),
+            },
+            "deliveryTxHash": delivery_tx.map(|tx| format!("{tx:?}")),
+            "queue": state.map(|state| json!({
+                "stage": state.stage.as_str(),
+                "numRetries": state.status.num_retries,
+                "nextAttemptInSecs": state.status.next_attempt_after.map(|next_attempt| {
+                    next_attempt.saturating_duration_since(Instant::now()).as_secs()
+                }),
+                "lastError": state.status.last_error,
+            })),
+        }))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::http::StatusCode;
     use ethers::utils::hex::ToHex;
-    use hyperlane_core::{MpmcChannel, MpmcReceiver};
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneLogStore, Indexed, LogMeta, MpmcChannel, MpmcReceiver,
+    };
     use std::net::SocketAddr;
 
+    #[test]
+    fn test_parse_tx_id() {
+        let hash = H256::random();
+        assert_eq!(parse_tx_id(&format!("{hash:?}")), Some(hash.into()));
+        let signature = H512::random();
+        assert_eq!(parse_tx_id(&format!("{signature:?}")), Some(signature));
+        let base58_signature = bs58::encode(signature.as_bytes()).into_string();
+        assert_eq!(parse_tx_id(&base58_signature), Some(signature));
+
+        // base58 has to be a whole signature
+        assert_eq!(
+            parse_tx_id(&bs58::encode(hash.as_bytes()).into_string()),
+            None
+        );
+        assert_eq!(parse_tx_id("0x1234"), None);
+        assert_eq!(parse_tx_id("not a hash"), None);
+    }
+
     fn setup_test_server() -> (SocketAddr, MpmcReceiver<MessageRetryRequest>) {
         let mpmc_channel = MpmcChannel::<MessageRetryRequest>::new(ENDPOINT_MESSAGES_QUEUE_SIZE);
         let message_retry_api = MessageRetryApi::new(mpmc_channel.sender());
@@ -176,4 +377,82 @@ mod tests {
             MessageRetryRequest::DestinationDomain(destination_domain)
         );
     }
+
+    fn setup_status_test_server(db: HyperlaneRocksDB) -> SocketAddr {
+        let message_status_api = MessageStatusApi::new(vec![db], OperationStates::default());
+        let (path, status_router) = message_status_api.get_route();
+        let app = Router::new().nest(path, status_router);
+
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_message_status() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let message = HyperlaneMessage {
+                nonce: 3,
+                destination: 42,
+                ..Default::default()
+            };
+            // The hash of an EVM transaction is 32 bytes
+            let origin_tx_hash = H256::random();
+            let meta = LogMeta {
+                transaction_id: origin_tx_hash.into(),
+                block_number: 7,
+                ..Default::default()
+            };
+            db.store_logs(&[(Indexed::new(message.clone()), meta)])
+                .await
+                .unwrap();
+            let delivery_tx = H512::random();
+            db.store_delivery_tx_by_message_id(&message.id(), &delivery_tx)
+                .unwrap();
+            let addr = setup_status_test_server(db);
+
+            let response = reqwest::get(format!(
+                "http://{}{}/{:?}",
+                addr,
+                MESSAGES_API_BASE,
+                message.id()
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let status: Value = response.json().await.unwrap();
+            assert_eq!(status["nonce"], json!(3));
+            assert_eq!(status["dispatchedBlockNumber"], json!(7));
+            assert_eq!(status["processed"], json!(false));
+            assert_eq!(status["deliveryTxHash"], json!(format!("{delivery_tx:?}")));
+            assert_eq!(status["queue"], Value::Null);
+
+            let response = reqwest::get(format!(
+                "http://{}{}?origin_tx_hash=0x{}",
+                addr,
+                MESSAGES_API_BASE,
+                origin_tx_hash.encode_hex::<String>()
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let statuses: Value = response.json().await.unwrap();
+            assert_eq!(statuses.as_array().unwrap().len(), 1);
+            assert_eq!(statuses[0]["id"], json!(format!("{:?}", message.id())));
+
+            let response = reqwest::get(format!(
+                "http://{}{}/{:?}",
+                addr,
+                MESSAGES_API_BASE,
+                H256::random()
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
 }
@@ -14,6 +14,8 @@ use serde::{
     Deserialize, Deserializer,
 };
 
+use crate::lifecycle::MessageLifecycleEvent;
+
 /// Defines a set of patterns for determining if a message should or should not
 /// be relayed. This is useful for determine if a message matches a given set or
 /// rules.
@@ -265,6 +267,17 @@ impl<'a> From<&'a HyperlaneMessage> for MatchInfo<'a> {
     }
 }
 
+impl<'a> From<&'a MessageLifecycleEvent> for MatchInfo<'a> {
+    fn from(event: &'a MessageLifecycleEvent) -> Self {
+        Self {
+            src_domain: event.origin,
+            src_addr: &event.sender,
+            dst_domain: event.destination,
+            dst_addr: &event.recipient,
+        }
+    }
+}
+
 impl MatchingList {
     /// Check if a message matches any of the rules.
     /// - `default`: What to return if the the matching list is empty.
@@ -272,6 +285,12 @@ impl MatchingList {
         self.matches(msg.into(), default)
     }
 
+    /// Check if the message of a lifecycle event matches any of the rules.
+    /// - `default`: What to return if the the matching list is empty.
+    pub fn event_matches(&self, event: &MessageLifecycleEvent, default: bool) -> bool {
+        self.matches(event.into(), default)
+    }
+
     /// Check if a message matches any of the rules.
     /// - `default`: What to return if the the matching list is empty.
     fn matches(&self, info: MatchInfo, default: bool) -> bool {
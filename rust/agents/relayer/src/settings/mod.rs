@@ -4,20 +4,23 @@
 //! and validations it defines are not applied here, we should mirror them.
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, path::PathBuf, time::Duration};
 
 use convert_case::Case;
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
 use eyre::{eyre, Context};
 use hyperlane_base::{
+    db::DbBackupConf,
     impl_loadable_from_settings,
+    server::ApiToken,
     settings::{
-        parser::{recase_json_value, RawAgentConf, ValueParser},
+        parser::{parse_api_token, parse_db_backup, recase_json_value, RawAgentConf, ValueParser},
         Settings,
     },
 };
 use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, U256};
 use itertools::Itertools;
+use reqwest::Url;
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -36,6 +39,24 @@ pub struct RelayerSettings {
 
     /// Database path
     pub db: PathBuf,
+    /// How long to keep the data of delivered messages in the database for. If
+    /// not specified, it's kept forever.
+    pub delivered_message_retention: Option<Duration>,
+    /// Where and how often to back up the database. Backups are disabled if not
+    /// specified.
+    pub db_backup: Option<DbBackupConf>,
+    /// Where to append the lifecycle events of messages to as JSON lines, if
+    /// anywhere.
+    pub lifecycle_events_file: Option<PathBuf>,
+    /// Webhooks to post the lifecycle events of messages to.
+    pub lifecycle_event_webhooks: Vec<LifecycleEventWebhookConf>,
+    /// Authentication of the server-sent stream of lifecycle events. The
+    /// stream is disabled if not specified.
+    pub lifecycle_events_api: Option<LifecycleEventsApiConf>,
+    /// Authentication and auditing of manual relays, which deliver a message
+    /// regardless of the whitelist, blacklist and gas payment enforcement.
+    /// Manual relays are disabled if not specified.
+    pub manual_relay: Option<ManualRelayConf>,
     /// The chain to relay messages from
     pub origin_chains: HashSet<HyperlaneDomain>,
     /// Chains to relay messages to
@@ -56,6 +77,10 @@ pub struct RelayerSettings {
     pub allow_local_checkpoint_syncers: bool,
     /// App contexts used for metrics.
     pub metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Priority lanes of the submitters' queues. A message is in the first
+    /// lane it matches, or in a default lane with a weight of 1 if it matches
+    /// none.
+    pub priority_lanes: Vec<PriorityLaneConf>,
 }
 
 /// Config for gas payment enforcement
@@ -68,6 +93,51 @@ pub struct GasPaymentEnforcementConf {
     pub matching_list: MatchingList,
 }
 
+/// Config for a priority lane of the submitters' queues
+#[derive(Debug, Clone)]
+pub struct PriorityLaneConf {
+    /// Name of the lane, for logs
+    pub name: String,
+    /// Share of the submitters' attention the lane gets, relative to the
+    /// weights of the other lanes with pending operations.
+    pub weight: u32,
+    /// Only messages that match are in the lane. By default all messages
+    /// match.
+    pub matching_list: MatchingList,
+    /// If specified, only messages classified as this app context are in the
+    /// lane.
+    pub app_context: Option<String>,
+    /// If specified, only messages whose unspent gas payment covers at least
+    /// this many times their estimated gas limit are in the lane.
+    pub min_gas_overpayment_ratio: Option<f64>,
+}
+
+/// Config for a webhook receiving the lifecycle events of messages
+#[derive(Debug, Clone)]
+pub struct LifecycleEventWebhookConf {
+    /// Url to post events to
+    pub url: Url,
+    /// Only events of messages that match are posted. By default all messages
+    /// match.
+    pub matching_list: MatchingList,
+}
+
+/// Config for the server-sent stream of lifecycle events
+#[derive(Debug, Clone)]
+pub struct LifecycleEventsApiConf {
+    /// Bearer token which requests must be authenticated with
+    pub token: ApiToken,
+}
+
+/// Config for manual relays
+#[derive(Debug, Clone)]
+pub struct ManualRelayConf {
+    /// Bearer token which requests must be authenticated with
+    pub token: ApiToken,
+    /// Where to append a record of each manual relay to as JSON lines
+    pub audit_log: PathBuf,
+}
+
 /// Config for a GasPaymentEnforcementPolicy
 #[derive(Debug, Clone, Default)]
 pub enum GasPaymentEnforcementPolicy {
@@ -120,6 +190,75 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .parse_from_str("Expected database path")
             .unwrap_or_else(|| std::env::current_dir().unwrap().join("hyperlane_db"));
 
+        let delivered_message_retention = p
+            .chain(&mut err)
+            .get_opt_key("deliveredMessageRetentionDays")
+            .parse_u64()
+            .end()
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+
+        let db_backup = parse_db_backup(&p).take_config_err_flat(&mut err);
+
+        let lifecycle_events_file = p
+            .chain(&mut err)
+            .get_opt_key("lifecycleEventsFile")
+            .parse_from_str("Expected lifecycle events file path")
+            .end();
+
+        let (raw_lifecycle_event_webhooks_path, raw_lifecycle_event_webhooks) = p
+            .get_opt_key("lifecycleEventWebhooks")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "lifecycle_event_webhooks", Value::Array(vec![])));
+
+        let lifecycle_event_webhooks_parser = ValueParser::new(
+            raw_lifecycle_event_webhooks_path,
+            &raw_lifecycle_event_webhooks,
+        );
+        let lifecycle_event_webhooks = lifecycle_event_webhooks_parser
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|webhook| {
+                    let url = webhook
+                        .chain(&mut err)
+                        .get_key("url")
+                        .parse_from_str("Expected webhook url")
+                        .end();
+
+                    let matching_list = webhook
+                        .chain(&mut err)
+                        .get_opt_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .unwrap_or_default();
+
+                    url.map(|url| LifecycleEventWebhookConf { url, matching_list })
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default();
+
+        let lifecycle_events_api = parse_api_token(&p, "lifecycleEventsApiToken", &mut err)
+            .map(|token| LifecycleEventsApiConf { token });
+
+        let manual_relay_token = parse_api_token(&p, "manualRelayToken", &mut err);
+        let manual_relay_audit_log = p
+            .chain(&mut err)
+            .get_opt_key("manualRelayAuditLog")
+            .parse_from_str("Expected manual relay audit log path")
+            .end();
+        let manual_relay = manual_relay_token.and_then(|token| {
+            let audit_log = match manual_relay_audit_log {
+                Some(audit_log) => Some(audit_log),
+                None => std::env::current_dir()
+                    .context(
+                        "Failed to default the manual relay audit log to the working directory",
+                    )
+                    .take_err(&mut err, || &p.cwp + "manual_relay_audit_log")
+                    .map(|dir| dir.join("manual_relay_audit.jsonl")),
+            };
+            audit_log.map(|audit_log| ManualRelayConf { token, audit_log })
+        });
+
         let (raw_gas_payment_enforcement_path, raw_gas_payment_enforcement) = p
             .get_opt_key("gasPaymentEnforcement")
             .take_config_err_flat(&mut err)
@@ -260,9 +399,71 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             })
             .unwrap_or_default();
 
+        let (raw_priority_lanes_path, raw_priority_lanes) = p
+            .get_opt_key("priorityLanes")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "priority_lanes", Value::Array(vec![])));
+
+        let priority_lanes_parser = ValueParser::new(raw_priority_lanes_path, &raw_priority_lanes);
+        let priority_lanes = priority_lanes_parser
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|lane| {
+                    let name = lane.chain(&mut err).get_key("name").parse_string().end();
+
+                    let weight = lane
+                        .chain(&mut err)
+                        .get_opt_key("weight")
+                        .parse_u32()
+                        .unwrap_or(1);
+                    let weight = if weight == 0 {
+                        Err(eyre!("Priority lane weights must be positive"))
+                            .take_err(&mut err, || &lane.cwp + "weight")
+                    } else {
+                        Some(weight)
+                    };
+
+                    let matching_list = lane
+                        .chain(&mut err)
+                        .get_opt_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .unwrap_or_default();
+
+                    let app_context = lane
+                        .chain(&mut err)
+                        .get_opt_key("appContext")
+                        .parse_string()
+                        .end()
+                        .map(str::to_owned);
+
+                    let min_gas_overpayment_ratio = lane
+                        .chain(&mut err)
+                        .get_opt_key("minGasOverpaymentRatio")
+                        .parse_f64()
+                        .end();
+
+                    name.zip(weight).map(|(name, weight)| PriorityLaneConf {
+                        name: name.to_owned(),
+                        weight,
+                        matching_list,
+                        app_context,
+                        min_gas_overpayment_ratio,
+                    })
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default();
+
         err.into_result(RelayerSettings {
             base,
             db,
+            delivered_message_retention,
+            db_backup,
+            lifecycle_events_file,
+            lifecycle_event_webhooks,
+            lifecycle_events_api,
+            manual_relay,
             origin_chains: relay_chains.clone(),
             destination_chains: relay_chains,
             gas_payment_enforcement,
@@ -272,6 +473,7 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             skip_transaction_gas_limit_for,
             allow_local_checkpoint_syncers,
             metric_app_contexts,
+            priority_lanes,
         })
     }
 }
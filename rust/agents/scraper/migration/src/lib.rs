@@ -13,6 +13,8 @@ mod m20230309_000003_create_table_transaction;
 mod m20230309_000004_create_table_delivered_message;
 mod m20230309_000004_create_table_gas_payment;
 mod m20230309_000005_create_table_message;
+mod m20261018_000001_create_table_merkle_tree_insertion;
+mod m20261018_000002_create_table_message_latency;
 
 pub struct Migrator;
 
@@ -30,6 +32,8 @@ impl MigratorTrait for Migrator {
             Box::new(m20230309_000004_create_table_gas_payment::Migration),
             Box::new(m20230309_000004_create_table_delivered_message::Migration),
             Box::new(m20230309_000005_create_table_message::Migration),
+            Box::new(m20261018_000001_create_table_merkle_tree_insertion::Migration),
+            Box::new(m20261018_000002_create_table_message_latency::Migration),
         ]
     }
 }
@@ -0,0 +1,120 @@
+use sea_orm_migration::prelude::*;
+
+use crate::l20230309_types::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // This table is derived from the message, delivered_message, transaction and
+        // block tables, and is kept up to date by the scraper as messages and
+        // deliveries are stored. It intentionally has no foreign keys so that it can
+        // be rebuilt independently of them.
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageLatency::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageLatency::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::TimeCreated)
+                            .timestamp()
+                            .not_null()
+                            .default("NOW()"),
+                    )
+                    .col(
+                        ColumnDef::new_with_type(MessageLatency::MsgId, Hash)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::OriginDomain)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::DestinationDomain)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::OriginTxId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::DestinationTxId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::SendOccurredAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::DeliveryOccurredAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLatency::LatencySeconds)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageLatency::Table)
+                    .name("message_latency_route_idx")
+                    .col(MessageLatency::OriginDomain)
+                    .col(MessageLatency::DestinationDomain)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageLatency::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum MessageLatency {
+    Table,
+    /// Unique database ID
+    Id,
+    /// Time of record creation
+    TimeCreated,
+    /// Id of the message on the blockchain
+    MsgId,
+    /// Domain ID of the origin chain
+    OriginDomain,
+    /// Domain ID of the destination chain
+    DestinationDomain,
+    /// Transaction the message was dispatched in on the origin chain
+    OriginTxId,
+    /// Transaction the message was delivered in on the destination chain
+    DestinationTxId,
+    /// Timestamp of the block the message was dispatched in
+    SendOccurredAt,
+    /// Timestamp of the block the message was delivered in
+    DeliveryOccurredAt,
+    /// Seconds between the dispatch and the delivery of the message
+    LatencySeconds,
+}
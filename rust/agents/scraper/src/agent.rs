@@ -4,11 +4,16 @@ use async_trait::async_trait;
 use derive_more::AsRef;
 use futures::future::try_join_all;
 use hyperlane_base::{
-    metrics::AgentMetrics, settings::IndexSettings, BaseAgent, ChainMetrics, ContractSyncMetrics,
-    ContractSyncer, CoreMetrics, HyperlaneAgentCore, MetricsUpdater,
+    join_agent_tasks, metrics::AgentMetrics, settings::IndexSettings, BaseAgent, ChainMetrics,
+    ComponentHealth, ContractSyncMetrics, ContractSyncer, CoreMetrics, HealthCheck,
+    HyperlaneAgentCore, MetricsUpdater,
 };
-use hyperlane_core::{Delivery, HyperlaneDomain, HyperlaneMessage, InterchainGasPayment};
+use hyperlane_core::{
+    Delivery, HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion,
+};
+use serde_json::json;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{info_span, instrument::Instrumented, trace, Instrument};
 
 use crate::{chain_scraper::HyperlaneSqlDb, db::ScraperDb, settings::ScraperSettings};
@@ -19,6 +24,7 @@ use crate::{chain_scraper::HyperlaneSqlDb, db::ScraperDb, settings::ScraperSetti
 pub struct Scraper {
     #[as_ref]
     core: HyperlaneAgentCore,
+    db: ScraperDb,
     contract_sync_metrics: Arc<ContractSyncMetrics>,
     scrapers: HashMap<u32, ChainScraper>,
     settings: ScraperSettings,
@@ -59,6 +65,7 @@ impl BaseAgent for Scraper {
             let db = HyperlaneSqlDb::new(
                 db.clone(),
                 chain_setup.addresses.mailbox,
+                chain_setup.addresses.merkle_tree_hook,
                 domain.clone(),
                 settings
                     .build_provider(domain, &metrics.clone())
@@ -81,6 +88,7 @@ impl BaseAgent for Scraper {
 
         Ok(Self {
             core,
+            db,
             contract_sync_metrics,
             scrapers,
             settings,
@@ -91,8 +99,10 @@ impl BaseAgent for Scraper {
     }
 
     #[allow(clippy::async_yields_async)]
-    async fn run(self) {
+    async fn run(self, cancel: CancellationToken) {
         let mut tasks = Vec::with_capacity(self.scrapers.len());
+        // Tasks which run until the scraper has finished its work in flight
+        let mut background_tasks = vec![];
 
         // running http server
         let server = self
@@ -100,11 +110,16 @@ impl BaseAgent for Scraper {
             .settings
             .server(self.core_metrics.clone())
             .expect("Failed to create server");
-        let server_task = server.run().instrument(info_span!("Relayer server"));
-        tasks.push(server_task);
+        let health_checks = self
+            .build_health_checks()
+            .expect("Failed to build health checks");
+        let server_task = server
+            .run_with_health_checks(vec![], health_checks)
+            .instrument(info_span!("Relayer server"));
+        background_tasks.push(server_task);
 
         for (domain, scraper) in self.scrapers.iter() {
-            tasks.push(self.scrape(*domain).await);
+            tasks.push(self.scrape(*domain, cancel.clone()).await);
 
             let chain_conf = self.settings.chain_setup(&scraper.domain).unwrap();
             let metrics_updater = MetricsUpdater::new(
@@ -116,24 +131,50 @@ impl BaseAgent for Scraper {
             )
             .await
             .unwrap();
-            tasks.push(metrics_updater.spawn());
+            background_tasks.push(metrics_updater.spawn());
         }
-        if let Err(err) = try_join_all(tasks).await {
+        if let Err(err) = join_agent_tasks(tasks, background_tasks).await {
             tracing::error!(error = ?err, "Scraper task panicked");
         }
     }
 }
 
 impl Scraper {
+    /// Readiness requires the RPCs of all chains to be reachable, their
+    /// indexers to be close to the tip and the database to be reachable.
+    fn build_health_checks(&self) -> eyre::Result<Vec<Box<dyn HealthCheck>>> {
+        let mut health_checks: Vec<Box<dyn HealthCheck>> =
+            vec![Box::new(ScraperDbHealthCheck(self.db.clone()))];
+        for scraper in self.scrapers.values() {
+            let health_check = self.settings.build_chain_health_check(
+                &scraper.domain,
+                Box::new(scraper.db.provider()),
+                vec![
+                    "message_dispatch",
+                    "message_delivery",
+                    "gas_payment",
+                    "merkle_tree_insertion",
+                ],
+                &self.contract_sync_metrics,
+            )?;
+            health_checks.push(Box::new(health_check));
+        }
+        Ok(health_checks)
+    }
+
     /// Sync contract data and other blockchain with the current chain state.
     /// This will spawn long-running contract sync tasks
-    async fn scrape(&self, domain_id: u32) -> Instrumented<JoinHandle<()>> {
+    async fn scrape(
+        &self,
+        domain_id: u32,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
         let scraper = self.scrapers.get(&domain_id).unwrap();
         let db = scraper.db.clone();
         let index_settings = scraper.index_settings.clone();
         let domain = scraper.domain.clone();
 
-        let mut tasks = Vec::with_capacity(2);
+        let mut tasks = Vec::with_capacity(4);
         tasks.push(
             self.build_message_indexer(
                 domain.clone(),
@@ -141,6 +182,7 @@ impl Scraper {
                 self.contract_sync_metrics.clone(),
                 db.clone(),
                 index_settings.clone(),
+                cancel.clone(),
             )
             .await,
         );
@@ -151,16 +193,29 @@ impl Scraper {
                 self.contract_sync_metrics.clone(),
                 db.clone(),
                 index_settings.clone(),
+                cancel.clone(),
             )
             .await,
         );
         tasks.push(
             self.build_interchain_gas_payment_indexer(
+                domain.clone(),
+                self.core_metrics.clone(),
+                self.contract_sync_metrics.clone(),
+                db.clone(),
+                index_settings.clone(),
+                cancel.clone(),
+            )
+            .await,
+        );
+        tasks.push(
+            self.build_merkle_tree_insertion_indexer(
                 domain,
                 self.core_metrics.clone(),
                 self.contract_sync_metrics.clone(),
                 db,
                 index_settings.clone(),
+                cancel.clone(),
             )
             .await,
         );
@@ -181,6 +236,7 @@ impl Scraper {
         contract_sync_metrics: Arc<ContractSyncMetrics>,
         db: HyperlaneSqlDb,
         index_settings: IndexSettings,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let sync = self
             .as_ref()
@@ -194,7 +250,7 @@ impl Scraper {
             .await
             .unwrap();
         let cursor = sync.cursor(index_settings.clone()).await;
-        tokio::spawn(async move { sync.sync("message_dispatch", cursor).await }).instrument(
+        tokio::spawn(async move { sync.sync("message_dispatch", cursor, cancel).await }).instrument(
             info_span!("ChainContractSync", chain=%domain.name(), event="message_dispatch"),
         )
     }
@@ -206,6 +262,7 @@ impl Scraper {
         contract_sync_metrics: Arc<ContractSyncMetrics>,
         db: HyperlaneSqlDb,
         index_settings: IndexSettings,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let sync = self
             .as_ref()
@@ -221,7 +278,7 @@ impl Scraper {
 
         let label = "message_delivery";
         let cursor = sync.cursor(index_settings.clone()).await;
-        tokio::spawn(async move { sync.sync(label, cursor).await })
+        tokio::spawn(async move { sync.sync(label, cursor, cancel).await })
             .instrument(info_span!("ChainContractSync", chain=%domain.name(), event=label))
     }
 
@@ -232,6 +289,7 @@ impl Scraper {
         contract_sync_metrics: Arc<ContractSyncMetrics>,
         db: HyperlaneSqlDb,
         index_settings: IndexSettings,
+        cancel: CancellationToken,
     ) -> Instrumented<JoinHandle<()>> {
         let sync = self
             .as_ref()
@@ -247,7 +305,53 @@ impl Scraper {
 
         let label = "gas_payment";
         let cursor = sync.cursor(index_settings.clone()).await;
-        tokio::spawn(async move { sync.sync(label, cursor).await })
+        tokio::spawn(async move { sync.sync(label, cursor, cancel).await })
             .instrument(info_span!("ChainContractSync", chain=%domain.name(), event=label))
     }
+
+    async fn build_merkle_tree_insertion_indexer(
+        &self,
+        domain: HyperlaneDomain,
+        metrics: Arc<CoreMetrics>,
+        contract_sync_metrics: Arc<ContractSyncMetrics>,
+        db: HyperlaneSqlDb,
+        index_settings: IndexSettings,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
+        let sync = self
+            .as_ref()
+            .settings
+            .sequenced_contract_sync::<MerkleTreeInsertion, _>(
+                &domain,
+                &metrics.clone(),
+                &contract_sync_metrics.clone(),
+                db.into(),
+            )
+            .await
+            .unwrap();
+
+        let label = "merkle_tree_insertion";
+        let cursor = sync.cursor(index_settings.clone()).await;
+        tokio::spawn(async move { sync.sync(label, cursor, cancel).await })
+            .instrument(info_span!("ChainContractSync", chain=%domain.name(), event=label))
+    }
+}
+
+/// Checks that the scraper's database can be queried
+#[derive(Debug)]
+struct ScraperDbHealthCheck(ScraperDb);
+
+#[async_trait]
+impl HealthCheck for ScraperDbHealthCheck {
+    async fn check(&self) -> Vec<ComponentHealth> {
+        let (ready, detail) = match self.0.check_connection().await {
+            Ok(()) => (true, json!({})),
+            Err(err) => (false, json!({ "error": err.to_string() })),
+        };
+        vec![ComponentHealth {
+            component: "db".to_owned(),
+            ready,
+            detail,
+        }]
+    }
 }
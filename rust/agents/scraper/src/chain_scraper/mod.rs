@@ -1,22 +1,23 @@
 //! This module (and children) are responsible for scraping blockchain data and
 //! keeping things updated.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, ops::RangeInclusive, sync::Arc};
 
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{eyre, Result};
 use hyperlane_base::settings::IndexSettings;
 use hyperlane_core::{
     unwrap_or_none_result, BlockInfo, Delivery, HyperlaneDomain, HyperlaneLogStore,
-    HyperlaneMessage, HyperlaneProvider, HyperlaneSequenceAwareIndexerStoreReader,
-    HyperlaneWatermarkedLogStore, Indexed, InterchainGasPayment, LogMeta, H256,
+    HyperlaneMessage, HyperlaneProvider, HyperlaneReorgAwareIndexerStore,
+    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, Indexed,
+    InterchainGasPayment, LogMeta, MerkleTreeInsertion, H256, H512,
 };
 use itertools::Itertools;
 use tracing::trace;
 
 use crate::db::{
-    BasicBlock, BlockCursor, ScraperDb, StorableDelivery, StorableMessage, StorablePayment,
-    StorableTxn,
+    BasicBlock, BlockCursor, ScraperDb, StorableDelivery, StorableMerkleTreeInsertion,
+    StorableMessage, StorablePayment, StorableTxn,
 };
 
 /// Maximum number of records to query at a time. This came about because when a
@@ -30,6 +31,7 @@ const CHUNK_SIZE: usize = 50;
 #[derive(Clone, Debug)]
 pub struct HyperlaneSqlDb {
     mailbox_address: H256,
+    merkle_tree_hook_address: H256,
     domain: HyperlaneDomain,
     db: ScraperDb,
     provider: Arc<dyn HyperlaneProvider>,
@@ -41,6 +43,7 @@ impl HyperlaneSqlDb {
     pub async fn new(
         db: ScraperDb,
         mailbox_address: H256,
+        merkle_tree_hook_address: H256,
         domain: HyperlaneDomain,
         provider: Arc<dyn HyperlaneProvider>,
         index_settings: &IndexSettings,
@@ -54,6 +57,7 @@ impl HyperlaneSqlDb {
             domain,
             provider,
             mailbox_address,
+            merkle_tree_hook_address,
             cursor,
         })
     }
@@ -62,6 +66,10 @@ impl HyperlaneSqlDb {
         &self.domain
     }
 
+    pub fn provider(&self) -> Arc<dyn HyperlaneProvider> {
+        self.provider.clone()
+    }
+
     pub async fn last_message_nonce(&self) -> Result<Option<u32>> {
         self.db
             .last_message_nonce(self.domain.id(), &self.mailbox_address)
@@ -76,21 +84,14 @@ impl HyperlaneSqlDb {
         &self,
         log_meta: impl Iterator<Item = &LogMeta>,
     ) -> Result<impl Iterator<Item = TxnWithId>> {
-        let block_hash_by_txn_hash: HashMap<H256, H256> = log_meta
-            .map(|meta| {
-                (
-                    meta.transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                    meta.block_hash,
-                )
-            })
+        let block_by_txn_hash: HashMap<H512, (H256, u64)> = log_meta
+            .map(|meta| (meta.transaction_id, (meta.block_hash, meta.block_number)))
             .collect();
 
         // all blocks we care about
         // hash of block maps to the block id and timestamp
         let blocks: HashMap<_, _> = self
-            .ensure_blocks(block_hash_by_txn_hash.values().copied())
+            .ensure_blocks(block_by_txn_hash.values().copied())
             .await?
             .map(|block| (block.hash, block))
             .collect();
@@ -98,8 +99,8 @@ impl HyperlaneSqlDb {
 
         // all txns we care about
         let txns_with_ids =
-            self.ensure_txns(block_hash_by_txn_hash.into_iter().map(
-                move |(txn_hash, block_hash)| {
+            self.ensure_txns(block_by_txn_hash.into_iter().map(
+                move |(txn_hash, (block_hash, _))| {
                     let block_info = *blocks.get(&block_hash).as_ref().unwrap();
                     TxnWithBlockId {
                         txn_hash,
@@ -123,7 +124,7 @@ impl HyperlaneSqlDb {
         txns: impl Iterator<Item = TxnWithBlockId>,
     ) -> Result<impl Iterator<Item = TxnWithId>> {
         // mapping of txn hash to (txn_id, block_id).
-        let mut txns: HashMap<H256, (Option<i64>, i64)> = txns
+        let mut txns: HashMap<H512, (Option<i64>, i64)> = txns
             .map(|TxnWithBlockId { txn_hash, block_id }| (txn_hash, (None, block_id)))
             .collect();
 
@@ -147,9 +148,9 @@ impl HyperlaneSqlDb {
         let mut txns_to_fetch = txns.iter_mut().filter(|(_, id)| id.0.is_none());
 
         let mut txns_to_insert: Vec<StorableTxn> = Vec::with_capacity(CHUNK_SIZE);
-        let mut hashes_to_insert: Vec<&H256> = Vec::with_capacity(CHUNK_SIZE);
+        let mut hashes_to_insert: Vec<&H512> = Vec::with_capacity(CHUNK_SIZE);
 
-        for mut chunk in as_chunks::<(&H256, &mut (Option<i64>, i64))>(txns_to_fetch, CHUNK_SIZE) {
+        for mut chunk in as_chunks::<(&H512, &mut (Option<i64>, i64))>(txns_to_fetch, CHUNK_SIZE) {
             for (hash, (_, block_id)) in chunk.iter() {
                 let info = self.provider.get_txn_by_hash(hash).await?;
                 hashes_to_insert.push(*hash);
@@ -175,21 +176,22 @@ impl HyperlaneSqlDb {
             }))
     }
 
-    /// Takes a list of block hashes for each block
+    /// Takes a list of block hashes and numbers for each block
     /// if it is in the database already:
     ///     Fetches its associated database id
     /// if it is not in the database already:
-    ///     Looks up its data with ethers and then returns the database id after
-    ///     inserting it into the database.
+    ///     Looks up its data by number with the provider and then returns the
+    ///     database id after inserting it into the database.
     async fn ensure_blocks(
         &self,
-        block_hashes: impl Iterator<Item = H256>,
+        block_hashes_and_numbers: impl Iterator<Item = (H256, u64)>,
     ) -> Result<impl Iterator<Item = BasicBlock>> {
-        // mapping of block hash to the database id and block timestamp. Optionals are
-        // in place because we will find the timestamp first if the block was not
+        // mapping of block hash to the block number and database id. Optionals are
+        // in place because we will find the block info first if the block was not
         // already in the db.
-        let mut blocks: HashMap<H256, Option<BasicBlock>> =
-            block_hashes.map(|b| (b, None)).collect();
+        let mut blocks: HashMap<H256, (u64, Option<BasicBlock>)> = block_hashes_and_numbers
+            .map(|(hash, number)| (hash, (number, None)))
+            .collect();
 
         let db_blocks: Vec<BasicBlock> = if !blocks.is_empty() {
             // check database to see which blocks we already know and fetch their IDs
@@ -202,6 +204,7 @@ impl HyperlaneSqlDb {
             let _ = blocks
                 .get_mut(&block.hash)
                 .expect("We found a block that we did not request")
+                .1
                 .insert(block);
         }
 
@@ -212,15 +215,23 @@ impl HyperlaneSqlDb {
         // inserted into db.
         let blocks_to_fetch = blocks
             .iter_mut()
-            .filter(|(_, block_info)| block_info.is_none());
+            .filter(|(_, (_, block_info))| block_info.is_none());
 
         let mut blocks_to_insert: Vec<(&mut BasicBlock, Option<BlockInfo>)> =
             Vec::with_capacity(CHUNK_SIZE);
         let mut hashes_to_insert: Vec<&H256> = Vec::with_capacity(CHUNK_SIZE);
         for chunk in as_chunks(blocks_to_fetch, CHUNK_SIZE) {
             debug_assert!(!chunk.is_empty());
-            for (hash, block_info) in chunk {
-                let info = self.provider.get_block_by_hash(hash).await?;
+            for (hash, (number, block_info)) in chunk {
+                // Not all chains support looking up blocks by hash, so look them up by
+                // number and make sure we got the block the log was emitted in.
+                let info = self.provider.get_block_by_height(*number).await?;
+                if info.hash != *hash {
+                    return Err(eyre!(
+                        "Block {number} has hash {:?}, expected {hash:?}",
+                        info.hash
+                    ));
+                }
                 let basic_info_ref = block_info.insert(BasicBlock {
                     id: -1,
                     hash: *hash,
@@ -254,7 +265,7 @@ impl HyperlaneSqlDb {
         // ensure we have updated all the block ids and that we have info for all of
         // them.
         #[cfg(debug_assertions)]
-        for (hash, block) in blocks.iter() {
+        for (hash, (_, block)) in blocks.iter() {
             let block = block.as_ref().unwrap();
             assert_eq!(hash, &block.hash);
             assert!(block.id > 0);
@@ -262,7 +273,7 @@ impl HyperlaneSqlDb {
 
         Ok(blocks
             .into_iter()
-            .map(|(hash, block_info)| block_info.unwrap()))
+            .map(|(hash, (_, block_info))| block_info.unwrap()))
     }
 }
 
@@ -273,19 +284,13 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneSqlDb {
         if messages.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<H256, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(messages.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = messages.iter().map(|m| {
-            let txn = txns
-                .get(
-                    &m.1.transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap();
+            let txn = txns.get(&m.1.transaction_id).unwrap();
             StorableMessage {
                 msg: m.0.inner().clone(),
                 meta: &m.1,
@@ -296,6 +301,8 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneSqlDb {
             .db
             .store_dispatched_messages(self.domain().id(), &self.mailbox_address, storable)
             .await?;
+        let message_ids = messages.iter().map(|m| m.0.inner().id()).collect_vec();
+        self.db.update_message_latencies(message_ids.iter()).await?;
         Ok(stored as u32)
     }
 }
@@ -306,21 +313,13 @@ impl HyperlaneLogStore<Delivery> for HyperlaneSqlDb {
         if deliveries.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<Delivery, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(deliveries.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = deliveries.iter().map(|(message_id, meta)| {
-            let txn_id = txns
-                .get(
-                    &meta
-                        .transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap()
-                .id;
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
             StorableDelivery {
                 message_id: *message_id.inner(),
                 meta,
@@ -332,6 +331,9 @@ impl HyperlaneLogStore<Delivery> for HyperlaneSqlDb {
             .db
             .store_deliveries(self.domain().id(), self.mailbox_address, storable)
             .await?;
+        self.db
+            .update_message_latencies(deliveries.iter().map(|(message_id, _)| message_id.inner()))
+            .await?;
         Ok(stored as u32)
     }
 }
@@ -345,21 +347,13 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
         if payments.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<H256, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(payments.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = payments.iter().map(|(payment, meta)| {
-            let txn_id = txns
-                .get(
-                    &meta
-                        .transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap()
-                .id;
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
             StorablePayment {
                 payment: payment.inner(),
                 meta,
@@ -372,6 +366,73 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
     }
 }
 
+#[async_trait]
+impl HyperlaneLogStore<MerkleTreeInsertion> for HyperlaneSqlDb {
+    /// Store merkle tree insertions from the merkle tree hook into the database.
+    async fn store_logs(
+        &self,
+        insertions: &[(Indexed<MerkleTreeInsertion>, LogMeta)],
+    ) -> Result<u32> {
+        if insertions.is_empty() {
+            return Ok(0);
+        }
+        let txns: HashMap<H512, TxnWithId> = self
+            .ensure_blocks_and_txns(insertions.iter().map(|r| &r.1))
+            .await?
+            .map(|t| (t.hash, t))
+            .collect();
+        let storable = insertions.iter().map(|(insertion, meta)| {
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
+            StorableMerkleTreeInsertion {
+                insertion: insertion.inner(),
+                meta,
+                txn_id,
+            }
+        });
+
+        let stored = self
+            .db
+            .store_merkle_tree_insertions(
+                self.domain().id(),
+                &self.merkle_tree_hook_address,
+                storable,
+            )
+            .await?;
+        Ok(stored as u32)
+    }
+}
+
+#[async_trait]
+impl HyperlaneSequenceAwareIndexerStoreReader<MerkleTreeInsertion> for HyperlaneSqlDb {
+    /// Gets a merkle tree insertion by its leaf index.
+    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<MerkleTreeInsertion>> {
+        let insertion = self
+            .db
+            .retrieve_merkle_tree_insertion_by_leaf_index(
+                self.domain().id(),
+                &self.merkle_tree_hook_address,
+                sequence,
+            )
+            .await?;
+        Ok(insertion)
+    }
+
+    /// Gets the block number at which the log occurred.
+    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+        let tx_id = unwrap_or_none_result!(
+            self.db
+                .retrieve_merkle_tree_insertion_tx_id(
+                    self.domain().id(),
+                    &self.merkle_tree_hook_address,
+                    sequence,
+                )
+                .await?
+        );
+        let block_id = unwrap_or_none_result!(self.db.retrieve_block_id(tx_id).await?);
+        Ok(self.db.retrieve_block_number(block_id).await?)
+    }
+}
+
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneSqlDb {
     /// Gets a message by its nonce.
@@ -395,6 +456,60 @@ impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneSql
     }
 }
 
+#[async_trait]
+impl HyperlaneReorgAwareIndexerStore<MerkleTreeInsertion> for HyperlaneSqlDb {
+    /// Gets the hash of the block at which the tree insertion occurred.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+        let tx_id = unwrap_or_none_result!(
+            self.db
+                .retrieve_merkle_tree_insertion_tx_id(
+                    self.domain().id(),
+                    &self.merkle_tree_hook_address,
+                    sequence,
+                )
+                .await?
+        );
+        let block_id = unwrap_or_none_result!(self.db.retrieve_block_id(tx_id).await?);
+        self.db.retrieve_block_hash(block_id).await
+    }
+
+    /// Deletes the merkle tree insertions with leaf indices in the given range.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+        let deleted = self
+            .db
+            .delete_merkle_tree_insertions(
+                self.domain().id(),
+                &self.merkle_tree_hook_address,
+                sequences,
+            )
+            .await?;
+        Ok(deleted as u32)
+    }
+}
+
+#[async_trait]
+impl HyperlaneReorgAwareIndexerStore<HyperlaneMessage> for HyperlaneSqlDb {
+    /// Gets the hash of the block at which the message was dispatched.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+        let tx_id = unwrap_or_none_result!(
+            self.db
+                .retrieve_dispatched_tx_id(self.domain().id(), &self.mailbox_address, sequence)
+                .await?
+        );
+        let block_id = unwrap_or_none_result!(self.db.retrieve_block_id(tx_id).await?);
+        self.db.retrieve_block_hash(block_id).await
+    }
+
+    /// Deletes the messages with nonces in the given range, along with their latencies.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+        let deleted = self
+            .db
+            .delete_dispatched_messages(self.domain().id(), &self.mailbox_address, sequences)
+            .await?;
+        Ok(deleted as u32)
+    }
+}
+
 #[async_trait]
 impl<T> HyperlaneWatermarkedLogStore<T> for HyperlaneSqlDb
 where
@@ -413,13 +528,13 @@ where
 
 #[derive(Debug, Clone)]
 struct TxnWithId {
-    hash: H256,
+    hash: H512,
     id: i64,
 }
 
 #[derive(Debug, Clone)]
 struct TxnWithBlockId {
-    txn_hash: H256,
+    txn_hash: H512,
     block_id: i64,
 }
 
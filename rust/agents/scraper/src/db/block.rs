@@ -53,6 +53,22 @@ impl ScraperDb {
         }
     }
 
+    /// Retrieves the block hash for a given block database ID
+    pub async fn retrieve_block_hash(&self, block_id: i64) -> Result<Option<H256>> {
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+        enum QueryAs {
+            Hash,
+        }
+        let block_hash = block::Entity::find()
+            .filter(block::Column::Id.eq(block_id))
+            .select_only()
+            .column_as(block::Column::Hash, QueryAs::Hash)
+            .into_values::<Vec<u8>, QueryAs>()
+            .one(&self.0)
+            .await?;
+        Ok(block_hash.map(|hash| H256::from_slice(&hash)))
+    }
+
     /// Get basic block data that can be used to insert a transaction or
     /// message. Any blocks which are not found will be excluded from the
     /// response.
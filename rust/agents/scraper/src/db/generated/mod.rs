@@ -7,5 +7,7 @@ pub mod cursor;
 pub mod delivered_message;
 pub mod domain;
 pub mod gas_payment;
+pub mod merkle_tree_insertion;
 pub mod message;
+pub mod message_latency;
 pub mod transaction;
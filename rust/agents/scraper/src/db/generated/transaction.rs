@@ -64,6 +64,7 @@ pub enum Relation {
     Block,
     DeliveredMessage,
     GasPayment,
+    MerkleTreeInsertion,
     Message,
 }
 
@@ -98,6 +99,9 @@ impl RelationTrait for Relation {
                 .into(),
             Self::DeliveredMessage => Entity::has_many(super::delivered_message::Entity).into(),
             Self::GasPayment => Entity::has_many(super::gas_payment::Entity).into(),
+            Self::MerkleTreeInsertion => {
+                Entity::has_many(super::merkle_tree_insertion::Entity).into()
+            }
             Self::Message => Entity::has_many(super::message::Entity).into(),
         }
     }
@@ -121,6 +125,12 @@ impl Related<super::gas_payment::Entity> for Entity {
     }
 }
 
+impl Related<super::merkle_tree_insertion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MerkleTreeInsertion.def()
+    }
+}
+
 impl Related<super::message::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Message.def()
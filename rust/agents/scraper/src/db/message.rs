@@ -1,6 +1,11 @@
+use std::ops::RangeInclusive;
+
 use eyre::Result;
 use itertools::Itertools;
-use sea_orm::{prelude::*, ActiveValue::*, DeriveColumn, EnumIter, Insert, QuerySelect};
+use sea_orm::{
+    prelude::*, ActiveValue::*, Condition, ConnectionTrait, DbBackend, DeleteMany, DeriveColumn,
+    EnumIter, Insert, QuerySelect, QueryTrait, Statement,
+};
 use tracing::{debug, instrument, trace};
 
 use hyperlane_core::{HyperlaneMessage, LogMeta, H256};
@@ -10,7 +15,7 @@ use crate::conversions::{address_to_bytes, bytes_to_address, h256_to_bytes};
 use crate::date_time;
 use crate::db::ScraperDb;
 
-use super::generated::{delivered_message, message};
+use super::generated::{delivered_message, message, message_latency};
 
 #[derive(Debug, Clone)]
 pub struct StorableDelivery<'a> {
@@ -178,6 +183,25 @@ impl ScraperDb {
         Ok(difference)
     }
 
+    /// Update the latency of the given messages, for those which have been both
+    /// dispatched and delivered. The latency is the time between the blocks the
+    /// message was dispatched and delivered in.
+    #[instrument(skip_all)]
+    pub async fn update_message_latencies(
+        &self,
+        message_ids: impl Iterator<Item = &H256>,
+    ) -> Result<()> {
+        let Some(statement) = message_latencies_statement(message_ids) else {
+            return Ok(());
+        };
+        let result = self.0.execute(statement).await?;
+        trace!(
+            rows = result.rows_affected(),
+            "Updated message latencies in database"
+        );
+        Ok(())
+    }
+
     async fn dispatched_messages_count(&self, domain: u32, origin_mailbox: Vec<u8>) -> Result<u64> {
         Ok(message::Entity::find()
             .filter(message::Column::Origin.eq(domain))
@@ -251,4 +275,163 @@ impl ScraperDb {
         }
         Ok(difference)
     }
+
+    /// Delete the messages dispatched from a mailbox with nonces in the given
+    /// range, e.g. because they were reorged out, along with their latencies.
+    /// Returns the number of messages that were deleted.
+    #[instrument(skip(self))]
+    pub async fn delete_dispatched_messages(
+        &self,
+        origin_domain: u32,
+        origin_mailbox: &H256,
+        nonces: RangeInclusive<u32>,
+    ) -> Result<u64> {
+        let messages = dispatched_messages_condition(origin_domain, origin_mailbox, &nonces);
+        // Latencies are looked up through the messages, so they're deleted first
+        let latencies = delete_message_latencies_query(messages.clone())
+            .exec(&self.0)
+            .await?;
+        let deleted = message::Entity::delete_many()
+            .filter(messages)
+            .exec(&self.0)
+            .await?;
+        debug!(
+            messages = deleted.rows_affected,
+            latencies = latencies.rows_affected,
+            "Deleted messages from database"
+        );
+        Ok(deleted.rows_affected)
+    }
+}
+
+/// Matches the messages dispatched from a mailbox with nonces in the given range.
+fn dispatched_messages_condition(
+    origin_domain: u32,
+    origin_mailbox: &H256,
+    nonces: &RangeInclusive<u32>,
+) -> Condition {
+    Condition::all()
+        .add(message::Column::Origin.eq(origin_domain))
+        .add(message::Column::OriginMailbox.eq(address_to_bytes(origin_mailbox)))
+        .add(message::Column::Nonce.between(*nonces.start(), *nonces.end()))
+}
+
+/// Builds the query deleting the latencies of the messages matching the condition.
+fn delete_message_latencies_query(messages: Condition) -> DeleteMany<message_latency::Entity> {
+    message_latency::Entity::delete_many().filter(
+        message_latency::Column::MsgId.in_subquery(
+            message::Entity::find()
+                .select_only()
+                .column(message::Column::MsgId)
+                .filter(messages)
+                .into_query(),
+        ),
+    )
+}
+
+/// Builds the statement upserting the latency of the given messages, with the
+/// message ids bound as parameters. Returns `None` if there are no message ids.
+fn message_latencies_statement<'a>(
+    message_ids: impl Iterator<Item = &'a H256>,
+) -> Option<Statement> {
+    let values: Vec<Value> = message_ids.map(|id| h256_to_bytes(id).into()).collect();
+    if values.is_empty() {
+        return None;
+    }
+    let placeholders = (1..=values.len()).map(|i| format!("${i}")).join(", ");
+
+    let sql = format!(
+        r#"
+        INSERT INTO "message_latency" (
+            "msg_id",
+            "origin_domain",
+            "destination_domain",
+            "origin_tx_id",
+            "destination_tx_id",
+            "send_occurred_at",
+            "delivery_occurred_at",
+            "latency_seconds"
+        )
+        SELECT DISTINCT ON ("msg"."msg_id")
+            "msg"."msg_id",
+            "msg"."origin",
+            "msg"."destination",
+            "msg"."origin_tx_id",
+            "dmsg"."destination_tx_id",
+            "origin_block"."timestamp",
+            "dest_block"."timestamp",
+            EXTRACT(EPOCH FROM "dest_block"."timestamp" - "origin_block"."timestamp")::BIGINT
+        FROM "message" AS "msg"
+            INNER JOIN "delivered_message"
+                AS "dmsg"
+                ON "dmsg"."msg_id" = "msg"."msg_id"
+            INNER JOIN "transaction"
+                AS "origin_tx"
+                ON "origin_tx"."id" = "msg"."origin_tx_id"
+            INNER JOIN "block"
+                AS "origin_block"
+                ON "origin_block"."id" = "origin_tx"."block_id"
+            INNER JOIN "transaction"
+                AS "dest_tx"
+                ON "dest_tx"."id" = "dmsg"."destination_tx_id"
+            INNER JOIN "block"
+                AS "dest_block"
+                ON "dest_block"."id" = "dest_tx"."block_id"
+        WHERE "msg"."msg_id" IN ({placeholders})
+        ON CONFLICT ("msg_id") DO UPDATE SET
+            "time_created" = NOW(),
+            "origin_domain" = EXCLUDED."origin_domain",
+            "destination_domain" = EXCLUDED."destination_domain",
+            "origin_tx_id" = EXCLUDED."origin_tx_id",
+            "destination_tx_id" = EXCLUDED."destination_tx_id",
+            "send_occurred_at" = EXCLUDED."send_occurred_at",
+            "delivery_occurred_at" = EXCLUDED."delivery_occurred_at",
+            "latency_seconds" = EXCLUDED."latency_seconds"
+        "#
+    );
+
+    Some(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &sql,
+        values,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_latencies_statement_is_empty_without_ids() {
+        assert!(message_latencies_statement(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn test_message_latencies_statement_binds_ids() {
+        let ids = [H256::repeat_byte(1), H256::repeat_byte(2)];
+        let statement = message_latencies_statement(ids.iter()).unwrap();
+
+        assert!(statement.sql.contains(r#"IN ($1, $2)"#));
+        assert!(!statement.sql.contains(&format!("{:x}", ids[0])));
+        let values = statement.values.unwrap().0;
+        assert_eq!(
+            values,
+            ids.iter()
+                .map(|id| Value::from(h256_to_bytes(id)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_delete_message_latencies_query_selects_nonce_range() {
+        let messages = dispatched_messages_condition(1, &H256::repeat_byte(1), &(3..=5));
+        let sql = delete_message_latencies_query(messages)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.starts_with(r#"DELETE FROM "message_latency""#));
+        assert!(sql.contains(r#""msg_id" IN (SELECT "message"."msg_id" FROM "message""#));
+        assert!(sql.contains(r#""message"."origin" = 1"#));
+        assert!(sql.contains(r#""message"."nonce" BETWEEN 3 AND 5"#));
+    }
 }
@@ -1,9 +1,10 @@
 pub use block::*;
 pub use block_cursor::BlockCursor;
 use eyre::Result;
+pub use merkle_tree_insertion::*;
 pub use message::*;
 pub use payment::*;
-use sea_orm::{Database, DbConn};
+use sea_orm::{ConnectionTrait, Database, DbConn, Statement};
 use tracing::instrument;
 pub use txn::*;
 
@@ -13,6 +14,7 @@ mod generated;
 // These modules implement additional functionality for the ScraperDb
 mod block;
 mod block_cursor;
+mod merkle_tree_insertion;
 mod message;
 mod payment;
 mod txn;
@@ -28,4 +30,13 @@ impl ScraperDb {
         let db = Database::connect(url).await?;
         Ok(Self(db))
     }
+
+    /// Check that the database can be queried.
+    pub async fn check_connection(&self) -> Result<()> {
+        let backend = self.0.get_database_backend();
+        self.0
+            .execute(Statement::from_string(backend, "SELECT 1".to_owned()))
+            .await?;
+        Ok(())
+    }
 }
@@ -9,9 +9,10 @@ use std::{collections::HashSet, path::PathBuf, time::Duration};
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
 use eyre::{eyre, Context};
 use hyperlane_base::{
+    db::DbBackupConf,
     impl_loadable_from_settings,
     settings::{
-        parser::{RawAgentConf, RawAgentSignerConf, ValueParser},
+        parser::{parse_db_backup, RawAgentConf, RawAgentSignerConf, ValueParser},
         CheckpointSyncerConf, Settings, SignerConf,
     },
 };
@@ -30,6 +31,9 @@ pub struct ValidatorSettings {
 
     /// Database path
     pub db: PathBuf,
+    /// Where and how often to back up the database. Backups are disabled if not
+    /// specified.
+    pub db_backup: Option<DbBackupConf>,
     /// Chain to validate messages on
     pub origin_chain: HyperlaneDomain,
     /// The validator attestation signer
@@ -101,6 +105,8 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
                     .join(format!("validator_db_{}", origin_chain_name.unwrap_or("")))
             });
 
+        let db_backup = parse_db_backup(&p).take_config_err_flat(&mut err);
+
         let checkpoint_syncer = p
             .chain(&mut err)
             .get_key("checkpointSyncer")
@@ -138,6 +144,7 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
         err.into_result(Self {
             base,
             db,
+            db_backup,
             origin_chain,
             validator,
             checkpoint_syncer,
@@ -7,6 +7,7 @@ use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use hyperlane_core::{ChainCommunicationError, ChainResult, MerkleTreeHook};
 use prometheus::IntGauge;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info};
 
 use hyperlane_base::{db::HyperlaneRocksDB, CheckpointSyncer, CoreMetrics};
@@ -25,6 +26,8 @@ pub(crate) struct ValidatorSubmitter {
     checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     message_db: HyperlaneRocksDB,
     metrics: ValidatorSubmitterMetrics,
+    /// Stops the submitter between checkpoints once cancelled
+    cancel: CancellationToken,
 }
 
 impl ValidatorSubmitter {
@@ -36,6 +39,7 @@ impl ValidatorSubmitter {
         checkpoint_syncer: Arc<dyn CheckpointSyncer>,
         message_db: HyperlaneRocksDB,
         metrics: ValidatorSubmitterMetrics,
+        cancel: CancellationToken,
     ) -> Self {
         Self {
             reorg_period: NonZeroU64::new(reorg_period),
@@ -45,6 +49,7 @@ impl ValidatorSubmitter {
             checkpoint_syncer,
             message_db,
             metrics,
+            cancel,
         }
     }
 
@@ -73,6 +78,10 @@ impl ValidatorSubmitter {
         })
         .await;
 
+        if self.cancel.is_cancelled() {
+            info!("Backfill checkpoint submitter stopped before reaching target checkpoint");
+            return;
+        }
         info!(
             ?target_checkpoint,
             "Backfill checkpoint submitter successfully reached target checkpoint"
@@ -98,13 +107,35 @@ impl ValidatorSubmitter {
             true
         };
 
-        loop {
+        while !self.cancel.is_cancelled() {
+            // Leaves can't be removed from the tree, and checkpoints may already have been
+            // signed over insertions which were reorged out, so stop rather than keep
+            // signing on top of them.
+            match self.message_db.take_rolled_back_leaf_index() {
+                Ok(Some(leaf_index)) if leaf_index < tree.count() as u32 => {
+                    error!(
+                        leaf_index,
+                        tree_count = tree.count(),
+                        "Merkle tree insertions already ingested by the tree were rolled back after a reorg. Checkpoints signed from this leaf index on may be invalid"
+                    );
+                    panic!("Merkle tree insertions from leaf index {leaf_index} on were rolled back after a reorg");
+                }
+                Ok(_) => {}
+                Err(err) => error!(
+                    ?err,
+                    "Failed to check for rolled back merkle tree insertions"
+                ),
+            }
+
             // Lag by reorg period because this is our correctness checkpoint.
             let latest_checkpoint = call_and_retry_indefinitely(|| {
                 let merkle_tree_hook = self.merkle_tree_hook.clone();
                 Box::pin(async move { merkle_tree_hook.latest_checkpoint(self.reorg_period).await })
-            })
-            .await;
+            });
+            let latest_checkpoint = tokio::select! {
+                latest_checkpoint = latest_checkpoint => latest_checkpoint,
+                _ = self.cancel.cancelled() => break,
+            };
 
             self.metrics
                 .latest_checkpoint_observed
@@ -129,7 +160,7 @@ impl ValidatorSubmitter {
                     tree_count = tree.count(),
                     "Latest checkpoint is behind tree, sleeping briefly"
                 );
-                sleep(self.interval).await;
+                self.sleep_unless_cancelled(self.interval).await;
                 continue;
             }
 
@@ -152,7 +183,14 @@ impl ValidatorSubmitter {
                 .latest_checkpoint_processed
                 .set(latest_checkpoint.index as i64);
 
-            sleep(self.interval).await;
+            self.sleep_unless_cancelled(self.interval).await;
+        }
+    }
+
+    async fn sleep_unless_cancelled(&self, duration: Duration) {
+        tokio::select! {
+            _ = sleep(duration) => {}
+            _ = self.cancel.cancelled() => {}
         }
     }
 
@@ -198,6 +236,9 @@ impl ValidatorSubmitter {
                     checkpoint,
                     message_id,
                 });
+            } else if self.cancel.is_cancelled() {
+                // Indexing stops when shutting down, so the insertion may never be indexed
+                return Ok(());
             } else {
                 // If we haven't yet indexed the next merkle tree insertion but know that
                 // it will soon exist (because we know the correctness checkpoint), wait a bit and
@@ -248,14 +289,20 @@ impl ValidatorSubmitter {
         Ok(())
     }
 
-    /// Signs and submits any previously unsubmitted checkpoints.
+    /// Signs and submits any previously unsubmitted checkpoints. Stops early,
+    /// between checkpoints, when shutting down.
     async fn sign_and_submit_checkpoints(
         &self,
         checkpoints: Vec<CheckpointWithMessageId>,
     ) -> ChainResult<()> {
-        let last_checkpoint = checkpoints.as_slice()[checkpoints.len() - 1];
+        let mut last_index = None;
 
         for queued_checkpoint in checkpoints {
+            if self.cancel.is_cancelled() {
+                info!(?last_index, "Stopped submitting checkpoints to shut down");
+                break;
+            }
+            last_index = Some(queued_checkpoint.index);
             let existing = self
                 .checkpoint_syncer
                 .fetch_checkpoint(queued_checkpoint.index)
@@ -281,9 +328,9 @@ impl ValidatorSubmitter {
             sleep(Duration::from_millis(100)).await;
         }
 
-        self.checkpoint_syncer
-            .update_latest_index(last_checkpoint.index)
-            .await?;
+        if let Some(index) = last_index {
+            self.checkpoint_syncer.update_latest_index(index).await?;
+        }
 
         Ok(())
     }
@@ -5,13 +5,15 @@ use async_trait::async_trait;
 use derive_more::AsRef;
 use eyre::Result;
 
-use futures_util::future::try_join_all;
 use tokio::{task::JoinHandle, time::sleep};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, info_span, instrument::Instrumented, warn, Instrument};
 
 use hyperlane_base::{
-    db::{HyperlaneRocksDB, DB},
+    db::{DbBackuper, HyperlaneRocksDB, DB},
+    join_agent_tasks_and_flush_db,
     metrics::AgentMetrics,
+    server::{DbHealthCheck, HealthCheck},
     settings::ChainConf,
     BaseAgent, ChainMetrics, CheckpointSyncer, ContractSyncMetrics, ContractSyncer, CoreMetrics,
     HyperlaneAgentCore, MetricsUpdater, SequencedDataContractSync,
@@ -47,7 +49,9 @@ pub struct Validator {
     reorg_period: u64,
     interval: Duration,
     checkpoint_syncer: Arc<dyn CheckpointSyncer>,
+    db_backuper: Option<Arc<DbBackuper>>,
     core_metrics: Arc<CoreMetrics>,
+    contract_sync_metrics: Arc<ContractSyncMetrics>,
     agent_metrics: AgentMetrics,
     chain_metrics: ChainMetrics,
 }
@@ -68,7 +72,13 @@ impl BaseAgent for Validator {
         Self: Sized,
     {
         let db = DB::from_path(&settings.db)?;
-        let msg_db = HyperlaneRocksDB::new(&settings.origin_chain, db);
+        let msg_db = HyperlaneRocksDB::new(&settings.origin_chain, db.clone());
+        let db_backuper = match &settings.db_backup {
+            Some(conf) => Some(Arc::new(
+                conf.build(db, vec![settings.origin_chain.clone()]).await?,
+            )),
+            None => None,
+        };
 
         // Intentionally using hyperlane_ethereum for the validator's signer
         let (signer_instance, signer) = SingletonSigner::new(settings.validator.build().await?);
@@ -119,32 +129,43 @@ impl BaseAgent for Validator {
             reorg_period: settings.reorg_period,
             interval: settings.interval,
             checkpoint_syncer,
+            db_backuper,
             agent_metrics,
             chain_metrics,
             core_metrics: metrics,
+            contract_sync_metrics,
         })
     }
 
     #[allow(clippy::async_yields_async)]
-    async fn run(mut self) {
+    async fn run(mut self, cancel: CancellationToken) {
         let mut tasks = vec![];
+        // Tasks which run until the validator has finished its work in flight
+        let mut background_tasks = vec![];
 
         // run server
-        let custom_routes =
+        let mut custom_routes =
             validator_server::routes(self.origin_chain.clone(), self.core.metrics.clone());
+        if let Some(db_backuper) = &self.db_backuper {
+            custom_routes.extend(db_backuper.clone().get_route());
+            background_tasks.extend(db_backuper.clone().spawn_scheduled());
+        }
+        let health_checks = self
+            .build_health_checks()
+            .expect("Failed to build health checks");
         let server = self
             .core
             .settings
             .server(self.core_metrics.clone())
             .expect("Failed to create server");
         let server_task = tokio::spawn(async move {
-            server.run_with_custom_routes(custom_routes);
+            server.run_with_health_checks(custom_routes, health_checks);
         })
         .instrument(info_span!("Validator server"));
-        tasks.push(server_task);
+        background_tasks.push(server_task);
 
         if let Some(signer_instance) = self.signer_instance.take() {
-            tasks.push(
+            background_tasks.push(
                 tokio::spawn(async move {
                     signer_instance.run().await;
                 })
@@ -161,7 +182,7 @@ impl BaseAgent for Validator {
         )
         .await
         .unwrap();
-        tasks.push(
+        background_tasks.push(
             tokio::spawn(async move {
                 metrics_updater.spawn().await.unwrap();
             })
@@ -179,11 +200,15 @@ impl BaseAgent for Validator {
             match self.merkle_tree_hook.count(reorg_period).await {
                 Ok(0) => {
                     info!("Waiting for first message in merkle tree hook");
-                    sleep(self.interval).await;
+                    tokio::select! {
+                        _ = sleep(self.interval) => {}
+                        _ = cancel.cancelled() => break,
+                    }
                 }
                 Ok(_) => {
-                    tasks.push(self.run_merkle_tree_hook_sync().await);
-                    for checkpoint_sync_task in self.run_checkpoint_submitters().await {
+                    tasks.push(self.run_merkle_tree_hook_sync(cancel.clone()).await);
+                    for checkpoint_sync_task in self.run_checkpoint_submitters(cancel.clone()).await
+                    {
                         tasks.push(checkpoint_sync_task);
                     }
                     break;
@@ -196,25 +221,52 @@ impl BaseAgent for Validator {
         }
 
         // Note that this only returns an error if one of the tasks panics
-        if let Err(err) = try_join_all(tasks).await {
+        if let Err(err) =
+            join_agent_tasks_and_flush_db(tasks, background_tasks, self.db.as_ref()).await
+        {
             error!(?err, "One of the validator tasks returned an error");
         }
     }
 }
 
 impl Validator {
-    async fn run_merkle_tree_hook_sync(&self) -> Instrumented<JoinHandle<()>> {
+    /// Readiness requires the RPC of the origin chain to be reachable, the
+    /// merkle tree hook indexer to be close to the tip and the db to be open.
+    fn build_health_checks(&self) -> Result<Vec<Box<dyn HealthCheck>>> {
+        let db: &DB = self.db.as_ref();
+        let chain_health_check = self.core.settings.build_chain_health_check(
+            &self.origin_chain,
+            self.merkle_tree_hook.provider(),
+            vec!["merkle_tree_hook"],
+            &self.contract_sync_metrics,
+        )?;
+        Ok(vec![
+            Box::new(DbHealthCheck::new(db.clone())),
+            Box::new(chain_health_check),
+        ])
+    }
+
+    async fn run_merkle_tree_hook_sync(
+        &self,
+        cancel: CancellationToken,
+    ) -> Instrumented<JoinHandle<()>> {
         let index_settings =
             self.as_ref().settings.chains[self.origin_chain.name()].index_settings();
         let contract_sync = self.merkle_tree_hook_sync.clone();
         let cursor = contract_sync.cursor(index_settings).await;
         tokio::spawn(async move {
-            contract_sync.clone().sync("merkle_tree_hook", cursor).await;
+            contract_sync
+                .clone()
+                .sync("merkle_tree_hook", cursor, cancel)
+                .await;
         })
         .instrument(info_span!("MerkleTreeHookSyncer"))
     }
 
-    async fn run_checkpoint_submitters(&self) -> Vec<Instrumented<JoinHandle<()>>> {
+    async fn run_checkpoint_submitters(
+        &self,
+        cancel: CancellationToken,
+    ) -> Vec<Instrumented<JoinHandle<()>>> {
         let submitter = ValidatorSubmitter::new(
             self.interval,
             self.reorg_period,
@@ -223,8 +275,22 @@ impl Validator {
             self.checkpoint_syncer.clone(),
             self.db.clone(),
             ValidatorSubmitterMetrics::new(&self.core.metrics, &self.origin_chain),
+            cancel,
         );
 
+        // The tip tree is fetched from the chain and the backfilled tree is rebuilt from
+        // the db, so rollbacks of insertions from before this run no longer matter.
+        match self.db.take_rolled_back_leaf_index() {
+            Ok(Some(leaf_index)) => {
+                info!(
+                    leaf_index,
+                    "Clearing merkle tree insertion rollback from a previous run"
+                )
+            }
+            Ok(None) => {}
+            Err(err) => warn!(?err, "Failed to clear merkle tree insertion rollback"),
+        }
+
         let reorg_period = NonZeroU64::new(self.reorg_period);
         let tip_tree = self
             .merkle_tree_hook
@@ -10,8 +10,9 @@ use crate::{
 };
 use async_trait::async_trait;
 use hyperlane_core::{
-    AggregationIsm, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
-    HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, RawHyperlaneMessage, H160, H256,
+    rpc_clients::FallbackProviderMetrics, AggregationIsm, ChainResult, ContractLocator,
+    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
+    RawHyperlaneMessage, H160, H256,
 };
 use tracing::instrument;
 
@@ -29,12 +30,14 @@ impl CosmosAggregationIsm {
         conf: ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
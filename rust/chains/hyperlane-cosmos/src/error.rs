@@ -1,5 +1,5 @@
 use cosmrs::proto::prost;
-use hyperlane_core::ChainCommunicationError;
+use hyperlane_core::{ChainCommunicationError, H256};
 use std::fmt::Debug;
 
 /// Errors from the crates specific to the hyperlane-cosmos
@@ -47,6 +47,9 @@ pub enum HyperlaneCosmosError {
     /// Fallback providers failed
     #[error("Fallback providers failed. (Errors: {0:?})")]
     FallbackProvidersFailed(Vec<HyperlaneCosmosError>),
+    /// Transaction has no signer info
+    #[error("Could not find signer info in transaction {0:?}")]
+    SignerInfoNotFound(H256),
 }
 
 impl From<HyperlaneCosmosError> for ChainCommunicationError {
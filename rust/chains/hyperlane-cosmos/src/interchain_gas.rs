@@ -1,10 +1,11 @@
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use futures::future;
+use futures::{future, StreamExt};
 use hyperlane_core::{
-    ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
-    HyperlaneDomain, HyperlaneProvider, Indexed, Indexer, InterchainGasPaymaster,
-    InterchainGasPayment, LogMeta, SequenceAwareIndexer, H256, U256,
+    rpc_clients::FallbackProviderMetrics, ChainCommunicationError, ChainResult, ContractLocator,
+    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer,
+    InterchainGasPaymaster, InterchainGasPayment, LogMeta, LogStream, SequenceAwareIndexer, H256,
+    U256,
 };
 use once_cell::sync::Lazy;
 use std::ops::RangeInclusive;
@@ -50,12 +51,14 @@ impl CosmosInterchainGasPaymaster {
         conf: ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
@@ -99,12 +102,14 @@ impl CosmosInterchainGasPaymasterIndexer {
         conf: ConnectionConf,
         locator: ContractLocator,
         reorg_period: u32,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let indexer = CosmosWasmIndexer::new(
             conf,
             locator,
             Self::INTERCHAIN_GAS_PAYMENT_EVENT_TYPE.into(),
             reorg_period,
+            metrics,
         )?;
 
         Ok(Self {
@@ -248,6 +253,20 @@ impl Indexer<InterchainGasPayment> for CosmosInterchainGasPaymasterIndexer {
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
         self.indexer.get_finalized_block_number().await
     }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<InterchainGasPayment>>> {
+        Ok(self
+            .indexer
+            .subscribe_logs(
+                Self::interchain_gas_payment_parser,
+                "InterchainGasPaymentCursor",
+            )
+            .await?
+            .map(|logs| {
+                logs.map(|log| log.map(|(log, meta)| (Indexed::new(log), meta)))
+                    .boxed()
+            }))
+    }
 }
 
 #[async_trait]
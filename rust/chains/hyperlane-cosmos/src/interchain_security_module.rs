@@ -1,8 +1,8 @@
 use async_trait::async_trait;
 use hyperlane_core::{
-    ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
-    HyperlaneMessage, HyperlaneProvider, InterchainSecurityModule, ModuleType, RawHyperlaneMessage,
-    H256, U256,
+    rpc_clients::FallbackProviderMetrics, ChainResult, ContractLocator, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
+    InterchainSecurityModule, ModuleType, RawHyperlaneMessage, H256, U256,
 };
 
 use crate::{
@@ -34,12 +34,14 @@ impl CosmosInterchainSecurityModule {
         conf: &ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
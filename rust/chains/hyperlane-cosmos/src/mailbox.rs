@@ -1,5 +1,5 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use futures::future;
+use futures::{future, StreamExt};
 use std::{
     fmt::{Debug, Formatter},
     io::Cursor,
@@ -24,9 +24,9 @@ use tendermint::abci::EventAttribute;
 
 use crate::utils::{CONTRACT_ADDRESS_ATTRIBUTE_KEY, CONTRACT_ADDRESS_ATTRIBUTE_KEY_BASE64};
 use hyperlane_core::{
-    utils::bytes_to_hex, ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
-    HyperlaneMessage, HyperlaneProvider, Indexed, Indexer, LogMeta, Mailbox, TxCostEstimate,
-    TxOutcome, H256, U256,
+    rpc_clients::FallbackProviderMetrics, utils::bytes_to_hex, ChainResult, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Indexed, Indexer,
+    LogMeta, LogStream, Mailbox, TxCostEstimate, TxOutcome, H256, U256,
 };
 use hyperlane_core::{
     ChainCommunicationError, ContractLocator, Decode, RawHyperlaneMessage, SequenceAwareIndexer,
@@ -49,12 +49,14 @@ impl CosmosMailbox {
         conf: ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
@@ -227,6 +229,7 @@ impl Mailbox for CosmosMailbox {
             gas_limit: gas_limit.into(),
             gas_price: self.provider.grpc().gas_price(),
             l2_gas_limit: None,
+            l1_data_fee: None,
         };
 
         Ok(result)
@@ -262,16 +265,23 @@ const MESSAGE_ATTRIBUTE_KEY: &str = "message";
 static MESSAGE_ATTRIBUTE_KEY_BASE64: Lazy<String> =
     Lazy::new(|| BASE64.encode(MESSAGE_ATTRIBUTE_KEY));
 
+const MESSAGE_ID_ATTRIBUTE_KEY: &str = "message_id";
+static MESSAGE_ID_ATTRIBUTE_KEY_BASE64: Lazy<String> =
+    Lazy::new(|| BASE64.encode(MESSAGE_ID_ATTRIBUTE_KEY));
+
 /// Struct that retrieves event data for a Cosmos Mailbox contract
 #[derive(Debug, Clone)]
 pub struct CosmosMailboxIndexer {
     mailbox: CosmosMailbox,
     indexer: Box<CosmosWasmIndexer>,
+    delivery_indexer: Box<CosmosWasmIndexer>,
 }
 
 impl CosmosMailboxIndexer {
     /// The message dispatch event type from the CW contract.
     const MESSAGE_DISPATCH_EVENT_TYPE: &str = "mailbox_dispatch";
+    /// The message process event type from the CW contract.
+    const MESSAGE_PROCESS_EVENT_TYPE: &str = "mailbox_process_id";
 
     /// Create a reference to a mailbox at a specific Cosmos address on some
     /// chain
@@ -280,18 +290,33 @@ impl CosmosMailboxIndexer {
         locator: ContractLocator,
         signer: Option<Signer>,
         reorg_period: u32,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
-        let mailbox = CosmosMailbox::new(conf.clone(), locator.clone(), signer.clone())?;
+        let mailbox = CosmosMailbox::new(
+            conf.clone(),
+            locator.clone(),
+            signer.clone(),
+            metrics.clone(),
+        )?;
         let indexer = CosmosWasmIndexer::new(
+            conf.clone(),
+            locator.clone(),
+            Self::MESSAGE_DISPATCH_EVENT_TYPE.into(),
+            reorg_period,
+            metrics.clone(),
+        )?;
+        let delivery_indexer = CosmosWasmIndexer::new(
             conf,
             locator,
-            Self::MESSAGE_DISPATCH_EVENT_TYPE.into(),
+            Self::MESSAGE_PROCESS_EVENT_TYPE.into(),
             reorg_period,
+            metrics,
         )?;
 
         Ok(Self {
             mailbox,
             indexer: Box::new(indexer),
+            delivery_indexer: Box::new(delivery_indexer),
         })
     }
 
@@ -346,6 +371,51 @@ impl CosmosMailboxIndexer {
 
         Ok(ParsedEvent::new(contract_address, message))
     }
+
+    #[instrument(err)]
+    fn hyperlane_delivery_parser(attrs: &Vec<EventAttribute>) -> ChainResult<ParsedEvent<H256>> {
+        let mut contract_address: Option<String> = None;
+        let mut message_id: Option<H256> = None;
+
+        for attr in attrs {
+            let key = attr.key.as_str();
+            let value = attr.value.as_str();
+
+            match key {
+                CONTRACT_ADDRESS_ATTRIBUTE_KEY => {
+                    contract_address = Some(value.to_string());
+                }
+                v if *CONTRACT_ADDRESS_ATTRIBUTE_KEY_BASE64 == v => {
+                    contract_address = Some(String::from_utf8(
+                        BASE64
+                            .decode(value)
+                            .map_err(Into::<HyperlaneCosmosError>::into)?,
+                    )?);
+                }
+
+                MESSAGE_ID_ATTRIBUTE_KEY => {
+                    message_id = Some(H256::from_slice(hex::decode(value)?.as_slice()));
+                }
+                v if *MESSAGE_ID_ATTRIBUTE_KEY_BASE64 == v => {
+                    let hex = String::from_utf8(
+                        BASE64
+                            .decode(value)
+                            .map_err(Into::<HyperlaneCosmosError>::into)?,
+                    )?;
+                    message_id = Some(H256::from_slice(hex::decode(hex)?.as_slice()));
+                }
+
+                _ => {}
+            }
+        }
+
+        let contract_address = contract_address
+            .ok_or_else(|| ChainCommunicationError::from_other_str("missing contract_address"))?;
+        let message_id = message_id
+            .ok_or_else(|| ChainCommunicationError::from_other_str("missing message_id"))?;
+
+        Ok(ParsedEvent::new(contract_address, message_id))
+    }
 }
 
 #[async_trait]
@@ -393,6 +463,21 @@ impl Indexer<HyperlaneMessage> for CosmosMailboxIndexer {
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
         self.indexer.get_finalized_block_number().await
     }
+
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        Ok(Some(self.indexer.get_block_hash(block_number).await?))
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<HyperlaneMessage>>> {
+        Ok(self
+            .indexer
+            .subscribe_logs(Self::hyperlane_message_parser, "HyperlaneMessageCursor")
+            .await?
+            .map(|logs| {
+                logs.map(|log| log.map(|(log, meta)| (log.into(), meta)))
+                    .boxed()
+            }))
+    }
 }
 
 #[async_trait]
@@ -401,12 +486,61 @@ impl Indexer<H256> for CosmosMailboxIndexer {
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<H256>, LogMeta)>> {
-        // TODO: implement when implementing Cosmos scraping
-        todo!()
+        let logs_futures: Vec<_> = range
+            .map(|block_number| {
+                let self_clone = self.clone();
+                tokio::spawn(async move {
+                    let logs = self_clone
+                        .delivery_indexer
+                        .get_logs_in_block(
+                            block_number,
+                            Self::hyperlane_delivery_parser,
+                            "DeliveryCursor",
+                        )
+                        .await;
+                    (logs, block_number)
+                })
+            })
+            .collect();
+
+        // TODO: this can be refactored when we rework indexing, to be part of the block-by-block indexing
+        let result = future::join_all(logs_futures)
+            .await
+            .into_iter()
+            .flatten()
+            .filter_map(|(logs_res, block_number)| match logs_res {
+                Ok(logs) => Some(logs),
+                Err(err) => {
+                    warn!(?err, ?block_number, "Failed to fetch logs for block");
+                    None
+                }
+            })
+            .flatten()
+            .map(|(log, meta)| (log.into(), meta))
+            .collect();
+
+        Ok(result)
     }
 
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        self.indexer.get_finalized_block_number().await
+        self.delivery_indexer.get_finalized_block_number().await
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        Ok(Some(
+            self.delivery_indexer.get_block_hash(block_number).await?,
+        ))
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<H256>>> {
+        Ok(self
+            .delivery_indexer
+            .subscribe_logs(Self::hyperlane_delivery_parser, "DeliveryCursor")
+            .await?
+            .map(|logs| {
+                logs.map(|log| log.map(|(log, meta)| (log.into(), meta)))
+                    .boxed()
+            }))
     }
 }
 
@@ -466,4 +600,31 @@ mod tests {
         );
         assert_parsed_event(&base64_attrs);
     }
+
+    #[test]
+    fn test_hyperlane_delivery_parser() {
+        let expected = ParsedEvent::new(
+            "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4".into(),
+            H256::from_str("a9b1d3dfc2ae4c8d9f4e3c2cbd54f9b1a0f25a8a5a2d3b0e0c6e0a7f3c27fb61")
+                .unwrap(),
+        );
+
+        let assert_parsed_event = |attrs: &Vec<EventAttribute>| {
+            let parsed_event = CosmosMailboxIndexer::hyperlane_delivery_parser(attrs).unwrap();
+
+            assert_eq!(parsed_event, expected);
+        };
+
+        // Non-base64 version
+        let non_base64_attrs = event_attributes_from_str(
+            r#"[{"key":"_contract_address","value":"neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4","index":true},{"key":"message_id","value":"a9b1d3dfc2ae4c8d9f4e3c2cbd54f9b1a0f25a8a5a2d3b0e0c6e0a7f3c27fb61","index":true}]"#,
+        );
+        assert_parsed_event(&non_base64_attrs);
+
+        // Base64 version
+        let base64_attrs = event_attributes_from_str(
+            r#"[{"key":"X2NvbnRyYWN0X2FkZHJlc3M=","value":"bmV1dHJvbjFzanp6ZDRnd2tnZ3k2aHJyczhreHhhdGV4emN1ejNqZWNzeG0zd3FncmVna3Vsemo4cjdxbG51ZWY0","index":true},{"key":"bWVzc2FnZV9pZA==","value":"YTliMWQzZGZjMmFlNGM4ZDlmNGUzYzJjYmQ1NGY5YjFhMGYyNWE4YTVhMmQzYjBlMGM2ZTBhN2YzYzI3ZmI2MQ==","index":true}]"#,
+        );
+        assert_parsed_event(&base64_attrs);
+    }
 }
@@ -2,11 +2,12 @@ use std::{fmt::Debug, num::NonZeroU64, ops::RangeInclusive, str::FromStr};
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use futures::future;
+use futures::{future, StreamExt};
 use hyperlane_core::{
-    accumulator::incremental::IncrementalMerkle, ChainCommunicationError, ChainResult, Checkpoint,
-    ContractLocator, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneProvider,
-    Indexed, Indexer, LogMeta, MerkleTreeHook, MerkleTreeInsertion, SequenceAwareIndexer, H256,
+    accumulator::incremental::IncrementalMerkle, rpc_clients::FallbackProviderMetrics,
+    ChainCommunicationError, ChainResult, Checkpoint, ContractLocator, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer, LogMeta, LogStream,
+    MerkleTreeHook, MerkleTreeInsertion, SequenceAwareIndexer, H256,
 };
 use once_cell::sync::Lazy;
 use tendermint::abci::EventAttribute;
@@ -43,12 +44,14 @@ impl CosmosMerkleTreeHook {
         conf: ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
@@ -204,16 +207,18 @@ impl CosmosMerkleTreeHookIndexer {
         locator: ContractLocator,
         signer: Option<Signer>,
         reorg_period: u32,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let indexer = CosmosWasmIndexer::new(
             conf.clone(),
             locator.clone(),
             Self::MERKLE_TREE_INSERTION_EVENT_TYPE.into(),
             reorg_period,
+            metrics.clone(),
         )?;
 
         Ok(Self {
-            merkle_tree_hook: CosmosMerkleTreeHook::new(conf, locator, signer)?,
+            merkle_tree_hook: CosmosMerkleTreeHook::new(conf, locator, signer, metrics)?,
             indexer: Box::new(indexer),
         })
     }
@@ -327,6 +332,24 @@ impl Indexer<MerkleTreeInsertion> for CosmosMerkleTreeHookIndexer {
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
         self.indexer.get_finalized_block_number().await
     }
+
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        Ok(Some(self.indexer.get_block_hash(block_number).await?))
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<MerkleTreeInsertion>>> {
+        Ok(self
+            .indexer
+            .subscribe_logs(
+                Self::merkle_tree_insertion_parser,
+                "MerkleTreeInsertionCursor",
+            )
+            .await?
+            .map(|logs| {
+                logs.map(|log| log.map(|(log, meta)| (log.into(), meta)))
+                    .boxed()
+            }))
+    }
 }
 
 #[async_trait]
@@ -6,8 +6,9 @@ use crate::{
 };
 use async_trait::async_trait;
 use hyperlane_core::{
-    ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
-    HyperlaneMessage, HyperlaneProvider, MultisigIsm, RawHyperlaneMessage, H160, H256,
+    rpc_clients::FallbackProviderMetrics, ChainResult, ContractLocator, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, MultisigIsm,
+    RawHyperlaneMessage, H160, H256,
 };
 
 use crate::payloads::multisig_ism::{self, VerifyInfoRequest, VerifyInfoRequestInner};
@@ -26,12 +27,14 @@ impl CosmosMultisigIsm {
         conf: ConnectionConf,
         locator: ContractLocator,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             signer,
+            metrics,
         )?;
 
         Ok(Self {
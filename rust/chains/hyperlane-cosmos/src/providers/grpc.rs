@@ -26,7 +26,7 @@ use cosmrs::{
 };
 use derive_new::new;
 use hyperlane_core::{
-    rpc_clients::{BlockNumberGetter, FallbackProvider},
+    rpc_clients::{BlockNumberGetter, FallbackProvider, FallbackProviderMetrics},
     ChainCommunicationError, ChainResult, ContractLocator, FixedPointNumber, HyperlaneDomain, U256,
 };
 use protobuf::Message as _;
@@ -40,7 +40,10 @@ use tracing::{debug, instrument};
 use url::Url;
 
 use crate::{address::CosmosAddress, CosmosAmount};
-use crate::{rpc_clients::CosmosFallbackProvider, HyperlaneCosmosError};
+use crate::{
+    rpc_clients::{is_cosmos_provider_error, CosmosFallbackProvider},
+    HyperlaneCosmosError,
+};
 use crate::{signers::Signer, ConnectionConf};
 
 /// A multiplier applied to a simulated transaction's gas usage to
@@ -54,8 +57,18 @@ const TIMEOUT_BLOCKS: u64 = 1000;
 struct CosmosChannel {
     channel: Channel,
     /// The url that this channel is connected to.
-    /// Not explicitly used, but useful for debugging.
-    _url: Url,
+    url: Url,
+}
+
+impl CosmosChannel {
+    /// The host and port of the url this channel is connected to, used to label metrics.
+    fn node_host(&self) -> String {
+        match (self.url.host_str(), self.url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => "unknown".to_owned(),
+        }
+    }
 }
 
 #[async_trait]
@@ -145,6 +158,7 @@ impl WasmGrpcProvider {
         gas_price: CosmosAmount,
         locator: Option<ContractLocator>,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         // get all the configured grpc urls and convert them to a Vec<Endpoint>
         let channels: Result<Vec<CosmosChannel>, _> = conf
@@ -157,7 +171,12 @@ impl WasmGrpcProvider {
             })
             .collect();
         let mut builder = FallbackProvider::builder();
-        builder = builder.add_providers(channels?);
+        builder = builder
+            .add_providers(channels?)
+            .with_error_classifier(is_cosmos_provider_error);
+        if let Some(metrics) = metrics {
+            builder = builder.with_metrics(metrics, domain.name(), CosmosChannel::node_host);
+        }
         let fallback_provider = builder.build();
         let provider = CosmosFallbackProvider::new(fallback_provider);
 
@@ -1,13 +1,32 @@
+use std::str::FromStr;
+
 use async_trait::async_trait;
+use cosmrs::{
+    proto::{cosmwasm::wasm::v1::MsgExecuteContract, traits::Message},
+    tx::SignerPublicKey,
+    Tx,
+};
 use hyperlane_core::{
-    BlockInfo, ChainInfo, ChainResult, ContractLocator, HyperlaneChain, HyperlaneDomain,
-    HyperlaneProvider, TxnInfo, H256, U256,
+    rpc_clients::{BlockNumberGetter, FallbackProvider, FallbackProviderMetrics},
+    BlockInfo, ChainCommunicationError, ChainInfo, ChainResult, ContractLocator, HyperlaneChain,
+    HyperlaneDomain, HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256,
+    H512, U256,
+};
+use tendermint::{
+    block::{Block, Height, Id as BlockId},
+    hash::Algorithm,
+    Hash,
 };
-use tendermint_rpc::{client::CompatMode, HttpClient};
+use tendermint_rpc::Client;
+use tracing::warn;
 
-use crate::{ConnectionConf, CosmosAmount, HyperlaneCosmosError, Signer};
+use crate::{
+    address::CosmosAddress,
+    rpc_clients::{is_cosmos_provider_error, CosmosFallbackProvider},
+    ConnectionConf, CosmosAmount, HyperlaneCosmosError, Signer,
+};
 
-use self::grpc::WasmGrpcProvider;
+use self::{grpc::WasmGrpcProvider, rpc::CosmosRpcClient};
 
 /// cosmos grpc provider
 pub mod grpc;
@@ -18,9 +37,10 @@ pub mod rpc;
 #[derive(Debug, Clone)]
 pub struct CosmosProvider {
     domain: HyperlaneDomain,
+    connection_conf: ConnectionConf,
     canonical_asset: String,
     grpc_client: WasmGrpcProvider,
-    rpc_client: HttpClient,
+    rpc_client: CosmosFallbackProvider<CosmosRpcClient>,
 }
 
 impl CosmosProvider {
@@ -30,6 +50,7 @@ impl CosmosProvider {
         conf: ConnectionConf,
         locator: Option<ContractLocator>,
         signer: Option<Signer>,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let gas_price = CosmosAmount::try_from(conf.get_minimum_gas_price().clone())?;
         let grpc_client = WasmGrpcProvider::new(
@@ -38,22 +59,28 @@ impl CosmosProvider {
             gas_price.clone(),
             locator,
             signer,
+            metrics.clone(),
         )?;
-        let rpc_client = HttpClient::builder(
-            conf.get_rpc_url()
-                .parse()
-                .map_err(Into::<HyperlaneCosmosError>::into)?,
-        )
-        // Consider supporting different compatibility modes.
-        .compat_mode(CompatMode::latest())
-        .build()
-        .map_err(Into::<HyperlaneCosmosError>::into)?;
+        let rpc_clients = conf
+            .get_rpc_urls()
+            .into_iter()
+            .map(CosmosRpcClient::new)
+            .collect::<ChainResult<Vec<_>>>()?;
+        let mut builder = FallbackProvider::builder();
+        builder = builder
+            .add_providers(rpc_clients)
+            .with_error_classifier(is_cosmos_provider_error);
+        if let Some(metrics) = metrics {
+            builder = builder.with_metrics(metrics, domain.name(), CosmosRpcClient::node_host);
+        }
+        let rpc_client = CosmosFallbackProvider::new(builder.build());
 
         Ok(Self {
             domain,
             rpc_client,
             grpc_client,
             canonical_asset: conf.get_canonical_asset(),
+            connection_conf: conf,
         })
     }
 
@@ -63,9 +90,79 @@ impl CosmosProvider {
     }
 
     /// Get an rpc client
-    pub fn rpc(&self) -> &HttpClient {
+    pub fn rpc(&self) -> &CosmosFallbackProvider<CosmosRpcClient> {
         &self.rpc_client
     }
+
+    /// The recipient of a transaction is the contract executed by its first
+    /// message, if any.
+    fn recipient(&self, tx: &Tx) -> ChainResult<Option<H256>> {
+        let Some(msg) = tx.body.messages.first() else {
+            return Ok(None);
+        };
+        if msg.type_url != "/cosmwasm.wasm.v1.MsgExecuteContract" {
+            return Ok(None);
+        }
+        let msg = MsgExecuteContract::decode(msg.value.as_slice())
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        Ok(Some(CosmosAddress::from_str(&msg.contract)?.digest()))
+    }
+}
+
+/// The sender of a transaction is the address of its first signer. Signers
+/// whose address can't be derived from their public key (e.g. multisig
+/// signers) fall back to the fee payer, or to the zero address if no payer
+/// is set.
+fn tx_sender(hash: &H256, tx: &Tx, prefix: &str) -> ChainResult<H256> {
+    let signer_info = tx
+        .auth_info
+        .signer_infos
+        .first()
+        .ok_or_else(|| HyperlaneCosmosError::SignerInfoNotFound(*hash))?;
+    if let Some(SignerPublicKey::Single(public_key)) = &signer_info.public_key {
+        return Ok(CosmosAddress::from_pubkey(*public_key, prefix)?.digest());
+    }
+    if let Some(payer) = &tx.auth_info.fee.payer {
+        return Ok(CosmosAddress::from_str(payer.as_ref())?.digest());
+    }
+    warn!(
+        ?hash,
+        "Unsupported signer public key in transaction, using zero address as sender"
+    );
+    Ok(H256::zero())
+}
+
+/// The gas price is the fee paid in the gas denom divided by the gas
+/// limit, since the full fee is charged regardless of the gas used.
+/// Returns `None` if the transaction didn't pay its fee in the gas denom.
+fn tx_gas_price(hash: &H256, tx: &Tx, denom: &str) -> Option<U256> {
+    let Some(fee) = tx
+        .auth_info
+        .fee
+        .amount
+        .iter()
+        .find(|coin| coin.denom.as_ref() == denom)
+    else {
+        warn!(
+            ?hash,
+            denom, "Could not find fee in gas denom in transaction"
+        );
+        return None;
+    };
+    let gas_limit = U256::from(tx.auth_info.fee.gas_limit);
+    Some(
+        U256::from(fee.amount)
+            .checked_div(gas_limit)
+            .unwrap_or_default(),
+    )
+}
+
+fn block_info(block_id: &BlockId, block: &Block) -> BlockInfo {
+    BlockInfo {
+        hash: H256::from_slice(block_id.hash.as_bytes()),
+        timestamp: block.header.time.unix_timestamp() as u64,
+        number: block.header.height.value(),
+    }
 }
 
 impl HyperlaneChain for CosmosProvider {
@@ -80,12 +177,94 @@ impl HyperlaneChain for CosmosProvider {
 
 #[async_trait]
 impl HyperlaneProvider for CosmosProvider {
-    async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
-        todo!() // FIXME
+    async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+        let tendermint_hash = Hash::from_bytes(Algorithm::Sha256, hash.as_bytes())
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        let response = self
+            .rpc_client
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .block_by_hash(tendermint_hash)
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
+            .await?;
+        let block = response
+            .block
+            .ok_or(HyperlaneProviderError::CouldNotFindObjectByHash(*hash))?;
+        Ok(block_info(&response.block_id, &block))
     }
 
-    async fn get_txn_by_hash(&self, _hash: &H256) -> ChainResult<TxnInfo> {
-        todo!() // FIXME
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        let tendermint_height =
+            Height::try_from(height).map_err(Into::<HyperlaneCosmosError>::into)?;
+        let response = self
+            .rpc_client
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .block(tendermint_height)
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
+            .await?;
+        Ok(block_info(&response.block_id, &response.block))
+    }
+
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        // Tendermint transaction hashes are 32 bytes.
+        let hash: H256 = (*hash).into();
+        let tendermint_hash = Hash::from_bytes(Algorithm::Sha256, hash.as_bytes())
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        let response = self
+            .rpc_client
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .tx(tendermint_hash, false)
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
+            .await?;
+        let tx = Tx::from_bytes(&response.tx).map_err(Into::<HyperlaneCosmosError>::into)?;
+
+        let gas_price = tx_gas_price(
+            &hash,
+            &tx,
+            &self.connection_conf.get_minimum_gas_price().denom,
+        );
+        let gas_used = U256::from(response.tx_result.gas_used.max(0) as u64);
+
+        Ok(TxnInfo {
+            hash: hash.into(),
+            gas_limit: U256::from(tx.auth_info.fee.gas_limit),
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_price,
+            nonce: tx
+                .auth_info
+                .signer_infos
+                .first()
+                .map(|signer_info| signer_info.sequence)
+                .unwrap_or_default(),
+            sender: tx_sender(&hash, &tx, &self.connection_conf.get_bech32_prefix())?,
+            recipient: self.recipient(&tx)?,
+            receipt: Some(TxnReceiptInfo {
+                gas_used,
+                cumulative_gas_used: gas_used,
+                effective_gas_price,
+            }),
+        })
     }
 
     async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
@@ -103,4 +282,102 @@ impl HyperlaneProvider for CosmosProvider {
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
         Ok(None)
     }
+
+    async fn get_block_number(&self) -> ChainResult<u64> {
+        self.rpc_client
+            .call(|client| {
+                let future = async move { client.get_block_number().await };
+                Box::pin(future)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmrs::{
+        crypto::{secp256k1::SigningKey, LegacyAminoMultisig, PublicKey},
+        tx::{AuthInfo, Body, Fee, SignerInfo},
+        Coin,
+    };
+    use hyperlane_core::utils::hex_or_base58_to_h256;
+
+    use super::*;
+
+    const PREFIX: &str = "neutron";
+
+    fn public_key() -> PublicKey {
+        let key = hex_or_base58_to_h256(
+            "0x5486418967eabc770b0fcb995f7ef6d9a72f7fc195531ef76c5109f44f51af26",
+        )
+        .unwrap();
+        SigningKey::from_slice(key.as_bytes()).unwrap().public_key()
+    }
+
+    fn tx(signer_public_key: SignerPublicKey, fee: Fee) -> Tx {
+        let signer_info = SignerInfo {
+            public_key: Some(signer_public_key),
+            ..SignerInfo::single_direct(None, 0)
+        };
+        Tx {
+            body: Body::new(Vec::<cosmrs::Any>::new(), "", 0u32),
+            auth_info: AuthInfo {
+                signer_infos: vec![signer_info],
+                fee,
+            },
+            signatures: vec![],
+        }
+    }
+
+    fn fee(denom: &str) -> Fee {
+        Fee::from_amount_and_gas(Coin::new(1_000_000, denom).unwrap(), 200_000u64)
+    }
+
+    #[test]
+    fn test_tx_gas_price() {
+        let tx = tx(SignerPublicKey::Single(public_key()), fee("untrn"));
+        assert_eq!(
+            tx_gas_price(&H256::zero(), &tx, "untrn"),
+            Some(U256::from(5))
+        );
+    }
+
+    #[test]
+    fn test_tx_gas_price_other_denom() {
+        let tx = tx(SignerPublicKey::Single(public_key()), fee("uatom"));
+        assert_eq!(tx_gas_price(&H256::zero(), &tx, "untrn"), None);
+    }
+
+    #[test]
+    fn test_tx_sender_single() {
+        let tx = tx(SignerPublicKey::Single(public_key()), fee("untrn"));
+        let expected = CosmosAddress::from_pubkey(public_key(), PREFIX)
+            .unwrap()
+            .digest();
+        assert_eq!(tx_sender(&H256::zero(), &tx, PREFIX).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_tx_sender_multisig() {
+        let multisig = SignerPublicKey::LegacyAminoMultisig(LegacyAminoMultisig {
+            threshold: 1,
+            public_keys: vec![public_key()],
+        });
+
+        // Without a fee payer, the sender falls back to the zero address
+        let tx_without_payer = tx(multisig.clone(), fee("untrn"));
+        assert_eq!(
+            tx_sender(&H256::zero(), &tx_without_payer, PREFIX).unwrap(),
+            H256::zero()
+        );
+
+        // With a fee payer, the payer is the sender
+        let payer = CosmosAddress::from_pubkey(public_key(), PREFIX).unwrap();
+        let mut tx_with_payer = tx(multisig, fee("untrn"));
+        tx_with_payer.auth_info.fee.payer = Some(payer.address().parse().unwrap());
+        assert_eq!(
+            tx_sender(&H256::zero(), &tx_with_payer, PREFIX).unwrap(),
+            payer.digest()
+        );
+    }
 }
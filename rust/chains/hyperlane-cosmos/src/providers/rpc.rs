@@ -1,20 +1,78 @@
 use async_trait::async_trait;
 use cosmrs::rpc::client::Client;
-use hyperlane_core::rpc_clients::call_with_retry;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use hyperlane_core::rpc_clients::{call_with_retry, BlockNumberGetter, FallbackProviderMetrics};
 use hyperlane_core::{ChainCommunicationError, ChainResult, ContractLocator, LogMeta, H256, U256};
 use sha256::digest;
 use std::fmt::Debug;
 use tendermint::abci::{Event, EventAttribute};
 use tendermint::hash::Algorithm;
 use tendermint::Hash;
+use tendermint_rpc::client::CompatMode;
 use tendermint_rpc::endpoint::block::Response as BlockResponse;
 use tendermint_rpc::endpoint::block_results::Response as BlockResultsResponse;
-use tendermint_rpc::HttpClient;
-use tracing::{debug, instrument, trace};
+use tendermint_rpc::event::{Event as RpcEvent, EventData};
+use tendermint_rpc::query::{EventType, Query};
+use tendermint_rpc::{HttpClient, SubscriptionClient, WebSocketClient};
+use tokio::sync::mpsc;
+use tracing::{debug, instrument, trace, warn};
+use url::Url;
 
 use crate::address::CosmosAddress;
+use crate::rpc_clients::CosmosFallbackProvider;
 use crate::{ConnectionConf, CosmosProvider, HyperlaneCosmosError};
 
+/// A Tendermint RPC client of one of a chain's RPC urls.
+#[derive(Debug, Clone)]
+pub struct CosmosRpcClient {
+    client: HttpClient,
+    /// The url that this client is connected to.
+    url: Url,
+}
+
+impl CosmosRpcClient {
+    /// Create a client of the given url.
+    pub fn new(url: Url) -> ChainResult<Self> {
+        let client = HttpClient::builder(
+            url.as_str()
+                .parse()
+                .map_err(Into::<HyperlaneCosmosError>::into)?,
+        )
+        // Consider supporting different compatibility modes.
+        .compat_mode(CompatMode::latest())
+        .build()
+        .map_err(Into::<HyperlaneCosmosError>::into)?;
+        Ok(Self { client, url })
+    }
+
+    /// Get the Tendermint client
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+
+    /// The host and port of the url this client is connected to, used to label metrics.
+    pub(crate) fn node_host(&self) -> String {
+        match (self.url.host_str(), self.url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => "unknown".to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+impl BlockNumberGetter for CosmosRpcClient {
+    async fn get_block_number(&self) -> Result<u64, ChainCommunicationError> {
+        let status = self
+            .client
+            .status()
+            .await
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        Ok(status.sync_info.latest_block_height.value())
+    }
+}
+
 #[async_trait]
 /// Trait for wasm indexer. Use rpc provider
 pub trait WasmIndexer: Send + Sync {
@@ -30,6 +88,19 @@ pub trait WasmIndexer: Send + Sync {
     ) -> ChainResult<Vec<(T, LogMeta)>>
     where
         T: Send + Sync + PartialEq + Debug + 'static;
+
+    /// Get the hash of the canonical block at the given height.
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<H256>;
+
+    /// Subscribe to logs parsed with the given parser as they are emitted.
+    /// Returns `None` if no websocket url is configured to subscribe with.
+    async fn subscribe_logs<T>(
+        &self,
+        parser: for<'a> fn(&'a Vec<EventAttribute>) -> ChainResult<ParsedEvent<T>>,
+        cursor_label: &'static str,
+    ) -> ChainResult<Option<BoxStream<'static, ChainResult<(T, LogMeta)>>>>
+    where
+        T: Send + Sync + PartialEq + Debug + 'static;
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -60,6 +131,7 @@ pub struct CosmosWasmIndexer {
     provider: CosmosProvider,
     contract_address: CosmosAddress,
     target_event_kind: String,
+    websocket_url: Option<Url>,
     reorg_period: u32,
 }
 
@@ -72,12 +144,14 @@ impl CosmosWasmIndexer {
         locator: ContractLocator,
         event_type: String,
         reorg_period: u32,
+        metrics: Option<FallbackProviderMetrics>,
     ) -> ChainResult<Self> {
         let provider = CosmosProvider::new(
             locator.domain.clone(),
             conf.clone(),
             Some(locator.clone()),
             None,
+            metrics,
         )?;
         Ok(Self {
             provider,
@@ -87,32 +161,80 @@ impl CosmosWasmIndexer {
                 conf.get_contract_address_bytes(),
             )?,
             target_event_kind: format!("{}-{}", Self::WASM_TYPE, event_type),
+            websocket_url: conf.get_websocket_url(),
             reorg_period,
         })
     }
 
-    async fn get_block(client: HttpClient, block_number: u32) -> ChainResult<BlockResponse> {
-        Ok(client
-            .block(block_number)
+    async fn get_block(
+        provider: CosmosFallbackProvider<CosmosRpcClient>,
+        block_number: u32,
+    ) -> ChainResult<BlockResponse> {
+        provider
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .block(block_number)
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
             .await
-            .map_err(Into::<HyperlaneCosmosError>::into)?)
     }
 
     async fn get_block_results(
-        client: HttpClient,
+        provider: CosmosFallbackProvider<CosmosRpcClient>,
         block_number: u32,
     ) -> ChainResult<BlockResultsResponse> {
-        Ok(client
-            .block_results(block_number)
+        provider
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .block_results(block_number)
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
             .await
-            .map_err(Into::<HyperlaneCosmosError>::into)?)
     }
 
-    async fn get_latest_block(client: HttpClient) -> ChainResult<BlockResponse> {
-        Ok(client
-            .latest_block()
+    async fn get_latest_block(
+        provider: CosmosFallbackProvider<CosmosRpcClient>,
+    ) -> ChainResult<BlockResponse> {
+        provider
+            .call(move |client| {
+                let future = async move {
+                    Ok(client
+                        .client()
+                        .latest_block()
+                        .await
+                        .map_err(Into::<HyperlaneCosmosError>::into)?)
+                };
+                Box::pin(future)
+            })
             .await
-            .map_err(Into::<HyperlaneCosmosError>::into)?)
+    }
+
+    async fn connect_websocket(url: &Url) -> ChainResult<WebSocketClient> {
+        let (client, driver) = WebSocketClient::builder(
+            url.as_str()
+                .parse()
+                .map_err(Into::<HyperlaneCosmosError>::into)?,
+        )
+        .compat_mode(CompatMode::latest())
+        .build()
+        .await
+        .map_err(Into::<HyperlaneCosmosError>::into)?;
+        tokio::spawn(async move {
+            if let Err(err) = driver.run().await {
+                warn!(?err, "Tendermint websocket driver stopped");
+            }
+        });
+        Ok(client)
     }
 }
 
@@ -164,6 +286,43 @@ impl CosmosWasmIndexer {
             .collect()
     }
 
+    // Parse the target events out of a tx pushed by a websocket subscription.
+    async fn handle_subscribed_tx<T>(
+        &self,
+        event: RpcEvent,
+        parser: for<'a> fn(&'a Vec<EventAttribute>) -> ChainResult<ParsedEvent<T>>,
+    ) -> ChainResult<Vec<(T, LogMeta)>>
+    where
+        T: PartialEq + 'static,
+    {
+        let EventData::Tx { tx_result } = event.data else {
+            return Ok(vec![]);
+        };
+        let block_number: u32 = tx_result
+            .height
+            .try_into()
+            .map_err(ChainCommunicationError::from_other)?;
+        let tx_hash = hex::decode(digest(tx_result.tx.as_slice()))
+            .map(|hash| H256::from_slice(&hash))
+            .map_err(ChainCommunicationError::from_other)?;
+        // The event only carries the height of the block the tx was included in,
+        // so the block is needed to fill in its hash.
+        let client = self.provider.rpc().clone();
+        let block =
+            call_with_retry(|| Box::pin(Self::get_block(client.clone(), block_number))).await?;
+        let transaction_index = tx_result.index.unwrap_or_default() as usize;
+
+        Ok(self
+            .handle_tx(
+                block,
+                tx_result.result.events,
+                tx_hash,
+                transaction_index,
+                parser,
+            )
+            .collect())
+    }
+
     // Iter through all events in the tx, looking for any target events
     // made by the contract we are indexing.
     fn handle_tx<T>(
@@ -249,4 +408,73 @@ impl WasmIndexer for CosmosWasmIndexer {
 
         Ok(self.handle_txs(block?, block_results?, parser, cursor_label))
     }
+
+    #[instrument(err, skip(self))]
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<H256> {
+        let client = self.provider.rpc().clone();
+        let block_number: u32 = block_number
+            .try_into()
+            .map_err(ChainCommunicationError::from_other)?;
+        let block =
+            call_with_retry(|| Box::pin(Self::get_block(client.clone(), block_number))).await?;
+        Ok(H256::from_slice(block.block_id.hash.as_bytes()))
+    }
+
+    #[instrument(err, skip(self, parser))]
+    async fn subscribe_logs<T>(
+        &self,
+        parser: for<'a> fn(&'a Vec<EventAttribute>) -> ChainResult<ParsedEvent<T>>,
+        cursor_label: &'static str,
+    ) -> ChainResult<Option<BoxStream<'static, ChainResult<(T, LogMeta)>>>>
+    where
+        T: Send + Sync + PartialEq + Debug + 'static,
+    {
+        let Some(websocket_url) = &self.websocket_url else {
+            return Ok(None);
+        };
+        let client = Self::connect_websocket(websocket_url).await?;
+        // Only subscribe to txs containing target events emitted by the contract we are
+        // indexing, rather than to every tx on the chain.
+        let query = Query::from(EventType::Tx).and_eq(
+            format!("{}._contract_address", self.target_event_kind),
+            self.contract_address.address(),
+        );
+        let mut subscription = client
+            .subscribe(query)
+            .await
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        debug!(cursor_label, domain=?self.provider.domain, "Subscribed to logs");
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let indexer = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = subscription.next().await {
+                let logs = match event {
+                    Ok(event) => indexer.handle_subscribed_tx(event, parser).await,
+                    Err(err) => Err(HyperlaneCosmosError::from(err).into()),
+                };
+                let logs = match logs {
+                    Ok(logs) => logs.into_iter().map(Ok).collect(),
+                    Err(err) => vec![Err(err)],
+                };
+                for log in logs {
+                    if sender.send(log).is_err() {
+                        debug!(
+                            cursor_label,
+                            "Log subscription receiver dropped, unsubscribing"
+                        );
+                        let _ = client.close();
+                        return;
+                    }
+                }
+            }
+            let _ = client.close();
+        });
+        Ok(Some(
+            stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|log| (log, receiver))
+            })
+            .boxed(),
+        ))
+    }
 }
@@ -4,7 +4,12 @@ use std::{
 };
 
 use derive_new::new;
-use hyperlane_core::rpc_clients::FallbackProvider;
+use hyperlane_core::rpc_clients::{is_provider_error, FallbackProvider};
+use hyperlane_core::ChainCommunicationError;
+use tendermint_rpc::error::ErrorDetail;
+use tonic::Code;
+
+use crate::HyperlaneCosmosError;
 
 /// Wrapper of `FallbackProvider` for use in `hyperlane-cosmos`
 #[derive(new, Clone)]
@@ -29,6 +34,35 @@ where
     }
 }
 
+/// Tells errors of the Cosmos sub-providers apart from errors of the requests, e.g. an invalid
+/// transaction or an error response from the node, which don't count against the provider.
+pub fn is_cosmos_provider_error(err: &ChainCommunicationError) -> bool {
+    let ChainCommunicationError::Other(other) = err else {
+        return is_provider_error(err);
+    };
+    let status = other.downcast_ref::<tonic::Status>().or_else(|| {
+        match other.downcast_ref::<HyperlaneCosmosError>() {
+            Some(HyperlaneCosmosError::GrpcError(status)) => Some(status),
+            _ => None,
+        }
+    });
+    if let Some(status) = status {
+        return !matches!(
+            status.code(),
+            Code::InvalidArgument
+                | Code::FailedPrecondition
+                | Code::OutOfRange
+                | Code::AlreadyExists
+        );
+    }
+    if let Some(HyperlaneCosmosError::TendermintError(err)) =
+        other.downcast_ref::<HyperlaneCosmosError>()
+    {
+        return !matches!(err.detail(), ErrorDetail::Response(_));
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -88,6 +122,81 @@ mod tests {
             .await?;
             Ok(())
         }
+
+        /// Like `low_level_test_call`, but providers with a request sleep configured fail.
+        async fn failing_test_call(&self) -> Result<(), ChainCommunicationError> {
+            self.call(|provider| {
+                provider.push("GET", "http://localhost:1234");
+                let future = async move {
+                    if provider.request_sleep().is_some() {
+                        return Err(ChainCommunicationError::from_other_str("request failed"));
+                    }
+                    Ok(())
+                };
+                Box::pin(future)
+            })
+            .await
+        }
+    }
+
+    fn cosmos_fallback_provider(
+        providers: Vec<CosmosProviderMock>,
+    ) -> CosmosFallbackProvider<CosmosProviderMock> {
+        let fallback_provider = FallbackProviderBuilder::default()
+            .add_providers(providers)
+            .with_error_classifier(is_cosmos_provider_error)
+            .build();
+        CosmosFallbackProvider::new(fallback_provider)
+    }
+
+    #[test]
+    fn test_is_cosmos_provider_error() {
+        assert!(is_cosmos_provider_error(
+            &ChainCommunicationError::from_other(tonic::Status::unavailable("connection refused"))
+        ));
+        assert!(is_cosmos_provider_error(
+            &HyperlaneCosmosError::GrpcError(tonic::Status::deadline_exceeded("timed out")).into()
+        ));
+        assert!(is_cosmos_provider_error(
+            &ChainCommunicationError::from_other_str("request failed")
+        ));
+        assert!(!is_cosmos_provider_error(
+            &ChainCommunicationError::from_other(tonic::Status::invalid_argument(
+                "query wasm contract failed"
+            ))
+        ));
+        assert!(!is_cosmos_provider_error(
+            &HyperlaneCosmosError::GrpcError(tonic::Status::failed_precondition(
+                "account sequence mismatch"
+            ))
+            .into()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_are_not_retried() {
+        let cosmos_fallback_provider = cosmos_fallback_provider(vec![
+            CosmosProviderMock::default(),
+            CosmosProviderMock::default(),
+        ]);
+        for _ in 0..3 {
+            let result: Result<(), _> = cosmos_fallback_provider
+                .call(|provider| {
+                    provider.push("GET", "http://localhost:1234");
+                    Box::pin(async {
+                        Err(ChainCommunicationError::from_other(
+                            tonic::Status::invalid_argument("query wasm contract failed"),
+                        ))
+                    })
+                })
+                .await;
+            assert!(result.is_err());
+        }
+
+        // The healthy first provider isn't deprioritized by the failing requests
+        let provider_call_count: Vec<_> =
+            ProviderMock::get_call_counts(&cosmos_fallback_provider).await;
+        assert_eq!(provider_call_count, vec![3, 0]);
     }
 
     #[tokio::test]
@@ -131,4 +240,21 @@ mod tests {
             ProviderMock::get_call_counts(&cosmos_fallback_provider).await;
         assert_eq!(provider_call_count, vec![0, 0, 1]);
     }
+
+    #[tokio::test]
+    async fn test_failing_provider_is_deprioritized() {
+        let cosmos_fallback_provider = cosmos_fallback_provider(vec![
+            CosmosProviderMock::new(Some(Duration::from_millis(0))),
+            CosmosProviderMock::default(),
+        ]);
+        // The failing provider's error rate drops it below the healthy provider,
+        // so it isn't called by the remaining requests.
+        for _ in 0..5 {
+            cosmos_fallback_provider.failing_test_call().await.unwrap();
+        }
+
+        let provider_call_count: Vec<_> =
+            ProviderMock::get_call_counts(&cosmos_fallback_provider).await;
+        assert_eq!(provider_call_count, vec![5, 1]);
+    }
 }
@@ -9,8 +9,11 @@ use url::Url;
 pub struct ConnectionConf {
     /// The GRPC url to connect to
     grpc_urls: Vec<Url>,
-    /// The RPC url to connect to
-    rpc_url: String,
+    /// The Tendermint RPC urls to connect to, in order of priority
+    rpc_urls: Vec<Url>,
+    /// The Tendermint websocket url to subscribe to events with, if any.
+    /// Indexers only poll the RPC url for events if this isn't set.
+    websocket_url: Option<Url>,
     /// The chain ID
     chain_id: String,
     /// The human readable address prefix for the chains using bech32.
@@ -83,9 +86,14 @@ impl ConnectionConf {
         self.grpc_urls.clone()
     }
 
-    /// Get the RPC url
-    pub fn get_rpc_url(&self) -> String {
-        self.rpc_url.clone()
+    /// Get the RPC urls
+    pub fn get_rpc_urls(&self) -> Vec<Url> {
+        self.rpc_urls.clone()
+    }
+
+    /// Get the websocket url
+    pub fn get_websocket_url(&self) -> Option<Url> {
+        self.websocket_url.clone()
     }
 
     /// Get the chain ID
@@ -117,7 +125,8 @@ impl ConnectionConf {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         grpc_urls: Vec<Url>,
-        rpc_url: String,
+        rpc_urls: Vec<Url>,
+        websocket_url: Option<Url>,
         chain_id: String,
         bech32_prefix: String,
         canonical_asset: String,
@@ -127,7 +136,8 @@ impl ConnectionConf {
     ) -> Self {
         Self {
             grpc_urls,
-            rpc_url,
+            rpc_urls,
+            websocket_url,
             chain_id,
             bech32_prefix,
             canonical_asset,
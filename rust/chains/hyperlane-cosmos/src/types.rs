@@ -36,5 +36,6 @@ pub fn tx_response_to_outcome(response: TxResponse) -> ChainResult<TxOutcome> {
         executed: response.code == 0,
         gas_used: U256::from(response.gas_used),
         gas_price: U256::one().try_into()?,
+        l1_data_fee: None,
     })
 }
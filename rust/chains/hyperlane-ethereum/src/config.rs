@@ -1,4 +1,6 @@
-use hyperlane_core::{config::OperationBatchConfig, U256};
+use std::collections::HashMap;
+
+use hyperlane_core::{config::OperationBatchConfig, rpc_clients::RpcRateLimiters, U256};
 use url::Url;
 
 /// Ethereum RPC connection configuration
@@ -8,6 +10,8 @@ pub enum RpcConnectionConf {
     HttpQuorum {
         /// List of urls to connect to
         urls: Vec<Url>,
+        /// Weights and per-method policies of the quorum
+        quorum: QuorumConf,
     },
     /// An HTTP-only fallback set.
     HttpFallback {
@@ -26,6 +30,86 @@ pub enum RpcConnectionConf {
     },
 }
 
+/// The agreement a quorum provider requires before returning a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuorumPolicy {
+    /// Providers with more than half of the total weight have to agree.
+    #[default]
+    Majority,
+    /// All providers have to agree.
+    All,
+    /// Providers with at least this combined weight have to agree.
+    Weight(u64),
+    /// Return the first successful response, trying providers in order of
+    /// weight as a fallback provider would.
+    First,
+}
+
+/// The quorum policy of a JSON-RPC method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodQuorumPolicy {
+    /// The JSON-RPC method, e.g. `eth_call`
+    pub method: String,
+    /// Only apply the policy to `eth_call`s of the function with this 4 byte
+    /// selector, as a lowercase `0x` prefixed hex string.
+    pub selector: Option<String>,
+    /// The policy to apply
+    pub policy: QuorumPolicy,
+}
+
+/// Configuration of a quorum of providers.
+#[derive(Debug, Clone, Default)]
+pub struct QuorumConf {
+    /// The weight of each url's response. Urls without a weight have a weight
+    /// of 1.
+    pub weights: HashMap<Url, u64>,
+    /// The policy of methods without a policy of their own.
+    pub default_policy: QuorumPolicy,
+    /// Per-method policies, which take precedence over the defaults for
+    /// methods whose responses legitimately differ between providers.
+    pub method_policies: Vec<MethodQuorumPolicy>,
+}
+
+/// Methods which, unless configured otherwise, return the first successful
+/// response because their responses legitimately differ between providers.
+const FIRST_RESPONSE_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_gasPrice",
+    "eth_maxPriorityFeePerGas",
+    "eth_feeHistory",
+    "eth_estimateGas",
+    "eth_sendRawTransaction",
+];
+
+impl QuorumConf {
+    /// The weight of the response of the provider at `url`.
+    pub fn weight(&self, url: &Url) -> u64 {
+        self.weights.get(url).copied().unwrap_or(1)
+    }
+
+    /// The policy to use for a request of `method`, which for `eth_call`s is
+    /// to the function with the given `selector`.
+    pub fn policy(&self, method: &str, selector: Option<&str>) -> QuorumPolicy {
+        let matching = |with_selector: bool| {
+            self.method_policies.iter().find(|p| {
+                p.method == method
+                    && match (&p.selector, selector) {
+                        (Some(expected), Some(actual)) => with_selector && expected == actual,
+                        (None, _) => !with_selector,
+                        (Some(_), None) => false,
+                    }
+            })
+        };
+        if let Some(p) = matching(true).or_else(|| matching(false)) {
+            p.policy
+        } else if FIRST_RESPONSE_METHODS.contains(&method) {
+            QuorumPolicy::First
+        } else {
+            self.default_policy
+        }
+    }
+}
+
 /// Ethereum connection configuration
 #[derive(Debug, Clone)]
 pub struct ConnectionConf {
@@ -35,6 +119,9 @@ pub struct ConnectionConf {
     pub transaction_overrides: TransactionOverrides,
     /// Operation batching configuration
     pub operation_batch: OperationBatchConfig,
+    /// Rate limiters of the RPC urls, shared by all providers built from this
+    /// config. Websocket connections aren't rate limited.
+    pub rate_limiters: RpcRateLimiters,
 }
 
 /// Ethereum transaction overrides.
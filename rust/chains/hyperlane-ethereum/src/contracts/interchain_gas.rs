@@ -6,19 +6,23 @@ use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use ethers::prelude::Middleware;
+use ethers::prelude::{Middleware, Provider, Ws};
 use hyperlane_core::{
     ChainCommunicationError, ChainResult, ContractLocator, HyperlaneAbi, HyperlaneChain,
     HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer,
-    InterchainGasPaymaster, InterchainGasPayment, LogMeta, SequenceAwareIndexer, H160, H256,
+    InterchainGasPaymaster, InterchainGasPayment, LogMeta, LogStream, SequenceAwareIndexer, H160,
+    H256,
 };
 use tracing::instrument;
 
 use crate::interfaces::i_interchain_gas_paymaster::{
-    IInterchainGasPaymaster as EthereumInterchainGasPaymasterInternal, IINTERCHAINGASPAYMASTER_ABI,
+    GasPaymentFilter, IInterchainGasPaymaster as EthereumInterchainGasPaymasterInternal,
+    IINTERCHAINGASPAYMASTER_ABI,
 };
 use crate::{BuildableWithProvider, ConnectionConf, EthereumProvider};
 
+use super::subscription::{connect_subscription_provider, subscribe_logs};
+
 impl<M> Display for EthereumInterchainGasPaymasterInternal<M>
 where
     M: Middleware,
@@ -40,11 +44,12 @@ impl BuildableWithProvider for InterchainGasPaymasterIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
         Box::new(EthereumInterchainGasPaymasterIndexer::new(
             Arc::new(provider),
+            connect_subscription_provider(conn).await,
             locator,
             self.reorg_period,
         ))
@@ -59,6 +64,8 @@ where
 {
     contract: Arc<EthereumInterchainGasPaymasterInternal<M>>,
     provider: Arc<M>,
+    /// Websocket provider used to subscribe to logs, if one is configured
+    subscription_provider: Option<Arc<Provider<Ws>>>,
     reorg_period: u32,
 }
 
@@ -67,18 +74,33 @@ where
     M: Middleware + 'static,
 {
     /// Create new EthereumInterchainGasPaymasterIndexer
-    pub fn new(provider: Arc<M>, locator: &ContractLocator, reorg_period: u32) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        subscription_provider: Option<Arc<Provider<Ws>>>,
+        locator: &ContractLocator,
+        reorg_period: u32,
+    ) -> Self {
         Self {
             contract: Arc::new(EthereumInterchainGasPaymasterInternal::new(
                 locator.address,
                 provider.clone(),
             )),
             provider,
+            subscription_provider,
             reorg_period,
         }
     }
 }
 
+fn gas_payment_from_log(log: GasPaymentFilter) -> Indexed<InterchainGasPayment> {
+    Indexed::new(InterchainGasPayment {
+        message_id: H256::from(log.message_id),
+        destination: log.destination_domain,
+        payment: log.payment.into(),
+        gas_amount: log.gas_amount.into(),
+    })
+}
+
 #[async_trait]
 impl<M> Indexer<InterchainGasPayment> for EthereumInterchainGasPaymasterIndexer<M>
 where
@@ -100,20 +122,20 @@ where
 
         Ok(events
             .into_iter()
-            .map(|(log, log_meta)| {
-                (
-                    Indexed::new(InterchainGasPayment {
-                        message_id: H256::from(log.message_id),
-                        destination: log.destination_domain,
-                        payment: log.payment.into(),
-                        gas_amount: log.gas_amount.into(),
-                    }),
-                    log_meta.into(),
-                )
-            })
+            .map(|(log, log_meta)| (gas_payment_from_log(log), log_meta.into()))
             .collect())
     }
 
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<InterchainGasPayment>>> {
+        Ok(self.subscription_provider.clone().map(|provider| {
+            subscribe_logs(
+                provider,
+                self.contract.gas_payment_filter().filter,
+                gas_payment_from_log,
+            )
+        }))
+    }
+
     #[instrument(level = "debug", err, ret, skip(self))]
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
         Ok(self
@@ -4,31 +4,38 @@
 use std::collections::HashMap;
 use std::num::NonZeroU64;
 use std::ops::RangeInclusive;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use ethers::abi::{AbiEncode, Detokenize};
-use ethers::prelude::Middleware;
+use ethers::prelude::{Middleware, Provider, Ws};
+use ethers::types::Signature;
 use ethers_contract::builders::ContractCall;
+use ethers_contract::{parse_log, LogMeta as EthersLogMeta};
 use futures_util::future::join_all;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
 use hyperlane_core::{
-    utils::bytes_to_hex, BatchItem, ChainCommunicationError, ChainResult, ContractLocator,
-    HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage,
-    HyperlaneProtocolError, HyperlaneProvider, Indexed, Indexer, LogMeta, Mailbox,
-    RawHyperlaneMessage, SequenceAwareIndexer, TxCostEstimate, TxOutcome, H160, H256, U256,
+    utils::bytes_to_hex, BatchItem, BlockFinality, ChainCommunicationError, ChainResult,
+    ContractLocator, HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
+    HyperlaneMessage, HyperlaneProtocolError, HyperlaneProvider, Indexed, Indexer, LogMeta,
+    LogStream, Mailbox, RawHyperlaneMessage, SequenceAwareIndexer, TxCostEstimate, TxOutcome, H160,
+    H256, H512, U256,
 };
 
 use crate::error::HyperlaneEthereumError;
 use crate::interfaces::arbitrum_node_interface::ArbitrumNodeInterface;
 use crate::interfaces::i_mailbox::{
-    IMailbox as EthereumMailboxInternal, ProcessCall, IMAILBOX_ABI,
+    DispatchFilter, IMailbox as EthereumMailboxInternal, ProcessCall, ProcessIdFilter, IMAILBOX_ABI,
 };
-use crate::tx::{call_with_lag, fill_tx_gas_params, report_tx};
+use crate::interfaces::op_gas_price_oracle::OpGasPriceOracle;
+use crate::tx::{call_at_finality, call_with_lag, fill_tx_gas_params, report_tx};
 use crate::{BuildableWithProvider, ConnectionConf, EthereumProvider, TransactionOverrides};
 
 use super::multicall::{self, build_multicall};
+use super::subscription::{connect_subscription_provider, subscribe_logs};
+use super::utils::get_block_hash;
 
 impl<M> std::fmt::Display for EthereumMailboxInternal<M>
 where
@@ -50,11 +57,12 @@ impl BuildableWithProvider for SequenceIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
         Box::new(EthereumMailboxIndexer::new(
             Arc::new(provider),
+            connect_subscription_provider(conn).await,
             locator,
             self.reorg_period,
         ))
@@ -72,11 +80,12 @@ impl BuildableWithProvider for DeliveryIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
         Box::new(EthereumMailboxIndexer::new(
             Arc::new(provider),
+            connect_subscription_provider(conn).await,
             locator,
             self.reorg_period,
         ))
@@ -91,6 +100,8 @@ where
 {
     contract: Arc<EthereumMailboxInternal<M>>,
     provider: Arc<M>,
+    /// Websocket provider used to subscribe to logs, if one is configured
+    subscription_provider: Option<Arc<Provider<Ws>>>,
     reorg_period: u32,
 }
 
@@ -99,7 +110,12 @@ where
     M: Middleware + 'static,
 {
     /// Create new EthereumMailboxIndexer
-    pub fn new(provider: Arc<M>, locator: &ContractLocator, reorg_period: u32) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        subscription_provider: Option<Arc<Provider<Ws>>>,
+        locator: &ContractLocator,
+        reorg_period: u32,
+    ) -> Self {
         let contract = Arc::new(EthereumMailboxInternal::new(
             locator.address,
             provider.clone(),
@@ -107,6 +123,7 @@ where
         Self {
             contract,
             provider,
+            subscription_provider,
             reorg_period,
         }
     }
@@ -157,6 +174,47 @@ where
         events.sort_by(|a, b| a.0.inner().nonce.cmp(&b.0.inner().nonce));
         Ok(events)
     }
+
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        get_block_hash(self.provider.as_ref(), block_number).await
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<HyperlaneMessage>>> {
+        Ok(self.subscription_provider.clone().map(|provider| {
+            subscribe_logs(
+                provider,
+                self.contract.dispatch_filter().filter,
+                |event: DispatchFilter| HyperlaneMessage::from(event.message.to_vec()).into(),
+            )
+        }))
+    }
+
+    #[instrument(err, skip(self))]
+    async fn fetch_logs_by_tx_hash(
+        &self,
+        tx_hash: H512,
+    ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
+        let tx_hash: H256 = tx_hash.into();
+        let Some(receipt) = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+        else {
+            return Ok(vec![]);
+        };
+        Ok(receipt
+            .logs
+            .into_iter()
+            .filter(|log| log.address == self.contract.address())
+            .filter_map(|log| {
+                let meta: LogMeta = EthersLogMeta::from(&log).into();
+                // The mailbox emits other events alongside `Dispatch`, e.g. `DispatchId`
+                let event = parse_log::<DispatchFilter>(log).ok()?;
+                Some((HyperlaneMessage::from(event.message.to_vec()).into(), meta))
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -198,6 +256,20 @@ where
             .map(|(event, meta)| (Indexed::new(H256::from(event.message_id)), meta.into()))
             .collect())
     }
+
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        get_block_hash(self.provider.as_ref(), block_number).await
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<H256>>> {
+        Ok(self.subscription_provider.clone().map(|provider| {
+            subscribe_logs(
+                provider,
+                self.contract.process_id_filter().filter,
+                |event: ProcessIdFilter| Indexed::new(H256::from(event.message_id)),
+            )
+        }))
+    }
 }
 
 #[async_trait]
@@ -229,6 +301,14 @@ impl BuildableWithProvider for MailboxBuilder {
     }
 }
 
+/// A signature with the largest possible `r` and `s`, to encode transactions
+/// whose L1 data fee is quoted before they are signed
+const DUMMY_SIGNATURE: Signature = Signature {
+    r: ethers::types::U256::MAX,
+    s: ethers::types::U256::MAX,
+    v: 1,
+};
+
 /// A reference to a Mailbox contract on some Ethereum chain
 #[derive(Debug)]
 pub struct EthereumMailbox<M>
@@ -239,6 +319,7 @@ where
     domain: HyperlaneDomain,
     provider: Arc<M>,
     arbitrum_node_interface: Option<Arc<ArbitrumNodeInterface<M>>>,
+    op_gas_price_oracle: Option<Arc<OpGasPriceOracle<M>>>,
     conn: ConnectionConf,
 }
 
@@ -260,6 +341,17 @@ where
             ))
         });
 
+        // OP Stack chains charge a fee for posting the transaction data to L1 on top
+        // of the L2 gas, which eth_estimateGas does not account for. The GasPriceOracle
+        // predeploy, found at address(0x420000000000000000000000000000000000000F), quotes it.
+        // See https://docs.optimism.io/stack/transactions/fees#l1-data-fee
+        let op_gas_price_oracle = locator.domain.is_op_stack().then(|| {
+            Arc::new(OpGasPriceOracle::new(
+                H160::from_str("0x420000000000000000000000000000000000000F").unwrap(),
+                provider.clone(),
+            ))
+        });
+
         Self {
             contract: Arc::new(EthereumMailboxInternal::new(
                 locator.address,
@@ -268,6 +360,7 @@ where
             domain: locator.domain.clone(),
             provider,
             arbitrum_node_interface,
+            op_gas_price_oracle,
             conn: conn.clone(),
         }
     }
@@ -304,6 +397,52 @@ where
         };
         fill_tx_gas_params(tx, self.provider.clone(), &tx_overrides).await
     }
+
+    /// Quotes the fee for posting the transaction data to L1 from the rollup's
+    /// gas price oracle. Returns None if the chain isn't a supported rollup, or
+    /// if the quote fails, in which case the estimate leaves the fee out.
+    ///
+    /// On Arbitrum Nitro chains the L1 fee is paid by the gas above the L2 gas
+    /// limit, which gas payment enforcement used to leave out. Quoting it here
+    /// intentionally makes enforcement require payment for it too, like on OP
+    /// Stack chains.
+    async fn estimate_l1_data_fee<D: Detokenize>(
+        &self,
+        contract_call: &ContractCall<M, D>,
+    ) -> Option<U256> {
+        if let Some(op_gas_price_oracle) = &self.op_gas_price_oracle {
+            // The fee is charged for the signed transaction, so quote it with a
+            // stand-in signature of the same size
+            let signed_tx = contract_call.tx.rlp_signed(&DUMMY_SIGNATURE);
+            return match op_gas_price_oracle.get_l1_fee(signed_tx).call().await {
+                Ok(l1_fee) => Some(l1_fee.into()),
+                Err(err) => {
+                    warn!(error=?err, "Failed to quote the L1 data fee from the gas price oracle");
+                    None
+                }
+            };
+        }
+        if let Some(arbitrum_node_interface) = &self.arbitrum_node_interface {
+            return match arbitrum_node_interface
+                .gas_estimate_l1_component(
+                    self.contract.address(),
+                    false,
+                    contract_call.calldata().unwrap_or_default(),
+                )
+                .call()
+                .await
+            {
+                Ok((gas_estimate_for_l1, base_fee, _)) => {
+                    Some(base_fee.saturating_mul(gas_estimate_for_l1.into()).into())
+                }
+                Err(err) => {
+                    warn!(error=?err, "Failed to quote the L1 data fee from the node interface");
+                    None
+                }
+            };
+        }
+        None
+    }
 }
 
 impl<M> HyperlaneChain for EthereumMailbox<M>
@@ -348,6 +487,13 @@ where
         Ok(self.contract.delivered(id.into()).call().await?)
     }
 
+    #[instrument(skip(self))]
+    async fn delivered_at_finality(&self, id: H256, finality: BlockFinality) -> ChainResult<bool> {
+        let call =
+            call_at_finality(self.contract.delivered(id.into()), &self.provider, finality).await?;
+        Ok(call.call().await?)
+    }
+
     #[instrument(skip(self))]
     async fn default_ism(&self) -> ChainResult<H256> {
         Ok(self.contract.default_ism().call().await?.into())
@@ -449,10 +595,13 @@ where
             .map_err(ChainCommunicationError::from_other)?
             .into();
 
+        let l1_data_fee = self.estimate_l1_data_fee(&contract_call).await;
+
         Ok(TxCostEstimate {
             gas_limit: gas_limit.into(),
             gas_price: gas_price.try_into()?,
             l2_gas_limit: l2_gas_limit.map(|v| v.into()),
+            l1_data_fee,
         })
     }
 
@@ -478,16 +627,17 @@ impl HyperlaneAbi for EthereumMailboxAbi {
 
 #[cfg(test)]
 mod test {
-    use std::{str::FromStr, sync::Arc};
+    use std::{num::NonZeroU64, str::FromStr, sync::Arc};
 
     use ethers::{
+        abi::{self, Token},
         providers::{MockProvider, Provider},
-        types::{Block, Transaction, U256 as EthersU256},
+        types::{Block, BlockId, BlockNumber, Bytes, Transaction, U256 as EthersU256, U64},
     };
 
     use hyperlane_core::{
-        ContractLocator, HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, Mailbox,
-        TxCostEstimate, H160, H256, U256,
+        BlockFinality, ContractLocator, HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain,
+        Mailbox, TxCostEstimate, H160, H256, U256,
     };
 
     use crate::{contracts::EthereumMailbox, ConnectionConf, RpcConnectionConf};
@@ -505,6 +655,7 @@ mod test {
             },
             transaction_overrides: Default::default(),
             operation_batch: Default::default(),
+            rate_limiters: Default::default(),
         };
 
         let mailbox = EthereumMailbox::new(
@@ -532,6 +683,17 @@ mod test {
         // order, so we start with the final RPCs and work toward the first
         // RPCs
 
+        // RPC 5: eth_call to the ArbitrumNodeInterface's gasEstimateL1Component function by process_estimate_costs
+        // Return 50k L1 gas at a 0.1 gwei base fee
+        let gas_estimate_for_l1 = 50000u64;
+        let base_fee = EthersU256::from(ethers::utils::parse_units("0.1", "gwei").unwrap());
+        let l1_component = abi::encode(&[
+            Token::Uint(gas_estimate_for_l1.into()),
+            Token::Uint(base_fee),
+            Token::Uint(EthersU256::zero()),
+        ]);
+        mock_provider.push(Bytes::from(l1_component)).unwrap();
+
         // RPC 4: eth_gasPrice by process_estimate_costs
         // Return 15 gwei
         let gas_price: U256 =
@@ -564,7 +726,200 @@ mod test {
                 gas_limit: estimated_gas_limit,
                 gas_price: gas_price.try_into().unwrap(),
                 l2_gas_limit: Some(l2_gas_limit),
+                l1_data_fee: Some((base_fee * gas_estimate_for_l1).into()),
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_estimate_costs_sets_l1_data_fee_for_op_stack() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let provider = Arc::new(Provider::new(mock_provider.clone()));
+        let connection_conf = ConnectionConf {
+            rpc_connection: RpcConnectionConf::Http {
+                url: "http://127.0.0.1:8545".parse().unwrap(),
+            },
+            transaction_overrides: Default::default(),
+            operation_batch: Default::default(),
+            rate_limiters: Default::default(),
+        };
+
+        let mailbox = EthereumMailbox::new(
+            provider.clone(),
+            &connection_conf,
+            &ContractLocator {
+                // An OP Stack chain
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::Optimism),
+                // Address doesn't matter because we're using a MockProvider
+                address: H256::default(),
+            },
+        );
+
+        let message = HyperlaneMessage::default();
+        let metadata: Vec<u8> = vec![];
+
+        assert!(mailbox.arbitrum_node_interface.is_none());
+        assert!(mailbox.op_gas_price_oracle.is_some());
+        assert_eq!(
+            H160::from(mailbox.op_gas_price_oracle.as_ref().unwrap().address()),
+            H160::from_str("0x420000000000000000000000000000000000000F").unwrap(),
+        );
+
+        // The MockProvider responses we push are processed in LIFO
+        // order, so we start with the final RPCs and work toward the first
+        // RPCs
+
+        // RPC 4: eth_call to the GasPriceOracle's getL1Fee function by process_estimate_costs
+        // Return 0.0001 ETH
+        let l1_fee = EthersU256::from(ethers::utils::parse_units("0.0001", "ether").unwrap());
+        mock_provider
+            .push(Bytes::from(abi::encode(&[Token::Uint(l1_fee)])))
+            .unwrap();
+
+        // RPC 3: eth_gasPrice by process_estimate_costs
+        // Return 0.01 gwei
+        let gas_price: U256 =
+            EthersU256::from(ethers::utils::parse_units("0.01", "gwei").unwrap()).into();
+        mock_provider.push(gas_price).unwrap();
+
+        // RPC 2: eth_getBlockByNumber from the estimate_eip1559_fees call in process_contract_call
+        mock_provider.push(Block::<Transaction>::default()).unwrap();
+
+        // RPC 1: eth_estimateGas from the estimate_gas call in process_contract_call
+        // Return 100k gas
+        let gas_limit = U256::from(100000u32);
+        mock_provider.push(gas_limit).unwrap();
+
+        let tx_cost_estimate = mailbox
+            .process_estimate_costs(&message, &metadata)
+            .await
+            .unwrap();
+
+        let estimated_gas_limit = gas_limit.saturating_add(GAS_ESTIMATE_BUFFER.into());
+
+        assert_eq!(
+            tx_cost_estimate,
+            TxCostEstimate {
+                gas_limit: estimated_gas_limit,
+                gas_price: gas_price.try_into().unwrap(),
+                l2_gas_limit: None,
+                l1_data_fee: Some(l1_fee.into()),
+            },
+        );
+        // 0.0001 ETH at 0.01 gwei is 10M gas on top of the gas limit
+        assert_eq!(
+            tx_cost_estimate.enforceable_gas_limit(),
+            estimated_gas_limit + U256::from(10_000_000u32),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_estimate_costs_leaves_out_l1_data_fee_if_the_quote_fails() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let provider = Arc::new(Provider::new(mock_provider.clone()));
+        let connection_conf = ConnectionConf {
+            rpc_connection: RpcConnectionConf::Http {
+                url: "http://127.0.0.1:8545".parse().unwrap(),
+            },
+            transaction_overrides: Default::default(),
+            operation_batch: Default::default(),
+            rate_limiters: Default::default(),
+        };
+
+        let mailbox = EthereumMailbox::new(
+            provider.clone(),
+            &connection_conf,
+            &ContractLocator {
+                // An OP Stack chain
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::Optimism),
+                // Address doesn't matter because we're using a MockProvider
+                address: H256::default(),
             },
         );
+
+        let message = HyperlaneMessage::default();
+        let metadata: Vec<u8> = vec![];
+
+        // No response is pushed for RPC 4, the eth_call to the GasPriceOracle, so it fails
+
+        // RPC 3: eth_gasPrice by process_estimate_costs
+        let gas_price: U256 =
+            EthersU256::from(ethers::utils::parse_units("0.01", "gwei").unwrap()).into();
+        mock_provider.push(gas_price).unwrap();
+
+        // RPC 2: eth_getBlockByNumber from the estimate_eip1559_fees call in process_contract_call
+        mock_provider.push(Block::<Transaction>::default()).unwrap();
+
+        // RPC 1: eth_estimateGas from the estimate_gas call in process_contract_call
+        let gas_limit = U256::from(100000u32);
+        mock_provider.push(gas_limit).unwrap();
+
+        let tx_cost_estimate = mailbox
+            .process_estimate_costs(&message, &metadata)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tx_cost_estimate,
+            TxCostEstimate {
+                gas_limit: gas_limit.saturating_add(GAS_ESTIMATE_BUFFER.into()),
+                gas_price: gas_price.try_into().unwrap(),
+                l2_gas_limit: None,
+                l1_data_fee: None,
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delivered_at_finality_reads_the_final_block() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let provider = Arc::new(Provider::new(mock_provider.clone()));
+        let connection_conf = ConnectionConf {
+            rpc_connection: RpcConnectionConf::Http {
+                url: "http://127.0.0.1:8545".parse().unwrap(),
+            },
+            transaction_overrides: Default::default(),
+            operation_batch: Default::default(),
+            rate_limiters: Default::default(),
+        };
+        let mailbox = EthereumMailbox::new(
+            provider.clone(),
+            &connection_conf,
+            &ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+                // Address doesn't matter because we're using a MockProvider
+                address: H256::default(),
+            },
+        );
+        let id = H256::random();
+        let delivered_call = mailbox.contract.delivered(id.into());
+        let delivered = Bytes::from(abi::encode(&[Token::Bool(true)]));
+
+        // With confirmations, the call reads the block that many blocks behind
+        // the latest one. Responses are processed in LIFO order.
+        mock_provider.push(delivered.clone()).unwrap();
+        mock_provider.push(U64::from(100)).unwrap();
+        let confirmations = BlockFinality::Confirmations(NonZeroU64::new(3).unwrap());
+        assert!(mailbox
+            .delivered_at_finality(id, confirmations)
+            .await
+            .unwrap());
+        mock_provider.assert_request("eth_blockNumber", ()).unwrap();
+        mock_provider
+            .assert_request("eth_call", (&delivered_call.tx, BlockId::from(97u64)))
+            .unwrap();
+
+        // With the finalized tag, the call reads the finalized block
+        mock_provider.push(delivered).unwrap();
+        assert!(mailbox
+            .delivered_at_finality(id, BlockFinality::Finalized)
+            .await
+            .unwrap());
+        mock_provider
+            .assert_request(
+                "eth_call",
+                (&delivered_call.tx, BlockId::from(BlockNumber::Finalized)),
+            )
+            .unwrap();
     }
 }
@@ -4,20 +4,25 @@ use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use ethers::prelude::Middleware;
+use ethers::prelude::{Middleware, Provider, Ws};
 use hyperlane_core::accumulator::incremental::IncrementalMerkle;
 use tracing::instrument;
 
 use hyperlane_core::{
     ChainCommunicationError, ChainResult, Checkpoint, ContractLocator, HyperlaneChain,
-    HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer, LogMeta,
+    HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer, LogMeta, LogStream,
     MerkleTreeHook, MerkleTreeInsertion, SequenceAwareIndexer, H256,
 };
 
-use crate::interfaces::merkle_tree_hook::{MerkleTreeHook as MerkleTreeHookContract, Tree};
+use crate::interfaces::merkle_tree_hook::{
+    InsertedIntoTreeFilter, MerkleTreeHook as MerkleTreeHookContract, Tree,
+};
 use crate::tx::call_with_lag;
 use crate::{BuildableWithProvider, ConnectionConf, EthereumProvider};
 
+use super::subscription::{connect_subscription_provider, subscribe_logs};
+use super::utils::get_block_hash;
+
 // We don't need the reverse of this impl, so it's ok to disable the clippy lint
 #[allow(clippy::from_over_into)]
 impl Into<IncrementalMerkle> for Tree {
@@ -62,11 +67,12 @@ impl BuildableWithProvider for MerkleTreeHookIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
         Box::new(EthereumMerkleTreeHookIndexer::new(
             Arc::new(provider),
+            connect_subscription_provider(conn).await,
             locator,
             self.reorg_period,
         ))
@@ -81,6 +87,8 @@ where
 {
     contract: Arc<MerkleTreeHookContract<M>>,
     provider: Arc<M>,
+    /// Websocket provider used to subscribe to logs, if one is configured
+    subscription_provider: Option<Arc<Provider<Ws>>>,
     reorg_period: u32,
 }
 
@@ -89,13 +97,19 @@ where
     M: Middleware + 'static,
 {
     /// Create new EthereumMerkleTreeHookIndexer
-    pub fn new(provider: Arc<M>, locator: &ContractLocator, reorg_period: u32) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        subscription_provider: Option<Arc<Provider<Ws>>>,
+        locator: &ContractLocator,
+        reorg_period: u32,
+    ) -> Self {
         Self {
             contract: Arc::new(MerkleTreeHookContract::new(
                 locator.address,
                 provider.clone(),
             )),
             provider,
+            subscription_provider,
             reorg_period,
         }
     }
@@ -132,6 +146,22 @@ where
         Ok(logs)
     }
 
+    async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+        get_block_hash(self.provider.as_ref(), block_number).await
+    }
+
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<MerkleTreeInsertion>>> {
+        Ok(self.subscription_provider.clone().map(|provider| {
+            subscribe_logs(
+                provider,
+                self.contract.inserted_into_tree_filter().filter,
+                |log: InsertedIntoTreeFilter| {
+                    MerkleTreeInsertion::new(log.index, H256::from(log.message_id)).into()
+                },
+            )
+        }))
+    }
+
     #[instrument(level = "debug", err, skip(self))]
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
         Ok(self
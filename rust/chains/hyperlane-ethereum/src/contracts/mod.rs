@@ -8,4 +8,8 @@ mod merkle_tree_hook;
 
 mod multicall;
 
+mod subscription;
+
+mod utils;
+
 mod validator_announce;
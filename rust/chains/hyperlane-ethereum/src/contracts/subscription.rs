@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use ethers::prelude::{Filter, Middleware, Provider, Ws};
+use ethers_contract::{parse_log, EthLogDecode, LogMeta as EthersLogMeta};
+use futures_util::{stream, StreamExt};
+use hyperlane_core::{ChainCommunicationError, ChainResult, Indexed, LogMeta, LogStream};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::{ConnectionConf, RpcConnectionConf};
+
+/// Connect the websocket provider used to subscribe to logs with
+/// `eth_subscribe`. Returns `None` if the chain isn't configured with a
+/// websocket connection, in which case indexers only poll for logs.
+pub(crate) async fn connect_subscription_provider(
+    conn: &ConnectionConf,
+) -> Option<Arc<Provider<Ws>>> {
+    let RpcConnectionConf::Ws { url } = &conn.rpc_connection else {
+        return None;
+    };
+    match Ws::connect(url).await {
+        Ok(ws) => Some(Arc::new(Provider::new(ws))),
+        Err(err) => {
+            warn!(
+                ?err,
+                "Failed to connect websocket provider for log subscriptions, falling back to polling"
+            );
+            None
+        }
+    }
+}
+
+/// Subscribe to the logs matching `filter`, decoding each of them as an `E`.
+/// Logs the node reports as removed by a reorg are skipped.
+///
+/// The subscription is driven by a background task which ends, closing the
+/// stream, when the websocket subscription ends or the stream is dropped.
+pub(crate) fn subscribe_logs<E, T>(
+    provider: Arc<Provider<Ws>>,
+    filter: Filter,
+    to_indexed: fn(E) -> Indexed<T>,
+) -> LogStream<T>
+where
+    E: EthLogDecode + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded_channel::<ChainResult<(Indexed<T>, LogMeta)>>();
+    tokio::spawn(async move {
+        let mut logs = match provider.subscribe_logs(&filter).await {
+            Ok(logs) => logs,
+            Err(err) => {
+                let _ = sender.send(Err(ChainCommunicationError::from_other(err)));
+                return;
+            }
+        };
+        while let Some(log) = logs.next().await {
+            if log.removed == Some(true) {
+                debug!(?log, "Skipping log removed by a reorg");
+                continue;
+            }
+            let meta: LogMeta = EthersLogMeta::from(&log).into();
+            let log = parse_log::<E>(log)
+                .map(|event| (to_indexed(event), meta))
+                .map_err(ChainCommunicationError::from_other);
+            if sender.send(log).is_err() {
+                debug!("Log subscription receiver dropped, unsubscribing");
+                return;
+            }
+        }
+    });
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|log| (log, receiver))
+    })
+    .boxed()
+}
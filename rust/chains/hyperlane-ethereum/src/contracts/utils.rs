@@ -0,0 +1,16 @@
+use ethers::prelude::Middleware;
+use hyperlane_core::{ChainCommunicationError, ChainResult, H256};
+
+/// Get the hash of the canonical block at the given height, or `None` if the
+/// provider doesn't know about the block yet.
+pub(crate) async fn get_block_hash<M: Middleware>(
+    provider: &M,
+    block_number: u64,
+) -> ChainResult<Option<H256>> {
+    Ok(provider
+        .get_block(block_number)
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .and_then(|block| block.hash)
+        .map(Into::into))
+}
@@ -2,7 +2,7 @@ use derive_new::new;
 use hyperlane_core::rpc_clients::{BlockNumberGetter, FallbackProvider};
 use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 use async_trait::async_trait;
@@ -102,16 +102,21 @@ where
             let priorities_snapshot = self.take_priorities_snapshot().await;
             for (idx, priority) in priorities_snapshot.iter().enumerate() {
                 let provider = &self.inner.providers[priority.index];
+                let start = Instant::now();
                 let fut = match params {
                     Value::Null => provider.request(method, ()),
                     _ => provider.request(method, &params),
                 };
-                let resp = fut.await;
+                let resp = categorize_client_response(method, fut.await);
+                // Non-retryable errors, e.g. reverts, say nothing about the provider's health
+                let success = matches!(resp, IsOk(_) | NonRetryableErr(_));
+                self.record_response(priority.index, start.elapsed(), success)
+                    .await;
                 self.handle_stalled_provider(priority, provider).await;
                 let _span =
                     warn_span!("request", fallback_count=%idx, provider_index=%priority.index, ?provider).entered();
 
-                match categorize_client_response(method, resp) {
+                match resp {
                     IsOk(v) => return Ok(serde_json::from_value(v)?),
                     RetryableErr(e) | RateLimitErr(e) => errors.push(e.into()),
                     NonRetryableErr(e) => return Err(e.into()),
@@ -0,0 +1,174 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use ethers::providers::{HttpClientError, JsonRpcClient, JsonRpcError};
+use hyperlane_core::rpc_clients::RateLimiter;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Client, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// The longest an endpoint's `Retry-After` header is honoured for, so that a
+/// misbehaving endpoint can't stall requests indefinitely.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Error { error: JsonRpcError },
+    Success { result: Value },
+}
+
+/// An HTTP JSON-RPC client which waits for its endpoint's rate limiter before
+/// sending each request, and pauses the rate limiter for as long as the
+/// endpoint asks with a `Retry-After` header when it rate limits us or is
+/// temporarily unavailable.
+///
+/// Only HTTP connections are rate limited; websocket connections send their
+/// requests and subscriptions unthrottled.
+#[derive(Clone)]
+pub struct RateLimitedHttp {
+    id: Arc<AtomicU64>,
+    client: Client,
+    url: Url,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedHttp {
+    /// Create a client for the endpoint at `url`, limited by `rate_limiter`.
+    pub fn new(url: Url, client: Client, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            id: Default::default(),
+            client,
+            url,
+            rate_limiter,
+        }
+    }
+}
+
+impl Debug for RateLimitedHttp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitedHttp")
+            .field("url", &self.url.host_str())
+            .finish()
+    }
+}
+
+/// The delay requested by a `Retry-After` header, which is either a number of
+/// seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0. => Duration::from_secs_f64(seconds),
+        Ok(_) => return None,
+        // A date in the past asks for no delay at all
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .unwrap_or_default(),
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for RateLimitedHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        self.rate_limiter.acquire().await;
+
+        let id = self.id.fetch_add(1, Ordering::SeqCst);
+        let mut payload = json!({ "id": id, "jsonrpc": "2.0", "method": method });
+        let params = serde_json::to_value(params).expect("valid");
+        if !params.is_null() {
+            payload["params"] = params;
+        }
+        let res = self
+            .client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(payload.to_string())
+            .send()
+            .await?;
+        let status = res.status();
+        let retry_after = retry_after(res.headers());
+        let body = res.bytes().await?;
+
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            if let Some(retry_after) = retry_after {
+                warn!(?retry_after, %status, url = ?self.url.host_str(), "Endpoint asked us to back off");
+                self.rate_limiter.pause_for(retry_after);
+            }
+            return Err(HttpClientError::JsonRpcError(JsonRpcError {
+                code: status.as_u16().into(),
+                message: format!("{status}: {}", String::from_utf8_lossy(&body)),
+                data: None,
+            }));
+        }
+
+        let result = match serde_json::from_slice(&body) {
+            Ok(Response::Success { result }) => result,
+            Ok(Response::Error { error }) => return Err(HttpClientError::JsonRpcError(error)),
+            Err(err) => {
+                return Err(HttpClientError::SerdeJson {
+                    err,
+                    text: String::from_utf8_lossy(&body).to_string(),
+                })
+            }
+        };
+        serde_json::from_value(result.clone()).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: result.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_after_is_parsed_and_capped() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+        assert_eq!(retry_after(&headers), Some(MAX_RETRY_AFTER));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_retry_after_http_date_is_parsed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        let in_a_minute = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&in_a_minute).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+
+        let in_a_day = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(86400));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&in_a_day).unwrap());
+        assert_eq!(retry_after(&headers), Some(MAX_RETRY_AFTER));
+    }
+}
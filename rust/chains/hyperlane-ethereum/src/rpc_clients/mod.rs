@@ -1,10 +1,12 @@
 use ethers::providers::HttpClientError;
 use tracing::{info, trace, warn};
 
-pub use self::{fallback::*, provider::*, retrying::*, trait_builder::*};
+pub use self::{fallback::*, http::*, provider::*, quorum::*, retrying::*, trait_builder::*};
 
 mod fallback;
+mod http;
 mod provider;
+mod quorum;
 mod retrying;
 mod trait_builder;
 
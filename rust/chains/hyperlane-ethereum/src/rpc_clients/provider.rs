@@ -14,6 +14,7 @@ use tracing::instrument;
 use hyperlane_core::{
     BlockInfo, ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain,
     HyperlaneDomain, HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256,
+    H512,
 };
 
 use crate::{BuildableWithProvider, ConnectionConf};
@@ -65,11 +66,30 @@ where
     }
 
     #[instrument(err, skip(self))]
-    async fn get_txn_by_hash(&self, hash: &H256) -> ChainResult<TxnInfo> {
-        let txn = get_with_retry_on_none(hash, |h| self.provider.get_transaction(*h)).await?;
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        let block = self
+            .provider
+            .get_block(BlockNumber::Number(height.into()))
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .ok_or(HyperlaneProviderError::CouldNotFindBlockByHeight(height))?;
+        Ok(BlockInfo {
+            hash: block
+                .hash
+                .ok_or(HyperlaneProviderError::CouldNotFindBlockByHeight(height))?
+                .into(),
+            timestamp: block.timestamp.as_u64(),
+            number: height,
+        })
+    }
+
+    #[instrument(err, skip(self))]
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        let hash: H256 = (*hash).into();
+        let txn = get_with_retry_on_none(&hash, |h| self.provider.get_transaction(*h)).await?;
         let receipt = self
             .provider
-            .get_transaction_receipt(*hash)
+            .get_transaction_receipt(hash)
             .await
             .map_err(ChainCommunicationError::from_other)?
             .map(|r| -> Result<_, HyperlaneProviderError> {
@@ -82,7 +102,7 @@ where
             .transpose()?;
 
         Ok(TxnInfo {
-            hash: *hash,
+            hash: hash.into(),
             max_fee_per_gas: txn.max_fee_per_gas.map(Into::into),
             max_priority_fee_per_gas: txn.max_priority_fee_per_gas.map(Into::into),
             gas_price: txn.gas_price.map(Into::into),
@@ -143,6 +163,14 @@ where
         );
         Ok(Some(chain_metrics))
     }
+
+    #[instrument(err, skip(self))]
+    async fn get_block_number(&self) -> ChainResult<u64> {
+        let number = self.provider.get_block_number().await.map_err(|e| {
+            ChainCommunicationError::Other(HyperlaneCustomErrorWrapper::new(Box::new(e)))
+        })?;
+        Ok(number.as_u64())
+    }
 }
 
 impl<M> EthereumProvider<M>
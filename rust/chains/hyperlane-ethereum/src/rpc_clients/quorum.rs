@@ -0,0 +1,543 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, ProviderError};
+use ethers::types::U64;
+use futures_util::future::join_all;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use futures_util::FutureExt;
+use prometheus::IntCounterVec;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{instrument, warn};
+
+use crate::{QuorumConf, QuorumPolicy};
+
+/// Methods whose params include a block tag, and the index of that tag. A
+/// `latest` tag is replaced with the lowest block number reported by the
+/// providers, so that providers at different heights are compared at the same
+/// block.
+const BLOCK_TAGGED_METHODS: &[(&str, usize)] = &[
+    ("eth_call", 1),
+    ("eth_getBalance", 1),
+    ("eth_getCode", 1),
+    ("eth_getTransactionCount", 1),
+    ("eth_getStorageAt", 2),
+    ("eth_getBlockByNumber", 0),
+];
+
+/// Methods which are sent to every provider even when returning the first
+/// successful response, so that e.g. a transaction reaches as many mempools as
+/// possible.
+const BROADCAST_METHODS: &[&str] = &["eth_sendRawTransaction"];
+
+/// Errors specific to the quorum provider.
+#[derive(Error, Debug)]
+pub enum QuorumError {
+    /// Not enough providers agreed on a response
+    #[error("No quorum reached for `{method}`, required weight {required}. (Responses: {responses:?}, Errors: {errors:?})")]
+    NoQuorumReached {
+        /// The method requested
+        method: String,
+        /// The combined weight of providers required to agree
+        required: u64,
+        /// The distinct responses and the combined weight of the providers
+        /// which returned them
+        responses: Vec<(Value, u64)>,
+        /// The errors returned by providers
+        errors: Vec<ProviderError>,
+    },
+    /// All providers failed
+    #[error("All providers failed. (Errors: {0:?})")]
+    AllProvidersFailed(Vec<ProviderError>),
+}
+
+impl From<QuorumError> for ProviderError {
+    fn from(src: QuorumError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(src))
+    }
+}
+
+struct QuorumMember<C> {
+    client: C,
+    weight: u64,
+    node_host: String,
+}
+
+impl<C: JsonRpcClient> QuorumMember<C> {
+    async fn send(&self, method: &str, params: &Value) -> Result<Value, ProviderError> {
+        match params {
+            Value::Null => self.client.request(method, ()).await,
+            _ => self.client.request(method, params).await,
+        }
+        .map_err(Into::into)
+    }
+}
+
+struct QuorumProviderInner<C> {
+    /// Sorted by descending weight
+    members: Vec<QuorumMember<C>>,
+    conf: QuorumConf,
+    chain_name: String,
+    disagreement_count: Option<IntCounterVec>,
+}
+
+impl<C> QuorumProviderInner<C> {
+    fn record_disagreement(&self, method: &str, index: usize, response: &Value, agreed: &Value) {
+        let member = &self.members[index];
+        warn!(
+            chain = self.chain_name,
+            provider = member.node_host,
+            method,
+            ?response,
+            ?agreed,
+            "Provider response disagreed with the quorum"
+        );
+        if let Some(counter) = &self.disagreement_count {
+            counter
+                .with_label_values(&[&member.node_host, &self.chain_name, method])
+                .inc();
+        }
+    }
+}
+
+/// A provider which sends requests to several weighted providers and returns
+/// the response that providers with enough combined weight agree on. How much
+/// agreement is required can be configured per method, including returning
+/// the first successful response as a fallback provider would, for methods
+/// whose responses legitimately differ between providers.
+///
+/// Providers which disagree with the agreed response are logged and counted.
+/// Responses that haven't arrived by the time the quorum is reached are
+/// dropped rather than compared.
+pub struct EthereumQuorumProvider<C> {
+    inner: Arc<QuorumProviderInner<C>>,
+}
+
+impl<C> Clone for EthereumQuorumProvider<C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<C> Debug for EthereumQuorumProvider<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuorumProvider")
+            .field("chain_name", &self.inner.chain_name)
+            .field(
+                "hosts",
+                &self
+                    .inner
+                    .members
+                    .iter()
+                    .map(|m| format!("{} ({})", m.node_host, m.weight))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .finish()
+    }
+}
+
+/// Builder to create a new quorum provider.
+pub struct EthereumQuorumProviderBuilder<C> {
+    members: Vec<QuorumMember<C>>,
+    conf: QuorumConf,
+    chain_name: String,
+    disagreement_count: Option<IntCounterVec>,
+}
+
+impl<C> EthereumQuorumProviderBuilder<C> {
+    /// Create a builder for a quorum with the given weights and policies.
+    pub fn new(conf: QuorumConf, chain_name: impl Into<String>) -> Self {
+        Self {
+            members: Vec::new(),
+            conf,
+            chain_name: chain_name.into(),
+            disagreement_count: None,
+        }
+    }
+
+    /// Add a provider whose responses count with the given weight. Providers
+    /// with equal weights are tried in the order they were added when
+    /// returning the first successful response.
+    pub fn add_provider(mut self, client: C, weight: u64, node_host: impl Into<String>) -> Self {
+        self.members.push(QuorumMember {
+            client,
+            weight,
+            node_host: node_host.into(),
+        });
+        self
+    }
+
+    /// Count disagreements with the given counter, labelled with the provider
+    /// node, chain name and method.
+    pub fn with_disagreement_count(mut self, counter: IntCounterVec) -> Self {
+        self.disagreement_count = Some(counter);
+        self
+    }
+
+    /// Create a quorum provider.
+    pub fn build(mut self) -> EthereumQuorumProvider<C> {
+        self.members.sort_by_key(|m| std::cmp::Reverse(m.weight));
+        EthereumQuorumProvider {
+            inner: Arc::new(QuorumProviderInner {
+                members: self.members,
+                conf: self.conf,
+                chain_name: self.chain_name,
+                disagreement_count: self.disagreement_count,
+            }),
+        }
+    }
+}
+
+impl<C> EthereumQuorumProvider<C>
+where
+    C: JsonRpcClient + 'static,
+{
+    /// The combined weight of providers which have to agree under `policy`.
+    fn required_weight(&self, policy: QuorumPolicy) -> u64 {
+        let total_weight: u64 = self.inner.members.iter().map(|m| m.weight).sum();
+        match policy {
+            QuorumPolicy::Majority => total_weight / 2 + 1,
+            QuorumPolicy::All => total_weight,
+            QuorumPolicy::Weight(weight) => weight,
+            QuorumPolicy::First => 0,
+        }
+    }
+
+    /// Return the first successful response, trying providers in order of
+    /// weight.
+    async fn first_response(&self, method: &str, params: &Value) -> Result<Value, QuorumError> {
+        let mut errors = vec![];
+        for member in &self.inner.members {
+            match member.send(method, params).await {
+                Ok(value) => return Ok(value),
+                Err(err) => errors.push(err),
+            }
+        }
+        Err(QuorumError::AllProvidersFailed(errors))
+    }
+
+    /// Send the request to all providers and return the first successful
+    /// response in order of weight.
+    async fn broadcast_response(&self, method: &str, params: &Value) -> Result<Value, QuorumError> {
+        let results = join_all(self.inner.members.iter().map(|m| m.send(method, params))).await;
+        let mut errors = vec![];
+        for result in results {
+            match result {
+                Ok(value) => return Ok(value),
+                Err(err) => errors.push(err),
+            }
+        }
+        Err(QuorumError::AllProvidersFailed(errors))
+    }
+
+    /// Send the request to all providers and return the first response that
+    /// providers with at least `required` combined weight agree on.
+    async fn quorum_response(
+        &self,
+        method: &str,
+        params: Value,
+        required: u64,
+    ) -> Result<Value, QuorumError> {
+        let params = &params;
+        let mut requests = self
+            .inner
+            .members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| async move { (index, member.send(method, params).await) })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut responses: Vec<(usize, Value)> = vec![];
+        let mut tally: Vec<(Value, u64)> = vec![];
+        let mut errors = vec![];
+        while let Some((index, result)) = requests.next().await {
+            let value = match result {
+                Ok(value) => value,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let weight = self.inner.members[index].weight;
+            match tally.iter_mut().find(|(v, _)| *v == value) {
+                Some((_, total)) => *total += weight,
+                None => tally.push((value.clone(), weight)),
+            }
+            responses.push((index, value));
+
+            let Some((agreed, _)) = tally.iter().find(|(_, total)| *total >= required) else {
+                continue;
+            };
+            for (index, response) in responses.iter().filter(|(_, v)| v != agreed) {
+                self.inner
+                    .record_disagreement(method, *index, response, agreed);
+            }
+            // Compare the responses that already arrived from the remaining providers,
+            // without waiting for the slower ones, which would delay the request.
+            while let Some(Some((index, result))) = requests.next().now_or_never() {
+                match result {
+                    Ok(response) if response != *agreed => self
+                        .inner
+                        .record_disagreement(method, index, &response, agreed),
+                    _ => {}
+                }
+            }
+            return Ok(agreed.clone());
+        }
+
+        // Without a quorum, the providers which disagree with the response most
+        // of the weight agreed on are the most likely to be at fault.
+        if let Some((plurality, _)) = tally.iter().max_by_key(|(_, total)| *total) {
+            for (index, response) in responses.iter().filter(|(_, v)| v != plurality) {
+                self.inner
+                    .record_disagreement(method, *index, response, plurality);
+            }
+        }
+        Err(QuorumError::NoQuorumReached {
+            method: method.to_owned(),
+            required,
+            responses: tally,
+            errors,
+        })
+    }
+
+    /// Replace a `latest` block tag in the params with the lowest block number
+    /// reported by the providers.
+    async fn pin_block_tag(&self, method: &str, params: &mut Value) {
+        let Some(&(_, index)) = BLOCK_TAGGED_METHODS.iter().find(|(m, _)| *m == method) else {
+            return;
+        };
+        let Some(tag) = params.get_mut(index) else {
+            return;
+        };
+        if tag.as_str() != Some("latest") {
+            return;
+        }
+        let block_numbers = join_all(
+            self.inner
+                .members
+                .iter()
+                .map(|m| m.send("eth_blockNumber", &Value::Null)),
+        )
+        .await;
+        if let Some(lowest) = block_numbers
+            .into_iter()
+            .filter_map(|r| r.ok().and_then(|v| serde_json::from_value::<U64>(v).ok()))
+            .min()
+        {
+            *tag = serde_json::to_value(lowest).expect("valid");
+        }
+    }
+}
+
+/// The selector of the function called by an `eth_call`.
+fn call_selector(method: &str, params: &Value) -> Option<String> {
+    if method != "eth_call" {
+        return None;
+    }
+    let tx = params.get(0)?;
+    let data = tx.get("data").or_else(|| tx.get("input"))?.as_str()?;
+    data.get(..10).map(str::to_ascii_lowercase)
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C> JsonRpcClient for EthereumQuorumProvider<C>
+where
+    C: JsonRpcClient + 'static,
+{
+    type Error = ProviderError;
+
+    #[instrument]
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let mut params = serde_json::to_value(params).expect("valid");
+        let policy = self
+            .inner
+            .conf
+            .policy(method, call_selector(method, &params).as_deref());
+        let value = match policy {
+            QuorumPolicy::First if BROADCAST_METHODS.contains(&method) => {
+                self.broadcast_response(method, &params).await?
+            }
+            QuorumPolicy::First => self.first_response(method, &params).await?,
+            policy => {
+                self.pin_block_tag(method, &mut params).await;
+                self.quorum_response(method, params, self.required_weight(policy))
+                    .await?
+            }
+        };
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use ethers::providers::HttpClientError;
+    use prometheus::Opts;
+
+    use crate::MethodQuorumPolicy;
+
+    use super::*;
+
+    /// Responds to every request with the same value.
+    #[derive(Debug, Clone)]
+    struct ConstantProviderMock(Value);
+
+    #[async_trait]
+    impl JsonRpcClient for ConstantProviderMock {
+        type Error = HttpClientError;
+
+        async fn request<T: Debug + Serialize + Send + Sync, R: DeserializeOwned>(
+            &self,
+            _method: &str,
+            _params: T,
+        ) -> Result<R, Self::Error> {
+            serde_json::from_value(self.0.clone()).map_err(|err| HttpClientError::SerdeJson {
+                err,
+                text: "".to_owned(),
+            })
+        }
+    }
+
+    /// Counts the requests it receives and responds with a transaction hash.
+    #[derive(Debug, Clone, Default)]
+    struct CountingProviderMock(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl JsonRpcClient for CountingProviderMock {
+        type Error = HttpClientError;
+
+        async fn request<T: Debug + Serialize + Send + Sync, R: DeserializeOwned>(
+            &self,
+            _method: &str,
+            _params: T,
+        ) -> Result<R, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            serde_json::from_value(Value::from(format!("0x{}", "00".repeat(32)))).map_err(|err| {
+                HttpClientError::SerdeJson {
+                    err,
+                    text: "".to_owned(),
+                }
+            })
+        }
+    }
+
+    fn quorum_provider(
+        conf: QuorumConf,
+        members: &[(u64, u64)],
+    ) -> EthereumQuorumProvider<ConstantProviderMock> {
+        members
+            .iter()
+            .enumerate()
+            .fold(
+                EthereumQuorumProviderBuilder::new(conf, "test"),
+                |builder, (i, (response, weight))| {
+                    builder.add_provider(
+                        ConstantProviderMock(Value::from(*response)),
+                        *weight,
+                        format!("node{i}"),
+                    )
+                },
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_weights_decide_the_majority() {
+        let provider = quorum_provider(QuorumConf::default(), &[(1, 1), (1, 1), (2, 3)]);
+        let response: u64 = provider.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(response, 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_quorum_is_an_error() {
+        let conf = QuorumConf {
+            default_policy: QuorumPolicy::All,
+            ..Default::default()
+        };
+        let provider = quorum_provider(conf, &[(1, 1), (1, 1), (2, 1)]);
+        assert!(provider.request::<_, u64>("eth_chainId", ()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_method_policy_takes_the_first_response() {
+        let conf = QuorumConf {
+            default_policy: QuorumPolicy::All,
+            method_policies: vec![MethodQuorumPolicy {
+                method: "eth_chainId".to_owned(),
+                selector: None,
+                policy: QuorumPolicy::First,
+            }],
+            ..Default::default()
+        };
+        let provider = quorum_provider(conf, &[(1, 1), (2, 1), (3, 2)]);
+        let response: u64 = provider.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(response, 3);
+    }
+
+    #[tokio::test]
+    async fn test_disagreements_are_counted() {
+        let counter = IntCounterVec::new(
+            Opts::new("disagreements", "help"),
+            &["provider_node", "chain", "method"],
+        )
+        .unwrap();
+        let provider = [(1, 1), (1, 1), (2, 1)]
+            .into_iter()
+            .enumerate()
+            .fold(
+                EthereumQuorumProviderBuilder::new(QuorumConf::default(), "test")
+                    .with_disagreement_count(counter.clone()),
+                |builder, (i, (response, weight))| {
+                    builder.add_provider(
+                        ConstantProviderMock(Value::from(response)),
+                        weight,
+                        format!("node{i}"),
+                    )
+                },
+            )
+            .build();
+        let response: u64 = provider.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(response, 1);
+
+        // The last response already arrived when the quorum was reached, so it's compared too
+        let count = |node: &str| {
+            counter
+                .with_label_values(&[node, "test", "eth_chainId"])
+                .get()
+        };
+        assert_eq!((count("node0"), count("node1"), count("node2")), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_raw_transactions_are_broadcast() {
+        let mocks: Vec<_> = (0..3).map(|_| CountingProviderMock::default()).collect();
+        let provider = mocks
+            .iter()
+            .enumerate()
+            .fold(
+                EthereumQuorumProviderBuilder::new(QuorumConf::default(), "test"),
+                |builder, (i, mock)| builder.add_provider(mock.clone(), 1, format!("node{i}")),
+            )
+            .build();
+        let _: Value = provider
+            .request("eth_sendRawTransaction", ["0x00"])
+            .await
+            .unwrap();
+
+        let counts: Vec<_> = mocks.iter().map(|m| m.0.load(Ordering::SeqCst)).collect();
+        assert_eq!(counts, vec![1, 1, 1]);
+    }
+}
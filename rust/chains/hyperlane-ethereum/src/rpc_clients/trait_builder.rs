@@ -7,16 +7,16 @@ use ethers::middleware::gas_oracle::{
     GasCategory, GasOracle, GasOracleMiddleware, Polygon, ProviderOracle,
 };
 use ethers::prelude::{
-    Http, JsonRpcClient, Middleware, NonceManagerMiddleware, Provider, Quorum, QuorumProvider,
-    SignerMiddleware, WeightedProvider, Ws, WsClientError,
+    JsonRpcClient, Middleware, NonceManagerMiddleware, Provider, SignerMiddleware, Ws,
+    WsClientError,
 };
-use hyperlane_core::rpc_clients::FallbackProvider;
+use hyperlane_core::rpc_clients::{FallbackProvider, FallbackProviderMetrics};
 use reqwest::{Client, Url};
 use thiserror::Error;
 
 use ethers_prometheus::json_rpc_client::{
     JsonRpcBlockGetter, JsonRpcClientMetrics, JsonRpcClientMetricsBuilder, NodeInfo,
-    PrometheusJsonRpcClient, PrometheusJsonRpcClientConfig,
+    PrometheusJsonRpcClient, PrometheusJsonRpcClientConfig, PrometheusJsonRpcClientConfigExt,
 };
 use ethers_prometheus::middleware::{MiddlewareMetrics, PrometheusMiddlewareConf};
 use hyperlane_core::{
@@ -24,7 +24,10 @@ use hyperlane_core::{
 };
 
 use crate::signer::Signers;
-use crate::{ConnectionConf, EthereumFallbackProvider, RetryingProvider, RpcConnectionConf};
+use crate::{
+    ConnectionConf, EthereumFallbackProvider, EthereumQuorumProviderBuilder, RateLimitedHttp,
+    RetryingProvider, RpcConnectionConf,
+};
 
 // This should be whatever the prometheus scrape interval is
 const HTTP_CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
@@ -61,17 +64,20 @@ pub trait BuildableWithProvider {
         locator: &ContractLocator,
         signer: Option<Signers>,
         rpc_metrics: Option<JsonRpcClientMetrics>,
+        fallback_metrics: Option<FallbackProviderMetrics>,
         middleware_metrics: Option<(MiddlewareMetrics, PrometheusMiddlewareConf)>,
     ) -> ChainResult<Self::Output> {
         Ok(match &conn.rpc_connection {
-            RpcConnectionConf::HttpQuorum { urls } => {
-                let mut builder = QuorumProvider::builder().quorum(Quorum::Majority);
+            RpcConnectionConf::HttpQuorum { urls, quorum } => {
+                let mut builder =
+                    EthereumQuorumProviderBuilder::new(quorum.clone(), locator.domain.name());
                 let http_client = Client::builder()
                     .timeout(HTTP_CLIENT_TIMEOUT)
                     .build()
                     .map_err(EthereumProviderConnectionError::from)?;
                 for url in urls {
-                    let http_provider = Http::new_with_client(url.clone(), http_client.clone());
+                    let http_provider =
+                        rate_limited_http(url, http_client.clone(), conn, locator, &rpc_metrics);
                     // Wrap the inner providers as RetryingProviders rather than the QuorumProvider.
                     // We've observed issues where the QuorumProvider will first get the latest
                     // block number and then submit an RPC at that block height,
@@ -87,10 +93,17 @@ pub trait BuildableWithProvider {
                         &rpc_metrics,
                         &middleware_metrics,
                     );
+                    let node_host = metrics_provider.node_host().to_owned();
                     let retrying_provider =
                         RetryingProvider::new(metrics_provider, Some(5), Some(1000));
-                    let weighted_provider = WeightedProvider::new(retrying_provider);
-                    builder = builder.add_provider(weighted_provider);
+                    builder =
+                        builder.add_provider(retrying_provider, quorum.weight(url), node_host);
+                }
+                if let Some(counter) = rpc_metrics
+                    .as_ref()
+                    .and_then(|m| m.quorum_disagreement_count())
+                {
+                    builder = builder.with_disagreement_count(counter.clone());
                 }
                 let quorum_provider = builder.build();
                 self.build(quorum_provider, conn, locator, signer).await?
@@ -102,7 +115,8 @@ pub trait BuildableWithProvider {
                     .build()
                     .map_err(EthereumProviderConnectionError::from)?;
                 for url in urls {
-                    let http_provider = Http::new_with_client(url.clone(), http_client.clone());
+                    let http_provider =
+                        rate_limited_http(url, http_client.clone(), conn, locator, &rpc_metrics);
                     let metrics_provider = self.wrap_rpc_with_metrics(
                         http_provider,
                         url.clone(),
@@ -111,10 +125,19 @@ pub trait BuildableWithProvider {
                     );
                     builder = builder.add_provider(metrics_provider);
                 }
+                if let Some(fallback_metrics) = fallback_metrics {
+                    builder = builder.with_metrics(
+                        fallback_metrics,
+                        locator.domain.name(),
+                        |provider: &PrometheusJsonRpcClient<RateLimitedHttp>| {
+                            provider.node_host().to_owned()
+                        },
+                    );
+                }
                 let fallback_provider = builder.build();
                 let ethereum_fallback_provider = EthereumFallbackProvider::<
                     _,
-                    JsonRpcBlockGetter<PrometheusJsonRpcClient<Http>>,
+                    JsonRpcBlockGetter<PrometheusJsonRpcClient<RateLimitedHttp>>,
                 >::new(fallback_provider);
                 self.build(ethereum_fallback_provider, conn, locator, signer)
                     .await?
@@ -124,7 +147,8 @@ pub trait BuildableWithProvider {
                     .timeout(HTTP_CLIENT_TIMEOUT)
                     .build()
                     .map_err(EthereumProviderConnectionError::from)?;
-                let http_provider = Http::new_with_client(url.clone(), http_client);
+                let http_provider =
+                    rate_limited_http(url, http_client, conn, locator, &rpc_metrics);
                 let metrics_provider = self.wrap_rpc_with_metrics(
                     http_provider,
                     url.clone(),
@@ -136,6 +160,7 @@ pub trait BuildableWithProvider {
                     .await?
             }
             RpcConnectionConf::Ws { url } => {
+                // Websocket requests aren't rate limited, unlike HTTP ones
                 let ws = Ws::connect(url)
                     .await
                     .map_err(EthereumProviderConnectionError::from)?;
@@ -231,6 +256,29 @@ pub trait BuildableWithProvider {
         M: Middleware + 'static;
 }
 
+/// Create an HTTP client for `url` which shares the url's rate limiter with all
+/// other clients built from the same connection config.
+fn rate_limited_http(
+    url: &Url,
+    http_client: Client,
+    conn: &ConnectionConf,
+    locator: &ContractLocator,
+    rpc_metrics: &Option<JsonRpcClientMetrics>,
+) -> RateLimitedHttp {
+    let rate_limiter = conn.rate_limiters.get(url.as_str(), || {
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => "unknown".to_owned(),
+        };
+        rpc_metrics
+            .as_ref()
+            .and_then(|m| m.request_throttled_seconds())
+            .map(|c| c.with_label_values(&[&host, locator.domain.name()]))
+    });
+    RateLimitedHttp::new(url.clone(), http_client, rate_limiter)
+}
+
 async fn wrap_with_signer<M: Middleware>(
     provider: M,
     signer: Signers,
@@ -16,7 +16,9 @@ use ethers_core::{
         EIP1559_FEE_ESTIMATION_REWARD_PERCENTILE,
     },
 };
-use hyperlane_core::{utils::bytes_to_hex, ChainCommunicationError, ChainResult, H256, U256};
+use hyperlane_core::{
+    utils::bytes_to_hex, BlockFinality, ChainCommunicationError, ChainResult, H256, U256,
+};
 use tracing::{error, info};
 
 use crate::{Middleware, TransactionOverrides};
@@ -213,3 +215,22 @@ where
         Ok(call)
     }
 }
+
+/// Make `call` read the state of the latest block which is final according to
+/// `finality`
+pub(crate) async fn call_at_finality<M, T>(
+    call: ethers::contract::builders::ContractCall<M, T>,
+    provider: &M,
+    finality: BlockFinality,
+) -> ChainResult<ethers::contract::builders::ContractCall<M, T>>
+where
+    M: Middleware + 'static,
+    T: Detokenize,
+{
+    match finality {
+        BlockFinality::Confirmations(confirmations) => {
+            call_with_lag(call, provider, Some(confirmations)).await
+        }
+        BlockFinality::Finalized => Ok(call.block(BlockNumber::Finalized)),
+    }
+}
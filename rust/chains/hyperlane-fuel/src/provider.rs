@@ -2,7 +2,7 @@ use async_trait::async_trait;
 
 use hyperlane_core::{
     BlockInfo, ChainInfo, ChainResult, HyperlaneChain, HyperlaneDomain, HyperlaneProvider, TxnInfo,
-    H256, U256,
+    H256, H512, U256,
 };
 
 /// A wrapper around a fuel provider to get generic blockchain information.
@@ -25,7 +25,11 @@ impl HyperlaneProvider for FuelProvider {
         todo!()
     }
 
-    async fn get_txn_by_hash(&self, hash: &H256) -> ChainResult<TxnInfo> {
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        todo!()
+    }
+
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
         todo!()
     }
 
@@ -40,4 +44,8 @@ impl HyperlaneProvider for FuelProvider {
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
         Ok(None)
     }
+
+    async fn get_block_number(&self) -> ChainResult<u64> {
+        todo!()
+    }
 }
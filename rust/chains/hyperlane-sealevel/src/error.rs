@@ -1,6 +1,6 @@
 use hyperlane_core::ChainCommunicationError;
 use solana_client::client_error::ClientError;
-use solana_sdk::pubkey::ParsePubkeyError;
+use solana_sdk::{hash::ParseHashError, pubkey::ParsePubkeyError, signature::ParseSignatureError};
 
 /// Errors from the crates specific to the hyperlane-sealevel
 /// implementation.
@@ -14,6 +14,12 @@ pub enum HyperlaneSealevelError {
     /// ClientError error
     #[error("{0}")]
     ClientError(#[from] ClientError),
+    /// ParseSignatureError error
+    #[error("{0}")]
+    ParseSignatureError(#[from] ParseSignatureError),
+    /// ParseHashError error
+    #[error("{0}")]
+    ParseHashError(#[from] ParseHashError),
 }
 
 impl From<HyperlaneSealevelError> for ChainCommunicationError {
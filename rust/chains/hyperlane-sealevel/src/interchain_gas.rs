@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use hyperlane_core::{
     config::StrOrIntParseError, ChainCommunicationError, ChainResult, ContractLocator,
     HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexed, Indexer,
-    InterchainGasPaymaster, InterchainGasPayment, LogMeta, SequenceAwareIndexer, H256, H512,
+    InterchainGasPaymaster, InterchainGasPayment, LogMeta, SequenceAwareIndexer, H256,
 };
 use hyperlane_sealevel_igp::{
     accounts::{GasPaymentAccount, ProgramDataAccount},
@@ -215,6 +215,17 @@ impl SealevelInterchainGasPaymasterIndexer {
 
         tracing::debug!(gas_payment_account=?gas_payment_account, "Found gas payment account");
 
+        let log_meta = self
+            .igp
+            .provider
+            .get_pda_creation_log_meta(
+                &self.igp.program_id,
+                &valid_payment_pda_pubkey,
+                gas_payment_account.slot,
+                sequence_number.into(),
+            )
+            .await?;
+
         let igp_payment = InterchainGasPayment {
             message_id: gas_payment_account.message_id,
             destination: gas_payment_account.destination_domain,
@@ -228,16 +239,7 @@ impl SealevelInterchainGasPaymasterIndexer {
                     .try_into()
                     .map_err(StrOrIntParseError::from)?,
             ),
-            LogMeta {
-                address: self.igp.program_id.to_bytes().into(),
-                block_number: gas_payment_account.slot,
-                // TODO: get these when building out scraper support.
-                // It's inconvenient to get these :|
-                block_hash: H256::zero(),
-                transaction_id: H512::zero(),
-                transaction_index: 0,
-                log_index: sequence_number.into(),
-            },
+            log_meta,
             H256::from(gas_payment_account.igp.to_bytes()),
         ))
     }
@@ -8,17 +8,17 @@ use jsonrpc_core::futures_util::TryFutureExt;
 use tracing::{debug, info, instrument, warn};
 
 use hyperlane_core::{
-    accumulator::incremental::IncrementalMerkle, BatchItem, ChainCommunicationError, ChainResult,
-    Checkpoint, ContractLocator, Decode as _, Encode as _, FixedPointNumber, HyperlaneAbi,
-    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
-    Indexed, Indexer, LogMeta, Mailbox, MerkleTreeHook, SequenceAwareIndexer, TxCostEstimate,
-    TxOutcome, H256, H512, U256,
+    accumulator::incremental::IncrementalMerkle, config::StrOrIntParseError, BatchItem,
+    ChainCommunicationError, ChainResult, Checkpoint, ContractLocator, Decode as _, Encode as _,
+    FixedPointNumber, HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
+    HyperlaneMessage, HyperlaneProvider, Indexed, Indexer, LogMeta, Mailbox, MerkleTreeHook,
+    SequenceAwareIndexer, TxCostEstimate, TxOutcome, H256, H512, U256,
 };
 use hyperlane_sealevel_interchain_security_module_interface::{
     InterchainSecurityModuleInstruction, VerifyInstruction,
 };
 use hyperlane_sealevel_mailbox::{
-    accounts::{DispatchedMessageAccount, InboxAccount, OutboxAccount},
+    accounts::{DispatchedMessageAccount, InboxAccount, OutboxAccount, ProcessedMessageAccount},
     instruction::InboxProcess,
     mailbox_dispatched_message_pda_seeds, mailbox_inbox_pda_seeds, mailbox_outbox_pda_seeds,
     mailbox_process_authority_pda_seeds, mailbox_processed_message_pda_seeds,
@@ -473,6 +473,7 @@ impl Mailbox for SealevelMailbox {
             // TODO use correct data upon integrating IGP support
             gas_price: U256::zero().try_into()?,
             gas_used: U256::zero(),
+            l1_data_fee: None,
         })
     }
 
@@ -487,6 +488,7 @@ impl Mailbox for SealevelMailbox {
             gas_limit: U256::zero(),
             gas_price: FixedPointNumber::zero(),
             l2_gas_limit: None,
+            l1_data_fee: None,
         })
     }
 
@@ -617,18 +619,119 @@ impl SealevelMailboxIndexer {
         let hyperlane_message =
             HyperlaneMessage::read_from(&mut &dispatched_message_account.encoded_message[..])?;
 
-        Ok((
-            hyperlane_message.into(),
-            LogMeta {
-                address: self.mailbox.program_id.to_bytes().into(),
-                block_number: dispatched_message_account.slot,
-                // TODO: get these when building out scraper support.
-                // It's inconvenient to get these :|
-                block_hash: H256::zero(),
-                transaction_id: H512::zero(),
-                transaction_index: 0,
-                log_index: U256::zero(),
+        let log_meta = self
+            .mailbox
+            .provider
+            .get_pda_creation_log_meta(
+                &self.mailbox.program_id,
+                &valid_message_storage_pda_pubkey,
+                dispatched_message_account.slot,
+                nonce.into(),
+            )
+            .await?;
+
+        Ok((hyperlane_message.into(), log_meta))
+    }
+
+    async fn get_delivered_message_with_sequence(
+        &self,
+        sequence: u32,
+    ) -> ChainResult<(Indexed<H256>, LogMeta)> {
+        let target_message_account_bytes = &[
+            &hyperlane_sealevel_mailbox::accounts::PROCESSED_MESSAGE_DISCRIMINATOR[..],
+            &u64::from(sequence).to_le_bytes()[..],
+        ]
+        .concat();
+        let target_message_account_bytes = base64::encode(target_message_account_bytes);
+
+        // First, find all accounts with the matching account data.
+        // To keep responses small in case there is ever more than 1
+        // match, we don't request the full account data, and just request
+        // the `message_id` field.
+        let memcmp = RpcFilterType::Memcmp(Memcmp {
+            // Ignore the first byte, which is the `initialized` bool flag.
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Base64(target_message_account_bytes),
+            encoding: None,
+        });
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![memcmp]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 1 + 8 + 8, // the offset to get the `message_id` field
+                    length: 32,        // the length of the `message_id` field
+                }),
+                commitment: Some(CommitmentConfig::finalized()),
+                min_context_slot: None,
             },
+            with_context: Some(false),
+        };
+        let accounts = self
+            .rpc()
+            .get_program_accounts_with_config(&self.mailbox.program_id, config)
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+
+        // Now loop through matching accounts and find the one with a valid account pubkey
+        // that proves it's an actual processed message PDA.
+        let mut valid_processed_message_pda_pubkey = Option::<Pubkey>::None;
+
+        for (pubkey, account) in accounts {
+            let message_id = H256::from_slice(&account.data);
+            let (expected_pubkey, _bump) = Pubkey::try_find_program_address(
+                mailbox_processed_message_pda_seeds!(message_id),
+                &self.mailbox.program_id,
+            )
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str(
+                    "Could not find program address for message_id",
+                )
+            })?;
+            if expected_pubkey == pubkey {
+                valid_processed_message_pda_pubkey = Some(pubkey);
+                break;
+            }
+        }
+
+        let valid_processed_message_pda_pubkey =
+            valid_processed_message_pda_pubkey.ok_or_else(|| {
+                ChainCommunicationError::from_other_str(
+                    "Could not find valid processed message PDA pubkey",
+                )
+            })?;
+
+        // Now that we have the valid processed message PDA pubkey, we can get the full account data.
+        let account = self
+            .rpc()
+            .get_account_with_commitment(
+                &valid_processed_message_pda_pubkey,
+                CommitmentConfig::finalized(),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .value
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Could not find account data")
+            })?;
+        let processed_message_account = ProcessedMessageAccount::fetch(&mut account.data.as_ref())
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+
+        let log_meta = self
+            .mailbox
+            .provider
+            .get_pda_creation_log_meta(
+                &self.mailbox.program_id,
+                &valid_processed_message_pda_pubkey,
+                processed_message_account.slot,
+                sequence.into(),
+            )
+            .await?;
+
+        Ok((
+            Indexed::new(processed_message_account.message_id).with_sequence(sequence),
+            log_meta,
         ))
     }
 }
@@ -672,9 +775,19 @@ impl Indexer<HyperlaneMessage> for SealevelMailboxIndexer {
 impl Indexer<H256> for SealevelMailboxIndexer {
     async fn fetch_logs(
         &self,
-        _range: RangeInclusive<u32>,
+        range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<H256>, LogMeta)>> {
-        todo!()
+        info!(
+            ?range,
+            "Fetching SealevelMailboxIndexer delivered message logs"
+        );
+
+        let message_capacity = range.end().saturating_sub(*range.start());
+        let mut delivered_messages = Vec::with_capacity(message_capacity as usize);
+        for sequence in range {
+            delivered_messages.push(self.get_delivered_message_with_sequence(sequence).await?);
+        }
+        Ok(delivered_messages)
     }
 
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
@@ -684,11 +797,26 @@ impl Indexer<H256> for SealevelMailboxIndexer {
 
 #[async_trait]
 impl SequenceAwareIndexer<H256> for SealevelMailboxIndexer {
+    #[instrument(err, skip(self))]
     async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
-        // TODO: implement when sealevel scraper support is implemented
-        info!("Message delivery indexing not implemented");
         let tip = Indexer::<H256>::get_finalized_block_number(self).await?;
-        Ok((Some(1), tip))
+        // TODO: need to make sure the call and tip are at the same height?
+        let inbox_account = self
+            .rpc()
+            .get_account_with_commitment(&self.mailbox.inbox.0, CommitmentConfig::finalized())
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .value
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Could not find inbox account data")
+            })?;
+        let processed_count = InboxAccount::fetch(&mut inbox_account.data.as_ref())
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner()
+            .processed_count
+            .try_into()
+            .map_err(StrOrIntParseError::from)?;
+        Ok((Some(processed_count), tip))
     }
 }
 
@@ -3,13 +3,24 @@ use std::{str::FromStr, sync::Arc};
 use async_trait::async_trait;
 
 use hyperlane_core::{
-    BlockInfo, ChainInfo, ChainResult, HyperlaneChain, HyperlaneDomain, HyperlaneProvider, TxnInfo,
-    H256, U256,
+    BlockInfo, ChainCommunicationError, ChainInfo, ChainResult, HyperlaneChain, HyperlaneDomain,
+    HyperlaneProvider, HyperlaneProviderError, LogMeta, TxnInfo, TxnReceiptInfo, H256, H512, U256,
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcBlockConfig, RpcTransactionConfig},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding};
 
 use crate::{client::RpcClientWithDebug, error::HyperlaneSealevelError, ConnectionConf};
 
+/// The most signatures the RPC returns for an address at once.
+const SIGNATURES_PAGE_LIMIT: usize = 1000;
+
 /// A wrapper around a Sealevel provider to get generic blockchain information.
 #[derive(Debug)]
 pub struct SealevelProvider {
@@ -44,6 +55,94 @@ impl SealevelProvider {
             .map_err(Into::<HyperlaneSealevelError>::into)?;
         Ok(balance.into())
     }
+
+    /// Get the finalized block at the given slot, optionally including the
+    /// signatures of its transactions.
+    async fn get_block(&self, slot: u64, with_signatures: bool) -> ChainResult<UiConfirmedBlock> {
+        let transaction_details = if with_signatures {
+            TransactionDetails::Signatures
+        } else {
+            TransactionDetails::None
+        };
+        let config = RpcBlockConfig {
+            encoding: None,
+            transaction_details: Some(transaction_details),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        self.rpc_client
+            .get_block_with_config(slot, config)
+            .await
+            .map_err(Into::<HyperlaneSealevelError>::into)
+            .map_err(Into::into)
+    }
+
+    /// Get the `LogMeta` of the transaction that created the account `pda` in
+    /// the given `slot`. Sealevel programs store their "events" in PDAs, so
+    /// the transaction that created the PDA is the one that emitted the event.
+    pub(crate) async fn get_pda_creation_log_meta(
+        &self,
+        program_id: &Pubkey,
+        pda: &Pubkey,
+        slot: u64,
+        log_index: U256,
+    ) -> ChainResult<LogMeta> {
+        let mut creation_signature = None;
+        let mut before = None;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_LIMIT),
+                commitment: Some(CommitmentConfig::finalized()),
+            };
+            let page = self
+                .rpc_client
+                .get_signatures_for_address_with_config(pda, config)
+                .await
+                .map_err(Into::<HyperlaneSealevelError>::into)?;
+            if let Some(signature) = oldest_successful_signature_in_slot(&page, slot) {
+                creation_signature = Some(signature);
+            }
+            let Some(oldest) = next_page_before(&page, slot) else {
+                break;
+            };
+            before =
+                Some(Signature::from_str(oldest).map_err(Into::<HyperlaneSealevelError>::into)?);
+        }
+        let signature = creation_signature.ok_or_else(|| {
+            ChainCommunicationError::from_other_str(
+                "Could not find the transaction that created the account",
+            )
+        })?;
+
+        let block = self.get_block(slot, true).await?;
+        let transaction_index = block
+            .signatures
+            .unwrap_or_default()
+            .iter()
+            .position(|s| *s == signature)
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str(
+                    "Could not find the transaction in its block",
+                )
+            })?;
+
+        let signature =
+            Signature::from_str(&signature).map_err(Into::<HyperlaneSealevelError>::into)?;
+        let block_hash =
+            Hash::from_str(&block.blockhash).map_err(Into::<HyperlaneSealevelError>::into)?;
+
+        Ok(LogMeta {
+            address: program_id.to_bytes().into(),
+            block_number: slot,
+            block_hash: block_hash.into(),
+            transaction_id: signature.into(),
+            transaction_index: transaction_index as u64,
+            log_index,
+        })
+    }
 }
 
 impl HyperlaneChain for SealevelProvider {
@@ -61,12 +160,82 @@ impl HyperlaneChain for SealevelProvider {
 
 #[async_trait]
 impl HyperlaneProvider for SealevelProvider {
-    async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
-        todo!() // FIXME
+    async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+        // The Sealevel RPC only supports looking up blocks by slot. The scraper, which is
+        // the only consumer of block lookups, looks blocks up by slot and checks their hash.
+        Err(ChainCommunicationError::CustomError(format!(
+            "Sealevel blocks can only be looked up by slot, not by hash {hash:?}"
+        )))
+    }
+
+    /// Sealevel blocks are numbered by slot, as are `LogMeta` block numbers
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        let block = self.get_block(height, false).await?;
+        let hash =
+            Hash::from_str(&block.blockhash).map_err(Into::<HyperlaneSealevelError>::into)?;
+        Ok(BlockInfo {
+            hash: hash.into(),
+            timestamp: block
+                .block_time
+                .ok_or(HyperlaneProviderError::CouldNotFindBlockByHeight(height))?
+                as u64,
+            number: height,
+        })
     }
 
-    async fn get_txn_by_hash(&self, _hash: &H256) -> ChainResult<TxnInfo> {
-        todo!() // FIXME
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        let signature = Signature::new(hash.as_bytes());
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        let txn = self
+            .rpc_client
+            .get_transaction_with_config(&signature, config)
+            .await
+            .map_err(Into::<HyperlaneSealevelError>::into)?
+            .transaction;
+
+        let meta = txn.meta.ok_or_else(|| {
+            ChainCommunicationError::from_other_str("Transaction is missing its status meta")
+        })?;
+        let decoded = txn.transaction.decode().ok_or_else(|| {
+            ChainCommunicationError::from_other_str("Could not decode transaction")
+        })?;
+        // The fee payer is always the first account.
+        let sender = decoded
+            .message
+            .static_account_keys()
+            .first()
+            .ok_or_else(|| ChainCommunicationError::from_other_str("Transaction has no fee payer"))?
+            .to_bytes()
+            .into();
+
+        // Compute units are the closest equivalent of gas.
+        let compute_units: U256 = Option::<u64>::from(meta.compute_units_consumed)
+            .unwrap_or_default()
+            .into();
+        let gas_price = U256::from(meta.fee)
+            .checked_div(compute_units)
+            .unwrap_or_default();
+
+        Ok(TxnInfo {
+            hash: *hash,
+            gas_limit: compute_units,
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_price: Some(gas_price),
+            // Sealevel transactions are not nonced
+            nonce: 0,
+            sender,
+            recipient: None,
+            receipt: Some(TxnReceiptInfo {
+                gas_used: compute_units,
+                cumulative_gas_used: compute_units,
+                effective_gas_price: Some(gas_price),
+            }),
+        })
     }
 
     async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
@@ -81,4 +250,98 @@ impl HyperlaneProvider for SealevelProvider {
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
         Ok(None)
     }
+
+    /// The finalized slot, in the same unit as `get_block_by_height`
+    async fn get_block_number(&self) -> ChainResult<u64> {
+        let slot = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await
+            .map_err(Into::<HyperlaneSealevelError>::into)?;
+        Ok(slot)
+    }
+}
+
+/// The oldest successful transaction in `slot` out of a page of an account's
+/// signatures, which are ordered from newest to oldest. The account may have been
+/// referenced by later transactions, including ones in the same slot, so the oldest
+/// one is the one that created it.
+fn oldest_successful_signature_in_slot(
+    page: &[RpcConfirmedTransactionStatusWithSignature],
+    slot: u64,
+) -> Option<String> {
+    page.iter()
+        .rev()
+        .find(|status| status.slot == slot && status.err.is_none())
+        .map(|status| status.signature.clone())
+}
+
+/// The signature to query the next, older page of an account's signatures before,
+/// if that page may still hold transactions from `slot`.
+fn next_page_before(
+    page: &[RpcConfirmedTransactionStatusWithSignature],
+    slot: u64,
+) -> Option<&str> {
+    let oldest = page.last()?;
+    (page.len() == SIGNATURES_PAGE_LIMIT && oldest.slot >= slot)
+        .then_some(oldest.signature.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    use super::*;
+
+    fn status(
+        signature: &str,
+        slot: u64,
+        failed: bool,
+    ) -> RpcConfirmedTransactionStatusWithSignature {
+        RpcConfirmedTransactionStatusWithSignature {
+            signature: signature.to_owned(),
+            slot,
+            err: failed.then_some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(0),
+            )),
+            memo: None,
+            block_time: None,
+            confirmation_status: None,
+        }
+    }
+
+    #[test]
+    fn test_finds_oldest_successful_signature_in_slot() {
+        let page = vec![
+            status("later", 12, false),
+            status("referenced", 10, false),
+            status("created", 10, false),
+            status("failed", 10, true),
+            status("earlier", 8, false),
+        ];
+        assert_eq!(
+            oldest_successful_signature_in_slot(&page, 10).as_deref(),
+            Some("created")
+        );
+        assert_eq!(oldest_successful_signature_in_slot(&page, 11), None);
+    }
+
+    #[test]
+    fn test_pages_back_until_past_slot() {
+        let full_page = |oldest_slot| {
+            let mut page = vec![status("newer", 20, false); SIGNATURES_PAGE_LIMIT - 1];
+            page.push(status("oldest", oldest_slot, false));
+            page
+        };
+
+        // The next page may still hold older transactions from the slot
+        assert_eq!(next_page_before(&full_page(10), 10), Some("oldest"));
+        assert_eq!(next_page_before(&full_page(15), 10), Some("oldest"));
+        // This page already reaches past the slot
+        assert_eq!(next_page_before(&full_page(9), 10), None);
+        // There are no older signatures
+        assert_eq!(next_page_before(&[status("oldest", 10, false)], 10), None);
+        assert_eq!(next_page_before(&[], 10), None);
+    }
 }
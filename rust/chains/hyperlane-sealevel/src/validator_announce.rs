@@ -125,6 +125,7 @@ impl ValidatorAnnounce for SealevelValidatorAnnounce {
             executed: false,
             gas_used: U256::zero(),
             gas_price: U256::zero().try_into()?,
+            l1_data_fee: None,
         })
     }
 }
@@ -51,6 +51,37 @@ pub struct JsonRpcClientMetrics {
     ///   might still be an "error" but not one with the transport layer.
     #[builder(setter(into, strip_option), default)]
     request_duration_seconds: Option<CounterVec>,
+
+    /// Total number of responses which disagreed with the response the rest of
+    /// a quorum agreed on.
+    /// - `provider_node`: node this is connecting to, e.g. `alchemy.com`,
+    ///   `quicknode.pro`, or `localhost:8545`.
+    /// - `chain`: chain name (or chain id if the name is unknown) of the chain
+    ///   the request was made on.
+    /// - `method`: request method string.
+    #[builder(setter(into, strip_option), default)]
+    quorum_disagreement_count: Option<IntCounterVec>,
+
+    /// Total number of seconds requests were held back for by client-side rate
+    /// limiting.
+    /// - `provider_node`: node this is connecting to, e.g. `alchemy.com`,
+    ///   `quicknode.pro`, or `localhost:8545`.
+    /// - `chain`: chain name (or chain id if the name is unknown) of the chain
+    ///   the request was made on.
+    #[builder(setter(into, strip_option), default)]
+    request_throttled_seconds: Option<CounterVec>,
+}
+
+impl JsonRpcClientMetrics {
+    /// Counter of responses which disagreed with the rest of a quorum.
+    pub fn quorum_disagreement_count(&self) -> Option<&IntCounterVec> {
+        self.quorum_disagreement_count.as_ref()
+    }
+
+    /// Counter of the time requests were held back for by rate limiting.
+    pub fn request_throttled_seconds(&self) -> Option<&CounterVec> {
+        self.request_throttled_seconds.as_ref()
+    }
 }
 
 /// Expected label names for the metric.
@@ -64,6 +95,18 @@ pub const REQUEST_DURATION_SECONDS_LABELS: &[&str] =
 /// Help string for the metric.
 pub const REQUEST_DURATION_SECONDS_HELP: &str = "Total number of seconds spent making requests";
 
+/// Expected label names for the metric.
+pub const QUORUM_DISAGREEMENT_COUNT_LABELS: &[&str] = &["provider_node", "chain", "method"];
+/// Help string for the metric.
+pub const QUORUM_DISAGREEMENT_COUNT_HELP: &str =
+    "Total number of responses which disagreed with the rest of a quorum";
+
+/// Expected label names for the metric.
+pub const REQUEST_THROTTLED_SECONDS_LABELS: &[&str] = &["provider_node", "chain"];
+/// Help string for the metric.
+pub const REQUEST_THROTTLED_SECONDS_HELP: &str =
+    "Total number of seconds requests were held back for by client-side rate limiting";
+
 /// Configuration for the prometheus JsonRpcClioent. This can be loaded via
 /// serde.
 #[derive(Default, Clone, Debug)]
@@ -1,12 +1,16 @@
-use std::{env, fmt::Debug, sync::Arc};
+use std::{env, fmt::Debug, future::Future, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{eyre, Result};
+use futures_util::future::try_join_all;
 use hyperlane_core::config::*;
-use tracing::info;
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, instrument::Instrumented, warn};
 
 use crate::{
     create_chain_metrics,
+    db::DB,
     metrics::{create_agent_metrics, AgentMetrics, CoreMetrics},
     settings::Settings,
     ChainMetrics,
@@ -48,9 +52,76 @@ pub trait BaseAgent: Send + Sync + Debug {
     where
         Self: Sized;
 
-    /// Start running this agent.
+    /// Start running this agent. Once `cancel` is cancelled, the agent stops
+    /// taking new work and returns after finishing the work in flight.
     #[allow(clippy::async_yields_async)]
-    async fn run(self);
+    async fn run(self, cancel: CancellationToken);
+}
+
+/// Wait for an agent's `tasks` to finish, which they only do once the agent is
+/// shutting down. `background_tasks`, e.g. servers and metrics updaters, keep
+/// running until then and are only watched for panics.
+///
+/// All the tasks are aborted once they're no longer waited for, including when
+/// the agent missed its shutdown deadline and stopped being polled.
+pub async fn join_agent_tasks(
+    tasks: Vec<Instrumented<JoinHandle<()>>>,
+    background_tasks: Vec<Instrumented<JoinHandle<()>>>,
+) -> Result<(), JoinError> {
+    let _abort = AbortOnDrop(
+        tasks
+            .iter()
+            .chain(&background_tasks)
+            .map(|task| task.inner().abort_handle())
+            .collect(),
+    );
+    tokio::select! {
+        res = try_join_all(tasks) => res.map(|_| ()),
+        Err(err) = try_join_all(background_tasks) => Err(err),
+    }
+}
+
+/// Like `join_agent_tasks`, then flushes `db` so that the tasks' writes aren't
+/// lost when the process exits.
+pub async fn join_agent_tasks_and_flush_db(
+    tasks: Vec<Instrumented<JoinHandle<()>>>,
+    background_tasks: Vec<Instrumented<JoinHandle<()>>>,
+    db: &DB,
+) -> Result<(), JoinError> {
+    join_agent_tasks(tasks, background_tasks).await?;
+    match db.flush() {
+        Ok(()) => info!("Flushed db"),
+        Err(err) => warn!(?err, "Failed to flush db"),
+    }
+    Ok(())
+}
+
+struct AbortOnDrop(Vec<AbortHandle>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// Resolves once the process is asked to shut down with SIGTERM or SIGINT.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
 }
 
 /// Call this from `main` to fully initialize and run the agent for its entire
@@ -78,10 +149,171 @@ pub async fn agent_main<A: BaseAgent>() -> Result<()> {
     core_settings.tracing.start_tracing(&metrics)?;
     let agent_metrics = create_agent_metrics(&metrics)?;
     let chain_metrics = create_chain_metrics(&metrics)?;
+    let shutdown_deadline = core_settings.shutdown_deadline;
     let agent = A::from_settings(settings, metrics.clone(), agent_metrics, chain_metrics).await?;
 
-    // This await will only end if a panic happens. We won't crash, but instead gracefully shut down
-    agent.run().await;
-    info!(agent = A::AGENT_NAME, "Shutting down agent...");
+    run_until_shutdown(agent, shutdown_signal(), shutdown_deadline).await
+}
+
+/// Run `agent` until `shutdown` resolves, then give it up to `shutdown_deadline`
+/// to finish the work in flight.
+async fn run_until_shutdown<A: BaseAgent>(
+    agent: A,
+    shutdown: impl Future<Output = ()>,
+    shutdown_deadline: Duration,
+) -> Result<()> {
+    let cancel = CancellationToken::new();
+    let run = agent.run(cancel.clone());
+    tokio::pin!(run);
+    tokio::select! {
+        // The agent only stops on its own if one of its tasks panicked
+        _ = &mut run => {
+            return Err(eyre!("Agent {} stopped unexpectedly", A::AGENT_NAME));
+        }
+        _ = shutdown => {}
+    }
+
+    info!(
+        agent = A::AGENT_NAME,
+        ?shutdown_deadline,
+        "Shutting down agent, waiting for work in flight to finish..."
+    );
+    cancel.cancel();
+    if tokio::time::timeout(shutdown_deadline, run).await.is_err() {
+        warn!(agent = A::AGENT_NAME, "Agent didn't shut down in time");
+        return Err(eyre!(
+            "Agent {} didn't finish its work in flight within {shutdown_deadline:?}",
+            A::AGENT_NAME
+        ));
+    }
+    info!(agent = A::AGENT_NAME, "Agent shut down gracefully");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    use tokio::time::sleep;
+    use tracing::{info_span, Instrument};
+
+    use crate::db::test_utils::run_test_db;
+
+    use super::*;
+
+    struct TestSettings(Settings);
+
+    impl AsRef<Settings> for TestSettings {
+        fn as_ref(&self) -> &Settings {
+            &self.0
+        }
+    }
+
+    impl LoadableFromSettings for TestSettings {
+        fn load() -> ConfigResult<Self> {
+            unimplemented!()
+        }
+    }
+
+    /// Sets its flag once dropped, e.g. because the task holding it was aborted.
+    struct SetOnDrop(Arc<AtomicBool>);
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// An agent with a single task which writes to the db once per tick.
+    #[derive(Debug)]
+    struct TestAgent {
+        db: DB,
+        tick: Duration,
+        ticks: Arc<AtomicU32>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl BaseAgent for TestAgent {
+        const AGENT_NAME: &'static str = "test";
+
+        type Settings = TestSettings;
+
+        async fn from_settings(
+            _settings: Self::Settings,
+            _metrics: Arc<CoreMetrics>,
+            _agent_metrics: AgentMetrics,
+            _chain_metrics: ChainMetrics,
+        ) -> Result<Self> {
+            unimplemented!()
+        }
+
+        async fn run(self, cancel: CancellationToken) {
+            let (db, ticks, dropped) = (self.db.clone(), self.ticks, self.dropped);
+            let tick = self.tick;
+            let task = tokio::spawn(async move {
+                let _dropped = SetOnDrop(dropped);
+                while !cancel.is_cancelled() {
+                    sleep(tick).await;
+                    let count = ticks.fetch_add(1, Ordering::SeqCst);
+                    db.store(&count.to_be_bytes(), b"tick").unwrap();
+                }
+            })
+            .instrument(info_span!("TestTask"));
+            join_agent_tasks_and_flush_db(vec![task], vec![], &self.db)
+                .await
+                .unwrap();
+        }
+    }
+
+    fn test_agent(db: DB, tick: Duration) -> (TestAgent, Arc<AtomicU32>, Arc<AtomicBool>) {
+        let ticks = Arc::new(AtomicU32::new(0));
+        let dropped = Arc::new(AtomicBool::new(false));
+        let agent = TestAgent {
+            db,
+            tick,
+            ticks: ticks.clone(),
+            dropped: dropped.clone(),
+        };
+        (agent, ticks, dropped)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_finishes_ticks_in_flight_and_flushes_db() {
+        run_test_db(|db| async move {
+            let (agent, ticks, _) = test_agent(db.clone(), Duration::from_secs(10));
+
+            // Shut down halfway through the second tick
+            let shutdown = sleep(Duration::from_secs(15));
+            run_until_shutdown(agent, shutdown, Duration::from_secs(60))
+                .await
+                .unwrap();
+
+            assert_eq!(ticks.load(Ordering::SeqCst), 2);
+            assert_eq!(
+                db.retrieve(&1u32.to_be_bytes()).unwrap(),
+                Some(b"tick".to_vec())
+            );
+            assert_eq!(db.unflushed_entries().unwrap(), Some(0));
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_aborts_tasks_after_deadline() {
+        run_test_db(|db| async move {
+            let (agent, ticks, dropped) = test_agent(db, Duration::from_secs(24 * 60 * 60));
+
+            let result =
+                run_until_shutdown(agent, std::future::ready(()), Duration::from_secs(60)).await;
+            assert!(result.is_err());
+
+            // The task is aborted rather than left running in the background
+            while !dropped.load(Ordering::SeqCst) {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(ticks.load(Ordering::SeqCst), 0);
+        })
+        .await;
+    }
+}
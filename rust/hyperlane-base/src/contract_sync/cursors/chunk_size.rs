@@ -0,0 +1,128 @@
+//! Sizing of the ranges queried by cursors, which shrinks when providers reject a range
+//! as too large and grows back to the configured size after successful queries.
+
+use hyperlane_core::ChainCommunicationError;
+use tracing::{debug, warn};
+
+/// Number of consecutive successful queries after which the chunk size is doubled,
+/// up to the configured chunk size.
+const SUCCESSES_BEFORE_GROWTH: u32 = 5;
+
+/// Lowercased fragments of the errors providers return when a query's range
+/// is too large or would return too many results.
+const RANGE_ERROR_MESSAGES: &[&str] = &[
+    "query returned more than",
+    "block range too large",
+    "block range is too wide",
+    "range too large",
+    "exceed maximum block range",
+    "exceeds the range",
+    "response size exceeded",
+    "too many results",
+];
+
+/// The chunk size used by a cursor, adapted to what the provider accepts.
+#[derive(Debug, Clone)]
+pub(crate) struct AdaptiveChunkSize {
+    /// The configured chunk size, which is never exceeded.
+    max: u32,
+    /// The chunk size currently in use.
+    current: u32,
+    /// The number of successful queries since the chunk size last changed.
+    consecutive_successes: u32,
+}
+
+impl AdaptiveChunkSize {
+    pub fn new(max: u32) -> Self {
+        Self {
+            max,
+            current: max,
+            consecutive_successes: 0,
+        }
+    }
+
+    /// The chunk size currently in use.
+    pub fn get(&self) -> u32 {
+        self.current
+    }
+
+    /// Records a successful query, growing the chunk size back towards the
+    /// configured size after enough consecutive successes.
+    pub fn on_success(&mut self) {
+        if self.current >= self.max {
+            return;
+        }
+        self.consecutive_successes += 1;
+        if self.consecutive_successes >= SUCCESSES_BEFORE_GROWTH {
+            self.current = u32::min(u32::max(self.current * 2, 1), self.max);
+            self.consecutive_successes = 0;
+            debug!(chunk_size = self.current, "Increased chunk size");
+        }
+    }
+
+    /// Records a failed query, halving the chunk size if the provider rejected the
+    /// range as too large. Returns true if the chunk size was reduced.
+    pub fn on_error(&mut self, err: &ChainCommunicationError) -> bool {
+        self.consecutive_successes = 0;
+        if self.current == 0 || !is_range_error(err) {
+            return false;
+        }
+        self.current /= 2;
+        warn!(
+            chunk_size = self.current,
+            ?err,
+            "Provider rejected query range, reduced chunk size"
+        );
+        true
+    }
+}
+
+/// Whether the error was caused by the queried range being too large.
+fn is_range_error(err: &ChainCommunicationError) -> bool {
+    let message = err.to_string().to_lowercase();
+    RANGE_ERROR_MESSAGES
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn halves_on_range_errors_only() {
+        let mut chunk_size = AdaptiveChunkSize::new(1000);
+
+        let err = ChainCommunicationError::CustomError("connection reset".to_owned());
+        assert!(!chunk_size.on_error(&err));
+        assert_eq!(chunk_size.get(), 1000);
+
+        let err = ChainCommunicationError::CustomError(
+            "Query returned more than 10000 results".to_owned(),
+        );
+        assert!(chunk_size.on_error(&err));
+        assert_eq!(chunk_size.get(), 500);
+        assert!(chunk_size.on_error(&err));
+        assert_eq!(chunk_size.get(), 250);
+    }
+
+    #[test]
+    fn grows_back_after_successes() {
+        let mut chunk_size = AdaptiveChunkSize::new(1000);
+        let err = ChainCommunicationError::CustomError("block range too large".to_owned());
+        chunk_size.on_error(&err);
+        chunk_size.on_error(&err);
+        assert_eq!(chunk_size.get(), 250);
+
+        for _ in 0..SUCCESSES_BEFORE_GROWTH {
+            chunk_size.on_success();
+        }
+        assert_eq!(chunk_size.get(), 500);
+
+        // Never grows beyond the configured chunk size.
+        for _ in 0..SUCCESSES_BEFORE_GROWTH * 3 {
+            chunk_size.on_success();
+        }
+        assert_eq!(chunk_size.get(), 1000);
+    }
+}
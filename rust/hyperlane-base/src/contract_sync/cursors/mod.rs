@@ -1,5 +1,8 @@
 pub(crate) mod sequence_aware;
 
+mod chunk_size;
+use chunk_size::AdaptiveChunkSize;
+
 use hyperlane_core::{
     Delivery, HyperlaneDomainProtocol, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion,
 };
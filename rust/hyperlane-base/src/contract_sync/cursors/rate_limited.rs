@@ -6,20 +6,21 @@ use std::{
 };
 
 use async_trait::async_trait;
-use derive_new::new;
 use eyre::Result;
 use hyperlane_core::{
-    ContractSyncCursor, CursorAction, HyperlaneWatermarkedLogStore, Indexed, Indexer, LogMeta,
+    ChainCommunicationError, ContractSyncCursor, CursorAction, HyperlaneWatermarkedLogStore,
+    Indexed, Indexer, LogMeta,
 };
 
+use super::AdaptiveChunkSize;
 use crate::contract_sync::eta_calculator::SyncerEtaCalculator;
 
 /// Time window for the moving average used in the eta calculator in seconds.
 const ETA_TIME_WINDOW: f64 = 2. * 60.;
 
-#[derive(Debug, new)]
+#[derive(Debug)]
 pub(crate) struct SyncState {
-    chunk_size: u32,
+    chunk_size: AdaptiveChunkSize,
     /// The starting block for the cursor
     start_block: u32,
     /// The next block that should be indexed.
@@ -28,6 +29,15 @@ pub(crate) struct SyncState {
 }
 
 impl SyncState {
+    fn new(chunk_size: u32, start_block: u32, next_block: u32, direction: SyncDirection) -> Self {
+        Self {
+            chunk_size: AdaptiveChunkSize::new(chunk_size),
+            start_block,
+            next_block,
+            direction,
+        }
+    }
+
     async fn get_next_range(&self, tip: u32) -> Result<Option<RangeInclusive<u32>>> {
         // We attempt to index a range of blocks that is as large as possible.
         let range = self.block_range(tip);
@@ -41,13 +51,13 @@ impl SyncState {
         let (from, to) = match self.direction {
             SyncDirection::Forward => {
                 let from = self.next_block;
-                let mut to = from + self.chunk_size;
+                let mut to = from + self.chunk_size.get();
                 to = u32::min(to, tip);
                 (from, to)
             }
             SyncDirection::Backward => {
                 let to = self.next_block;
-                let from = to.saturating_sub(self.chunk_size);
+                let from = to.saturating_sub(self.chunk_size.get());
                 (from, to)
             }
         };
@@ -76,6 +86,11 @@ pub enum SyncDirection {
 /// Tool for handling the logic of what the next block range that should be
 /// queried is and also handling rate limiting. Rate limiting is automatically
 /// performed by `next_action`.
+///
+/// Unlike the sequence aware cursors, this cursor doesn't check for reorgs. It only
+/// queries blocks up to the finalized block number, so the logs it stores can't be
+/// reorged out, and its stores key logs by transaction rather than by sequence, so
+/// there'd be no cheap way to find the logs of a reorged block range anyway.
 pub(crate) struct RateLimitedContractSyncCursor<T> {
     indexer: Arc<dyn Indexer<T>>,
     db: Arc<dyn HyperlaneWatermarkedLogStore<T>>,
@@ -113,7 +128,7 @@ impl<T> RateLimitedContractSyncCursor<T> {
     /// Wait based on how close we are to the tip and update the tip,
     /// i.e. the highest block we may scrape.
     async fn get_rate_limit(&self) -> Result<Option<Duration>> {
-        if self.sync_state.next_block + self.sync_state.chunk_size < self.tip {
+        if self.sync_state.next_block + self.sync_state.chunk_size.get() < self.tip {
             // If doing the full chunk wouldn't exceed the already known tip we do not need to rate limit.
             return Ok(None);
         }
@@ -137,7 +152,7 @@ impl<T> RateLimitedContractSyncCursor<T> {
     }
 
     fn sync_step(&self) -> u32 {
-        self.sync_state.chunk_size
+        self.sync_state.chunk_size.get()
     }
 
     async fn get_next_range(&self) -> Result<Option<RangeInclusive<u32>>> {
@@ -194,10 +209,11 @@ where
                 self.sync_state.start_block,
                 self.sync_state
                     .next_block
-                    .saturating_sub(self.sync_state.chunk_size),
+                    .saturating_sub(self.sync_state.chunk_size.get()),
             ))
             .await?;
         self.sync_state.update_range(range);
+        self.sync_state.chunk_size.on_success();
 
         match self.indexer.get_finalized_block_number().await {
             Ok(tip) => {
@@ -214,6 +230,18 @@ where
             }
         }
     }
+
+    fn handle_query_error(
+        &mut self,
+        _: RangeInclusive<u32>,
+        err: &ChainCommunicationError,
+    ) -> bool {
+        self.sync_state.chunk_size.on_error(err)
+    }
+
+    fn chunk_size(&self) -> u32 {
+        self.sync_state.chunk_size.get()
+    }
 }
 
 #[cfg(test)]
@@ -323,6 +351,30 @@ pub(crate) mod test {
         assert!(matches!(action_3, CursorAction::Query(_expected_range)));
     }
 
+    #[tokio::test]
+    async fn test_next_action_shrinks_range_after_range_error() {
+        let mut cursor = mock_rate_limited_cursor(None).await;
+        let (action, _) = cursor.next_action().await.unwrap();
+        let range = match action {
+            CursorAction::Query(range) => range,
+            _ => panic!("Expected Query action"),
+        };
+
+        let err = ChainCommunicationError::CustomError("block range too large".to_owned());
+        assert!(cursor.handle_query_error(range.clone(), &err));
+        assert_eq!(cursor.chunk_size(), CHUNK_SIZE / 2);
+
+        let (action, _) = cursor.next_action().await.unwrap();
+        let shrunk_range = match action {
+            CursorAction::Query(range) => range,
+            _ => panic!("Expected Query action"),
+        };
+        assert_eq!(
+            shrunk_range,
+            *range.start()..=(range.start() + CHUNK_SIZE / 2)
+        );
+    }
+
     #[tokio::test]
     async fn test_next_action_sleeps_if_tip_is_not_updated() {
         let chain_tips = vec![10];
@@ -5,13 +5,14 @@ use std::{collections::HashSet, fmt::Debug, ops::RangeInclusive, sync::Arc, time
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction,
+    indexed_to_sequence_indexed_array, ChainCommunicationError, ContractSyncCursor, CursorAction,
     HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceIndexed,
 };
 use itertools::Itertools;
 use tracing::{debug, warn};
 
 use super::{LastIndexedSnapshot, TargetSnapshot};
+use crate::contract_sync::cursors::AdaptiveChunkSize;
 
 /// A sequence-aware cursor that syncs backward until there are no earlier logs to index.
 #[derive(Debug)]
@@ -19,7 +20,8 @@ pub(crate) struct BackwardSequenceAwareSyncCursor<T> {
     /// The max chunk size to query for logs.
     /// If in sequence mode, this is the max number of sequences to query.
     /// If in block mode, this is the max number of blocks to query.
-    chunk_size: u32,
+    /// This is reduced when the provider rejects a range as too large.
+    chunk_size: AdaptiveChunkSize,
     /// A DB used to check which logs have already been indexed.
     db: Arc<dyn HyperlaneSequenceAwareIndexerStoreReader<T>>,
     /// A snapshot of the last log to be indexed, or if no indexing has occurred yet,
@@ -50,7 +52,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
         };
 
         Self {
-            chunk_size,
+            chunk_size: AdaptiveChunkSize::new(chunk_size),
             db,
             current_indexing_snapshot: last_indexed_snapshot.previous_target(),
             last_indexed_snapshot,
@@ -85,7 +87,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
         // Query the block range ending at the current_indexing_snapshot's at_block.
         current_indexing_snapshot
             .at_block
-            .saturating_sub(self.chunk_size)..=current_indexing_snapshot.at_block
+            .saturating_sub(self.chunk_size.get())..=current_indexing_snapshot.at_block
     }
 
     /// Gets the next sequence range to index.
@@ -97,7 +99,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
         // Query the sequence range ending at the current_indexing_snapshot's sequence.
         current_indexing_snapshot
             .sequence
-            .saturating_sub(self.chunk_size)..=current_indexing_snapshot.sequence
+            .saturating_sub(self.chunk_size.get())..=current_indexing_snapshot.sequence
     }
 
     /// Reads the DB to check if the current indexing sequence has already been indexed,
@@ -138,7 +140,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
     /// log for the sequence number hasn't been indexed.
     async fn get_sequence_log_block_number(&self, sequence: u32) -> Result<Option<u32>> {
         // Ensure there's a full entry for the sequence.
-        if self.db.retrieve_by_sequence(sequence).await?.is_some() {
+        if self.db.is_sequence_indexed(sequence).await? {
             // And get the block number.
             if let Some(block_number) = self
                 .db
@@ -334,6 +336,8 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
     ) -> Result<()> {
+        self.chunk_size.on_success();
+
         let Some(current_indexing_snapshot) = self.current_indexing_snapshot.clone() else {
             // We're synced, no need to update at all.
             return Ok(());
@@ -366,6 +370,18 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
 
         Ok(())
     }
+
+    fn handle_query_error(
+        &mut self,
+        _: RangeInclusive<u32>,
+        err: &ChainCommunicationError,
+    ) -> bool {
+        self.chunk_size.on_error(err)
+    }
+
+    fn chunk_size(&self) -> u32 {
+        self.chunk_size.get()
+    }
 }
 
 #[cfg(test)]
@@ -955,7 +971,7 @@ mod test {
             let mut cursor = get_cursor().await;
 
             // Set the chunk size to 100 to make it easier to test.
-            cursor.chunk_size = 100;
+            cursor.chunk_size = AdaptiveChunkSize::new(100);
 
             // Expect the range to be:
             // (current - chunk_size, current)
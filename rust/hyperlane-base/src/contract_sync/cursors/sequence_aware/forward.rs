@@ -8,7 +8,7 @@ use std::{
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction,
+    indexed_to_sequence_indexed_array, ChainCommunicationError, ContractSyncCursor, CursorAction,
     HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceAwareIndexer,
     SequenceIndexed,
 };
@@ -16,6 +16,7 @@ use itertools::Itertools;
 use tracing::{debug, warn};
 
 use super::{LastIndexedSnapshot, TargetSnapshot};
+use crate::contract_sync::cursors::AdaptiveChunkSize;
 
 /// A sequence-aware cursor that syncs forwards in perpetuity.
 #[derive(Debug)]
@@ -23,7 +24,8 @@ pub(crate) struct ForwardSequenceAwareSyncCursor<T> {
     /// The max chunk size to query for logs.
     /// If in sequence mode, this is the max number of sequences to query.
     /// If in block mode, this is the max number of blocks to query.
-    chunk_size: u32,
+    /// This is reduced when the provider rejects a range as too large.
+    chunk_size: AdaptiveChunkSize,
     /// The latest sequence count querier.
     /// This is used to check if there are new logs to index and to
     /// establish targets to index towards.
@@ -59,7 +61,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
         };
 
         Self {
-            chunk_size,
+            chunk_size: AdaptiveChunkSize::new(chunk_size),
             latest_sequence_querier,
             db,
             last_indexed_snapshot,
@@ -72,6 +74,11 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
         }
     }
 
+    /// The sequence of the last indexed log, if any.
+    pub fn last_indexed_sequence(&self) -> Option<u32> {
+        self.last_indexed_snapshot.sequence
+    }
+
     /// Gets the next range of logs to index.
     /// If there are no logs to index, returns `None`.
     /// If there are logs to index, returns the range of logs, either by sequence or block number
@@ -123,10 +130,12 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
                 }
             }
             Ordering::Greater => {
-                // Providers may be internally inconsistent, e.g. RPC request A could hit a node
-                // whose tip is N and subsequent RPC request B could hit a node whose tip is < N.
-                // Just warn and try to continue as normal.
-                warn!(
+                // Logs pushed by a subscription are stored before they're final, and so
+                // before the onchain sequence count reflects them. Providers may also be
+                // internally inconsistent, e.g. RPC request A could hit a node whose tip is
+                // N and subsequent RPC request B could hit a node whose tip is < N.
+                // Just continue as normal.
+                debug!(
                     current_sequence,
                     onchain_sequence_count,
                     "Current sequence is greater than the onchain sequence count"
@@ -157,7 +166,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
         Some(
             self.current_indexing_snapshot.at_block
                 ..=u32::min(
-                    self.current_indexing_snapshot.at_block + self.chunk_size,
+                    self.current_indexing_snapshot.at_block + self.chunk_size.get(),
                     tip,
                 ),
         )
@@ -171,7 +180,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
         target_sequence: u32,
     ) -> RangeInclusive<u32> {
         // Query the sequence range starting from the cursor count.
-        current_sequence..=u32::min(target_sequence, current_sequence + self.chunk_size)
+        current_sequence..=u32::min(target_sequence, current_sequence + self.chunk_size.get())
     }
 
     /// Reads the DB to check if the current indexing sequence has already been indexed,
@@ -204,7 +213,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
     /// log for the sequence number hasn't been indexed.
     async fn get_sequence_log_block_number(&self, sequence: u32) -> Result<Option<u32>> {
         // Ensure there's a full entry for the sequence.
-        if self.db.retrieve_by_sequence(sequence).await?.is_some() {
+        if self.db.is_sequence_indexed(sequence).await? {
             // And get the block number.
             if let Some(block_number) = self
                 .db
@@ -425,6 +434,8 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
     ) -> Result<()> {
+        self.chunk_size.on_success();
+
         // Remove any sequence duplicates, filter out any logs preceding our current snapshot,
         // and sort in ascending order.
         let logs = indexed_to_sequence_indexed_array(logs)?
@@ -445,6 +456,18 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         };
         Ok(())
     }
+
+    fn handle_query_error(
+        &mut self,
+        _: RangeInclusive<u32>,
+        err: &ChainCommunicationError,
+    ) -> bool {
+        self.chunk_size.on_error(err)
+    }
+
+    fn chunk_size(&self) -> u32 {
+        self.chunk_size.get()
+    }
 }
 
 #[cfg(test)]
@@ -523,7 +546,7 @@ pub(crate) mod test {
         }
     }
 
-    #[derive(Debug, Clone, new)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, new)]
     pub struct MockSequencedData {
         pub sequence: u32,
     }
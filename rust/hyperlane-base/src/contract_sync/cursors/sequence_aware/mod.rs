@@ -1,15 +1,22 @@
-use std::{fmt::Debug, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{bail, Result};
 use hyperlane_core::{
-    ChainCommunicationError, ContractSyncCursor, CursorAction,
-    HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceAwareIndexer,
+    ChainCommunicationError, ContractSyncCursor, CursorAction, HyperlaneSequenceAwareIndexerStore,
+    IndexMode, Indexed, LogMeta, SequenceAwareIndexer, H256,
 };
+use prometheus::IntCounter;
 use std::ops::RangeInclusive;
+use tracing::{error, warn};
 
 mod backward;
-mod forward;
+pub(crate) mod forward;
 
 pub(crate) use backward::BackwardSequenceAwareSyncCursor;
 pub(crate) use forward::ForwardSequenceAwareSyncCursor;
@@ -54,6 +61,10 @@ pub(crate) struct TargetSnapshot {
     pub at_block: u32,
 }
 
+/// How often the block hashes of the most recently indexed logs are checked
+/// against the chain to detect reorgs.
+const REORG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug)]
 pub enum SyncDirection {
     Forward,
@@ -62,19 +73,35 @@ pub enum SyncDirection {
 
 /// A cursor that prefers to sync forward, but will sync backward if there is nothing to
 /// sync forward.
+///
+/// It also periodically checks whether the most recently indexed logs were reorged out,
+/// in which case they're removed from the db and both directions are reset to index them
+/// again. If rolling them back fails, the cursor retries it before indexing anything else,
+/// rather than indexing on top of reorged logs.
 pub(crate) struct ForwardBackwardSequenceAwareSyncCursor<T> {
     forward: ForwardSequenceAwareSyncCursor<T>,
     backward: BackwardSequenceAwareSyncCursor<T>,
     last_direction: SyncDirection,
+    latest_sequence_querier: Arc<dyn SequenceAwareIndexer<T>>,
+    db: Arc<dyn HyperlaneSequenceAwareIndexerStore<T>>,
+    chunk_size: u32,
+    mode: IndexMode,
+    last_reorg_check: Option<Instant>,
+    /// The first sequence of reorged logs which couldn't be rolled back yet, if any
+    pending_rollback: Option<u32>,
+    reorgs: IntCounter,
+    failed_rollbacks: IntCounter,
 }
 
-impl<T: Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
+impl<T: Debug + 'static> ForwardBackwardSequenceAwareSyncCursor<T> {
     /// Construct a new contract sync helper.
     pub async fn new(
         latest_sequence_querier: Arc<dyn SequenceAwareIndexer<T>>,
-        db: Arc<dyn HyperlaneSequenceAwareIndexerStoreReader<T>>,
+        db: Arc<dyn HyperlaneSequenceAwareIndexerStore<T>>,
         chunk_size: u32,
         mode: IndexMode,
+        reorgs: IntCounter,
+        failed_rollbacks: IntCounter,
     ) -> Result<Self> {
         let (sequence_count, tip) = latest_sequence_querier
             .latest_sequence_count_and_tip()
@@ -85,19 +112,164 @@ impl<T: Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
         let forward_cursor = ForwardSequenceAwareSyncCursor::new(
             chunk_size,
             latest_sequence_querier.clone(),
-            db.clone(),
+            Arc::new(db.clone()),
+            sequence_count,
+            tip,
+            mode,
+        );
+        let backward_cursor = BackwardSequenceAwareSyncCursor::new(
+            chunk_size,
+            Arc::new(db.clone()),
             sequence_count,
             tip,
             mode,
         );
-        let backward_cursor =
-            BackwardSequenceAwareSyncCursor::new(chunk_size, db, sequence_count, tip, mode);
         Ok(Self {
             forward: forward_cursor,
             backward: backward_cursor,
             last_direction: SyncDirection::Forward,
+            latest_sequence_querier,
+            db,
+            chunk_size,
+            mode,
+            last_reorg_check: None,
+            pending_rollback: None,
+            reorgs,
+            failed_rollbacks,
         })
     }
+
+    /// Checks whether the most recently indexed logs were reorged out, by comparing the
+    /// hashes of the blocks they were indexed at against the chain's canonical blocks.
+    /// If they were, the reorged logs are rolled back in the db and the cursor is reset
+    /// to index them again.
+    async fn check_for_reorg(&mut self) -> Result<()> {
+        if self.pending_rollback.is_none()
+            && self
+                .last_reorg_check
+                .is_some_and(|last_check| last_check.elapsed() < REORG_CHECK_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_reorg_check = Some(Instant::now());
+
+        let Some(latest_sequence) = self.forward.last_indexed_sequence() else {
+            return Ok(());
+        };
+        // Once reorged logs were found, they're rolled back even if some of them are gone
+        // from the db after a partially failed attempt.
+        let first_reorged_sequence = match self.pending_rollback {
+            Some(first_reorged_sequence) => first_reorged_sequence,
+            None => {
+                let Some(first_reorged_sequence) =
+                    self.first_reorged_sequence(latest_sequence).await?
+                else {
+                    return Ok(());
+                };
+                first_reorged_sequence
+            }
+        };
+
+        let rolled_back = match self
+            .db
+            .rollback_logs(first_reorged_sequence..=latest_sequence)
+            .await
+        {
+            Ok(rolled_back) => rolled_back,
+            Err(err) => {
+                self.failed_rollbacks.inc();
+                self.pending_rollback = Some(first_reorged_sequence);
+                error!(
+                    ?err,
+                    first_reorged_sequence,
+                    latest_sequence,
+                    "Reorg detected, but the reorged logs couldn't be rolled back. Indexing is paused until they are"
+                );
+                return Ok(());
+            }
+        };
+        // Logs that were reorged out can only be re-included after the last log that
+        // wasn't, so index forward from that log's block. If every log was reorged
+        // out, there's no such bound and indexing restarts from the beginning.
+        let start_block = match first_reorged_sequence.checked_sub(1) {
+            Some(last_canonical_sequence) => self
+                .db
+                .retrieve_log_block_number_by_sequence(last_canonical_sequence)
+                .await?
+                .map(u32::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            None => 0,
+        };
+
+        self.pending_rollback = None;
+        self.reorgs.inc();
+        warn!(
+            first_reorged_sequence,
+            latest_sequence,
+            rolled_back,
+            start_block,
+            "Reorg detected, rolled back indexed logs and reset cursor"
+        );
+
+        self.forward = ForwardSequenceAwareSyncCursor::new(
+            self.chunk_size,
+            self.latest_sequence_querier.clone(),
+            Arc::new(self.db.clone()),
+            first_reorged_sequence,
+            start_block,
+            self.mode,
+        );
+        self.backward = BackwardSequenceAwareSyncCursor::new(
+            self.chunk_size,
+            Arc::new(self.db.clone()),
+            first_reorged_sequence,
+            start_block,
+            self.mode,
+        );
+        self.last_direction = SyncDirection::Forward;
+        Ok(())
+    }
+
+    /// Walks back from the latest indexed log, returning the earliest sequence whose block
+    /// is no longer canonical. The walk stops at the first log whose block is still
+    /// canonical, since all of that block's ancestors must be too.
+    async fn first_reorged_sequence(&self, latest_sequence: u32) -> Result<Option<u32>> {
+        let mut canonical_hashes: HashMap<u64, Option<H256>> = HashMap::new();
+        let mut first_reorged_sequence = None;
+        for sequence in (0..=latest_sequence).rev() {
+            let (Some(block_number), Some(indexed_hash)) = (
+                self.db
+                    .retrieve_log_block_number_by_sequence(sequence)
+                    .await?,
+                self.db
+                    .retrieve_log_block_hash_by_sequence(sequence)
+                    .await?,
+            ) else {
+                // The log's block hash wasn't recorded, so it can't be checked.
+                break;
+            };
+            let canonical_hash = match canonical_hashes.get(&block_number) {
+                Some(hash) => *hash,
+                None => {
+                    let hash = self
+                        .latest_sequence_querier
+                        .get_block_hash(block_number)
+                        .await?;
+                    canonical_hashes.insert(block_number, hash);
+                    hash
+                }
+            };
+            match canonical_hash {
+                Some(canonical_hash) if canonical_hash != indexed_hash => {
+                    first_reorged_sequence = Some(sequence);
+                }
+                // Either the block is still canonical, or the indexer can't look it up.
+                _ => break,
+            }
+        }
+        Ok(first_reorged_sequence)
+    }
 }
 
 #[async_trait]
@@ -107,6 +279,14 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
     async fn next_action(&mut self) -> Result<(CursorAction, Duration)> {
         // TODO: Proper ETA for backwards sync
         let eta = Duration::from_secs(0);
+        if let Err(err) = self.check_for_reorg().await {
+            warn!(?err, "Failed to check indexed logs for reorgs");
+        }
+        if let Some(first_reorged_sequence) = self.pending_rollback {
+            bail!(
+                "Logs from sequence {first_reorged_sequence} on were reorged out but couldn't be rolled back yet"
+            );
+        }
         // Prioritize forward syncing over backward syncing.
         if let Some(forward_range) = self.forward.get_next_range().await? {
             self.last_direction = SyncDirection::Forward;
@@ -135,4 +315,218 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
             SyncDirection::Backward => self.backward.update(logs, range).await,
         }
     }
+
+    fn handle_query_error(
+        &mut self,
+        range: RangeInclusive<u32>,
+        err: &ChainCommunicationError,
+    ) -> bool {
+        match self.last_direction {
+            SyncDirection::Forward => self.forward.handle_query_error(range, err),
+            SyncDirection::Backward => self.backward.handle_query_error(range, err),
+        }
+    }
+
+    fn chunk_size(&self) -> u32 {
+        match self.last_direction {
+            SyncDirection::Forward => self.forward.chunk_size(),
+            SyncDirection::Backward => self.backward.chunk_size(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    use hyperlane_core::{
+        ChainResult, HyperlaneLogStore, HyperlaneReorgAwareIndexerStore,
+        HyperlaneSequenceAwareIndexerStoreReader, Indexer, Sequenced,
+    };
+
+    use super::forward::test::{log_meta_with_block, MockSequencedData};
+    use super::*;
+
+    const REORGED_HASH: H256 = H256::repeat_byte(0xff);
+
+    #[derive(Debug)]
+    struct MockReorgedIndexer {
+        reorged_blocks: Vec<u64>,
+    }
+
+    #[async_trait]
+    impl SequenceAwareIndexer<MockSequencedData> for MockReorgedIndexer {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            Ok((Some(5), 100))
+        }
+    }
+
+    #[async_trait]
+    impl Indexer<MockSequencedData> for MockReorgedIndexer {
+        async fn fetch_logs(
+            &self,
+            _range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<MockSequencedData>, LogMeta)>> {
+            Ok(vec![])
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(100)
+        }
+
+        async fn get_block_hash(&self, block_number: u64) -> ChainResult<Option<H256>> {
+            if self.reorged_blocks.contains(&block_number) {
+                Ok(Some(REORGED_HASH))
+            } else {
+                Ok(Some(H256::from_low_u64_be(block_number)))
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct MockReorgAwareStore {
+        logs: Mutex<Vec<(MockSequencedData, LogMeta)>>,
+        rollback_fails: AtomicBool,
+    }
+
+    impl MockReorgAwareStore {
+        /// A store with logs for sequences 0 to 4, at blocks 10 to 50.
+        fn new(rollback_fails: bool) -> Self {
+            let logs = (0..5)
+                .map(|sequence| {
+                    let block_number = (sequence as u64 + 1) * 10;
+                    let meta = LogMeta {
+                        block_hash: H256::from_low_u64_be(block_number),
+                        ..log_meta_with_block(block_number)
+                    };
+                    (MockSequencedData::new(sequence), meta)
+                })
+                .collect();
+            Self {
+                logs: Mutex::new(logs),
+                rollback_fails: AtomicBool::new(rollback_fails),
+            }
+        }
+
+        fn find(&self, sequence: u32) -> Option<(MockSequencedData, LogMeta)> {
+            self.logs
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(log, _)| log.sequence() == Some(sequence))
+                .cloned()
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneLogStore<MockSequencedData> for MockReorgAwareStore {
+        async fn store_logs(&self, logs: &[(Indexed<MockSequencedData>, LogMeta)]) -> Result<u32> {
+            Ok(logs.len() as u32)
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneSequenceAwareIndexerStoreReader<MockSequencedData> for MockReorgAwareStore {
+        async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<MockSequencedData>> {
+            Ok(self.find(sequence).map(|(log, _)| log))
+        }
+
+        async fn retrieve_log_block_number_by_sequence(
+            &self,
+            sequence: u32,
+        ) -> Result<Option<u64>> {
+            Ok(self.find(sequence).map(|(_, meta)| meta.block_number))
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneReorgAwareIndexerStore<MockSequencedData> for MockReorgAwareStore {
+        async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+            Ok(self.find(sequence).map(|(_, meta)| meta.block_hash))
+        }
+
+        async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+            if self.rollback_fails.load(Ordering::SeqCst) {
+                bail!("Failed to roll back logs");
+            }
+            let mut logs = self.logs.lock().unwrap();
+            let count = logs.len();
+            logs.retain(|(log, _)| !sequences.contains(&log.sequence));
+            Ok((count - logs.len()) as u32)
+        }
+    }
+
+    async fn get_cursor(
+        reorged_blocks: Vec<u64>,
+        store: Arc<MockReorgAwareStore>,
+    ) -> (
+        ForwardBackwardSequenceAwareSyncCursor<MockSequencedData>,
+        IntCounter,
+        IntCounter,
+    ) {
+        let reorgs = IntCounter::new("reorgs", "help string").unwrap();
+        let failed_rollbacks = IntCounter::new("failed_rollbacks", "help string").unwrap();
+        let cursor = ForwardBackwardSequenceAwareSyncCursor::new(
+            Arc::new(MockReorgedIndexer { reorged_blocks }),
+            store,
+            100,
+            IndexMode::Sequence,
+            reorgs.clone(),
+            failed_rollbacks.clone(),
+        )
+        .await
+        .unwrap();
+        (cursor, reorgs, failed_rollbacks)
+    }
+
+    #[tokio::test]
+    async fn test_does_not_roll_back_canonical_logs() {
+        let store = Arc::new(MockReorgAwareStore::new(false));
+        let (mut cursor, reorgs, failed_rollbacks) = get_cursor(vec![], store.clone()).await;
+
+        cursor.next_action().await.unwrap();
+
+        assert_eq!(reorgs.get(), 0);
+        assert_eq!(failed_rollbacks.get(), 0);
+        assert_eq!(store.logs.lock().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_rolls_back_reorged_logs_and_reindexes_them() {
+        let store = Arc::new(MockReorgAwareStore::new(false));
+        let (mut cursor, reorgs, failed_rollbacks) = get_cursor(vec![40, 50], store.clone()).await;
+
+        let (action, _) = cursor.next_action().await.unwrap();
+
+        // The logs at the reorged blocks are rolled back and queried again
+        assert!(matches!(action, CursorAction::Query(range) if range == (3..=4)));
+        assert_eq!(reorgs.get(), 1);
+        assert_eq!(failed_rollbacks.get(), 0);
+        assert!(store.find(2).is_some());
+        assert!(store.find(3).is_none());
+        assert!(store.find(4).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retries_rolling_back_reorged_logs() {
+        let store = Arc::new(MockReorgAwareStore::new(true));
+        let (mut cursor, reorgs, failed_rollbacks) = get_cursor(vec![40, 50], store.clone()).await;
+
+        // The cursor keeps refusing to index on top of the reorged logs
+        assert!(cursor.next_action().await.is_err());
+        assert!(cursor.next_action().await.is_err());
+        assert_eq!(reorgs.get(), 0);
+        assert_eq!(failed_rollbacks.get(), 2);
+        assert_eq!(store.logs.lock().unwrap().len(), 5);
+
+        // Once rolling back succeeds, the reorged logs are queried again
+        store.rollback_fails.store(false, Ordering::SeqCst);
+        let (action, _) = cursor.next_action().await.unwrap();
+        assert!(matches!(action, CursorAction::Query(range) if range == (3..=4)));
+        assert_eq!(reorgs.get(), 1);
+        assert!(store.find(3).is_none());
+    }
 }
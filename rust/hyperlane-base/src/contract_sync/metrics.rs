@@ -20,6 +20,27 @@ pub struct ContractSyncMetrics {
 
     /// See `last_known_message_nonce` in CoreMetrics.
     pub message_nonce: IntGaugeVec,
+
+    /// Reorgs detected in indexed logs, which caused them to be rolled back and re-indexed.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the indexer is collecting data from.
+    pub reorgs: IntCounterVec,
+
+    /// Failed attempts to roll back reorged logs. Indexing pauses until rolling them
+    /// back succeeds.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the indexer is collecting data from.
+    pub failed_rollbacks: IntCounterVec,
+
+    /// The max number of blocks, or sequences, currently queried at once, which shrinks
+    /// when providers reject ranges as too large.
+    ///
+    /// Labels:
+    /// - `data_type`: the data the indexer is recording. E.g. `messages` or `gas_payments`.
+    /// - `chain`: Chain the indexer is collecting data from.
+    pub chunk_size: IntGaugeVec,
 }
 
 impl ContractSyncMetrics {
@@ -43,10 +64,37 @@ impl ContractSyncMetrics {
 
         let message_nonce = metrics.last_known_message_nonce();
 
+        let reorgs = metrics
+            .new_int_counter(
+                "contract_sync_reorgs",
+                "Number of reorgs detected in indexed logs",
+                &["chain"],
+            )
+            .expect("failed to register reorgs metric");
+
+        let failed_rollbacks = metrics
+            .new_int_counter(
+                "contract_sync_failed_rollbacks",
+                "Number of failed attempts to roll back reorged logs",
+                &["chain"],
+            )
+            .expect("failed to register failed_rollbacks metric");
+
+        let chunk_size = metrics
+            .new_int_gauge(
+                "contract_sync_chunk_size",
+                "Number of blocks, or sequences, currently queried at once",
+                &["data_type", "chain"],
+            )
+            .expect("failed to register chunk_size metric");
+
         ContractSyncMetrics {
             indexed_height,
             stored_events,
             message_nonce,
+            reorgs,
+            failed_rollbacks,
+            chunk_size,
         }
     }
 }
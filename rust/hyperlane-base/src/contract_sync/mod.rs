@@ -5,13 +5,16 @@ use std::{
 use axum::async_trait;
 use cursors::*;
 use derive_new::new;
+use futures_util::StreamExt;
 use hyperlane_core::{
     utils::fmt_sync_time, ContractSyncCursor, CursorAction, HyperlaneDomain, HyperlaneLogStore,
     HyperlaneSequenceAwareIndexerStore, HyperlaneWatermarkedLogStore, Indexer,
     SequenceAwareIndexer,
 };
 pub use metrics::ContractSyncMetrics;
+use prometheus::IntCounter;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 use crate::settings::IndexSettings;
@@ -33,6 +36,9 @@ pub struct ContractSync<T, D: HyperlaneLogStore<T>, I: Indexer<T>> {
     db: D,
     indexer: I,
     metrics: ContractSyncMetrics,
+    /// Whether to store logs as soon as the indexer's subscription pushes them,
+    /// if it supports subscriptions
+    subscribe: bool,
     _phantom: PhantomData<T>,
 }
 
@@ -53,27 +59,60 @@ where
     D: HyperlaneLogStore<T>,
     I: Indexer<T> + 'static,
 {
-    /// Sync logs and write them to the LogStore
-    #[tracing::instrument(name = "ContractSync", fields(domain=self.domain().name()), skip(self, cursor))]
-    pub async fn sync(&self, label: &'static str, mut cursor: Box<dyn ContractSyncCursor<T>>) {
+    /// Sync logs and write them to the LogStore.
+    ///
+    /// If subscriptions are enabled and supported by the indexer, logs pushed by
+    /// the chain are written as soon as they arrive, before they reach finality.
+    /// The cursor then skips over them, so it only queries the logs which
+    /// weren't pushed, e.g. while the subscription was down, and checks the
+    /// written logs for reorgs.
+    ///
+    /// Returns once `cancel` is cancelled, after writing the logs in flight.
+    #[tracing::instrument(name = "ContractSync", fields(domain=self.domain().name()), skip(self, cursor, cancel))]
+    pub async fn sync(
+        &self,
+        label: &'static str,
+        cursor: Box<dyn ContractSyncCursor<T>>,
+        cancel: CancellationToken,
+    ) {
+        let stored_logs = self
+            .metrics
+            .stored_events
+            .with_label_values(&[label, self.domain.as_ref()]);
+        tokio::join!(
+            self.poll(label, cursor, &stored_logs, &cancel),
+            self.subscribe(&stored_logs, &cancel)
+        );
+    }
+
+    /// Query logs in the ranges returned by the cursor and write them to the
+    /// LogStore.
+    async fn poll(
+        &self,
+        label: &'static str,
+        mut cursor: Box<dyn ContractSyncCursor<T>>,
+        stored_logs: &IntCounter,
+        cancel: &CancellationToken,
+    ) {
         let chain_name = self.domain.as_ref();
         let indexed_height = self
             .metrics
             .indexed_height
             .with_label_values(&[label, chain_name]);
-        let stored_logs = self
+        let chunk_size = self
             .metrics
-            .stored_events
+            .chunk_size
             .with_label_values(&[label, chain_name]);
 
-        loop {
+        while !cancel.is_cancelled() {
             indexed_height.set(cursor.latest_queried_block() as i64);
+            chunk_size.set(cursor.chunk_size() as i64);
 
             let (action, eta) = match cursor.next_action().await {
                 Ok((action, eta)) => (action, eta),
                 Err(err) => {
                     warn!(?err, "Error getting next action");
-                    sleep(SLEEP_DURATION).await;
+                    sleep_unless_cancelled(SLEEP_DURATION, cancel).await;
                     continue;
                 }
             };
@@ -88,6 +127,11 @@ where
                         Ok(logs) => logs,
                         Err(err) => {
                             warn!(?err, "Error fetching logs");
+                            // If the cursor narrowed its range in response, e.g. because the
+                            // provider rejected the range as too large, retry right away.
+                            if cursor.handle_query_error(range, &err) {
+                                break Default::default();
+                            }
                             break SLEEP_DURATION;
                         }
                     };
@@ -119,11 +163,67 @@ where
                 },
                 CursorAction::Sleep(duration) => duration,
             };
-            sleep(sleep_duration).await;
+            sleep_unless_cancelled(sleep_duration, cancel).await;
+        }
+    }
+
+    /// Write the logs pushed by the indexer's subscription to the LogStore,
+    /// resubscribing whenever the subscription ends. Returns immediately if
+    /// subscriptions are disabled or the indexer does not support them.
+    ///
+    /// Logs which fail to be written are left for the cursor to query once
+    /// they're final.
+    async fn subscribe(&self, stored_logs: &IntCounter, cancel: &CancellationToken) {
+        if !self.subscribe {
+            return;
+        }
+        while !cancel.is_cancelled() {
+            let mut logs = match self.indexer.subscribe_logs().await {
+                Ok(Some(logs)) => logs,
+                Ok(None) => {
+                    debug!("Indexer does not support subscriptions, only polling for logs");
+                    return;
+                }
+                Err(err) => {
+                    warn!(?err, "Error subscribing to logs");
+                    sleep_unless_cancelled(SLEEP_DURATION, cancel).await;
+                    continue;
+                }
+            };
+            info!("Subscribed to logs");
+
+            loop {
+                let log = tokio::select! {
+                    log = logs.next() => log,
+                    _ = cancel.cancelled() => return,
+                };
+                match log {
+                    Some(Ok(log)) => {
+                        debug!(meta=?log.1, "Received log from subscription");
+                        match self.db.store_logs(&[log]).await {
+                            Ok(stored) => stored_logs.inc_by(stored as u64),
+                            Err(err) => warn!(?err, "Error storing log from subscription"),
+                        }
+                    }
+                    Some(Err(err)) => warn!(?err, "Error receiving log from subscription"),
+                    None => break,
+                }
+            }
+
+            warn!("Log subscription ended, resubscribing");
+            sleep_unless_cancelled(SLEEP_DURATION, cancel).await;
         }
     }
 }
 
+/// Sleep for `duration`, waking up early if `cancel` is cancelled.
+async fn sleep_unless_cancelled(duration: Duration, cancel: &CancellationToken) {
+    tokio::select! {
+        _ = sleep(duration) => {}
+        _ = cancel.cancelled() => {}
+    }
+}
+
 /// A ContractSync for syncing events using a SequenceAwareIndexer
 pub type SequenceAwareContractSync<T, U> = ContractSync<T, U, Arc<dyn SequenceAwareIndexer<T>>>;
 
@@ -140,8 +240,14 @@ pub trait ContractSyncer<T>: Send + Sync {
     /// Returns a new cursor to be used for syncing events from the indexer
     async fn cursor(&self, index_settings: IndexSettings) -> Box<dyn ContractSyncCursor<T>>;
 
-    /// Syncs events from the indexer using the provided cursor
-    async fn sync(&self, label: &'static str, cursor: Box<dyn ContractSyncCursor<T>>);
+    /// Syncs events from the indexer using the provided cursor, until `cancel`
+    /// is cancelled
+    async fn sync(
+        &self,
+        label: &'static str,
+        cursor: Box<dyn ContractSyncCursor<T>>,
+        cancel: CancellationToken,
+    );
 
     /// The domain of this syncer
     fn domain(&self) -> &HyperlaneDomain;
@@ -157,8 +263,7 @@ where
         let watermark = self.db.retrieve_high_watermark().await.unwrap();
         let index_settings = IndexSettings {
             from: watermark.unwrap_or(index_settings.from),
-            chunk_size: index_settings.chunk_size,
-            mode: index_settings.mode,
+            ..index_settings
         };
         Box::new(
             RateLimitedContractSyncCursor::new(
@@ -172,8 +277,13 @@ where
         )
     }
 
-    async fn sync(&self, label: &'static str, cursor: Box<dyn ContractSyncCursor<T>>) {
-        ContractSync::sync(self, label, cursor).await;
+    async fn sync(
+        &self,
+        label: &'static str,
+        cursor: Box<dyn ContractSyncCursor<T>>,
+        cancel: CancellationToken,
+    ) {
+        ContractSync::sync(self, label, cursor, cancel).await;
     }
 
     fn domain(&self) -> &HyperlaneDomain {
@@ -198,20 +308,264 @@ where
         Box::new(
             ForwardBackwardSequenceAwareSyncCursor::new(
                 self.indexer.clone(),
-                Arc::new(self.db.clone()),
+                self.db.clone(),
                 index_settings.chunk_size,
                 index_settings.mode,
+                self.metrics
+                    .reorgs
+                    .with_label_values(&[self.domain.as_ref()]),
+                self.metrics
+                    .failed_rollbacks
+                    .with_label_values(&[self.domain.as_ref()]),
             )
             .await
             .unwrap(),
         )
     }
 
-    async fn sync(&self, label: &'static str, cursor: Box<dyn ContractSyncCursor<T>>) {
-        ContractSync::sync(self, label, cursor).await;
+    async fn sync(
+        &self,
+        label: &'static str,
+        cursor: Box<dyn ContractSyncCursor<T>>,
+        cancel: CancellationToken,
+    ) {
+        ContractSync::sync(self, label, cursor, cancel).await;
     }
 
     fn domain(&self) -> &HyperlaneDomain {
         ContractSync::domain(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, ops::RangeInclusive, sync::Mutex};
+
+    use futures_util::stream;
+    use hyperlane_core::{
+        ChainResult, HyperlaneReorgAwareIndexerStore, HyperlaneSequenceAwareIndexerStoreReader,
+        IndexMode, Indexed, LogMeta, LogStream, H256,
+    };
+    use prometheus::Registry;
+    use tokio::{
+        sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+        task::JoinHandle,
+    };
+
+    use super::cursors::sequence_aware::forward::test::{log_meta_with_block, MockSequencedData};
+    use super::*;
+    use crate::CoreMetrics;
+
+    type MockLog = (Indexed<MockSequencedData>, LogMeta);
+
+    fn mock_log(sequence: u32) -> MockLog {
+        (
+            MockSequencedData::new(sequence).into(),
+            log_meta_with_block(sequence as u64 * 10),
+        )
+    }
+
+    /// Returns its final logs from queries, and pushes the logs sent to its
+    /// subscription, which can only be subscribed to once
+    #[derive(Debug)]
+    struct MockPushingIndexer {
+        final_logs: Mutex<Vec<MockLog>>,
+        queried_ranges: Mutex<Vec<RangeInclusive<u32>>>,
+        subscription: Mutex<Option<UnboundedReceiver<MockLog>>>,
+    }
+
+    impl MockPushingIndexer {
+        fn new() -> (Arc<Self>, UnboundedSender<MockLog>) {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let indexer = Self {
+                final_logs: Default::default(),
+                queried_ranges: Default::default(),
+                subscription: Mutex::new(Some(receiver)),
+            };
+            (Arc::new(indexer), sender)
+        }
+    }
+
+    #[async_trait]
+    impl Indexer<MockSequencedData> for MockPushingIndexer {
+        async fn fetch_logs(&self, range: RangeInclusive<u32>) -> ChainResult<Vec<MockLog>> {
+            self.queried_ranges.lock().unwrap().push(range.clone());
+            Ok(self
+                .final_logs
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(log, _)| log.sequence.map_or(false, |s| range.contains(&s)))
+                .cloned()
+                .collect())
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(100)
+        }
+
+        async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<MockSequencedData>>> {
+            let Some(receiver) = self.subscription.lock().unwrap().take() else {
+                return Ok(None);
+            };
+            Ok(Some(
+                stream::unfold(receiver, |mut receiver| async move {
+                    receiver.recv().await.map(|log| (Ok(log), receiver))
+                })
+                .boxed(),
+            ))
+        }
+    }
+
+    #[async_trait]
+    impl SequenceAwareIndexer<MockSequencedData> for MockPushingIndexer {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            Ok((Some(self.final_logs.lock().unwrap().len() as u32), 100))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct MockStore {
+        logs: Mutex<BTreeMap<u32, (MockSequencedData, LogMeta)>>,
+    }
+
+    impl MockStore {
+        /// Waits until the store has logs for exactly `sequences`, panicking if
+        /// that takes too long
+        async fn wait_for(&self, sequences: &[u32]) {
+            tokio::time::timeout(Duration::from_secs(60), async {
+                while !self.logs.lock().unwrap().keys().eq(sequences) {
+                    sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("Logs weren't stored");
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneLogStore<MockSequencedData> for MockStore {
+        async fn store_logs(&self, logs: &[MockLog]) -> eyre::Result<u32> {
+            let mut stored = self.logs.lock().unwrap();
+            let count = stored.len();
+            for (log, meta) in logs {
+                stored.insert(log.inner().sequence, (log.inner().clone(), meta.clone()));
+            }
+            Ok((stored.len() - count) as u32)
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneSequenceAwareIndexerStoreReader<MockSequencedData> for MockStore {
+        async fn retrieve_by_sequence(
+            &self,
+            sequence: u32,
+        ) -> eyre::Result<Option<MockSequencedData>> {
+            Ok(self
+                .logs
+                .lock()
+                .unwrap()
+                .get(&sequence)
+                .map(|(log, _)| log.clone()))
+        }
+
+        async fn retrieve_log_block_number_by_sequence(
+            &self,
+            sequence: u32,
+        ) -> eyre::Result<Option<u64>> {
+            Ok(self
+                .logs
+                .lock()
+                .unwrap()
+                .get(&sequence)
+                .map(|(_, meta)| meta.block_number))
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneReorgAwareIndexerStore<MockSequencedData> for MockStore {
+        async fn retrieve_log_block_hash_by_sequence(
+            &self,
+            _sequence: u32,
+        ) -> eyre::Result<Option<H256>> {
+            Ok(None)
+        }
+
+        async fn rollback_logs(&self, _sequences: RangeInclusive<u32>) -> eyre::Result<u32> {
+            Ok(0)
+        }
+    }
+
+    /// Spawns a sync with subscriptions enabled, which runs until `cancel` is
+    /// cancelled
+    async fn spawn_sync(
+        indexer: Arc<MockPushingIndexer>,
+        store: Arc<MockStore>,
+        cancel: CancellationToken,
+    ) -> JoinHandle<()> {
+        let indexer: Arc<dyn SequenceAwareIndexer<MockSequencedData>> = indexer;
+        let db: SequenceAwareLogStore<MockSequencedData> = store;
+        let metrics =
+            ContractSyncMetrics::new(&CoreMetrics::new("test", 9090, Registry::new()).unwrap());
+        let cursor = ForwardBackwardSequenceAwareSyncCursor::new(
+            indexer.clone(),
+            db.clone(),
+            100,
+            IndexMode::Sequence,
+            metrics.reorgs.with_label_values(&["test"]),
+            metrics.failed_rollbacks.with_label_values(&["test"]),
+        )
+        .await
+        .unwrap();
+        let contract_sync = ContractSync::new(
+            HyperlaneDomain::new_test_domain("test"),
+            db,
+            indexer,
+            metrics,
+            true,
+        );
+        tokio::spawn(async move {
+            contract_sync
+                .sync("dispatched_messages", Box::new(cursor), cancel)
+                .await
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stores_pushed_logs() {
+        let (indexer, subscription) = MockPushingIndexer::new();
+        let store = Arc::new(MockStore::default());
+        let cancel = CancellationToken::new();
+        let sync = spawn_sync(indexer.clone(), store.clone(), cancel.clone()).await;
+
+        // The logs aren't final yet, so only the subscription finds them
+        subscription.send(mock_log(0)).unwrap();
+        subscription.send(mock_log(1)).unwrap();
+        store.wait_for(&[0, 1]).await;
+        assert!(indexer.queried_ranges.lock().unwrap().is_empty());
+
+        cancel.cancel();
+        sync.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backfills_gaps_after_subscription_drops() {
+        let (indexer, subscription) = MockPushingIndexer::new();
+        let store = Arc::new(MockStore::default());
+        let cancel = CancellationToken::new();
+        let sync = spawn_sync(indexer.clone(), store.clone(), cancel.clone()).await;
+
+        // Logs 1 and 2 are emitted while the subscription is down
+        subscription.send(mock_log(0)).unwrap();
+        drop(subscription);
+        store.wait_for(&[0]).await;
+
+        // Once they're final, the cursor queries for them, skipping the pushed log
+        *indexer.final_logs.lock().unwrap() = (0..3).map(mock_log).collect();
+        store.wait_for(&[0, 1, 2]).await;
+        assert_eq!(*indexer.queried_ranges.lock().unwrap(), vec![1..=2]);
+
+        cancel.cancel();
+        sync.await.unwrap();
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing, Json, Router,
+};
+use eyre::{bail, eyre, Context, Result};
+use hyperlane_core::HyperlaneDomain;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Mutex, task::JoinHandle};
+use tracing::{error, info, info_span, instrument::Instrumented, warn, Instrument};
+
+use crate::{
+    db::HyperlaneRocksDB,
+    server::{is_authorized, ApiToken},
+    settings::CheckpointSyncerConf,
+    BackupStorage,
+};
+
+use super::DB;
+
+const DB_BACKUP_API_BASE: &str = "/db_backup";
+/// The file pointing at the most recent backup, at the root of the backups
+const LATEST_BACKUP_FILE: &str = "latest_backup.json";
+/// The names of the retained backups, oldest first, at the root of the backups
+const BACKUPS_INDEX_FILE: &str = "backups.json";
+const MANIFEST_FILE: &str = "manifest.json";
+/// How many backups are kept if not configured
+pub const DEFAULT_RETAINED_DB_BACKUPS: usize = 7;
+
+/// Config for backups of an agent's database
+#[derive(Debug, Clone)]
+pub struct DbBackupConf {
+    /// Where to write backups to
+    pub storage: CheckpointSyncerConf,
+    /// How often to back up the database. If not specified, backups are only
+    /// made when requested via the server.
+    pub interval: Option<Duration>,
+    /// How many of the most recent backups to keep. Older backups are deleted
+    /// once a new backup is complete.
+    pub retained_backups: usize,
+    /// Bearer token which requests to `POST /db_backup` must be authenticated
+    /// with. The route isn't served if not specified.
+    pub api_token: Option<ApiToken>,
+}
+
+impl DbBackupConf {
+    /// Build a backuper of the data of `domains` in `db`.
+    pub async fn build(&self, db: DB, domains: Vec<HyperlaneDomain>) -> Result<DbBackuper> {
+        let storage = self.storage.build_backup_storage().await?;
+        Ok(DbBackuper::new(
+            db,
+            domains,
+            storage,
+            self.interval,
+            self.retained_backups,
+            self.api_token.clone(),
+        ))
+    }
+}
+
+/// Describes a backup, so that it can be validated before it's restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// Name of the backup, which is also the directory it's stored in
+    pub name: String,
+    /// Unix timestamp in seconds of when the backup was made
+    pub created_at: u64,
+    /// The files making up the snapshot of the database
+    pub files: Vec<BackupFile>,
+    /// The domains with data in the snapshot
+    pub domains: Vec<DomainSnapshot>,
+}
+
+/// A file of a backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    /// File name
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// The latest sequences indexed for a domain at the time of a backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainSnapshot {
+    /// Name of the domain
+    pub domain: String,
+    /// The highest message nonce stored
+    pub latest_message_nonce: Option<u32>,
+    /// The highest merkle tree leaf index stored
+    pub latest_merkle_leaf_index: Option<u32>,
+}
+
+impl DomainSnapshot {
+    fn new(db: &HyperlaneRocksDB) -> Result<Self> {
+        Ok(Self {
+            domain: db.domain().name().to_owned(),
+            latest_message_nonce: db.retrieve_latest_message_nonce()?,
+            latest_merkle_leaf_index: db.retrieve_latest_merkle_leaf_index()?,
+        })
+    }
+}
+
+/// Makes online backups of a database using RocksDB checkpoints, and writes them
+/// to a storage backend.
+pub struct DbBackuper {
+    db: DB,
+    domains: Vec<HyperlaneDomain>,
+    storage: Box<dyn BackupStorage>,
+    interval: Option<Duration>,
+    retained_backups: usize,
+    api_token: Option<ApiToken>,
+    /// Held while a backup is in progress, so that backups never overlap. Holds
+    /// the timestamp in milliseconds the last backup was named after.
+    in_progress: Mutex<u128>,
+}
+
+impl std::fmt::Debug for DbBackuper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DbBackuper")
+            .field("db", &self.db)
+            .field("domains", &self.domains)
+            .field("storage", &self.storage)
+            .field("interval", &self.interval)
+            .field("retained_backups", &self.retained_backups)
+            .field("api_token", &self.api_token)
+            .finish()
+    }
+}
+
+impl DbBackuper {
+    /// Create a backuper of the data of `domains` in `db`, which backs up the
+    /// database every `interval` once spawned and keeps the most recent
+    /// `retained_backups` backups.
+    pub fn new(
+        db: DB,
+        domains: Vec<HyperlaneDomain>,
+        storage: Box<dyn BackupStorage>,
+        interval: Option<Duration>,
+        retained_backups: usize,
+        api_token: Option<ApiToken>,
+    ) -> Self {
+        Self {
+            db,
+            domains,
+            storage,
+            interval,
+            retained_backups,
+            api_token,
+            in_progress: Mutex::new(0),
+        }
+    }
+
+    /// Snapshot the database and write the snapshot to storage. The snapshot is
+    /// staged next to the database, so that its files can be hard linked.
+    pub async fn backup(&self) -> Result<BackupManifest> {
+        let mut last_backup = self.in_progress.lock().await;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let created_at = now.as_secs();
+        // Backups made in quick succession must not overwrite each other
+        *last_backup = now.as_millis().max(*last_backup + 1);
+        let name = format!("backup_{}", *last_backup);
+        let staging_dir = staging_dir(self.db.path(), &name);
+        if staging_dir.exists() {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        }
+        self.db
+            .create_checkpoint(&staging_dir)
+            .context("Creating db checkpoint")?;
+
+        let result = self.upload(&staging_dir, name, created_at).await;
+        if let Err(err) = tokio::fs::remove_dir_all(&staging_dir).await {
+            error!(?err, ?staging_dir, "Failed to remove staged db backup");
+        }
+        let manifest = result?;
+
+        // The new backup is complete regardless of whether old ones are deleted
+        if let Err(err) = self.prune_backups(&manifest.name).await {
+            error!(?err, "Failed to delete old db backups");
+        }
+        Ok(manifest)
+    }
+
+    async fn upload(
+        &self,
+        staging_dir: &Path,
+        name: String,
+        created_at: u64,
+    ) -> Result<BackupManifest> {
+        // Describe the snapshot rather than the live db, which may have moved on
+        let domains = {
+            let snapshot = DB::from_path_read_only(staging_dir)?;
+            self.domains
+                .iter()
+                .map(|domain| DomainSnapshot::new(&HyperlaneRocksDB::new(domain, snapshot.clone())))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(staging_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry
+                .file_name()
+                .into_string()
+                .map_err(|name| eyre!("Invalid db file name {name:?}"))?;
+            let size = entry.metadata().await?.len();
+            self.storage
+                .upload_backup_file(&format!("{name}/{file_name}"), &entry.path())
+                .await
+                .with_context(|| format!("Writing db backup file {file_name}"))?;
+            files.push(BackupFile {
+                name: file_name,
+                size,
+            });
+        }
+
+        let manifest = BackupManifest {
+            name,
+            created_at,
+            files,
+            domains,
+        };
+        // Written last, so that only complete backups are ever pointed at
+        self.storage
+            .write_backup_file(
+                &format!("{}/{MANIFEST_FILE}", manifest.name),
+                serde_json::to_vec_pretty(&manifest)?,
+            )
+            .await?;
+        self.storage
+            .write_backup_file(LATEST_BACKUP_FILE, serde_json::to_vec(&manifest.name)?)
+            .await?;
+        info!(name = manifest.name, files = manifest.files.len(), domains = ?manifest.domains, "Backed up db");
+        Ok(manifest)
+    }
+
+    /// Record `name` in the index of backups and delete the oldest backups
+    /// beyond the number retained. Backups which fail to be deleted stay in the
+    /// index, so that deleting them is retried after the next backup.
+    async fn prune_backups(&self, name: &str) -> Result<()> {
+        let mut backups: Vec<String> =
+            match self.storage.read_backup_file(BACKUPS_INDEX_FILE).await? {
+                Some(index) => serde_json::from_slice(&index)?,
+                None => vec![],
+            };
+        backups.push(name.to_owned());
+
+        let excess = backups.len().saturating_sub(self.retained_backups);
+        let mut retained = vec![];
+        for old in backups.drain(..excess) {
+            match self.delete_backup(&old).await {
+                Ok(()) => info!(name = old, "Deleted old db backup"),
+                Err(err) => {
+                    error!(?err, name = old, "Failed to delete old db backup");
+                    retained.push(old);
+                }
+            }
+        }
+        retained.extend(backups);
+        self.storage
+            .write_backup_file(BACKUPS_INDEX_FILE, serde_json::to_vec(&retained)?)
+            .await
+    }
+
+    /// Delete the files of a backup, and lastly its manifest, so that a backup
+    /// is never partially deleted while its manifest remains.
+    async fn delete_backup(&self, name: &str) -> Result<()> {
+        let manifest_path = format!("{name}/{MANIFEST_FILE}");
+        let Some(manifest) = self.storage.read_backup_file(&manifest_path).await? else {
+            return Ok(());
+        };
+        let manifest: BackupManifest = serde_json::from_slice(&manifest)?;
+        for file in &manifest.files {
+            self.storage
+                .delete_backup_file(&format!("{name}/{}", file.name))
+                .await?;
+        }
+        self.storage.delete_backup_file(&manifest_path).await
+    }
+
+    /// Back up the database on a schedule, if an interval was configured.
+    pub fn spawn_scheduled(self: Arc<Self>) -> Option<Instrumented<JoinHandle<()>>> {
+        let interval = self.interval?;
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(err) = self.backup().await {
+                    error!(?err, "Failed to back up db");
+                }
+            }
+        })
+        .instrument(info_span!("DbBackuper"));
+        Some(handle)
+    }
+
+    /// A route to trigger a backup with `POST /db_backup`, which responds with the
+    /// manifest of the backup. Only served if an api token is configured.
+    pub fn get_route(self: Arc<Self>) -> Option<(&'static str, Router)> {
+        self.api_token.as_ref()?;
+        Some((
+            DB_BACKUP_API_BASE,
+            Router::new()
+                .route("/", routing::post(trigger_backup))
+                .with_state(self),
+        ))
+    }
+}
+
+async fn trigger_backup(
+    State(backuper): State<Arc<DbBackuper>>,
+    headers: HeaderMap,
+) -> Result<Json<BackupManifest>, (StatusCode, String)> {
+    let authorized = backuper
+        .api_token
+        .as_ref()
+        .map_or(false, |token| is_authorized(&headers, token));
+    if !authorized {
+        warn!("Rejected unauthorized db backup request");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Invalid or missing bearer token".to_owned(),
+        ));
+    }
+    backuper.backup().await.map(Json).map_err(|err| {
+        error!(?err, "Failed to back up db");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to back up db: {err}"),
+        )
+    })
+}
+
+fn staging_dir(db_path: &Path, name: &str) -> PathBuf {
+    let mut dir_name = db_path.file_name().unwrap_or_default().to_os_string();
+    dir_name.push(format!("_{name}"));
+    db_path.with_file_name(dir_name)
+}
+
+/// Restore a backup into a new database directory at `target`. The most recent
+/// backup is restored if `name` isn't specified.
+///
+/// The backup must contain data for all of the `expected_domains` names, and
+/// the restored database must hold the same latest sequences as the backup's
+/// manifest.
+pub async fn restore_db_backup(
+    storage: &dyn BackupStorage,
+    name: Option<&str>,
+    target: &Path,
+    expected_domains: &[&str],
+) -> Result<BackupManifest> {
+    if target.exists() {
+        bail!("Refusing to restore over the existing path {target:?}");
+    }
+
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None => {
+            let latest = storage
+                .read_backup_file(LATEST_BACKUP_FILE)
+                .await?
+                .ok_or_else(|| eyre!("No db backups found"))?;
+            serde_json::from_slice(&latest)?
+        }
+    };
+    let manifest: BackupManifest = serde_json::from_slice(
+        &storage
+            .read_backup_file(&format!("{name}/{MANIFEST_FILE}"))
+            .await?
+            .ok_or_else(|| eyre!("No manifest for db backup {name}"))?,
+    )?;
+
+    let missing_domains = expected_domains
+        .iter()
+        .filter(|domain| !manifest.domains.iter().any(|d| d.domain == **domain))
+        .collect::<Vec<_>>();
+    if !missing_domains.is_empty() {
+        bail!("Db backup {name} has no data for domains {missing_domains:?}");
+    }
+
+    let staging_dir = staging_dir(target, "restoring");
+    if staging_dir.exists() {
+        tokio::fs::remove_dir_all(&staging_dir).await?;
+    }
+    tokio::fs::create_dir_all(&staging_dir).await?;
+    let result = download(storage, &manifest, &staging_dir).await;
+    if let Err(err) = result {
+        tokio::fs::remove_dir_all(&staging_dir).await?;
+        return Err(err);
+    }
+    tokio::fs::rename(&staging_dir, target).await?;
+    info!(name = manifest.name, ?target, "Restored db backup");
+    Ok(manifest)
+}
+
+async fn download(
+    storage: &dyn BackupStorage,
+    manifest: &BackupManifest,
+    dir: &Path,
+) -> Result<()> {
+    for file in &manifest.files {
+        let data = storage
+            .read_backup_file(&format!("{}/{}", manifest.name, file.name))
+            .await?
+            .ok_or_else(|| eyre!("Db backup file {} is missing", file.name))?;
+        if data.len() as u64 != file.size {
+            bail!(
+                "Db backup file {} is {} bytes, expected {}",
+                file.name,
+                data.len(),
+                file.size
+            );
+        }
+        tokio::fs::write(dir.join(&file.name), data).await?;
+    }
+
+    let db = DB::from_path_read_only(dir)?;
+    for expected in &manifest.domains {
+        let restored = DomainSnapshot::new(&HyperlaneRocksDB::from_domain_name(
+            &expected.domain,
+            db.clone(),
+        ))?;
+        if &restored != expected {
+            bail!("Restored db doesn't match the backup's manifest; expected {expected:?}, got {restored:?}");
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,16 @@
+use std::ops::RangeInclusive;
+
 use async_trait::async_trait;
 use eyre::{bail, Result};
 use paste::paste;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, instrument, trace, warn};
 
 use hyperlane_core::{
-    GasPaymentKey, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
-    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, Indexed,
-    InterchainGasExpenditure, InterchainGasPayment, InterchainGasPaymentMeta, LogMeta,
-    MerkleTreeInsertion, H256,
+    Decode, Encode, GasPaymentKey, HyperlaneDomain, HyperlaneDomainProtocol,
+    HyperlaneDomainTechnicalStack, HyperlaneDomainType, HyperlaneLogStore, HyperlaneMessage,
+    HyperlaneReorgAwareIndexerStore, HyperlaneSequenceAwareIndexerStoreReader,
+    HyperlaneWatermarkedLogStore, Indexed, InterchainGasExpenditure, InterchainGasPayment,
+    InterchainGasPaymentMeta, KnownHyperlaneDomain, LogMeta, MerkleTreeInsertion, H256, H512,
 };
 
 use super::{
@@ -20,10 +23,20 @@ use super::{
 
 const MESSAGE_ID: &str = "message_id_";
 const MESSAGE_DISPATCHED_BLOCK_NUMBER: &str = "message_dispatched_block_number_";
+const MESSAGE_DISPATCHED_BLOCK_HASH: &str = "message_dispatched_block_hash_";
 const MESSAGE: &str = "message_";
+const MESSAGE_DISPATCH_TX_BY_NONCE: &str = "message_dispatch_tx_by_nonce_";
+const MESSAGE_NONCE_BY_DISPATCH_TX: &str = "message_nonce_by_dispatch_tx_";
+const MESSAGE_DELIVERY_TX_BY_ID: &str = "message_delivery_tx_by_id_";
 const NONCE_PROCESSED: &str = "nonce_processed_";
-const GAS_PAYMENT_BY_SEQUENCE: &str = "gas_payment_by_sequence_";
+const NONCE_PROCESSED_AT: &str = "nonce_processed_at_";
+const GAS_PAYMENT_BY_SEQUENCE: &str = "gas_payment_by_sequence_v2_";
+// Gas payment block numbers used to share a prefix with the payments themselves, which
+// they overwrote. They keep that prefix so existing dbs don't lose them.
+const GAS_PAYMENT_BLOCK_BY_SEQUENCE: &str = "gas_payment_by_sequence_";
+const GAS_PAYMENT_META_BY_SEQUENCE: &str = "gas_payment_meta_by_sequence_";
 const GAS_PAYMENT_FOR_MESSAGE_ID: &str = "gas_payment_sequence_for_message_id_v2_";
+const GAS_PAYMENT_BLOCK_HASH_BY_SEQUENCE: &str = "gas_payment_block_hash_by_sequence_";
 const GAS_PAYMENT_META_PROCESSED: &str = "gas_payment_meta_processed_v3_";
 const GAS_EXPENDITURE_FOR_MESSAGE_ID: &str = "gas_expenditure_for_message_id_v2_";
 const PENDING_MESSAGE_RETRY_COUNT_FOR_MESSAGE_ID: &str =
@@ -32,10 +45,28 @@ const MERKLE_TREE_INSERTION: &str = "merkle_tree_insertion_";
 const MERKLE_LEAF_INDEX_BY_MESSAGE_ID: &str = "merkle_leaf_index_by_message_id_";
 const MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX: &str =
     "merkle_tree_insertion_block_number_by_leaf_index_";
+const MERKLE_TREE_INSERTION_BLOCK_HASH_BY_LEAF_INDEX: &str =
+    "merkle_tree_insertion_block_hash_by_leaf_index_";
 const LATEST_INDEXED_GAS_PAYMENT_BLOCK: &str = "latest_indexed_gas_payment_block";
+const PRUNED_UP_TO_NONCE: &str = "pruned_up_to_nonce";
+const MESSAGE_ROLLBACK_NONCE: &str = "message_rollback_nonce";
+const MERKLE_TREE_INSERTION_ROLLBACK_LEAF_INDEX: &str = "merkle_tree_insertion_rollback_leaf_index";
+const PRUNE_SKIPPED_NONCE: &str = "prune_skipped_nonce_";
+const PRUNE_SKIPPED_RESUME_NONCE: &str = "prune_skipped_resume_nonce";
 
 type DbResult<T> = std::result::Result<T, DbError>;
 
+/// What a pass of [`HyperlaneRocksDB::prune_delivered_messages`] deleted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Number of messages whose data was pruned
+    pub messages: u32,
+    /// Combined size in bytes of the keys and values that were deleted
+    pub bytes: u64,
+    /// Whether the pass scanned up to the latest indexed message
+    pub reached_latest: bool,
+}
+
 /// DB handle for storing data tied to a specific Mailbox.
 #[derive(Debug, Clone)]
 pub struct HyperlaneRocksDB(HyperlaneDomain, TypedDB);
@@ -66,6 +97,23 @@ impl HyperlaneRocksDB {
         Self(domain.clone(), TypedDB::new(domain, db))
     }
 
+    /// Instantiate a `HyperlaneRocksDB` for the domain called `name`, e.g. to
+    /// inspect a database outside of an agent. Keys are only scoped by the domain's
+    /// name, so the other properties of unknown domains don't matter.
+    pub fn from_domain_name(name: &str, db: DB) -> Self {
+        let domain = match name.parse::<KnownHyperlaneDomain>() {
+            Ok(domain) => HyperlaneDomain::Known(domain),
+            Err(_) => HyperlaneDomain::Unknown {
+                domain_id: 0,
+                domain_name: name.to_owned(),
+                domain_type: HyperlaneDomainType::Unknown,
+                domain_protocol: HyperlaneDomainProtocol::Ethereum,
+                domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
+            },
+        };
+        Self::new(&domain, db)
+    }
+
     /// Get the domain this database is scoped to
     pub fn domain(&self) -> &HyperlaneDomain {
         &self.0
@@ -99,6 +147,26 @@ impl HyperlaneRocksDB {
         Ok(true)
     }
 
+    /// Store the transaction a message was dispatched in, and index the message
+    /// by it
+    fn store_dispatch_tx(&self, nonce: u32, tx_id: &H512) -> DbResult<()> {
+        self.store_dispatch_tx_by_nonce(&nonce, tx_id)?;
+        self.store_encodable(
+            MESSAGE_NONCE_BY_DISPATCH_TX,
+            dispatch_tx_key(tx_id, nonce),
+            &nonce,
+        )
+    }
+
+    /// The nonces of the messages dispatched in a transaction
+    pub fn retrieve_message_nonces_by_dispatch_tx(&self, tx_id: &H512) -> DbResult<Vec<u32>> {
+        let mut prefix = MESSAGE_NONCE_BY_DISPATCH_TX.as_bytes().to_vec();
+        prefix.extend(tx_id.to_vec());
+        self.keys_with_prefix(prefix)
+            .map(|key| Ok(u32::read_from(&mut key?.as_slice())?))
+            .collect()
+    }
+
     /// Retrieve a message by its nonce
     pub fn retrieve_message_by_nonce(&self, nonce: u32) -> DbResult<Option<HyperlaneMessage>> {
         let id = self.retrieve_message_id_by_nonce(&nonce)?;
@@ -108,6 +176,40 @@ impl HyperlaneRocksDB {
         }
     }
 
+    /// The highest nonce of the messages stored in the db
+    pub fn retrieve_latest_message_nonce(&self) -> DbResult<Option<u32>> {
+        self.retrieve_latest_sequence(MESSAGE_ID)
+    }
+
+    /// The highest leaf index of the merkle tree insertions stored in the db
+    pub fn retrieve_latest_merkle_leaf_index(&self) -> DbResult<Option<u32>> {
+        self.retrieve_latest_sequence(MERKLE_TREE_INSERTION)
+    }
+
+    /// The highest `u32` key stored under `prefix`. Longer keys belong to other
+    /// prefixes which happen to start with `prefix`.
+    fn retrieve_latest_sequence(&self, prefix: &str) -> DbResult<Option<u32>> {
+        let mut latest = None;
+        for key in self.keys_with_prefix(prefix) {
+            let key = key?;
+            if key.len() == std::mem::size_of::<u32>() {
+                let sequence = u32::read_from(&mut key.as_slice())?;
+                latest = latest.max(Some(sequence));
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Whether the message with the given nonce was delivered and has since had its
+    /// body pruned.
+    pub fn is_message_pruned(&self, nonce: u32) -> DbResult<bool> {
+        let Some(id) = self.retrieve_message_id_by_nonce(&nonce)? else {
+            return Ok(false);
+        };
+        Ok(self.retrieve_processed_by_nonce(&nonce)?.unwrap_or(false)
+            && self.retrieve_message_by_id(&id)?.is_none())
+    }
+
     /// If the provided gas payment, identified by its metadata, has not been
     /// processed, processes the gas payment and records it as processed.
     /// Returns whether the gas payment was processed for the first time.
@@ -135,7 +237,9 @@ impl HyperlaneRocksDB {
         }
 
         self.store_gas_payment_by_sequence(&gas_payment_sequence, indexed_payment.inner())?;
+        self.store_gas_payment_meta_by_sequence(&gas_payment_sequence, &log_meta.into())?;
         self.store_gas_payment_block_by_sequence(&gas_payment_sequence, &log_meta.block_number)?;
+        self.store_gas_payment_block_hash_by_sequence(&gas_payment_sequence, &log_meta.block_hash)?;
 
         Ok(gas_processing_successful)
     }
@@ -198,6 +302,276 @@ impl HyperlaneRocksDB {
         Ok(true)
     }
 
+    /// Remove the messages with nonces in the given range, e.g. because they were
+    /// reorged out. Returns the number of messages that were removed.
+    ///
+    /// The first rolled back nonce is recorded so that the message processor can
+    /// rewind to it, see `take_rolled_back_message_nonce`.
+    pub fn rollback_messages(&self, nonces: RangeInclusive<u32>) -> DbResult<u32> {
+        self.record_rollback(MESSAGE_ROLLBACK_NONCE, *nonces.start())?;
+        let mut removed = 0;
+        for nonce in nonces {
+            let Some(id) = self.retrieve_message_id_by_nonce(&nonce)? else {
+                continue;
+            };
+            debug!(nonce, ?id, "Rolling back message in db");
+            self.delete_keyed(MESSAGE, &id)?;
+            self.delete_keyed(MESSAGE_ID, &nonce)?;
+            self.delete_keyed(MESSAGE_DISPATCHED_BLOCK_NUMBER, &nonce)?;
+            self.delete_keyed(MESSAGE_DISPATCHED_BLOCK_HASH, &nonce)?;
+            // A different message may be dispatched with this nonce once re-indexed
+            self.delete_keyed(NONCE_PROCESSED, &nonce)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Remove the merkle tree insertions with leaf indices in the given range, e.g.
+    /// because they were reorged out. Returns the number of insertions that were removed.
+    ///
+    /// The first rolled back leaf index is recorded so that merkle trees built from the
+    /// insertions can be rebuilt, see `take_rolled_back_leaf_index`.
+    pub fn rollback_tree_insertions(&self, leaf_indices: RangeInclusive<u32>) -> DbResult<u32> {
+        self.record_rollback(
+            MERKLE_TREE_INSERTION_ROLLBACK_LEAF_INDEX,
+            *leaf_indices.start(),
+        )?;
+        let mut removed = 0;
+        for leaf_index in leaf_indices {
+            let Some(insertion) = self.retrieve_merkle_tree_insertion_by_leaf_index(&leaf_index)?
+            else {
+                continue;
+            };
+            debug!(?insertion, "Rolling back tree insertion in db");
+            self.delete_keyed(MERKLE_TREE_INSERTION, &leaf_index)?;
+            // Only remove the mapping from the message id if it still points at this leaf
+            if self.retrieve_merkle_leaf_index_by_message_id(&insertion.message_id())?
+                == Some(leaf_index)
+            {
+                self.delete_keyed(MERKLE_LEAF_INDEX_BY_MESSAGE_ID, &insertion.message_id())?;
+            }
+            self.delete_keyed(
+                MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX,
+                &leaf_index,
+            )?;
+            self.delete_keyed(MERKLE_TREE_INSERTION_BLOCK_HASH_BY_LEAF_INDEX, &leaf_index)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Remove the gas payments with sequences in the given range, e.g. because they were
+    /// reorged out, and deduct them from the totals paid for their messages. Returns the
+    /// number of gas payments that were removed.
+    pub fn rollback_gas_payments(&self, sequences: RangeInclusive<u32>) -> DbResult<u32> {
+        let mut removed = 0;
+        for sequence in sequences {
+            if self
+                .retrieve_gas_payment_block_by_sequence(&sequence)?
+                .is_none()
+            {
+                continue;
+            }
+            match (
+                self.retrieve_gas_payment_by_sequence(&sequence)?,
+                self.retrieve_gas_payment_meta_by_sequence(&sequence)?,
+            ) {
+                (Some(payment), Some(payment_meta)) => {
+                    debug!(sequence, ?payment, "Rolling back gas payment in db");
+                    let total = self.retrieve_gas_payment_by_gas_payment_key(payment.into())?;
+                    let total = InterchainGasPayment {
+                        payment: total.payment.saturating_sub(payment.payment),
+                        gas_amount: total.gas_amount.saturating_sub(payment.gas_amount),
+                        ..total
+                    };
+                    self.store_interchain_gas_payment_data_by_gas_payment_key(
+                        &payment.into(),
+                        &total.into(),
+                    )?;
+                    // The payment is counted again if it's re-indexed
+                    self.delete_keyed(GAS_PAYMENT_META_PROCESSED, &payment_meta)?;
+                    self.delete_keyed(GAS_PAYMENT_META_BY_SEQUENCE, &sequence)?;
+                    self.delete_keyed(GAS_PAYMENT_BY_SEQUENCE, &sequence)?;
+                }
+                // Payments indexed before their data was kept by sequence can't be deducted,
+                // but they stay counted when they're re-indexed, since they were processed.
+                _ => warn!(
+                    sequence,
+                    "Rolling back gas payment without its data, its message's total is unchanged"
+                ),
+            }
+            self.delete_keyed(GAS_PAYMENT_BLOCK_BY_SEQUENCE, &sequence)?;
+            self.delete_keyed(GAS_PAYMENT_BLOCK_HASH_BY_SEQUENCE, &sequence)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Takes the lowest nonce of the messages rolled back since the last call, if any.
+    /// Messages from this nonce on must be processed again.
+    pub fn take_rolled_back_message_nonce(&self) -> DbResult<Option<u32>> {
+        self.take_rollback(MESSAGE_ROLLBACK_NONCE)
+    }
+
+    /// Takes the lowest leaf index of the merkle tree insertions rolled back since the
+    /// last call, if any. Trees which already ingested this leaf must be rebuilt.
+    pub fn take_rolled_back_leaf_index(&self) -> DbResult<Option<u32>> {
+        self.take_rollback(MERKLE_TREE_INSERTION_ROLLBACK_LEAF_INDEX)
+    }
+
+    /// Records that logs were rolled back from `sequence` on, keeping the lowest
+    /// sequence if earlier rollbacks haven't been taken yet.
+    fn record_rollback(&self, key: &str, sequence: u32) -> DbResult<()> {
+        let existing: Option<u32> = self.retrieve_decodable("", key)?;
+        let sequence = existing.map_or(sequence, |existing| existing.min(sequence));
+        self.store_encodable("", key, &sequence)
+    }
+
+    fn take_rollback(&self, key: &str) -> DbResult<Option<u32>> {
+        let sequence: Option<u32> = self.retrieve_decodable("", key)?;
+        if sequence.is_some() {
+            self.delete_value("", key)?;
+        }
+        Ok(sequence)
+    }
+
+    /// Prune the data of messages which were confirmed delivered before
+    /// `delivered_before`, a unix timestamp in seconds. Each pass scans at most
+    /// `max_nonces` nonces, carrying on from where the previous pass stopped.
+    ///
+    /// Nonces that can't be pruned yet, because their message wasn't delivered or
+    /// was delivered too recently, are recorded as skipped and checked again by the
+    /// passes that reach the latest indexed message. That way the scan never
+    /// revisits pruned nonces, even if a message is never delivered. Those passes
+    /// also check at most `max_nonces` skipped nonces, carrying on from where the
+    /// previous one stopped, so undelivered messages piling up don't slow them down.
+    ///
+    /// Message bodies, retry counts and delivery and dispatch transactions are
+    /// deleted. The `nonce` --> `id` mapping, dispatch block data, processed flags,
+    /// gas payment and expenditure totals and merkle tree insertions are kept, so
+    /// that indexing carries on from where it was, gas payments indexed after
+    /// delivery aren't mistaken for fresh ones and merkle proofs can still be built.
+    ///
+    /// Messages processed before delivery times were recorded are stamped with
+    /// `now` the first time they're scanned, and pruned once they're old enough.
+    pub fn prune_delivered_messages(
+        &self,
+        delivered_before: u64,
+        now: u64,
+        max_nonces: u32,
+    ) -> DbResult<PruneStats> {
+        let start: u32 = self
+            .retrieve_decodable("", PRUNED_UP_TO_NONCE)?
+            .unwrap_or_default();
+
+        let mut stats = PruneStats::default();
+        let mut next_scan = start;
+        while next_scan < start.saturating_add(max_nonces) {
+            let nonce = next_scan;
+            if self.retrieve_message_id_by_nonce(&nonce)?.is_none() {
+                stats.reached_latest = true;
+                break;
+            }
+            next_scan += 1;
+            if !self.prune_if_delivered_before(nonce, delivered_before, now, &mut stats)? {
+                self.store_keyed_encodable(PRUNE_SKIPPED_NONCE, &nonce, &true)?;
+            }
+        }
+        if next_scan != start {
+            self.store_encodable("", PRUNED_UP_TO_NONCE, &next_scan)?;
+        }
+
+        if stats.reached_latest {
+            for nonce in self.skipped_nonces_to_recheck(max_nonces)? {
+                if self.prune_if_delivered_before(nonce, delivered_before, now, &mut stats)? {
+                    self.delete_keyed(PRUNE_SKIPPED_NONCE, &nonce)?;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Up to `max_nonces` of the nonces skipped by earlier passes, starting after the
+    /// last one that was checked and wrapping around to the lowest one.
+    fn skipped_nonces_to_recheck(&self, max_nonces: u32) -> DbResult<Vec<u32>> {
+        let max_nonces = max_nonces as usize;
+        let resume: u32 = self
+            .retrieve_decodable("", PRUNE_SKIPPED_RESUME_NONCE)?
+            .unwrap_or_default();
+        let decode = |key: DbResult<Vec<u8>>| Ok(u32::read_from(&mut key?.as_slice())?);
+        let mut nonces = self
+            .keys_with_prefix_from(PRUNE_SKIPPED_NONCE, resume.to_vec())
+            .take(max_nonces)
+            .map(decode)
+            .collect::<DbResult<Vec<u32>>>()?;
+        for key in self.keys_with_prefix(PRUNE_SKIPPED_NONCE) {
+            let nonce = decode(key)?;
+            if nonces.len() == max_nonces || nonce >= resume {
+                break;
+            }
+            nonces.push(nonce);
+        }
+
+        let next_resume = match nonces.last() {
+            Some(last) if nonces.len() == max_nonces => last.saturating_add(1),
+            _ => 0,
+        };
+        self.store_encodable("", PRUNE_SKIPPED_RESUME_NONCE, &next_resume)?;
+        Ok(nonces)
+    }
+
+    /// Prune the data of the message with the given nonce if it was delivered before
+    /// `delivered_before`. Returns whether it was, in which case there's nothing
+    /// left to prune.
+    fn prune_if_delivered_before(
+        &self,
+        nonce: u32,
+        delivered_before: u64,
+        now: u64,
+        stats: &mut PruneStats,
+    ) -> DbResult<bool> {
+        if !self.is_delivered_before(nonce, delivered_before, now)? {
+            return Ok(false);
+        }
+        // The body is gone if the message was pruned by an earlier pass
+        if let Some(message) = self.retrieve_message_by_nonce(nonce)? {
+            debug!(nonce, id=?message.id(), "Pruning delivered message from db");
+            stats.bytes += self.prune_message_data(&message)? as u64;
+            stats.messages += 1;
+        }
+        Ok(true)
+    }
+
+    fn is_delivered_before(&self, nonce: u32, delivered_before: u64, now: u64) -> DbResult<bool> {
+        if !self.retrieve_processed_by_nonce(&nonce)?.unwrap_or(false) {
+            return Ok(false);
+        }
+        match self.retrieve_processed_at_by_nonce(&nonce)? {
+            Some(processed_at) => Ok(processed_at < delivered_before),
+            None => {
+                self.store_processed_at_by_nonce(&nonce, &now)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Delete the body, retry count and transactions of a message. Returns the number
+    /// of bytes deleted.
+    fn prune_message_data(&self, message: &HyperlaneMessage) -> DbResult<usize> {
+        let id = message.id();
+        let mut bytes = self.prune_keyed(MESSAGE, &id)?
+            + self.prune_keyed(PENDING_MESSAGE_RETRY_COUNT_FOR_MESSAGE_ID, &id)?
+            + self.prune_keyed(MESSAGE_DELIVERY_TX_BY_ID, &id)?;
+        if let Some(tx_id) = self.retrieve_dispatch_tx_by_nonce(&message.nonce)? {
+            bytes += self.prune_keyed(MESSAGE_DISPATCH_TX_BY_NONCE, &message.nonce)?;
+            bytes += self.prune_value(
+                MESSAGE_NONCE_BY_DISPATCH_TX,
+                dispatch_tx_key(&tx_id, message.nonce),
+            )?;
+        }
+        Ok(bytes)
+    }
+
     /// Processes the gas expenditure and store the total expenditure for the
     /// message.
     pub fn process_gas_expenditure(&self, expenditure: InterchainGasExpenditure) -> DbResult<()> {
@@ -268,6 +642,11 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneRocksDB {
         for (message, meta) in messages {
             let stored_message = self.store_message(message.inner(), meta.block_number)?;
             if stored_message {
+                self.store_dispatched_block_hash_by_nonce(
+                    &message.inner().nonce,
+                    &meta.block_hash,
+                )?;
+                self.store_dispatch_tx(message.inner().nonce, &meta.transaction_id)?;
                 stored += 1;
             }
         }
@@ -322,6 +701,10 @@ impl HyperlaneLogStore<MerkleTreeInsertion> for HyperlaneRocksDB {
         let mut insertions = 0;
         for (insertion, meta) in leaves {
             if self.process_tree_insertion(insertion.inner(), meta.block_number)? {
+                self.store_merkle_tree_insertion_block_hash_by_leaf_index(
+                    &insertion.inner().index(),
+                    &meta.block_hash,
+                )?;
                 insertions += 1;
             }
         }
@@ -342,6 +725,11 @@ impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneRoc
         let number = self.retrieve_dispatched_block_number_by_nonce(&sequence)?;
         Ok(number)
     }
+
+    /// Pruned messages lose their body but keep their `nonce` --> `id` mapping.
+    async fn is_sequence_indexed(&self, sequence: u32) -> Result<bool> {
+        Ok(self.retrieve_message_id_by_nonce(&sequence)?.is_some())
+    }
 }
 
 #[async_trait]
@@ -359,6 +747,48 @@ impl HyperlaneSequenceAwareIndexerStoreReader<MerkleTreeInsertion> for Hyperlane
     }
 }
 
+#[async_trait]
+impl HyperlaneReorgAwareIndexerStore<HyperlaneMessage> for HyperlaneRocksDB {
+    /// Gets the hash of the block at which the message was dispatched.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+        let hash = self.retrieve_dispatched_block_hash_by_nonce(&sequence)?;
+        Ok(hash)
+    }
+
+    /// Removes the messages with nonces in the given range.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+        Ok(self.rollback_messages(sequences)?)
+    }
+}
+
+#[async_trait]
+impl HyperlaneReorgAwareIndexerStore<MerkleTreeInsertion> for HyperlaneRocksDB {
+    /// Gets the hash of the block at which the tree insertion occurred.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+        let hash = self.retrieve_merkle_tree_insertion_block_hash_by_leaf_index(&sequence)?;
+        Ok(hash)
+    }
+
+    /// Removes the tree insertions with leaf indices in the given range.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+        Ok(self.rollback_tree_insertions(sequences)?)
+    }
+}
+
+#[async_trait]
+impl HyperlaneReorgAwareIndexerStore<InterchainGasPayment> for HyperlaneRocksDB {
+    /// Gets the hash of the block at which the gas payment occurred.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>> {
+        let hash = self.retrieve_gas_payment_block_hash_by_sequence(&sequence)?;
+        Ok(hash)
+    }
+
+    /// Removes the gas payments with sequences in the given range.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32> {
+        Ok(self.rollback_gas_payments(sequences)?)
+    }
+}
+
 // TODO: replace this blanket implementation to be able to do sequence-aware indexing
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<InterchainGasPayment> for HyperlaneRocksDB {
@@ -416,6 +846,14 @@ impl HyperlaneWatermarkedLogStore<MerkleTreeInsertion> for HyperlaneRocksDB {
     }
 }
 
+/// The key of a message in the index of messages by the transaction they were
+/// dispatched in
+fn dispatch_tx_key(tx_id: &H512, nonce: u32) -> Vec<u8> {
+    let mut key = tx_id.to_vec();
+    key.extend(nonce.to_vec());
+    key
+}
+
 /// Generate a call to ChainSetup for the given builder
 macro_rules! make_store_and_retrieve {
     ($vis:vis, $name_suffix:ident, $key_prefix: ident, $key_ty:ty, $val_ty:ty$(,)?) => {
@@ -443,14 +881,38 @@ macro_rules! make_store_and_retrieve {
 }
 
 make_store_and_retrieve!(pub, message_id_by_nonce, MESSAGE_ID, u32, H256);
-make_store_and_retrieve!(pub(self), message_by_id, MESSAGE, H256, HyperlaneMessage);
-make_store_and_retrieve!(pub(self), dispatched_block_number_by_nonce, MESSAGE_DISPATCHED_BLOCK_NUMBER, u32, u64);
+make_store_and_retrieve!(pub, message_by_id, MESSAGE, H256, HyperlaneMessage);
+make_store_and_retrieve!(
+    pub,
+    dispatched_block_number_by_nonce,
+    MESSAGE_DISPATCHED_BLOCK_NUMBER,
+    u32,
+    u64
+);
+make_store_and_retrieve!(
+    pub,
+    dispatch_tx_by_nonce,
+    MESSAGE_DISPATCH_TX_BY_NONCE,
+    u32,
+    H512
+);
+make_store_and_retrieve!(
+    pub,
+    delivery_tx_by_message_id,
+    MESSAGE_DELIVERY_TX_BY_ID,
+    H256,
+    H512
+);
+make_store_and_retrieve!(pub(self), dispatched_block_hash_by_nonce, MESSAGE_DISPATCHED_BLOCK_HASH, u32, H256);
 make_store_and_retrieve!(pub, processed_by_nonce, NONCE_PROCESSED, u32, bool);
+make_store_and_retrieve!(pub, processed_at_by_nonce, NONCE_PROCESSED_AT, u32, u64);
 make_store_and_retrieve!(pub(self), processed_by_gas_payment_meta, GAS_PAYMENT_META_PROCESSED, InterchainGasPaymentMeta, bool);
 make_store_and_retrieve!(pub(self), interchain_gas_expenditure_data_by_message_id, GAS_EXPENDITURE_FOR_MESSAGE_ID, H256, InterchainGasExpenditureData);
 make_store_and_retrieve!(pub(self), interchain_gas_payment_data_by_gas_payment_key, GAS_PAYMENT_FOR_MESSAGE_ID, GasPaymentKey, InterchainGasPaymentData);
 make_store_and_retrieve!(pub(self), gas_payment_by_sequence, GAS_PAYMENT_BY_SEQUENCE, u32, InterchainGasPayment);
-make_store_and_retrieve!(pub(self), gas_payment_block_by_sequence, GAS_PAYMENT_BY_SEQUENCE, u32, u64);
+make_store_and_retrieve!(pub(self), gas_payment_block_by_sequence, GAS_PAYMENT_BLOCK_BY_SEQUENCE, u32, u64);
+make_store_and_retrieve!(pub(self), gas_payment_meta_by_sequence, GAS_PAYMENT_META_BY_SEQUENCE, u32, InterchainGasPaymentMeta);
+make_store_and_retrieve!(pub(self), gas_payment_block_hash_by_sequence, GAS_PAYMENT_BLOCK_HASH_BY_SEQUENCE, u32, H256);
 make_store_and_retrieve!(
     pub,
     pending_message_retry_count_by_message_id,
@@ -479,3 +941,10 @@ make_store_and_retrieve!(
     u32,
     u64
 );
+make_store_and_retrieve!(
+    pub(self),
+    merkle_tree_insertion_block_hash_by_leaf_index,
+    MERKLE_TREE_INSERTION_BLOCK_HASH_BY_LEAF_INDEX,
+    u32,
+    H256
+);
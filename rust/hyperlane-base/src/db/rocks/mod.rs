@@ -2,15 +2,18 @@ use std::path::PathBuf;
 use std::{io, path::Path, sync::Arc};
 
 use hyperlane_core::{ChainCommunicationError, HyperlaneProtocolError};
-use rocksdb::{Options, DB as Rocks};
+use rocksdb::{checkpoint::Checkpoint, Direction, IteratorMode, Options, DB as Rocks};
 use tracing::info;
 
+pub use backup::*;
 pub use hyperlane_db::*;
 pub use typed_db::*;
 
 /// Shared functionality surrounding use of rocksdb
 pub mod iterator;
 
+/// Online backups and restores of the DB
+mod backup;
 /// DB operations tied to specific Mailbox
 mod hyperlane_db;
 /// Type-specific db operations
@@ -68,8 +71,18 @@ type Result<T> = std::result::Result<T, DbError>;
 
 impl DB {
     /// Opens db at `db_path` and creates if missing
-    #[tracing::instrument(err)]
     pub fn from_path(db_path: &Path) -> Result<DB> {
+        Self::open(db_path, false)
+    }
+
+    /// Opens the existing db at `db_path` for reading only. This doesn't lock the
+    /// db, so it can be inspected while an agent is using it.
+    pub fn from_path_read_only(db_path: &Path) -> Result<DB> {
+        Self::open(db_path, true)
+    }
+
+    #[tracing::instrument(err)]
+    fn open(db_path: &Path, read_only: bool) -> Result<DB> {
         let path = {
             let mut path = db_path
                 .parent()
@@ -89,9 +102,14 @@ impl DB {
         }
 
         let mut opts = Options::default();
-        opts.create_if_missing(true);
+        opts.create_if_missing(!read_only);
 
-        Rocks::open(&opts, &path)
+        let rocks = if read_only {
+            Rocks::open_for_read_only(&opts, &path, false)
+        } else {
+            Rocks::open(&opts, &path)
+        };
+        rocks
             .map_err(|e| DbError::OpeningError {
                 source: e,
                 path: db_path.into(),
@@ -109,4 +127,73 @@ impl DB {
     pub fn retrieve(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         Ok(self.0.get(key)?)
     }
+
+    /// Delete a value from the DB
+    pub fn delete(&self, key: &[u8]) -> Result<()> {
+        Ok(self.0.delete(key)?)
+    }
+
+    /// Iterate over all the keys in the DB, in order
+    pub fn keys(&self) -> impl Iterator<Item = Result<Box<[u8]>>> + '_ {
+        self.0
+            .iterator(IteratorMode::Start)
+            .map(|r| r.map(|(k, _)| k).map_err(Into::into))
+    }
+
+    /// Iterate over the keys in the DB which start with `prefix`, in order
+    pub fn keys_with_prefix(
+        &self,
+        prefix: Vec<u8>,
+    ) -> impl Iterator<Item = Result<Box<[u8]>>> + '_ {
+        self.keys_with_prefix_from(prefix.clone(), prefix)
+    }
+
+    /// Iterate over the keys in the DB which start with `prefix`, in order, starting
+    /// at the first key which isn't less than `from`
+    pub fn keys_with_prefix_from(
+        &self,
+        prefix: Vec<u8>,
+        from: Vec<u8>,
+    ) -> impl Iterator<Item = Result<Box<[u8]>>> + '_ {
+        self.0
+            .iterator(IteratorMode::From(&from, Direction::Forward))
+            .map(|r| r.map(|(k, _)| k).map_err(Into::into))
+            .take_while(move |k| k.as_ref().map_or(true, |k| k.starts_with(&prefix)))
+    }
+
+    /// The path of the DB's directory
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Create a consistent snapshot of the DB in the directory at `path`, which
+    /// must not exist yet. Files are hard linked where possible, so this is cheap
+    /// when `path` is on the same filesystem as the DB.
+    pub fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        Ok(Checkpoint::new(&self.0)?.create_checkpoint(path)?)
+    }
+
+    /// Write the DB's memtables and write-ahead log to disk, so that no writes
+    /// are lost when the process exits.
+    pub fn flush(&self) -> Result<()> {
+        self.0.flush_wal(true)?;
+        Ok(self.0.flush()?)
+    }
+
+    /// RocksDB's estimate of the size of the live data in the DB, in bytes. Space
+    /// freed by deletions is only reflected once the deleted keys are compacted.
+    pub fn estimated_live_data_size(&self) -> Result<Option<u64>> {
+        Ok(self
+            .0
+            .property_int_value("rocksdb.estimate-live-data-size")?)
+    }
+
+    /// The number of entries in the DB's active memtable, which haven't been
+    /// flushed yet.
+    #[cfg(test)]
+    pub(crate) fn unflushed_entries(&self) -> Result<Option<u64>> {
+        Ok(self
+            .0
+            .property_int_value("rocksdb.num-entries-active-mem-table")?)
+    }
 }
@@ -31,11 +31,15 @@ where
 #[cfg(test)]
 mod test {
     use hyperlane_core::{
-        HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, Indexed, LogMeta,
-        RawHyperlaneMessage, H256, H512, U256,
+        HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, HyperlaneReorgAwareIndexerStore,
+        HyperlaneSequenceAwareIndexerStoreReader, Indexed, InterchainGasExpenditure,
+        InterchainGasPayment, LogMeta, RawHyperlaneMessage, H256, H512, U256,
     };
 
-    use crate::db::HyperlaneRocksDB;
+    use crate::{
+        db::{restore_db_backup, DbBackuper, HyperlaneRocksDB, TypedDB},
+        BackupStorage, LocalStorage,
+    };
 
     use super::*;
 
@@ -77,4 +81,441 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn db_rolls_back_messages() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_rolls_back_messages"),
+                db,
+            );
+
+            let logs = (0..3)
+                .map(|nonce| {
+                    let message = HyperlaneMessage {
+                        nonce,
+                        ..Default::default()
+                    };
+                    let meta = LogMeta {
+                        address: H256::from_low_u64_be(1),
+                        block_number: nonce as u64,
+                        block_hash: H256::from_low_u64_be(nonce as u64),
+                        transaction_id: H512::from_low_u64_be(1),
+                        transaction_index: 0,
+                        log_index: U256::from(0),
+                    };
+                    (Indexed::new(message), meta)
+                })
+                .collect::<Vec<_>>();
+            db.store_logs(&logs).await.unwrap();
+            db.store_processed_by_nonce(&2, &true).unwrap();
+            assert_eq!(
+                HyperlaneReorgAwareIndexerStore::<HyperlaneMessage>::retrieve_log_block_hash_by_sequence(&db, 2)
+                    .await
+                    .unwrap(),
+                Some(H256::from_low_u64_be(2))
+            );
+
+            let rolled_back =
+                HyperlaneReorgAwareIndexerStore::<HyperlaneMessage>::rollback_logs(&db, 1..=2)
+                    .await
+                    .unwrap();
+            assert_eq!(rolled_back, 2);
+            assert_eq!(db.retrieve_processed_by_nonce(&2).unwrap(), None);
+
+            // The first rolled back nonce is recorded until it's taken
+            assert_eq!(db.take_rolled_back_message_nonce().unwrap(), Some(1));
+            assert_eq!(db.take_rolled_back_message_nonce().unwrap(), None);
+
+            assert!(db.retrieve_message_by_nonce(0).unwrap().is_some());
+            for nonce in 1..=2 {
+                assert!(db.retrieve_message_by_nonce(nonce).unwrap().is_none());
+                assert!(
+                    HyperlaneSequenceAwareIndexerStoreReader::<HyperlaneMessage>::retrieve_log_block_number_by_sequence(&db, nonce)
+                        .await
+                        .unwrap()
+                        .is_none()
+                );
+            }
+
+            // Rolled back messages are stored again when they're re-indexed.
+            db.store_logs(&logs[1..]).await.unwrap();
+            assert!(db.retrieve_message_by_nonce(2).unwrap().is_some());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_rolls_back_gas_payments() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_rolls_back_gas_payments"),
+                db,
+            );
+
+            let payment = InterchainGasPayment {
+                message_id: H256::from_low_u64_be(1),
+                destination: 2,
+                payment: U256::from(10),
+                gas_amount: U256::from(100),
+            };
+            let logs = (0..3)
+                .map(|sequence| {
+                    let meta = LogMeta {
+                        address: H256::from_low_u64_be(1),
+                        block_number: sequence as u64,
+                        block_hash: H256::from_low_u64_be(sequence as u64),
+                        transaction_id: H512::from_low_u64_be(sequence as u64),
+                        transaction_index: 0,
+                        log_index: U256::from(0),
+                    };
+                    (Indexed::new(payment).with_sequence(sequence), meta)
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 3);
+            assert_eq!(
+                db.retrieve_gas_payment_by_gas_payment_key(payment.into())
+                    .unwrap()
+                    .payment,
+                U256::from(30)
+            );
+
+            let rolled_back =
+                HyperlaneReorgAwareIndexerStore::<InterchainGasPayment>::rollback_logs(&db, 1..=2)
+                    .await
+                    .unwrap();
+            assert_eq!(rolled_back, 2);
+            let total = db
+                .retrieve_gas_payment_by_gas_payment_key(payment.into())
+                .unwrap();
+            assert_eq!(total.payment, U256::from(10));
+            assert_eq!(total.gas_amount, U256::from(100));
+            for sequence in 1..=2 {
+                assert!(
+                    HyperlaneSequenceAwareIndexerStoreReader::<InterchainGasPayment>::retrieve_log_block_number_by_sequence(&db, sequence)
+                        .await
+                        .unwrap()
+                        .is_none()
+                );
+            }
+            assert!(
+                HyperlaneSequenceAwareIndexerStoreReader::<InterchainGasPayment>::retrieve_log_block_number_by_sequence(&db, 0)
+                    .await
+                    .unwrap()
+                    .is_some()
+            );
+
+            // Rolled back payments are counted again when they're re-indexed.
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 2);
+            assert_eq!(
+                db.retrieve_gas_payment_by_gas_payment_key(payment.into())
+                    .unwrap()
+                    .payment,
+                U256::from(30)
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_lists_domain_names() {
+        run_test_db(|db| async move {
+            // One domain name is a prefix of the other, up to the `_` after it
+            for name in ["domain", "domain_b"] {
+                let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain(name), db.clone());
+                db.store_message_id_by_nonce(&0, &H256::zero()).unwrap();
+            }
+            HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("domain_b"), db.clone())
+                .store_message_id_by_nonce(&1, &H256::zero())
+                .unwrap();
+
+            let domains = TypedDB::domain_names(&db, ["domain", "domain_b", "domain_c"]).unwrap();
+            assert_eq!(
+                domains.into_iter().collect::<Vec<_>>(),
+                vec![("domain".to_owned(), 1), ("domain_b".to_owned(), 2)]
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_indexes_messages_by_dispatch_tx() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_indexes_messages_by_dispatch_tx"),
+                db,
+            );
+
+            // Nonces 0 and 1 are dispatched in one transaction, nonce 2 in another
+            let tx_ids = [1, 1, 2].map(H512::from_low_u64_be);
+            let logs = tx_ids
+                .iter()
+                .enumerate()
+                .map(|(nonce, tx_id)| {
+                    let message = HyperlaneMessage {
+                        nonce: nonce as u32,
+                        ..Default::default()
+                    };
+                    let meta = LogMeta {
+                        transaction_id: *tx_id,
+                        ..Default::default()
+                    };
+                    (Indexed::new(message), meta)
+                })
+                .collect::<Vec<_>>();
+            db.store_logs(&logs).await.unwrap();
+
+            assert_eq!(
+                db.retrieve_message_nonces_by_dispatch_tx(&tx_ids[0])
+                    .unwrap(),
+                vec![0, 1]
+            );
+            assert_eq!(
+                db.retrieve_message_nonces_by_dispatch_tx(&tx_ids[2])
+                    .unwrap(),
+                vec![2]
+            );
+            assert!(db
+                .retrieve_message_nonces_by_dispatch_tx(&H512::from_low_u64_be(3))
+                .unwrap()
+                .is_empty());
+            assert_eq!(
+                db.retrieve_dispatch_tx_by_nonce(&2).unwrap(),
+                Some(tx_ids[2])
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_prunes_delivered_messages() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_prunes_delivered_messages"),
+                db,
+            );
+
+            let logs = (0..4)
+                .map(|nonce| {
+                    let message = HyperlaneMessage {
+                        nonce,
+                        ..Default::default()
+                    };
+                    let meta = LogMeta {
+                        address: H256::from_low_u64_be(1),
+                        block_number: nonce as u64,
+                        block_hash: H256::from_low_u64_be(nonce as u64),
+                        transaction_id: H512::from_low_u64_be(1),
+                        transaction_index: 0,
+                        log_index: U256::from(0),
+                    };
+                    (Indexed::new(message), meta)
+                })
+                .collect::<Vec<_>>();
+            db.store_logs(&logs).await.unwrap();
+            for (message, _) in &logs {
+                db.store_pending_message_retry_count_by_message_id(&message.inner().id(), &3)
+                    .unwrap();
+                db.process_gas_expenditure(InterchainGasExpenditure {
+                    message_id: message.inner().id(),
+                    tokens_used: U256::from(5),
+                    gas_used: U256::from(1),
+                })
+                .unwrap();
+            }
+
+            // Nonces 0 and 2 were delivered long ago, nonce 1 is still pending and
+            // nonce 3 was delivered before delivery times were recorded.
+            for nonce in [0, 2, 3] {
+                db.store_processed_by_nonce(&nonce, &true).unwrap();
+            }
+            for nonce in [0, 2] {
+                db.store_processed_at_by_nonce(&nonce, &100).unwrap();
+            }
+
+            let stats = db.prune_delivered_messages(1_000, 2_000, 100).unwrap();
+            assert_eq!(stats.messages, 2);
+            assert!(stats.bytes > 0);
+            assert!(stats.reached_latest);
+
+            for nonce in [0, 2] {
+                let id = logs[nonce as usize].0.inner().id();
+                assert!(db.retrieve_message_by_nonce(nonce).unwrap().is_none());
+                assert!(db.is_message_pruned(nonce).unwrap());
+                assert_eq!(
+                    db.retrieve_pending_message_retry_count_by_message_id(&id)
+                        .unwrap(),
+                    None
+                );
+                // Gas records are kept
+                assert_eq!(
+                    db.retrieve_gas_expenditure_by_message_id(id)
+                        .unwrap()
+                        .tokens_used,
+                    U256::from(5)
+                );
+                // Indexing and merkle proofs still see the message
+                assert_eq!(db.retrieve_message_id_by_nonce(&nonce).unwrap(), Some(id));
+                assert!(
+                    HyperlaneSequenceAwareIndexerStoreReader::<HyperlaneMessage>::is_sequence_indexed(&db, nonce)
+                        .await
+                        .unwrap()
+                );
+            }
+            for nonce in [1, 3] {
+                assert!(db.retrieve_message_by_nonce(nonce).unwrap().is_some());
+                assert!(!db.is_message_pruned(nonce).unwrap());
+            }
+            // Pruned messages are no longer indexed by their dispatch transaction
+            assert_eq!(
+                db.retrieve_message_nonces_by_dispatch_tx(&H512::from_low_u64_be(1))
+                    .unwrap(),
+                vec![1, 3]
+            );
+            // Messages without a delivery time are stamped so they're pruned later
+            assert_eq!(db.retrieve_processed_at_by_nonce(&3).unwrap(), Some(2_000));
+
+            // A later pass only prunes what has become old enough since
+            let stats = db.prune_delivered_messages(3_000, 4_000, 100).unwrap();
+            assert_eq!(stats.messages, 1);
+            assert!(db.retrieve_message_by_nonce(3).unwrap().is_none());
+            assert!(db.retrieve_message_by_nonce(1).unwrap().is_some());
+
+            // The undelivered message doesn't hold the scan back, and is pruned once
+            // it's delivered
+            let stats = db.prune_delivered_messages(3_000, 4_000, 1).unwrap();
+            assert!(stats.reached_latest);
+            assert_eq!(stats.messages, 0);
+            db.store_processed_by_nonce(&1, &true).unwrap();
+            db.store_processed_at_by_nonce(&1, &100).unwrap();
+            let stats = db.prune_delivered_messages(3_000, 4_000, 1).unwrap();
+            assert_eq!(stats.messages, 1);
+            assert!(db.is_message_pruned(1).unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_rechecks_skipped_nonces_in_bounded_batches() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_rechecks_skipped_nonces_in_bounded_batches"),
+                db,
+            );
+            let logs = (0..3)
+                .map(|nonce| {
+                    let message = HyperlaneMessage {
+                        nonce,
+                        ..Default::default()
+                    };
+                    let meta = LogMeta {
+                        address: H256::from_low_u64_be(1),
+                        block_number: nonce as u64,
+                        block_hash: H256::from_low_u64_be(nonce as u64),
+                        transaction_id: H512::from_low_u64_be(1),
+                        transaction_index: 0,
+                        log_index: U256::from(0),
+                    };
+                    (Indexed::new(message), meta)
+                })
+                .collect::<Vec<_>>();
+            db.store_logs(&logs).await.unwrap();
+
+            // None of the messages are delivered yet, so they're all skipped
+            let stats = db.prune_delivered_messages(1_000, 2_000, 10).unwrap();
+            assert_eq!(stats.messages, 0);
+            for nonce in 0..3 {
+                db.store_processed_by_nonce(&nonce, &true).unwrap();
+                db.store_processed_at_by_nonce(&nonce, &100).unwrap();
+            }
+
+            // Each pass only rechecks one skipped nonce, carrying on from the last one
+            for nonce in 0..3 {
+                let stats = db.prune_delivered_messages(1_000, 2_000, 1).unwrap();
+                assert!(stats.reached_latest);
+                assert_eq!(stats.messages, 1);
+                assert!(db.is_message_pruned(nonce).unwrap());
+            }
+            let stats = db.prune_delivered_messages(1_000, 2_000, 1).unwrap();
+            assert_eq!(stats.messages, 0);
+            assert_eq!(db.keys_with_prefix("prune_skipped_nonce_").count(), 0);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_backs_up_and_restores() {
+        run_test_db(|db| async move {
+            let domain = HyperlaneDomain::new_test_domain("db_backs_up_and_restores");
+            let domain_db = HyperlaneRocksDB::new(&domain, db.clone());
+            let logs = (0..3)
+                .map(|nonce| {
+                    let message = HyperlaneMessage {
+                        nonce,
+                        ..Default::default()
+                    };
+                    (Indexed::new(message), LogMeta::default())
+                })
+                .collect::<Vec<_>>();
+            domain_db.store_logs(&logs).await.unwrap();
+            assert_eq!(domain_db.retrieve_latest_message_nonce().unwrap(), Some(2));
+            assert_eq!(domain_db.retrieve_latest_merkle_leaf_index().unwrap(), None);
+
+            let backups_dir = TempDir::new().unwrap();
+            let storage = || Box::new(LocalStorage::new(backups_dir.path().into(), None).unwrap());
+            let backuper = DbBackuper::new(db, vec![domain.clone()], storage(), None, 2, None);
+            let manifest = backuper.backup().await.unwrap();
+            assert_eq!(manifest.domains[0].latest_message_nonce, Some(2));
+
+            let restore_dir = TempDir::new().unwrap();
+            let target = restore_dir.path().join("restored");
+            let unknown_domain = restore_db_backup(
+                storage().as_ref(),
+                None,
+                &target,
+                &[domain.name(), "unknown"],
+            )
+            .await;
+            assert!(unknown_domain.is_err());
+            assert!(!target.exists());
+
+            let restored = restore_db_backup(storage().as_ref(), None, &target, &[domain.name()])
+                .await
+                .unwrap();
+            assert_eq!(restored, manifest);
+            let restored_db =
+                HyperlaneRocksDB::new(&domain, DB::from_path_read_only(&target).unwrap());
+            assert!(restored_db.retrieve_message_by_nonce(2).unwrap().is_some());
+
+            // Restoring never overwrites an existing database
+            assert!(
+                restore_db_backup(storage().as_ref(), None, &target, &[domain.name()])
+                    .await
+                    .is_err()
+            );
+
+            // Only the most recent backups are retained
+            let second = backuper.backup().await.unwrap();
+            let third = backuper.backup().await.unwrap();
+            let index = storage().read_backup_file("backups.json").await.unwrap();
+            assert_eq!(
+                serde_json::from_slice::<Vec<String>>(&index.unwrap()).unwrap(),
+                vec![second.name, third.name]
+            );
+            assert!(!backups_dir
+                .path()
+                .join("db_backups")
+                .join(&manifest.name)
+                .exists());
+            assert!(restore_db_backup(
+                storage().as_ref(),
+                Some(&manifest.name),
+                &restore_dir.path().join("pruned"),
+                &[domain.name()]
+            )
+            .await
+            .is_err());
+        })
+        .await;
+    }
 }
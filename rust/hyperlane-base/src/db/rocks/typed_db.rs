@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use hyperlane_core::{Decode, Encode, HyperlaneDomain};
 
 use crate::db::{DbError, DB};
@@ -32,6 +34,29 @@ impl TypedDB {
         Self { domain_prefix, db }
     }
 
+    /// The names of the domains among `names` with data in `db`, and the number
+    /// of keys stored for each. Domain names may contain the `_` which follows
+    /// them in keys, so each key is counted for the longest name it starts with.
+    pub fn domain_names<'a>(
+        db: &DB,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<BTreeMap<String, u64>> {
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        let mut domains = BTreeMap::new();
+        for key in db.keys() {
+            let key = key?;
+            let domain = names.iter().find(|name| {
+                key.strip_prefix(name.as_bytes())
+                    .map_or(false, |rest| rest.starts_with(b"_"))
+            });
+            if let Some(name) = domain {
+                *domains.entry((*name).to_owned()).or_default() += 1;
+            }
+        }
+        Ok(domains)
+    }
+
     fn prefixed_key(&self, prefix: &[u8], key: &[u8]) -> Vec<u8> {
         self.domain_prefix
             .iter()
@@ -67,6 +92,12 @@ impl TypedDB {
             .map_err(Into::into)
     }
 
+    /// Delete the value stored under a key
+    pub fn delete_value(&self, prefix: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Result<()> {
+        self.db
+            .delete(&self.prefixed_key(prefix.as_ref(), key.as_ref()))
+    }
+
     /// Store encodable kv pair
     pub fn store_keyed_encodable<K: Encode, V: Encode>(
         &self,
@@ -85,4 +116,50 @@ impl TypedDB {
     ) -> Result<Option<V>> {
         self.retrieve_decodable(prefix, key.to_vec())
     }
+
+    /// Delete the value stored under an encodable key
+    pub fn delete_keyed<K: Encode>(&self, prefix: impl AsRef<[u8]>, key: &K) -> Result<()> {
+        self.delete_value(prefix, key.to_vec())
+    }
+
+    /// Iterate over the keys stored under `prefix`, with the domain prefix and
+    /// `prefix` stripped.
+    pub fn keys_with_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> impl Iterator<Item = Result<Vec<u8>>> + '_ {
+        self.keys_with_prefix_from(prefix, [])
+    }
+
+    /// Like `keys_with_prefix`, but starting at the first key which isn't less
+    /// than `from`.
+    pub fn keys_with_prefix_from(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        from: impl AsRef<[u8]>,
+    ) -> impl Iterator<Item = Result<Vec<u8>>> + '_ {
+        let from = self.prefixed_key(prefix.as_ref(), from.as_ref());
+        let prefix = self.prefixed_key(prefix.as_ref(), &[]);
+        let len = prefix.len();
+        self.db
+            .keys_with_prefix_from(prefix, from)
+            .map(move |key| key.map(|key| key[len..].to_vec()))
+    }
+
+    /// Delete the value stored under an encodable key if there is one. Returns the
+    /// combined size in bytes of the key and value that were deleted.
+    pub fn prune_keyed<K: Encode>(&self, prefix: impl AsRef<[u8]>, key: &K) -> Result<usize> {
+        self.prune_value(prefix, key.to_vec())
+    }
+
+    /// Delete the value stored under a key if there is one. Returns the combined
+    /// size in bytes of the key and value that were deleted.
+    pub fn prune_value(&self, prefix: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Result<usize> {
+        let key = self.prefixed_key(prefix.as_ref(), key.as_ref());
+        let Some(value) = self.db.retrieve(&key)? else {
+            return Ok(0);
+        };
+        self.db.delete(&key)?;
+        Ok(key.len() + value.len())
+    }
 }
@@ -3,7 +3,7 @@ use std::fmt::{Debug, Formatter};
 use std::sync::OnceLock;
 
 use eyre::Result;
-use hyperlane_core::{HyperlaneDomain, H160};
+use hyperlane_core::{rpc_clients::FallbackProviderMetrics, HyperlaneDomain, H160};
 use prometheus::{
     histogram_opts, labels, opts, register_counter_vec_with_registry,
     register_gauge_vec_with_registry, register_histogram_vec_with_registry,
@@ -15,6 +15,7 @@ use tokio::sync::RwLock;
 use ethers_prometheus::{json_rpc_client::JsonRpcClientMetrics, middleware::MiddlewareMetrics};
 
 use crate::metrics::{
+    fallback_provider::create_fallback_provider_metrics,
     json_rpc_client::create_json_rpc_client_metrics, provider::create_provider_metrics,
 };
 
@@ -38,6 +39,8 @@ pub struct CoreMetrics {
     span_events: IntCounterVec,
     last_known_message_nonce: IntGaugeVec,
     submitter_queue_length: IntGaugeVec,
+    submitter_paused: IntGaugeVec,
+    submitter_paused_seconds: CounterVec,
 
     operations_processed_count: IntCounterVec,
     messages_processed_count: IntCounterVec,
@@ -48,6 +51,10 @@ pub struct CoreMetrics {
     /// quorum provider.
     json_rpc_client_metrics: OnceLock<JsonRpcClientMetrics>,
 
+    /// Set of metrics describing the health of the sub-providers of fallback
+    /// providers.
+    fallback_provider_metrics: OnceLock<FallbackProviderMetrics>,
+
     /// Set of provider-specific metrics. These only need to get created once.
     provider_metrics: OnceLock<MiddlewareMetrics>,
 
@@ -136,6 +143,26 @@ impl CoreMetrics {
             registry
         )?;
 
+        let submitter_paused = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("submitter_paused"),
+                "Whether a submitter is paused because its wallet's balance is too low",
+                const_labels_ref
+            ),
+            &["remote", "wallet"],
+            registry
+        )?;
+
+        let submitter_paused_seconds = register_counter_vec_with_registry!(
+            opts!(
+                namespaced!("submitter_paused_seconds"),
+                "Time submitters spent paused because their wallet's balance was too low",
+                const_labels_ref
+            ),
+            &["remote", "wallet"],
+            registry
+        )?;
+
         let latest_checkpoint = register_int_gauge_vec_with_registry!(
             opts!(
                 namespaced!("latest_checkpoint"),
@@ -178,6 +205,8 @@ impl CoreMetrics {
             last_known_message_nonce,
 
             submitter_queue_length,
+            submitter_paused,
+            submitter_paused_seconds,
 
             operations_processed_count,
             messages_processed_count,
@@ -185,6 +214,7 @@ impl CoreMetrics {
             latest_checkpoint,
 
             json_rpc_client_metrics: OnceLock::new(),
+            fallback_provider_metrics: OnceLock::new(),
             provider_metrics: OnceLock::new(),
 
             validator_metrics: ValidatorObservabilityMetricManager::new(
@@ -212,6 +242,17 @@ impl CoreMetrics {
             .clone()
     }
 
+    /// Create the fallback provider metrics attached to this core metrics
+    /// instance.
+    pub fn fallback_provider_metrics(&self) -> FallbackProviderMetrics {
+        self.fallback_provider_metrics
+            .get_or_init(|| {
+                create_fallback_provider_metrics(self)
+                    .expect("Failed to create fallback provider metrics!")
+            })
+            .clone()
+    }
+
     /// Create and register a new int gauge.
     pub fn new_int_gauge(
         &self,
@@ -327,6 +368,26 @@ impl CoreMetrics {
         self.submitter_queue_length.clone()
     }
 
+    /// Whether a submitter is paused because the balance of its wallet is
+    /// below the chain's minimum signer balance.
+    ///
+    /// Labels:
+    /// - `remote`: Remote chain the submitter delivers to.
+    /// - `wallet`: Index of the submitter's wallet in the chain's wallet pool.
+    pub fn submitter_paused(&self) -> IntGaugeVec {
+        self.submitter_paused.clone()
+    }
+
+    /// Time in seconds submitters spent paused because the balance of their
+    /// wallet was below the chain's minimum signer balance.
+    ///
+    /// Labels:
+    /// - `remote`: Remote chain the submitter delivers to.
+    /// - `wallet`: Index of the submitter's wallet in the chain's wallet pool.
+    pub fn submitter_paused_seconds(&self) -> CounterVec {
+        self.submitter_paused_seconds.clone()
+    }
+
     /// The number of operations successfully submitted by this process during
     /// its lifetime.
     ///
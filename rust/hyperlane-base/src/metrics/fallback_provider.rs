@@ -0,0 +1,26 @@
+use eyre::Result;
+use hyperlane_core::rpc_clients::{FallbackProviderMetrics, FALLBACK_PROVIDER_METRICS_LABELS};
+
+use crate::CoreMetrics;
+
+pub(crate) fn create_fallback_provider_metrics(
+    metrics: &CoreMetrics,
+) -> Result<FallbackProviderMetrics> {
+    Ok(FallbackProviderMetrics {
+        latency_seconds: Some(metrics.new_gauge(
+            "fallback_provider_latency_seconds",
+            "Rolling latency of successful requests to a fallback sub-provider",
+            FALLBACK_PROVIDER_METRICS_LABELS,
+        )?),
+        error_rate: Some(metrics.new_gauge(
+            "fallback_provider_error_rate",
+            "Rolling rate of failed requests to a fallback sub-provider",
+            FALLBACK_PROVIDER_METRICS_LABELS,
+        )?),
+        circuit_state: Some(metrics.new_int_gauge(
+            "fallback_provider_circuit_state",
+            "Circuit breaker state of a fallback sub-provider: 0 closed, 1 half-open, 2 open",
+            FALLBACK_PROVIDER_METRICS_LABELS,
+        )?),
+    })
+}
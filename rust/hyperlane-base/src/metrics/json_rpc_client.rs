@@ -17,5 +17,15 @@ pub(crate) fn create_json_rpc_client_metrics(
             REQUEST_DURATION_SECONDS_HELP,
             REQUEST_DURATION_SECONDS_LABELS,
         )?)
+        .quorum_disagreement_count(metrics.new_int_counter(
+            "quorum_disagreement_count",
+            QUORUM_DISAGREEMENT_COUNT_HELP,
+            QUORUM_DISAGREEMENT_COUNT_LABELS,
+        )?)
+        .request_throttled_seconds(metrics.new_counter(
+            "request_throttled_seconds",
+            REQUEST_THROTTLED_SECONDS_HELP,
+            REQUEST_THROTTLED_SECONDS_LABELS,
+        )?)
         .build()?)
 }
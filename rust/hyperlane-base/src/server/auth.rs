@@ -0,0 +1,38 @@
+use std::fmt::{Debug, Formatter};
+
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+
+/// A bearer token which requests to an api must be authenticated with. It's
+/// redacted from `Debug` output, so configs holding one can be logged.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiToken(String);
+
+impl ApiToken {
+    /// Wrap a token
+    pub fn new(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl Debug for ApiToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ApiToken(<redacted>)")
+    }
+}
+
+/// Whether the request carries `token` as its bearer token
+pub fn is_authorized(headers: &HeaderMap, token: &ApiToken) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |provided| {
+            constant_time_eq(provided.as_bytes(), token.0.as_bytes())
+        })
+}
+
+/// Compares in a time which doesn't depend on where the inputs differ, so the
+/// token can't be guessed byte by byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
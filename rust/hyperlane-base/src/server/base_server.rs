@@ -1,4 +1,7 @@
-use crate::CoreMetrics;
+use crate::{
+    server::health::{self, HealthCheck},
+    CoreMetrics,
+};
 use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
 use derive_new::new;
 use std::{net::SocketAddr, sync::Arc};
@@ -18,24 +21,37 @@ impl Server {
     }
 
     /// Run an HTTP server serving agent-specific different routes
+    pub fn run_with_custom_routes(
+        self: Arc<Self>,
+        custom_routes: Vec<(&str, Router)>,
+    ) -> JoinHandle<()> {
+        self.run_with_health_checks(custom_routes, vec![])
+    }
+
+    /// Run an HTTP server serving agent-specific different routes, with a
+    /// readiness probe computed from `health_checks`
     ///
     /// routes:
     ///  - metrics - serving OpenMetrics format reports on `/metrics`
     ///     (this is compatible with Prometheus, which ought to be configured to scrape this endpoint)
+    ///  - health - serving liveness and readiness probes on `/health/live` and `/health/ready`
     ///  - custom_routes - additional routes to be served by the server as per the specific agent
-    pub fn run_with_custom_routes(
+    pub fn run_with_health_checks(
         self: Arc<Self>,
         custom_routes: Vec<(&str, Router)>,
+        health_checks: Vec<Box<dyn HealthCheck>>,
     ) -> JoinHandle<()> {
         let port = self.listen_port;
         tracing::info!(port, "starting server on 0.0.0.0");
 
         let core_metrics_clone = self.core_metrics.clone();
 
-        let mut app = Router::new().route(
-            "/metrics",
-            get(move || Self::gather_metrics(core_metrics_clone)),
-        );
+        let mut app = Router::new()
+            .route(
+                "/metrics",
+                get(move || Self::gather_metrics(core_metrics_clone)),
+            )
+            .nest("/health", health::routes(health_checks));
 
         for (route, router) in custom_routes {
             app = app.nest(route, router);
@@ -112,4 +128,59 @@ mod tests {
         let body = response.text().await.expect("Failed to read response body");
         assert!(body.contains("expected_metric_content"));
     }
+
+    #[derive(Debug)]
+    struct MockHealthCheck(bool);
+
+    #[async_trait::async_trait]
+    impl HealthCheck for MockHealthCheck {
+        async fn check(&self) -> Vec<health::ComponentHealth> {
+            vec![health::ComponentHealth {
+                component: "mock".to_owned(),
+                ready: self.0,
+                detail: serde_json::Value::Null,
+            }]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoints() {
+        let server = Arc::new(Server::new(
+            8081,
+            Arc::new(CoreMetrics::new("test", 8081, Registry::new()).unwrap()),
+        ));
+        let _server_task = tokio::spawn(async move {
+            server
+                .run_with_health_checks(
+                    vec![],
+                    vec![
+                        Box::new(MockHealthCheck(true)),
+                        Box::new(MockHealthCheck(false)),
+                    ],
+                )
+                .await
+                .unwrap();
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get("http://127.0.0.1:8081/health/live")
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert!(response.status().is_success());
+
+        let response = client
+            .get("http://127.0.0.1:8081/health/ready")
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.text().await.expect("Failed to read response body");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["components"].as_array().unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,264 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use derive_new::new;
+use futures_util::future::join_all;
+use hyperlane_core::{HyperlaneDomain, HyperlaneProvider};
+use prometheus::IntGaugeVec;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::db::DB;
+
+/// How long a component has to respond to a health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The health of one component of an agent, e.g. an indexer or a submitter
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealth {
+    /// Name of the component, e.g. `indexer:dispatched_messages:ethereum`
+    pub component: String,
+    /// Whether the component is ready to do its work
+    pub ready: bool,
+    /// Details of the component's state
+    pub detail: Value,
+}
+
+/// Checks the health of components of an agent, to compute its readiness.
+#[async_trait]
+pub trait HealthCheck: Send + Sync + Debug {
+    /// Check the health of the components this check is responsible for
+    async fn check(&self) -> Vec<ComponentHealth>;
+}
+
+/// The readiness of an agent, served by `GET /health/ready`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    /// Whether all components are ready
+    pub ready: bool,
+    /// The health of each component
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Routes for liveness and readiness probes:
+///  - `/health/live` responds with 200 as long as the agent is serving requests
+///  - `/health/ready` responds with 200 if all components are ready and 503
+///    otherwise, with the health of each component as JSON
+pub(crate) fn routes(checks: Vec<Box<dyn HealthCheck>>) -> Router {
+    Router::new()
+        .route("/live", get(|| async { Json(json!({ "live": true })) }))
+        .route("/ready", get(ready))
+        .with_state(Arc::new(checks))
+}
+
+async fn ready(
+    State(checks): State<Arc<Vec<Box<dyn HealthCheck>>>>,
+) -> (StatusCode, Json<Readiness>) {
+    let components = join_all(checks.iter().map(|check| check.check()))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let ready = components.iter().all(|c| c.ready);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(Readiness { ready, components }))
+}
+
+/// Checks that the RPC of a chain is reachable, and that the chain's indexers
+/// are within `max_lag` blocks of the tip.
+#[derive(new)]
+pub struct ChainHealthCheck {
+    domain: HyperlaneDomain,
+    provider: Box<dyn HyperlaneProvider>,
+    /// The `indexed_height` metric of the chain's indexers
+    indexed_height: IntGaugeVec,
+    /// The labels of the chain's indexers in `indexed_height`
+    indexer_labels: Vec<&'static str>,
+    max_lag: u64,
+}
+
+impl Debug for ChainHealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ChainHealthCheck {{ domain: {}, indexer_labels: {:?}, max_lag: {} }}",
+            self.domain, self.indexer_labels, self.max_lag
+        )
+    }
+}
+
+#[async_trait]
+impl HealthCheck for ChainHealthCheck {
+    async fn check(&self) -> Vec<ComponentHealth> {
+        let chain = self.domain.name();
+        let tip = match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, self.provider.get_block_number())
+            .await
+        {
+            Ok(Ok(tip)) => Ok(tip),
+            Ok(Err(err)) => Err(err.to_string()),
+            Err(_) => Err("Timed out".to_owned()),
+        };
+        let rpc = ComponentHealth {
+            component: format!("rpc:{chain}"),
+            ready: tip.is_ok(),
+            detail: match &tip {
+                Ok(tip) => json!({ "latestBlock": tip }),
+                Err(err) => json!({ "error": err }),
+            },
+        };
+
+        let mut components = vec![rpc];
+        for label in &self.indexer_labels {
+            let indexed = self
+                .indexed_height
+                .with_label_values(&[label, chain])
+                .get()
+                .max(0) as u64;
+            // The lag is unknown, and so not ready, if the tip can't be fetched
+            let lag = tip.as_ref().ok().map(|tip| tip.saturating_sub(indexed));
+            components.push(ComponentHealth {
+                component: format!("indexer:{label}:{chain}"),
+                ready: lag.map_or(false, |lag| lag <= self.max_lag),
+                detail: json!({
+                    "indexedHeight": indexed,
+                    "lag": lag,
+                    "maxLag": self.max_lag,
+                }),
+            });
+        }
+        components
+    }
+}
+
+/// Checks that the RocksDB database of an agent can be read
+#[derive(Debug, new)]
+pub struct DbHealthCheck {
+    db: DB,
+}
+
+#[async_trait]
+impl HealthCheck for DbHealthCheck {
+    async fn check(&self) -> Vec<ComponentHealth> {
+        let (ready, detail) = match self.db.estimated_live_data_size() {
+            Ok(size) => (true, json!({ "estimatedLiveDataBytes": size })),
+            Err(err) => (false, json!({ "error": err.to_string() })),
+        };
+        vec![ComponentHealth {
+            component: "db".to_owned(),
+            ready,
+            detail,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperlane_core::{
+        BlockInfo, ChainCommunicationError, ChainInfo, ChainResult, HyperlaneChain, TxnInfo, H256,
+        H512, U256,
+    };
+    use prometheus::Opts;
+
+    use super::*;
+
+    /// A provider which only knows the latest block, or fails if it's `None`
+    #[derive(Debug, Clone)]
+    struct TipProvider(HyperlaneDomain, Option<u64>);
+
+    impl HyperlaneChain for TipProvider {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.0
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneProvider for TipProvider {
+        async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_block_by_height(&self, _height: u64) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_txn_by_hash(&self, _hash: &H512) -> ChainResult<TxnInfo> {
+            unimplemented!()
+        }
+
+        async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+            unimplemented!()
+        }
+
+        async fn get_balance(&self, _address: String) -> ChainResult<U256> {
+            unimplemented!()
+        }
+
+        async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+            Ok(None)
+        }
+
+        async fn get_block_number(&self) -> ChainResult<u64> {
+            self.1
+                .ok_or_else(|| ChainCommunicationError::from_other_str("unreachable"))
+        }
+    }
+
+    fn chain_health_check(tip: Option<u64>, indexed: i64) -> ChainHealthCheck {
+        let domain = HyperlaneDomain::new_test_domain("test_chain_health_check");
+        let indexed_height =
+            IntGaugeVec::new(Opts::new("indexed_height", "test"), &["data_type", "chain"]).unwrap();
+        indexed_height
+            .with_label_values(&["messages", domain.name()])
+            .set(indexed);
+        ChainHealthCheck::new(
+            domain.clone(),
+            Box::new(TipProvider(domain, tip)),
+            indexed_height,
+            vec!["messages"],
+            10,
+        )
+    }
+
+    async fn readiness(check: ChainHealthCheck) -> Vec<bool> {
+        check.check().await.into_iter().map(|c| c.ready).collect()
+    }
+
+    #[tokio::test]
+    async fn test_indexer_ready_within_max_lag() {
+        assert_eq!(
+            readiness(chain_health_check(Some(100), 90)).await,
+            [true, true]
+        );
+        assert_eq!(
+            readiness(chain_health_check(Some(100), 89)).await,
+            [true, false]
+        );
+        // Indexers may be ahead of the provider they're checked against
+        assert_eq!(
+            readiness(chain_health_check(Some(100), 101)).await,
+            [true, true]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_nothing_ready_if_rpc_unreachable() {
+        let check = chain_health_check(None, 100);
+        let components = check.check().await;
+        assert_eq!(
+            components.iter().map(|c| c.ready).collect::<Vec<_>>(),
+            [false, false]
+        );
+        assert_eq!(components[1].detail["lag"], Value::Null);
+    }
+}
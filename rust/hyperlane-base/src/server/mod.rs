@@ -1,2 +1,6 @@
+mod auth;
 mod base_server;
+mod health;
+pub use auth::{is_authorized, ApiToken};
 pub use base_server::Server;
+pub use health::*;
@@ -1,15 +1,17 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc, time::Duration};
 
 use eyre::{eyre, Context, Result};
 use futures_util::future::try_join_all;
 use hyperlane_core::{
     HyperlaneChain, HyperlaneDomain, HyperlaneLogStore, HyperlaneProvider,
-    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, InterchainGasPaymaster,
-    Mailbox, MerkleTreeHook, MultisigIsm, SequenceAwareIndexer, ValidatorAnnounce, H256,
+    HyperlaneReorgAwareIndexerStore, HyperlaneSequenceAwareIndexerStoreReader,
+    HyperlaneWatermarkedLogStore, InterchainGasPaymaster, Mailbox, MerkleTreeHook, MultisigIsm,
+    SequenceAwareIndexer, ValidatorAnnounce, H256,
 };
 
 use crate::{
     cursors::{CursorType, Indexable},
+    server::ChainHealthCheck,
     settings::{chains::ChainConf, trace::TracingConfig},
     ContractSync, ContractSyncMetrics, ContractSyncer, CoreMetrics, HyperlaneAgentCore,
     SequenceAwareLogStore, SequencedDataContractSync, Server, WatermarkContractSync,
@@ -48,6 +50,11 @@ pub struct Settings {
     pub chains: HashMap<String, ChainConf>,
     /// Port to listen for prometheus scrape requests
     pub metrics_port: u16,
+    /// How long to wait for work in flight to finish when shutting down
+    pub shutdown_deadline: Duration,
+    /// How many blocks beyond the reorg period an indexer may lag behind the
+    /// tip of its chain before the agent is reported as not ready
+    pub health_max_indexing_lag: u64,
     /// The tracing configuration
     pub tracing: TracingConfig,
 }
@@ -103,12 +110,34 @@ impl Settings {
         Ok(Arc::new(Server::new(self.metrics_port, core_metrics)))
     }
 
+    /// Build a health check of the RPC of `domain` and of its indexers with
+    /// the `indexer_labels` data types, using a provider the agent already has
+    /// for the chain.
+    pub fn build_chain_health_check(
+        &self,
+        domain: &HyperlaneDomain,
+        provider: Box<dyn HyperlaneProvider>,
+        indexer_labels: Vec<&'static str>,
+        sync_metrics: &ContractSyncMetrics,
+    ) -> Result<ChainHealthCheck> {
+        let setup = self.chain_setup(domain)?;
+        Ok(ChainHealthCheck::new(
+            domain.clone(),
+            provider,
+            sync_metrics.indexed_height.clone(),
+            indexer_labels,
+            setup.reorg_period as u64 + self.health_max_indexing_lag,
+        ))
+    }
+
     /// Private to preserve linearity of AgentCore::from_settings -- creating an
     /// agent consumes the settings.
     fn clone(&self) -> Self {
         Self {
             chains: self.chains.clone(),
             metrics_port: self.metrics_port,
+            shutdown_deadline: self.shutdown_deadline,
+            health_max_indexing_lag: self.health_max_indexing_lag,
             tracing: self.tracing.clone(),
         }
     }
@@ -162,7 +191,10 @@ impl Settings {
     where
         T: Debug,
         SequenceIndexer<T>: TryFromWithMetrics<ChainConf>,
-        D: HyperlaneLogStore<T> + HyperlaneSequenceAwareIndexerStoreReader<T> + 'static,
+        D: HyperlaneLogStore<T>
+            + HyperlaneSequenceAwareIndexerStoreReader<T>
+            + HyperlaneReorgAwareIndexerStore<T>
+            + 'static,
     {
         let setup = self.chain_setup(domain)?;
         // Currently, all indexers are of the `SequenceIndexer` type
@@ -172,6 +204,7 @@ impl Settings {
             db.clone() as SequenceAwareLogStore<_>,
             indexer,
             sync_metrics.clone(),
+            setup.index.subscribe,
         )))
     }
 
@@ -196,6 +229,9 @@ impl Settings {
             db.clone() as WatermarkLogStore<_>,
             indexer,
             sync_metrics.clone(),
+            // Watermarked stores can't roll back logs that are reorged out, so they
+            // only store logs once they're final
+            false,
         )))
     }
 
@@ -214,6 +250,7 @@ impl Settings {
         SequenceIndexer<T>: TryFromWithMetrics<ChainConf>,
         D: HyperlaneLogStore<T>
             + HyperlaneSequenceAwareIndexerStoreReader<T>
+            + HyperlaneReorgAwareIndexerStore<T>
             + HyperlaneWatermarkedLogStore<T>
             + 'static,
     {
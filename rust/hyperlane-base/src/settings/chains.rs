@@ -11,7 +11,7 @@ use hyperlane_core::{
     HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneMessage, HyperlaneProvider, IndexMode,
     InterchainGasPaymaster, InterchainGasPayment, InterchainSecurityModule, Mailbox,
     MerkleTreeHook, MerkleTreeInsertion, MultisigIsm, RoutingIsm, SequenceAwareIndexer,
-    ValidatorAnnounce, H256,
+    ValidatorAnnounce, H256, U256,
 };
 use hyperlane_cosmos as h_cosmos;
 use hyperlane_ethereum::{
@@ -44,8 +44,19 @@ pub struct ChainConf {
     pub domain: HyperlaneDomain,
     /// Signer configuration for this chain
     pub signer: Option<SignerConf>,
+    /// Further signers which the relayer submits transactions with in
+    /// parallel to `signer`, as a pool of wallets
+    pub additional_signers: Vec<SignerConf>,
+    /// The balance below which a signer is considered out of funds, in the
+    /// lowest denomination of the native token. The relayer stops submitting
+    /// with a signer while its balance is below it.
+    pub min_signer_balance: Option<U256>,
     /// The reorg period of the chain, i.e. the number of blocks until finality
     pub reorg_period: u32,
+    /// When deliveries of messages to the chain are considered final. If not
+    /// specified, deliveries are checked at the latest block after a fixed
+    /// delay.
+    pub delivery_finality: Option<BlockFinality>,
     /// Addresses of contracts on the chain
     pub addresses: CoreContractAddresses,
     /// The chain connection details
@@ -156,9 +167,14 @@ pub struct IndexSettings {
     /// The height at which to start indexing contracts.
     pub from: u32,
     /// The number of blocks to query at once when indexing contracts.
+    /// Cursors query fewer blocks at once while the provider rejects ranges as too large.
     pub chunk_size: u32,
     /// The indexing mode.
     pub mode: IndexMode,
+    /// Whether to subscribe to sequenced logs, e.g. dispatched messages, over the
+    /// chain's websocket connection to store them as soon as they are emitted,
+    /// rather than only polling for them once they're final.
+    pub subscribe: bool,
 }
 
 impl ChainConf {
@@ -190,6 +206,7 @@ impl ChainConf {
                     conf.clone(),
                     Some(locator.clone()),
                     None,
+                    Some(metrics.fallback_provider_metrics()),
                 )?;
                 Ok(Box::new(provider) as Box<dyn HyperlaneProvider>)
             }
@@ -221,9 +238,14 @@ impl ChainConf {
             }
             ChainConnectionConf::Cosmos(conf) => {
                 let signer = self.cosmos_signer().await.context(ctx)?;
-                h_cosmos::CosmosMailbox::new(conf.clone(), locator.clone(), signer.clone())
-                    .map(|m| Box::new(m) as Box<dyn Mailbox>)
-                    .map_err(Into::into)
+                h_cosmos::CosmosMailbox::new(
+                    conf.clone(),
+                    locator.clone(),
+                    signer.clone(),
+                    Some(metrics.fallback_provider_metrics()),
+                )
+                .map(|m| Box::new(m) as Box<dyn Mailbox>)
+                .map_err(Into::into)
             }
         }
         .context(ctx)
@@ -252,8 +274,12 @@ impl ChainConf {
             }
             ChainConnectionConf::Cosmos(conf) => {
                 let signer = self.cosmos_signer().await.context(ctx)?;
-                let hook =
-                    h_cosmos::CosmosMerkleTreeHook::new(conf.clone(), locator.clone(), signer)?;
+                let hook = h_cosmos::CosmosMerkleTreeHook::new(
+                    conf.clone(),
+                    locator.clone(),
+                    signer,
+                    Some(metrics.fallback_provider_metrics()),
+                )?;
 
                 Ok(Box::new(hook) as Box<dyn MerkleTreeHook>)
             }
@@ -293,6 +319,7 @@ impl ChainConf {
                     locator,
                     signer,
                     self.reorg_period,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(indexer as Box<dyn SequenceAwareIndexer<HyperlaneMessage>>)
             }
@@ -332,6 +359,7 @@ impl ChainConf {
                     locator,
                     signer,
                     self.reorg_period,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(indexer as Box<dyn SequenceAwareIndexer<H256>>)
             }
@@ -371,6 +399,7 @@ impl ChainConf {
                     conf.clone(),
                     locator.clone(),
                     signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(paymaster as Box<dyn InterchainGasPaymaster>)
             }
@@ -411,6 +440,7 @@ impl ChainConf {
                     conf.clone(),
                     locator,
                     self.reorg_period,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(indexer as Box<dyn SequenceAwareIndexer<InterchainGasPayment>>)
             }
@@ -455,6 +485,7 @@ impl ChainConf {
                     // TODO: remove signer requirement entirely
                     signer,
                     self.reorg_period,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(indexer as Box<dyn SequenceAwareIndexer<MerkleTreeInsertion>>)
             }
@@ -485,6 +516,7 @@ impl ChainConf {
                     conf.clone(),
                     locator.clone(),
                     signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
 
                 Ok(va as Box<dyn ValidatorAnnounce>)
@@ -524,7 +556,10 @@ impl ChainConf {
             ChainConnectionConf::Cosmos(conf) => {
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let ism = Box::new(h_cosmos::CosmosInterchainSecurityModule::new(
-                    conf, locator, signer,
+                    conf,
+                    locator,
+                    signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(ism as Box<dyn InterchainSecurityModule>)
             }
@@ -559,6 +594,7 @@ impl ChainConf {
                     conf.clone(),
                     locator.clone(),
                     signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(ism as Box<dyn MultisigIsm>)
             }
@@ -593,6 +629,7 @@ impl ChainConf {
                     &conf.clone(),
                     locator.clone(),
                     signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
                 Ok(ism as Box<dyn RoutingIsm>)
             }
@@ -627,6 +664,7 @@ impl ChainConf {
                     conf.clone(),
                     locator.clone(),
                     signer,
+                    Some(metrics.fallback_provider_metrics()),
                 )?);
 
                 Ok(ism as Box<dyn AggregationIsm>)
@@ -663,6 +701,15 @@ impl ChainConf {
         .context(ctx)
     }
 
+    /// This chain's configuration with `signer` as its signer, e.g. to build
+    /// contracts which submit transactions with one of the `additional_signers`
+    pub fn with_signer(&self, signer: SignerConf) -> Self {
+        Self {
+            signer: Some(signer),
+            ..self.clone()
+        }
+    }
+
     async fn signer<S: BuildableWithSignerConf>(&self) -> Result<Option<S>> {
         if let Some(conf) = &self.signer {
             Ok(Some(conf.build::<S>().await?))
@@ -785,9 +832,17 @@ impl ChainConf {
         let signer = self.ethereum_signer().await?;
         let metrics_conf = self.metrics_conf();
         let rpc_metrics = Some(metrics.json_rpc_client_metrics());
+        let fallback_metrics = Some(metrics.fallback_provider_metrics());
         let middleware_metrics = Some((metrics.provider_metrics(), metrics_conf));
         let res = builder
-            .build_with_connection_conf(conf, locator, signer, rpc_metrics, middleware_metrics)
+            .build_with_connection_conf(
+                conf,
+                locator,
+                signer,
+                rpc_metrics,
+                fallback_metrics,
+                middleware_metrics,
+            )
             .await;
         Ok(res?)
     }
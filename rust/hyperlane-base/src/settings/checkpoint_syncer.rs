@@ -1,6 +1,6 @@
 use crate::{
-    CheckpointSyncer, GcsStorageClientBuilder, LocalStorage, S3Storage, GCS_SERVICE_ACCOUNT_KEY,
-    GCS_USER_SECRET,
+    BackupStorage, CheckpointSyncer, GcsStorageClientBuilder, LocalStorage, S3Storage,
+    GCS_SERVICE_ACCOUNT_KEY, GCS_USER_SECRET,
 };
 use core::str::FromStr;
 use eyre::{eyre, Context, Report, Result};
@@ -120,22 +120,51 @@ impl CheckpointSyncerConf {
                 folder,
                 service_account_key,
                 user_secrets,
-            } => {
-                let auth = if let Some(path) = service_account_key {
-                    AuthFlow::ServiceAccount(ServiceAccountAuth::Path(path.into()))
-                } else if let Some(path) = user_secrets {
-                    AuthFlow::UserAccount(path.into())
-                } else {
-                    // Public data access only - no `insert`
-                    AuthFlow::NoAuth
-                };
+            } => Box::new(
+                GcsStorageClientBuilder::new(gcs_auth(service_account_key, user_secrets))
+                    .build(bucket, folder.to_owned())
+                    .await?,
+            ),
+        })
+    }
 
-                Box::new(
-                    GcsStorageClientBuilder::new(auth)
-                        .build(bucket, folder.to_owned())
-                        .await?,
-                )
+    /// Turn conf into storage for database backups
+    pub async fn build_backup_storage(&self) -> Result<Box<dyn BackupStorage>, Report> {
+        Ok(match self {
+            CheckpointSyncerConf::LocalStorage { path } => {
+                Box::new(LocalStorage::new(path.clone(), None)?)
             }
+            CheckpointSyncerConf::S3 {
+                bucket,
+                folder,
+                region,
+            } => Box::new(S3Storage::new(
+                bucket.clone(),
+                folder.clone(),
+                region.clone(),
+                None,
+            )),
+            CheckpointSyncerConf::Gcs {
+                bucket,
+                folder,
+                service_account_key,
+                user_secrets,
+            } => Box::new(
+                GcsStorageClientBuilder::new(gcs_auth(service_account_key, user_secrets))
+                    .build(bucket, folder.to_owned())
+                    .await?,
+            ),
         })
     }
 }
+
+fn gcs_auth(service_account_key: &Option<String>, user_secrets: &Option<String>) -> AuthFlow {
+    if let Some(path) = service_account_key {
+        AuthFlow::ServiceAccount(ServiceAccountAuth::Path(path.into()))
+    } else if let Some(path) = user_secrets {
+        AuthFlow::UserAccount(path.into())
+    } else {
+        // Public data access only - no `insert`
+        AuthFlow::NoAuth
+    }
+}
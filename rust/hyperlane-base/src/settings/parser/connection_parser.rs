@@ -1,7 +1,13 @@
+use std::collections::HashMap;
+
 use eyre::eyre;
-use h_eth::TransactionOverrides;
-use hyperlane_core::config::{ConfigErrResultExt, OperationBatchConfig};
-use hyperlane_core::{config::ConfigParsingError, HyperlaneDomainProtocol};
+use h_eth::{MethodQuorumPolicy, QuorumConf, QuorumPolicy, TransactionOverrides};
+use hyperlane_core::config::{ConfigErrResultExt, ConfigResult, OperationBatchConfig};
+use hyperlane_core::{
+    config::ConfigParsingError,
+    rpc_clients::{RateLimitConf, RpcRateLimiters},
+    HyperlaneDomainProtocol,
+};
 use url::Url;
 
 use crate::settings::envs::*;
@@ -32,6 +38,7 @@ pub fn build_ethereum_connection_conf(
         }),
         "quorum" => Some(h_eth::RpcConnectionConf::HttpQuorum {
             urls: rpcs.to_owned().clone(),
+            quorum: parse_quorum_conf(rpcs, chain, err),
         }),
         ty => Err(eyre!("unknown rpc consensus type `{ty}`"))
             .take_err(err, || &chain.cwp + "rpc_consensus_type"),
@@ -69,9 +76,177 @@ pub fn build_ethereum_connection_conf(
         rpc_connection: rpc_connection_conf?,
         transaction_overrides,
         operation_batch,
+        rate_limiters: RpcRateLimiters::new(parse_rate_limits(chain, err)),
     }))
 }
 
+/// Parse the client-side rate limits of the `rpcUrls`, keyed by url.
+fn parse_rate_limits(
+    chain: &ValueParser,
+    err: &mut ConfigParsingError,
+) -> HashMap<String, RateLimitConf> {
+    chain
+        .chain(err)
+        .get_opt_key("rpcUrls")
+        .into_array_iter()
+        .map(|urls| {
+            urls.filter_map(|rpc| {
+                let url: Url = rpc
+                    .chain(err)
+                    .get_key("http")
+                    .parse_from_str("Invalid url")
+                    .end()?;
+                let rate_limit = rpc.chain(err).get_opt_key("rateLimit").end()?;
+                let requests_per_second = rate_limit
+                    .chain(err)
+                    .get_key("requestsPerSecond")
+                    .parse_f64()
+                    .end()?;
+                if requests_per_second <= 0. {
+                    err.push(
+                        &rate_limit.cwp + "requests_per_second",
+                        eyre!("Expected a positive number of requests per second"),
+                    );
+                    return None;
+                }
+                let burst = rate_limit
+                    .chain(err)
+                    .get_opt_key("burst")
+                    .parse_u32()
+                    .unwrap_or_else(|| requests_per_second.ceil() as u32);
+                Some((
+                    url.to_string(),
+                    RateLimitConf {
+                        requests_per_second,
+                        burst,
+                    },
+                ))
+            })
+            .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse the weights of the `rpcUrls` and the quorum policies in `rpcQuorum`.
+fn parse_quorum_conf(
+    rpcs: &[Url],
+    chain: &ValueParser,
+    err: &mut ConfigParsingError,
+) -> QuorumConf {
+    let weights: HashMap<Url, u64> = chain
+        .chain(err)
+        .get_opt_key("rpcUrls")
+        .into_array_iter()
+        .map(|urls| {
+            urls.filter_map(|rpc| {
+                let url = rpc
+                    .chain(err)
+                    .get_key("http")
+                    .parse_from_str("Invalid url")
+                    .end()?;
+                let weight = rpc.chain(err).get_opt_key("weight").parse_u64().end()?;
+                Some((url, weight))
+            })
+            .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(quorum) = chain
+        .get_opt_key("rpcQuorum")
+        .take_err(err, || &chain.cwp + "rpc_quorum")
+        .flatten()
+    else {
+        return QuorumConf {
+            weights,
+            ..Default::default()
+        };
+    };
+
+    let total_weight = rpcs
+        .iter()
+        .map(|url| weights.get(url).copied().unwrap_or(1))
+        .sum();
+    let default_policy = quorum
+        .chain(err)
+        .get_opt_key("policy")
+        .and_then(|policy| parse_quorum_policy(policy, total_weight))
+        .unwrap_or_default();
+
+    let method_policies = quorum
+        .chain(err)
+        .get_opt_key("methods")
+        .into_array_iter()
+        .map(|methods| {
+            methods
+                .filter_map(|method_policy| {
+                    let method = method_policy
+                        .chain(err)
+                        .get_key("method")
+                        .parse_string()
+                        .end();
+                    let selector = method_policy
+                        .chain(err)
+                        .get_opt_key("selector")
+                        .parse_string()
+                        .end()
+                        .and_then(|selector| {
+                            let is_selector = selector.len() == 10
+                                && selector.starts_with("0x")
+                                && selector[2..].chars().all(|c| c.is_ascii_hexdigit());
+                            if !is_selector {
+                                err.push(
+                                    &method_policy.cwp + "selector",
+                                    eyre!(
+                                        "Expected a 4 byte hex function selector, got `{selector}`"
+                                    ),
+                                );
+                            }
+                            is_selector.then(|| selector.to_ascii_lowercase())
+                        });
+                    let policy = method_policy
+                        .chain(err)
+                        .get_key("policy")
+                        .and_then(|policy| parse_quorum_policy(policy, total_weight))
+                        .end();
+                    Some(MethodQuorumPolicy {
+                        method: method?.to_owned(),
+                        selector,
+                        policy: policy?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    QuorumConf {
+        weights,
+        default_policy,
+        method_policies,
+    }
+}
+
+/// Parse a quorum policy, which is either `majority`, `all`, `first`, or the
+/// combined weight of providers that have to agree. That weight has to be
+/// reachable by the providers, whose weights add up to `total_weight`, and
+/// can't be 0, which any response would satisfy.
+fn parse_quorum_policy(policy: ValueParser, total_weight: u64) -> ConfigResult<QuorumPolicy> {
+    match policy.val.as_str() {
+        Some("majority") => Ok(QuorumPolicy::Majority),
+        Some("all") => Ok(QuorumPolicy::All),
+        Some("first") => Ok(QuorumPolicy::First),
+        _ => {
+            let weight = policy.parse_u64()?;
+            if weight == 0 || weight > total_weight {
+                return Err(eyre!(
+                    "Expected a quorum weight between 1 and the total weight of the rpc urls, {total_weight}, got {weight}"
+                ))
+                .into_config_result(|| policy.cwp.clone());
+            }
+            Ok(QuorumPolicy::Weight(weight))
+        }
+    }
+}
+
 pub fn build_cosmos_connection_conf(
     rpcs: &[Url],
     chain: &ValueParser,
@@ -134,13 +309,20 @@ pub fn build_cosmos_connection_conf(
         .parse_u64()
         .end();
 
+    let websocket_url = chain
+        .chain(err)
+        .get_opt_key("websocketUrl")
+        .parse_from_str::<Url>("Invalid websocket url")
+        .end();
+
     if !local_err.is_ok() {
         err.merge(local_err);
         None
     } else {
         Some(ChainConnectionConf::Cosmos(h_cosmos::ConnectionConf::new(
             grpcs,
-            rpcs.first().unwrap().to_string(),
+            rpcs.to_vec(),
+            websocket_url,
             chain_id.unwrap().to_string(),
             prefix.unwrap().to_string(),
             canonical_asset.unwrap(),
@@ -7,13 +7,16 @@
 use std::{
     collections::{HashMap, HashSet},
     default::Default,
+    num::NonZeroU64,
+    str::FromStr,
+    time::Duration,
 };
 
 use convert_case::{Case, Casing};
 use eyre::{eyre, Context};
 use h_cosmos::RawCosmosAmount;
 use hyperlane_core::{
-    cfg_unwrap_all, config::*, HyperlaneDomain, HyperlaneDomainProtocol,
+    cfg_unwrap_all, config::*, BlockFinality, HyperlaneDomain, HyperlaneDomainProtocol,
     HyperlaneDomainTechnicalStack, IndexMode,
 };
 use itertools::Itertools;
@@ -23,9 +26,14 @@ use url::Url;
 
 pub use self::json_value_parser::ValueParser;
 pub use super::envs::*;
-use crate::settings::{
-    chains::IndexSettings, parser::connection_parser::build_connection_conf, trace::TracingConfig,
-    ChainConf, CoreContractAddresses, Settings, SignerConf,
+use crate::{
+    db::DbBackupConf,
+    server::ApiToken,
+    settings::{
+        chains::IndexSettings, parser::connection_parser::build_connection_conf,
+        trace::TracingConfig, ChainConf, CheckpointSyncerConf, CoreContractAddresses, Settings,
+        SignerConf,
+    },
 };
 
 mod connection_parser;
@@ -52,6 +60,19 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
             .parse_u16()
             .unwrap_or(9090);
 
+        let shutdown_deadline = p
+            .chain(&mut err)
+            .get_opt_key("shutdownDeadline")
+            .parse_u64()
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(60));
+
+        let health_max_indexing_lag = p
+            .chain(&mut err)
+            .get_opt_key("healthMaxIndexingLag")
+            .parse_u64()
+            .unwrap_or(100);
+
         let fmt = p
             .chain(&mut err)
             .get_opt_key("log")
@@ -109,6 +130,8 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
         err.into_result(Self {
             chains,
             metrics_port,
+            shutdown_deadline,
+            health_max_indexing_lag,
             tracing: TracingConfig { fmt, level },
         })
     }
@@ -129,12 +152,47 @@ fn parse_chain(
         .and_then(parse_signer)
         .end();
 
+    let additional_signers = chain
+        .chain(&mut err)
+        .get_opt_key("additionalSigners")
+        .into_array_iter()
+        .map(|signers| {
+            signers
+                .filter_map(|signer| parse_signer(signer).take_config_err(&mut err))
+                .collect()
+        })
+        .unwrap_or_default();
+    let min_signer_balance = chain
+        .chain(&mut err)
+        .get_opt_key("minSignerBalance")
+        .parse_u256()
+        .end();
+
     let reorg_period = chain
         .chain(&mut err)
         .get_opt_key("blocks")
         .get_key("reorgPeriod")
         .parse_u32()
         .unwrap_or(1);
+    let delivery_finality = chain
+        .chain(&mut err)
+        .get_opt_key("deliveryFinality")
+        .and_then(parse_block_finality)
+        .end();
+    // Only EVM mailboxes can check delivery at a past block, so other chains
+    // would silently check the latest block instead
+    let delivery_finality = match (delivery_finality, &domain) {
+        (Some(_), Some(domain))
+            if domain.domain_protocol() != HyperlaneDomainProtocol::Ethereum =>
+        {
+            Err(eyre!(
+                "Delivery finality is only supported on EVM chains, not {:?}",
+                domain.domain_protocol()
+            ))
+            .take_err(&mut err, || &chain.cwp + "delivery_finality")
+        }
+        (delivery_finality, _) => delivery_finality,
+    };
 
     let rpcs = parse_base_and_override_urls(&chain, "rpcUrls", "customRpcUrls", "http", &mut err);
 
@@ -150,6 +208,12 @@ fn parse_chain(
         .get_opt_key("chunk")
         .parse_u32()
         .unwrap_or(1999);
+    let subscribe = chain
+        .chain(&mut err)
+        .get_opt_key("index")
+        .get_opt_key("subscribe")
+        .parse_bool()
+        .unwrap_or(false);
     let mode = chain
         .chain(&mut err)
         .get_opt_key("index")
@@ -216,7 +280,10 @@ fn parse_chain(
     err.into_result(ChainConf {
         domain,
         signer,
+        additional_signers,
+        min_signer_balance,
         reorg_period,
+        delivery_finality,
         addresses: CoreContractAddresses {
             mailbox,
             interchain_gas_paymaster,
@@ -229,6 +296,7 @@ fn parse_chain(
             from,
             chunk_size,
             mode,
+            subscribe,
         },
     })
 }
@@ -281,6 +349,21 @@ fn parse_domain(chain: ValueParser, name: &str) -> ConfigResult<HyperlaneDomain>
     err.into_result(domain)
 }
 
+/// Parses the optional api token at `key`, which must not be empty if it's set.
+pub fn parse_api_token(
+    p: &ValueParser,
+    key: &str,
+    err: &mut ConfigParsingError,
+) -> Option<ApiToken> {
+    p.chain(err)
+        .get_opt_key(key)
+        .and_then(|token| match token.parse_string()? {
+            "" => Err(eyre!("Api token must not be empty")).into_config_result(|| token.cwp),
+            value => Ok(ApiToken::new(value.to_owned())),
+        })
+        .end()
+}
+
 /// Expects AgentSigner.
 fn parse_signer(signer: ValueParser) -> ConfigResult<SignerConf> {
     let mut err = ConfigParsingError::default();
@@ -349,6 +432,18 @@ fn parse_signer(signer: ValueParser) -> ConfigResult<SignerConf> {
     }
 }
 
+/// Either a number of block confirmations or the `finalized` block tag
+fn parse_block_finality(finality: ValueParser) -> ConfigResult<BlockFinality> {
+    if finality.val == "finalized" {
+        return Ok(BlockFinality::Finalized);
+    }
+    let confirmations = finality.parse_u64()?;
+    NonZeroU64::new(confirmations)
+        .map(BlockFinality::Confirmations)
+        .ok_or_else(|| eyre!("Expected a positive number of confirmations or `finalized`"))
+        .into_config_result(|| finality.cwp.clone())
+}
+
 /// Parser for agent signers.
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
@@ -385,6 +480,47 @@ pub fn recase_json_value(mut val: Value, case: Case) -> Value {
     val
 }
 
+/// Parses the optional database backup settings of an agent, which are
+/// `dbBackupLocation`, `dbBackupInterval`, `dbBackupRetention` and
+/// `dbBackupApiToken`.
+pub fn parse_db_backup(p: &ValueParser) -> ConfigResult<Option<DbBackupConf>> {
+    let mut err = ConfigParsingError::default();
+
+    let location = p
+        .chain(&mut err)
+        .get_opt_key("dbBackupLocation")
+        .parse_string()
+        .end();
+    let storage = location.and_then(|location| {
+        CheckpointSyncerConf::from_str(location)
+            .take_err(&mut err, || &p.cwp + "db_backup_location")
+    });
+    let interval = p
+        .chain(&mut err)
+        .get_opt_key("dbBackupInterval")
+        .parse_u64()
+        .end()
+        .map(Duration::from_secs);
+    let retained_backups = match p
+        .chain(&mut err)
+        .get_opt_key("dbBackupRetention")
+        .parse_u64()
+        .end()
+    {
+        Some(0) => Err(eyre!("At least one db backup must be retained"))
+            .take_err(&mut err, || &p.cwp + "db_backup_retention"),
+        retention => retention.map(|retention| retention as usize),
+    };
+    let api_token = parse_api_token(p, "dbBackupApiToken", &mut err);
+
+    err.into_result(storage.map(|storage| DbBackupConf {
+        storage,
+        interval,
+        retained_backups: retained_backups.unwrap_or(DEFAULT_RETAINED_DB_BACKUPS),
+        api_token,
+    }))
+}
+
 /// Expects AgentSigner.
 fn parse_cosmos_gas_price(gas_price: ValueParser) -> ConfigResult<RawCosmosAmount> {
     let mut err = ConfigParsingError::default();
@@ -466,3 +602,33 @@ fn parse_base_and_override_urls(
     }
     combined
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn block_finality(val: Value) -> ConfigResult<BlockFinality> {
+        parse_block_finality(ValueParser::new(Default::default(), &val))
+    }
+
+    #[test]
+    fn test_parse_block_finality() {
+        assert_eq!(
+            block_finality(json!("finalized")).unwrap(),
+            BlockFinality::Finalized
+        );
+        assert_eq!(
+            block_finality(json!(12)).unwrap(),
+            BlockFinality::Confirmations(NonZeroU64::new(12).unwrap())
+        );
+        assert_eq!(
+            block_finality(json!("12")).unwrap(),
+            BlockFinality::Confirmations(NonZeroU64::new(12).unwrap())
+        );
+        assert!(block_finality(json!(0)).is_err());
+        assert!(block_finality(json!("safe")).is_err());
+        assert!(block_finality(json!(-1)).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use std::{fmt::Debug, path::Path};
+
+use async_trait::async_trait;
+use eyre::Result;
+
+/// A generic trait to read/write database backups offchain
+#[async_trait]
+pub trait BackupStorage: Debug + Send + Sync {
+    /// Write the file at `path`, relative to the root of the backups
+    async fn write_backup_file(&self, path: &str, data: Vec<u8>) -> Result<()>;
+    /// Upload the local file at `source` to `path`, relative to the root of the
+    /// backups, without reading the whole file into memory
+    async fn upload_backup_file(&self, path: &str, source: &Path) -> Result<()>;
+    /// Read the file at `path`, relative to the root of the backups, if it exists
+    async fn read_backup_file(&self, path: &str) -> Result<Option<Vec<u8>>>;
+    /// Delete the file at `path`, relative to the root of the backups. Deleting
+    /// a file which doesn't exist isn't an error.
+    async fn delete_backup_file(&self, path: &str) -> Result<()>;
+}
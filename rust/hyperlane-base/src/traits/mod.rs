@@ -1,3 +1,5 @@
+mod backup_storage;
 mod checkpoint_syncer;
 
+pub use backup_storage::*;
 pub use checkpoint_syncer::*;
@@ -1,9 +1,10 @@
-use crate::CheckpointSyncer;
+use crate::{BackupStorage, CheckpointSyncer};
 use async_trait::async_trait;
 use derive_new::new;
-use eyre::{bail, Result};
+use eyre::{bail, Context, Result};
 use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
-use std::fmt;
+use std::{fmt, path::Path};
+use tokio_util::io::ReaderStream;
 use ya_gcp::{storage::StorageClient, AuthFlow, ClientBuilder, ClientBuilderConfig};
 
 const LATEST_INDEX_KEY: &str = "gcsLatestIndexKey";
@@ -101,6 +102,9 @@ impl GcsStorageClient {
     fn get_checkpoint_key(index: u32) -> String {
         format!("checkpoint_{index}_with_id.json")
     }
+    fn get_backup_key(path: &str) -> String {
+        format!("db_backups/{path}")
+    }
     // #test only method[s]
     #[cfg(test)]
     pub(crate) async fn get_by_path(&self, path: impl AsRef<str>) -> Result<()> {
@@ -192,6 +196,59 @@ impl CheckpointSyncer for GcsStorageClient {
     }
 }
 
+#[async_trait]
+impl BackupStorage for GcsStorageClient {
+    async fn write_backup_file(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        self.inner
+            .insert_object(&self.bucket, GcsStorageClient::get_backup_key(path), data)
+            .await?;
+        Ok(())
+    }
+
+    /// Streams the file into the request body rather than reading it into memory
+    async fn upload_backup_file(&self, path: &str, source: &Path) -> Result<()> {
+        let file = tokio::fs::File::open(source)
+            .await
+            .with_context(|| format!("Opening {source:?}"))?;
+        self.inner
+            .insert_object(
+                &self.bucket,
+                GcsStorageClient::get_backup_key(path),
+                hyper::Body::wrap_stream(ReaderStream::new(file)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn read_backup_file(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        match self
+            .inner
+            .get_object(&self.bucket, GcsStorageClient::get_backup_key(path))
+            .await
+        {
+            Ok(data) => Ok(Some(data.as_ref().to_vec())),
+            Err(e) => match e {
+                // never written before to this bucket
+                ya_gcp::storage::ObjectError::InvalidName(_) => Ok(None),
+                _ => bail!(e),
+            },
+        }
+    }
+
+    async fn delete_backup_file(&self, path: &str) -> Result<()> {
+        match self
+            .inner
+            .delete_object(&self.bucket, GcsStorageClient::get_backup_key(path))
+            .await
+        {
+            Ok(_) => Ok(()),
+            // never written before to this bucket
+            Err(ya_gcp::storage::ObjectError::InvalidName(_)) => Ok(()),
+            Err(e) => bail!(e),
+        }
+    }
+}
+
 #[tokio::test]
 async fn public_landset_no_auth_works_test() {
     const LANDSAT_BUCKET: &str = "gcp-public-data-landsat";
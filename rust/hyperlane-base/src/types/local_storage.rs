@@ -1,11 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 use eyre::{Context, Result};
 use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
 use prometheus::IntGauge;
 
-use crate::traits::CheckpointSyncer;
+use crate::traits::{BackupStorage, CheckpointSyncer};
 
 #[derive(Debug, Clone)]
 /// Type for reading/write to LocalStorage
@@ -40,6 +40,10 @@ impl LocalStorage {
     fn announcement_file_path(&self) -> PathBuf {
         self.path.join("announcement.json")
     }
+
+    fn backup_file_path(&self, path: &str) -> PathBuf {
+        self.path.join("db_backups").join(path)
+    }
 }
 
 #[async_trait]
@@ -104,3 +108,55 @@ impl CheckpointSyncer for LocalStorage {
         format!("file://{}", self.path.to_str().unwrap())
     }
 }
+
+#[async_trait]
+impl BackupStorage for LocalStorage {
+    async fn write_backup_file(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.backup_file_path(path);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Creating backup directory {dir:?}"))?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .with_context(|| format!("Writing backup file to {path:?}"))?;
+        Ok(())
+    }
+
+    async fn upload_backup_file(&self, path: &str, source: &Path) -> Result<()> {
+        let path = self.backup_file_path(path);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Creating backup directory {dir:?}"))?;
+        }
+        tokio::fs::copy(source, &path)
+            .await
+            .with_context(|| format!("Copying {source:?} to backup file {path:?}"))?;
+        Ok(())
+    }
+
+    async fn read_backup_file(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.backup_file_path(path)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete_backup_file(&self, path: &str) -> Result<()> {
+        let path = self.backup_file_path(path);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err).with_context(|| format!("Deleting backup file {path:?}")),
+        }
+        // Backups are stored in a directory each, which is removed along with
+        // its last file
+        if let Some(dir) = path.parent() {
+            let _ = tokio::fs::remove_dir(dir).await;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
-use std::{fmt, sync::OnceLock, time::Duration};
+use std::{fmt, path::Path, sync::OnceLock, time::Duration};
 
 use async_trait::async_trait;
 use derive_new::new;
-use eyre::{bail, Result};
+use eyre::{bail, eyre, Context, Result};
 use futures_util::TryStreamExt;
 use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
 use prometheus::IntGauge;
@@ -10,16 +10,28 @@ use rusoto_core::{
     credential::{Anonymous, AwsCredentials, StaticProvider},
     Region, RusotoError,
 };
-use rusoto_s3::{GetObjectError, GetObjectRequest, PutObjectRequest, S3Client, S3};
-use tokio::time::timeout;
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
+    CompletedPart, CreateMultipartUploadRequest, DeleteObjectRequest, GetObjectError,
+    GetObjectRequest, PutObjectRequest, S3Client, UploadPartRequest, S3,
+};
+use tokio::{io::AsyncReadExt, time::timeout};
 
 use crate::types::utils;
-use crate::{settings::aws_credentials::AwsChainCredentialsProvider, CheckpointSyncer};
+use crate::{
+    settings::aws_credentials::AwsChainCredentialsProvider, BackupStorage, CheckpointSyncer,
+};
 
 /// The timeout for S3 requests. Rusoto doesn't offer timeout configuration
 /// out of the box, so S3 requests must be wrapped with a timeout.
 /// See https://github.com/rusoto/rusoto/issues/1795.
 const S3_REQUEST_TIMEOUT_SECONDS: u64 = 30;
+/// The timeout for requests of database backup files, which can be much larger
+/// than checkpoints.
+const S3_BACKUP_REQUEST_TIMEOUT_SECONDS: u64 = 10 * 60;
+/// The size of the parts database backup files are uploaded in, which bounds
+/// the memory an upload takes. S3 allows at most 10,000 parts per upload.
+const S3_BACKUP_PART_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Clone, new)]
 /// Type for reading/writing to S3
@@ -69,16 +81,26 @@ impl S3Storage {
 
     /// Uses an anonymous client. This should only be used for publicly accessible buckets.
     async fn anonymously_read_from_bucket(&self, key: String) -> Result<Option<Vec<u8>>> {
+        self.read_from_bucket_with(
+            self.anonymous_client(),
+            key,
+            Duration::from_secs(S3_REQUEST_TIMEOUT_SECONDS),
+        )
+        .await
+    }
+
+    async fn read_from_bucket_with(
+        &self,
+        client: &S3Client,
+        key: String,
+        request_timeout: Duration,
+    ) -> Result<Option<Vec<u8>>> {
         let req = GetObjectRequest {
             key: self.get_composite_key(key),
             bucket: self.bucket.clone(),
             ..Default::default()
         };
-        let get_object_result = timeout(
-            Duration::from_secs(S3_REQUEST_TIMEOUT_SECONDS),
-            self.anonymous_client().get_object(req),
-        )
-        .await?;
+        let get_object_result = timeout(request_timeout, client.get_object(req)).await?;
 
         match get_object_result {
             Ok(res) => match res.body {
@@ -139,6 +161,59 @@ impl S3Storage {
     fn announcement_key() -> String {
         "announcement.json".to_owned()
     }
+
+    fn backup_key(path: &str) -> String {
+        format!("db_backups/{path}")
+    }
+
+    /// Uploads the parts of a multipart upload, returning them so that the
+    /// upload can be completed.
+    async fn upload_backup_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        source: &Path,
+    ) -> Result<Vec<CompletedPart>> {
+        let mut file = tokio::fs::File::open(source)
+            .await
+            .with_context(|| format!("Opening {source:?}"))?;
+        let mut parts = vec![];
+        loop {
+            let mut part = Vec::with_capacity(S3_BACKUP_PART_SIZE);
+            (&mut file)
+                .take(S3_BACKUP_PART_SIZE as u64)
+                .read_to_end(&mut part)
+                .await?;
+            // An empty file is still uploaded as a single empty part
+            if part.is_empty() && !parts.is_empty() {
+                break;
+            }
+            let is_last = part.len() < S3_BACKUP_PART_SIZE;
+            let part_number = parts.len() as i64 + 1;
+            let req = UploadPartRequest {
+                key: key.to_owned(),
+                bucket: self.bucket.clone(),
+                upload_id: upload_id.to_owned(),
+                part_number,
+                content_length: Some(part.len() as i64),
+                body: Some(part.into()),
+                ..Default::default()
+            };
+            let res = timeout(
+                Duration::from_secs(S3_BACKUP_REQUEST_TIMEOUT_SECONDS),
+                self.authenticated_client().upload_part(req),
+            )
+            .await??;
+            parts.push(CompletedPart {
+                e_tag: res.e_tag,
+                part_number: Some(part_number),
+            });
+            if is_last {
+                break;
+            }
+        }
+        Ok(parts)
+    }
 }
 
 #[async_trait]
@@ -204,3 +279,97 @@ impl CheckpointSyncer for S3Storage {
         }
     }
 }
+
+#[async_trait]
+impl BackupStorage for S3Storage {
+    async fn write_backup_file(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        let req = PutObjectRequest {
+            key: self.get_composite_key(S3Storage::backup_key(path)),
+            bucket: self.bucket.clone(),
+            body: Some(data.into()),
+            content_type: Some("application/octet-stream".to_owned()),
+            ..Default::default()
+        };
+        timeout(
+            Duration::from_secs(S3_BACKUP_REQUEST_TIMEOUT_SECONDS),
+            self.authenticated_client().put_object(req),
+        )
+        .await??;
+        Ok(())
+    }
+
+    /// Uploads the file in parts with a multipart upload, which is aborted if
+    /// any part fails so that no incomplete parts are left in the bucket.
+    async fn upload_backup_file(&self, path: &str, source: &Path) -> Result<()> {
+        let key = self.get_composite_key(S3Storage::backup_key(path));
+        let req = CreateMultipartUploadRequest {
+            key: key.clone(),
+            bucket: self.bucket.clone(),
+            content_type: Some("application/octet-stream".to_owned()),
+            ..Default::default()
+        };
+        let upload_id = timeout(
+            Duration::from_secs(S3_REQUEST_TIMEOUT_SECONDS),
+            self.authenticated_client().create_multipart_upload(req),
+        )
+        .await??
+        .upload_id
+        .ok_or_else(|| eyre!("No upload id for multipart upload of {key}"))?;
+
+        let parts = match self.upload_backup_parts(&key, &upload_id, source).await {
+            Ok(parts) => parts,
+            Err(err) => {
+                let req = AbortMultipartUploadRequest {
+                    key,
+                    bucket: self.bucket.clone(),
+                    upload_id,
+                    ..Default::default()
+                };
+                let _ = timeout(
+                    Duration::from_secs(S3_REQUEST_TIMEOUT_SECONDS),
+                    self.authenticated_client().abort_multipart_upload(req),
+                )
+                .await;
+                return Err(err);
+            }
+        };
+
+        let req = CompleteMultipartUploadRequest {
+            key,
+            bucket: self.bucket.clone(),
+            upload_id,
+            multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+            ..Default::default()
+        };
+        timeout(
+            Duration::from_secs(S3_BACKUP_REQUEST_TIMEOUT_SECONDS),
+            self.authenticated_client().complete_multipart_upload(req),
+        )
+        .await??;
+        Ok(())
+    }
+
+    /// Backups are private, so they're read with the authenticated client.
+    async fn read_backup_file(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        self.read_from_bucket_with(
+            self.authenticated_client(),
+            S3Storage::backup_key(path),
+            Duration::from_secs(S3_BACKUP_REQUEST_TIMEOUT_SECONDS),
+        )
+        .await
+    }
+
+    async fn delete_backup_file(&self, path: &str) -> Result<()> {
+        let req = DeleteObjectRequest {
+            key: self.get_composite_key(S3Storage::backup_key(path)),
+            bucket: self.bucket.clone(),
+            ..Default::default()
+        };
+        timeout(
+            Duration::from_secs(S3_REQUEST_TIMEOUT_SECONDS),
+            self.authenticated_client().delete_object(req),
+        )
+        .await??;
+        Ok(())
+    }
+}
@@ -199,6 +199,7 @@ impl HyperlaneDomainProtocol {
 )]
 pub enum HyperlaneDomainTechnicalStack {
     ArbitrumNitro,
+    OpStack,
     #[default]
     Other,
 }
@@ -245,8 +246,9 @@ impl KnownHyperlaneDomain {
 
         many_to_one!(match self {
             HyperlaneDomainTechnicalStack::ArbitrumNitro: [Arbitrum, PlumeTestnet],
+            HyperlaneDomainTechnicalStack::OpStack: [Optimism],
             HyperlaneDomainTechnicalStack::Other: [
-                Ethereum, Sepolia, Polygon, Avalanche, Fuji, Optimism,
+                Ethereum, Sepolia, Polygon, Avalanche, Fuji,
                 BinanceSmartChain, BinanceSmartChainTestnet, Celo, Gnosis, Alfajores, Moonbeam, MoonbaseAlpha,
                 ScrollSepolia, Chiado, MantaPacific, Neutron, Injective, InEvm,
                 Test1, Test2, Test3, FuelTest1, SealevelTest1, SealevelTest2, CosmosTest99990, CosmosTest99991
@@ -422,6 +424,13 @@ impl HyperlaneDomain {
         )
     }
 
+    pub const fn is_op_stack(&self) -> bool {
+        matches!(
+            self.domain_technical_stack(),
+            HyperlaneDomainTechnicalStack::OpStack
+        )
+    }
+
     pub const fn is_injective(&self) -> bool {
         matches!(self, Self::Known(KnownHyperlaneDomain::Injective))
     }
@@ -17,9 +17,16 @@ use crate::{Error as PrimitiveTypeError, HyperlaneSignerError, H256, U256};
 pub type ChainResult<T> = Result<T, ChainCommunicationError>;
 
 /// An "Any"-typed error.
-pub trait HyperlaneCustomError: StdError + Send + Sync + Any {}
+pub trait HyperlaneCustomError: StdError + Send + Sync + Any {
+    /// Get the error as `Any`, so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
 
-impl<E: StdError + Send + Sync + Any> HyperlaneCustomError for E {}
+impl<E: StdError + Send + Sync + Any> HyperlaneCustomError for E {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
 /// Thin wrapper around a boxed HyperlaneCustomError; required to satisfy
 /// AsDynError implementations. Basically a trait-object adaptor.
@@ -27,6 +34,13 @@ impl<E: StdError + Send + Sync + Any> HyperlaneCustomError for E {}
 #[derive(new)]
 pub struct HyperlaneCustomErrorWrapper(Box<dyn HyperlaneCustomError>);
 
+impl HyperlaneCustomErrorWrapper {
+    /// Get the wrapped error if it is of type `E`
+    pub fn downcast_ref<E: HyperlaneCustomError>(&self) -> Option<&E> {
+        self.0.as_ref().as_any().downcast_ref()
+    }
+}
+
 impl Debug for HyperlaneCustomErrorWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", AsRef::<dyn HyperlaneCustomError>::as_ref(&self))
@@ -82,6 +96,9 @@ pub enum ChainCommunicationError {
     /// No signer is available and was required for the operation
     #[error("Signer unavailable")]
     SignerUnavailable,
+    /// The chain doesn't support the operation
+    #[error("{0} is not supported on this chain")]
+    Unsupported(&'static str),
     /// Batching transaction failed
     #[error("Batching transaction failed")]
     BatchingFailed,
@@ -2,6 +2,7 @@ use async_rwlock::RwLock;
 use async_trait::async_trait;
 use derive_new::new;
 use itertools::Itertools;
+use prometheus::{GaugeVec, IntGaugeVec};
 use std::{
     fmt::{Debug, Formatter},
     future::Future,
@@ -11,7 +12,7 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio;
-use tracing::{info, trace, warn_span};
+use tracing::{info, trace, warn, warn_span};
 
 use crate::ChainCommunicationError;
 
@@ -26,6 +27,193 @@ pub trait BlockNumberGetter: Send + Sync + Debug {
 
 const MAX_BLOCK_TIME: Duration = Duration::from_secs(2 * 60);
 
+/// Weight given to the latest sample when updating a provider's rolling latency and error rate.
+const HEALTH_SMOOTHING_FACTOR: f64 = 0.2;
+/// Latency penalty, in seconds, of a provider whose requests all fail. Added to the rolling
+/// latency in proportion to the rolling error rate when scoring a provider.
+const ERROR_RATE_PENALTY_SECS: f64 = 5.;
+/// Providers whose scores are within the same bucket of this size keep their relative
+/// priority, so that small differences in latency don't cause providers to be reordered.
+const SCORE_BUCKET_SECS: f64 = 0.1;
+/// Consecutive failures after which a provider's circuit opens, benching it.
+const CIRCUIT_BREAKER_THRESHOLD: u32 = 3;
+/// How long a provider is benched for before a single probe request is sent to it.
+const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// The state of a provider's circuit breaker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The provider is healthy and used in order of its score.
+    Closed,
+    /// The provider failed too many times in a row and is benched until the given time.
+    Open {
+        /// When the provider may be probed again
+        until: Instant,
+    },
+    /// The provider's cooldown elapsed and a single probe request was sent to it, which
+    /// decides whether it's readmitted or benched again.
+    HalfOpen {
+        /// When the probe was sent
+        probing_since: Instant,
+    },
+}
+
+impl CircuitState {
+    fn metric_value(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen { .. } => 1,
+            CircuitState::Open { .. } => 2,
+        }
+    }
+}
+
+/// Rolling latency and error rate of a provider, along with its circuit breaker.
+#[derive(Clone, Copy, Debug)]
+pub struct ProviderHealth {
+    /// Rolling latency of successful requests, in seconds. None until a request succeeds.
+    latency_secs: Option<f64>,
+    /// Rolling rate of failed requests, from 0 to 1.
+    error_rate: f64,
+    /// Number of requests that failed since the last successful one.
+    consecutive_failures: u32,
+    /// Whether the provider is benched.
+    circuit: CircuitState,
+}
+
+impl Default for ProviderHealth {
+    fn default() -> Self {
+        Self {
+            latency_secs: None,
+            error_rate: 0.,
+            consecutive_failures: 0,
+            circuit: CircuitState::Closed,
+        }
+    }
+}
+
+impl ProviderHealth {
+    /// Lower is better. Providers without any successful requests yet are scored
+    /// optimistically, so that they get a chance to be measured.
+    fn score(&self) -> f64 {
+        self.latency_secs.unwrap_or_default() + self.error_rate * ERROR_RATE_PENALTY_SECS
+    }
+
+    fn score_bucket(&self) -> u64 {
+        (self.score() / SCORE_BUCKET_SECS) as u64
+    }
+
+    fn record_success(&mut self, latency: Duration) {
+        let latency = latency.as_secs_f64();
+        self.latency_secs = Some(match self.latency_secs {
+            Some(rolling) => smooth(rolling, latency),
+            None => latency,
+        });
+        self.error_rate = smooth(self.error_rate, 0.);
+        self.consecutive_failures = 0;
+        self.circuit = CircuitState::Closed;
+    }
+
+    fn record_failure(&mut self) {
+        self.error_rate = smooth(self.error_rate, 1.);
+        self.consecutive_failures += 1;
+        let should_open = match self.circuit {
+            CircuitState::Closed => self.consecutive_failures >= CIRCUIT_BREAKER_THRESHOLD,
+            // A failed probe benches the provider again straight away.
+            CircuitState::HalfOpen { .. } => true,
+            CircuitState::Open { .. } => false,
+        };
+        if should_open {
+            self.circuit = CircuitState::Open {
+                until: Instant::now() + CIRCUIT_BREAKER_COOLDOWN,
+            };
+        }
+    }
+
+    /// Whether the provider can be sent a probe request, either because its cooldown
+    /// elapsed or because a previous probe never completed.
+    fn can_probe(&self, now: Instant) -> bool {
+        match self.circuit {
+            CircuitState::Closed => false,
+            CircuitState::Open { until } => until <= now,
+            CircuitState::HalfOpen { probing_since } => {
+                now.duration_since(probing_since) >= CIRCUIT_BREAKER_COOLDOWN
+            }
+        }
+    }
+}
+
+fn smooth(rolling: f64, sample: f64) -> f64 {
+    rolling * (1. - HEALTH_SMOOTHING_FACTOR) + sample * HEALTH_SMOOTHING_FACTOR
+}
+
+/// Prometheus metrics describing the health of the sub-providers of a `FallbackProvider`.
+/// To make this as flexible as possible, the metric vecs need to be created and named
+/// externally, and must include the described labels.
+#[derive(Clone, Debug, Default)]
+pub struct FallbackProviderMetrics {
+    /// Rolling latency of successful requests, in seconds.
+    /// - `provider_node`: node the sub-provider is connecting to.
+    /// - `chain`: chain the sub-provider is connected to.
+    pub latency_seconds: Option<GaugeVec>,
+    /// Rolling rate of failed requests, from 0 to 1.
+    /// - `provider_node`: node the sub-provider is connecting to.
+    /// - `chain`: chain the sub-provider is connected to.
+    pub error_rate: Option<GaugeVec>,
+    /// State of the sub-provider's circuit breaker: 0 if closed, 1 if half-open and 2 if open.
+    /// - `provider_node`: node the sub-provider is connecting to.
+    /// - `chain`: chain the sub-provider is connected to.
+    pub circuit_state: Option<IntGaugeVec>,
+}
+
+/// Expected label names for the fallback provider metrics.
+pub const FALLBACK_PROVIDER_METRICS_LABELS: &[&str] = &["provider_node", "chain"];
+
+/// Metrics along with the labels identifying each sub-provider.
+struct LabelledFallbackProviderMetrics {
+    metrics: FallbackProviderMetrics,
+    chain_name: String,
+    /// The `provider_node` label of each sub-provider, by index.
+    provider_nodes: Vec<String>,
+}
+
+impl LabelledFallbackProviderMetrics {
+    fn report(&self, priority: &PrioritizedProviderInner) {
+        let Some(provider_node) = self.provider_nodes.get(priority.index) else {
+            return;
+        };
+        let labels = [provider_node.as_str(), self.chain_name.as_str()];
+        let health = &priority.health;
+        if let (Some(gauge), Some(latency_secs)) =
+            (&self.metrics.latency_seconds, health.latency_secs)
+        {
+            gauge.with_label_values(&labels).set(latency_secs);
+        }
+        if let Some(gauge) = &self.metrics.error_rate {
+            gauge.with_label_values(&labels).set(health.error_rate);
+        }
+        if let Some(gauge) = &self.metrics.circuit_state {
+            gauge
+                .with_label_values(&labels)
+                .set(health.circuit.metric_value());
+        }
+    }
+}
+
+/// Whether an error returned by a sub-provider is an error of the provider, e.g. a connection
+/// failure, rather than of the request, e.g. a reverting call. Only errors of the provider
+/// count against its health and are retried with the other providers.
+pub type ProviderErrorClassifier = fn(&ChainCommunicationError) -> bool;
+
+/// The default `ProviderErrorClassifier`, which treats contract errors and invalid requests
+/// as errors of the request, and any other error as an error of the provider.
+pub fn is_provider_error(err: &ChainCommunicationError) -> bool {
+    !matches!(
+        err,
+        ChainCommunicationError::ContractError(_) | ChainCommunicationError::InvalidRequest { .. }
+    )
+}
+
 /// Information about a provider in `PrioritizedProviders`
 
 #[derive(Clone, Copy, new)]
@@ -35,27 +223,28 @@ pub struct PrioritizedProviderInner {
     /// Tuple of the block number and the time when it was queried
     #[new(value = "(0, Instant::now())")]
     last_block_height: (u64, Instant),
+    /// Rolling health of the provider
+    #[new(default)]
+    health: ProviderHealth,
 }
 
-impl PrioritizedProviderInner {
-    fn from_block_height(index: usize, block_height: u64) -> Self {
-        Self {
-            index,
-            last_block_height: (block_height, Instant::now()),
-        }
-    }
-}
 /// Sub-providers and priority information
 pub struct PrioritizedProviders<T> {
     /// Unsorted list of providers this provider calls
     pub providers: Vec<T>,
     /// Sorted list of providers this provider calls, in descending order or reliability
     pub priorities: RwLock<Vec<PrioritizedProviderInner>>,
+    metrics: Option<LabelledFallbackProviderMetrics>,
 }
 
 /// A provider that bundles multiple providers and attempts to call the first,
 /// then the second, and so on until a response is received.
 ///
+/// Providers are ordered by their rolling latency and error rate. A provider that fails
+/// too many times in a row is benched for a cooldown, after which a single probe request
+/// decides whether it's readmitted. Providers whose block height stops increasing are
+/// deprioritized as well.
+///
 /// Although no trait bounds are used in the struct definition, the intended purpose of `B`
 /// is to be bound by `BlockNumberGetter` and have `T` be convertible to `B`. That is,
 /// inner providers should be able to get the current block number, or be convertible into
@@ -64,6 +253,7 @@ pub struct FallbackProvider<T, B> {
     /// The sub-providers called by this provider
     pub inner: Arc<PrioritizedProviders<T>>,
     max_block_time: Duration,
+    is_provider_error: ProviderErrorClassifier,
     _phantom: PhantomData<B>,
 }
 
@@ -72,6 +262,7 @@ impl<T, B> Clone for FallbackProvider<T, B> {
         Self {
             inner: self.inner.clone(),
             max_block_time: self.max_block_time,
+            is_provider_error: self.is_provider_error,
             _phantom: PhantomData,
         }
     }
@@ -113,26 +304,114 @@ where
         Self::builder().add_providers(providers).build()
     }
 
-    async fn deprioritize_provider(&self, priority: PrioritizedProviderInner) {
+    async fn deprioritize_provider(&self, provider_index: usize) {
         // De-prioritize the current provider by moving it to the end of the queue
         let mut priorities = self.inner.priorities.write().await;
-        priorities.retain(|&p| p.index != priority.index);
-        priorities.push(priority);
+        if let Some(position) = priorities.iter().position(|p| p.index == provider_index) {
+            let mut priority = priorities.remove(position);
+            // A stalled provider is as unhelpful as a failing one
+            priority.health.record_failure();
+            self.report_metrics(&priority);
+            priorities.push(priority);
+        }
     }
 
     async fn update_last_seen_block(&self, provider_index: usize, current_block_height: u64) {
         let mut priorities = self.inner.priorities.write().await;
         // Get provider position in the up-to-date priorities vec
-        if let Some(position) = priorities.iter().position(|p| p.index == provider_index) {
-            priorities[position] =
-                PrioritizedProviderInner::from_block_height(provider_index, current_block_height);
+        if let Some(priority) = priorities.iter_mut().find(|p| p.index == provider_index) {
+            priority.last_block_height = (current_block_height, Instant::now());
         }
     }
 
-    /// Used to iterate the providers in a non-blocking way
+    /// Used to iterate the providers in a non-blocking way.
+    ///
+    /// Benched providers are left out of the snapshot, except for at most one whose cooldown
+    /// elapsed, which is placed first so that it's sent a probe request. If every provider is
+    /// benched, they're all returned so that requests are still attempted.
     pub async fn take_priorities_snapshot(&self) -> Vec<PrioritizedProviderInner> {
-        let read_lock = self.inner.priorities.read().await;
-        (*read_lock).clone()
+        let now = Instant::now();
+        {
+            // Most of the time no provider is due a probe, so a read lock is enough
+            let priorities = self.inner.priorities.read().await;
+            if !priorities.iter().any(|p| p.health.can_probe(now)) {
+                let snapshot: Vec<_> = priorities
+                    .iter()
+                    .filter(|p| p.health.circuit == CircuitState::Closed)
+                    .copied()
+                    .collect();
+                if snapshot.is_empty() {
+                    return priorities.clone();
+                }
+                return snapshot;
+            }
+        }
+
+        let mut priorities = self.inner.priorities.write().await;
+        let mut probe = None;
+        let mut snapshot = vec![];
+        for priority in priorities.iter_mut() {
+            if priority.health.circuit == CircuitState::Closed {
+                snapshot.push(*priority);
+            } else if probe.is_none() && priority.health.can_probe(now) {
+                priority.health.circuit = CircuitState::HalfOpen { probing_since: now };
+                self.report_metrics(priority);
+                probe = Some(*priority);
+            }
+        }
+        if let Some(probe) = probe {
+            info!(
+                provider_index=%probe.index,
+                provider=?self.inner.providers[probe.index],
+                "Probing a benched inner provider in FallbackProvider",
+            );
+            snapshot.insert(0, probe);
+        }
+        if snapshot.is_empty() {
+            return priorities.clone();
+        }
+        snapshot
+    }
+
+    /// Record the outcome of a request to a provider, updating its rolling latency and error
+    /// rate, its circuit breaker, and its priority accordingly.
+    ///
+    /// Errors that say nothing about the provider's health, e.g. a reverting call, should be
+    /// recorded as successes.
+    pub async fn record_response(&self, provider_index: usize, latency: Duration, success: bool) {
+        let mut priorities = self.inner.priorities.write().await;
+        let Some(priority) = priorities.iter_mut().find(|p| p.index == provider_index) else {
+            return;
+        };
+        let was_closed = priority.health.circuit == CircuitState::Closed;
+        if success {
+            priority.health.record_success(latency);
+        } else {
+            priority.health.record_failure();
+        }
+        match (was_closed, priority.health.circuit) {
+            (true, CircuitState::Open { .. }) => warn!(
+                provider_index,
+                provider=?self.inner.providers[provider_index],
+                consecutive_failures=priority.health.consecutive_failures,
+                "Benching an inner provider in FallbackProvider",
+            ),
+            (false, CircuitState::Closed) => info!(
+                provider_index,
+                provider=?self.inner.providers[provider_index],
+                "Readmitting an inner provider in FallbackProvider",
+            ),
+            _ => {}
+        }
+        self.report_metrics(priority);
+        // Stable sort, so providers with similar scores keep their relative priority
+        priorities.sort_by_key(|p| p.health.score_bucket());
+    }
+
+    fn report_metrics(&self, priority: &PrioritizedProviderInner) {
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.report(priority);
+        }
     }
 
     /// De-prioritize a provider that has either timed out or returned a bad response
@@ -153,7 +432,7 @@ where
             .unwrap_or(priority.last_block_height.0);
         if current_block_height <= priority.last_block_height.0 {
             // The `max_block_time` elapsed but the block number returned by the provider has not increased
-            self.deprioritize_provider(*priority).await;
+            self.deprioritize_provider(priority.index).await;
             info!(
                 provider_index=%priority.index,
                 provider=?self.inner.providers[priority.index],
@@ -180,12 +459,19 @@ where
             let priorities_snapshot = self.take_priorities_snapshot().await;
             for (idx, priority) in priorities_snapshot.iter().enumerate() {
                 let provider = &self.inner.providers[priority.index];
+                let start = Instant::now();
                 let resp = f(provider.clone()).await;
+                // Errors of the request rather than of the provider, e.g. reverts, say nothing
+                // about the provider's health and wouldn't be any different with another one.
+                let provider_failed = matches!(&resp, Err(e) if (self.is_provider_error)(e));
+                self.record_response(priority.index, start.elapsed(), !provider_failed)
+                    .await;
                 self.handle_stalled_provider(priority, provider).await;
                 let _span =
                     warn_span!("FallbackProvider::call", fallback_count=%idx, provider_index=%priority.index, ?provider).entered();
                 match resp {
                     Ok(v) => return Ok(v),
+                    Err(e) if !provider_failed => return Err(e),
                     Err(e) => {
                         trace!(
                             error=?e,
@@ -206,6 +492,8 @@ where
 pub struct FallbackProviderBuilder<T, B> {
     providers: Vec<T>,
     max_block_time: Duration,
+    metrics: Option<(FallbackProviderMetrics, String, fn(&T) -> String)>,
+    is_provider_error: ProviderErrorClassifier,
     _phantom: PhantomData<B>,
 }
 
@@ -214,6 +502,8 @@ impl<T, B> Default for FallbackProviderBuilder<T, B> {
         Self {
             providers: Vec::new(),
             max_block_time: MAX_BLOCK_TIME,
+            metrics: None,
+            is_provider_error,
             _phantom: PhantomData,
         }
     }
@@ -240,9 +530,35 @@ impl<T, B> FallbackProviderBuilder<T, B> {
         self
     }
 
+    /// Report the health of each provider with the given metrics, labelling them with the
+    /// chain name and the node returned by `provider_node`.
+    pub fn with_metrics(
+        mut self,
+        metrics: FallbackProviderMetrics,
+        chain_name: impl Into<String>,
+        provider_node: fn(&T) -> String,
+    ) -> Self {
+        self.metrics = Some((metrics, chain_name.into(), provider_node));
+        self
+    }
+
+    /// Tell errors of the sub-providers apart from errors of the requests with the given
+    /// classifier, instead of the default `is_provider_error`.
+    pub fn with_error_classifier(mut self, is_provider_error: ProviderErrorClassifier) -> Self {
+        self.is_provider_error = is_provider_error;
+        self
+    }
+
     /// Create a fallback provider.
     pub fn build(self) -> FallbackProvider<T, B> {
         let provider_count = self.providers.len();
+        let metrics = self.metrics.map(|(metrics, chain_name, provider_node)| {
+            LabelledFallbackProviderMetrics {
+                metrics,
+                chain_name,
+                provider_nodes: self.providers.iter().map(provider_node).collect(),
+            }
+        });
         let prioritized_providers = PrioritizedProviders {
             providers: self.providers,
             // The order of `self.providers` gives the initial priority.
@@ -251,10 +567,12 @@ impl<T, B> FallbackProviderBuilder<T, B> {
                     .map(PrioritizedProviderInner::new)
                     .collect(),
             ),
+            metrics,
         };
         FallbackProvider {
             inner: Arc::new(prioritized_providers),
             max_block_time: self.max_block_time,
+            is_provider_error: self.is_provider_error,
             _phantom: PhantomData,
         }
     }
@@ -332,3 +650,213 @@ pub mod test {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use super::test::ProviderMock;
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct BlockProviderMock(ProviderMock);
+
+    impl Deref for BlockProviderMock {
+        type Target = ProviderMock;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    #[async_trait]
+    impl BlockNumberGetter for BlockProviderMock {
+        async fn get_block_number(&self) -> Result<u64, ChainCommunicationError> {
+            Ok(0)
+        }
+    }
+
+    fn fallback_provider(count: usize) -> FallbackProvider<BlockProviderMock, BlockProviderMock> {
+        FallbackProvider::new((0..count).map(|_| BlockProviderMock::default()))
+    }
+
+    async fn circuit(
+        provider: &FallbackProvider<BlockProviderMock, BlockProviderMock>,
+        index: usize,
+    ) -> CircuitState {
+        provider
+            .inner
+            .priorities
+            .read()
+            .await
+            .iter()
+            .find(|p| p.index == index)
+            .unwrap()
+            .health
+            .circuit
+    }
+
+    /// Make the cooldown of a benched provider elapse
+    async fn end_cooldown(
+        provider: &FallbackProvider<BlockProviderMock, BlockProviderMock>,
+        index: usize,
+    ) {
+        let mut priorities = provider.inner.priorities.write().await;
+        let priority = priorities.iter_mut().find(|p| p.index == index).unwrap();
+        priority.health.circuit = CircuitState::Open {
+            until: Instant::now(),
+        };
+    }
+
+    fn snapshot_indices(snapshot: &[PrioritizedProviderInner]) -> Vec<usize> {
+        snapshot.iter().map(|p| p.index).collect()
+    }
+
+    #[test]
+    fn test_circuit_opens_after_consecutive_failures() {
+        let mut health = ProviderHealth::default();
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD - 1 {
+            health.record_failure();
+        }
+        assert_eq!(health.circuit, CircuitState::Closed);
+
+        // A success resets the count of consecutive failures
+        health.record_success(Duration::from_millis(10));
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD - 1 {
+            health.record_failure();
+        }
+        assert_eq!(health.circuit, CircuitState::Closed);
+
+        health.record_failure();
+        assert!(matches!(health.circuit, CircuitState::Open { .. }));
+        assert!(!health.can_probe(Instant::now()));
+        assert!(health.can_probe(Instant::now() + CIRCUIT_BREAKER_COOLDOWN));
+    }
+
+    #[test]
+    fn test_failed_probe_reopens_circuit() {
+        let mut health = ProviderHealth {
+            circuit: CircuitState::HalfOpen {
+                probing_since: Instant::now(),
+            },
+            ..Default::default()
+        };
+        health.record_failure();
+        assert!(matches!(health.circuit, CircuitState::Open { .. }));
+    }
+
+    #[test]
+    fn test_successful_probe_closes_circuit() {
+        let mut health = ProviderHealth {
+            circuit: CircuitState::HalfOpen {
+                probing_since: Instant::now(),
+            },
+            consecutive_failures: CIRCUIT_BREAKER_THRESHOLD,
+            ..Default::default()
+        };
+        health.record_success(Duration::from_millis(10));
+        assert_eq!(health.circuit, CircuitState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_benched_provider_is_probed_after_cooldown() {
+        let provider = fallback_provider(3);
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            provider.record_response(0, Duration::ZERO, false).await;
+        }
+        assert!(matches!(
+            circuit(&provider, 0).await,
+            CircuitState::Open { .. }
+        ));
+        assert_eq!(
+            snapshot_indices(&provider.take_priorities_snapshot().await),
+            vec![1, 2]
+        );
+
+        // Once the cooldown elapsed, the provider is probed first, and only once
+        end_cooldown(&provider, 0).await;
+        assert_eq!(
+            snapshot_indices(&provider.take_priorities_snapshot().await),
+            vec![0, 1, 2]
+        );
+        assert!(matches!(
+            circuit(&provider, 0).await,
+            CircuitState::HalfOpen { .. }
+        ));
+        assert_eq!(
+            snapshot_indices(&provider.take_priorities_snapshot().await),
+            vec![1, 2]
+        );
+
+        // A successful probe readmits the provider
+        provider
+            .record_response(0, Duration::from_millis(10), true)
+            .await;
+        assert_eq!(circuit(&provider, 0).await, CircuitState::Closed);
+        assert_eq!(provider.take_priorities_snapshot().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_benches_provider_again() {
+        let provider = fallback_provider(2);
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            provider.record_response(0, Duration::ZERO, false).await;
+        }
+        end_cooldown(&provider, 0).await;
+        assert_eq!(
+            snapshot_indices(&provider.take_priorities_snapshot().await),
+            vec![0, 1]
+        );
+
+        provider.record_response(0, Duration::ZERO, false).await;
+        assert!(matches!(
+            circuit(&provider, 0).await,
+            CircuitState::Open { .. }
+        ));
+        assert_eq!(
+            snapshot_indices(&provider.take_priorities_snapshot().await),
+            vec![1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_providers_benched_are_still_attempted() {
+        let provider = fallback_provider(2);
+        for index in 0..2 {
+            for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+                provider.record_response(index, Duration::ZERO, false).await;
+            }
+        }
+        assert_eq!(provider.take_priorities_snapshot().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_do_not_count_against_provider() {
+        let provider = fallback_provider(2);
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            let result: Result<(), _> = provider
+                .call(|provider| {
+                    provider.push("call", ());
+                    Box::pin(async {
+                        Err(ChainCommunicationError::InvalidRequest {
+                            msg: "reverted".to_owned(),
+                        })
+                    })
+                })
+                .await;
+            assert!(matches!(
+                result,
+                Err(ChainCommunicationError::InvalidRequest { .. })
+            ));
+        }
+
+        // The error is returned straight away, without retrying other providers, and
+        // the provider stays healthy and first in line.
+        assert_eq!(
+            ProviderMock::get_call_counts(&provider).await,
+            vec![CIRCUIT_BREAKER_THRESHOLD as usize, 0]
+        );
+        assert_eq!(circuit(&provider, 0).await, CircuitState::Closed);
+    }
+}
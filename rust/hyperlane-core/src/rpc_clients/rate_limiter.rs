@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use prometheus::Counter;
+use tokio::time::sleep;
+use tracing::debug;
+
+/// Requests-per-second and burst limits of an RPC endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConf {
+    /// The sustained number of requests per second
+    pub requests_per_second: f64,
+    /// The number of requests which may be sent at once after a quiet period
+    pub burst: u32,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// A token bucket limiting the rate of requests to an RPC endpoint. Requests
+/// are also held back while the endpoint has asked clients to back off, e.g.
+/// with a `Retry-After` header, even if no rate limit is configured.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: Option<RateLimitConf>,
+    state: Mutex<BucketState>,
+    throttled_seconds: Option<Counter>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter, optionally counting the time requests were held
+    /// back for with `throttled_seconds`.
+    pub fn new(limit: Option<RateLimitConf>, throttled_seconds: Option<Counter>) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: limit.map(|l| l.burst.max(1) as f64).unwrap_or_default(),
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+            throttled_seconds,
+        }
+    }
+
+    /// Wait until a request may be sent. Returns how long the request was held
+    /// back for.
+    pub async fn acquire(&self) -> Duration {
+        let mut throttled = Duration::ZERO;
+        while let Some(wait) = self.try_acquire(Instant::now()) {
+            sleep(wait).await;
+            throttled += wait;
+        }
+        if !throttled.is_zero() {
+            if let Some(counter) = &self.throttled_seconds {
+                counter.inc_by(throttled.as_secs_f64());
+            }
+        }
+        throttled
+    }
+
+    /// Hold back all requests for `duration`.
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        if state
+            .paused_until
+            .map_or(true, |paused_until| paused_until < until)
+        {
+            debug!(?duration, "Pausing requests to rate limited endpoint");
+            state.paused_until = Some(until);
+        }
+    }
+
+    /// Take a token if a request may be sent at `now`, otherwise return how
+    /// long to wait before trying again.
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(paused_until) = state.paused_until {
+            if paused_until > now {
+                return Some(paused_until - now);
+            }
+            state.paused_until = None;
+        }
+        let limit = self.limit?;
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.tokens = f64::min(
+            state.tokens + elapsed.as_secs_f64() * limit.requests_per_second,
+            limit.burst.max(1) as f64,
+        );
+        state.last_refill = now;
+        if state.tokens >= 1. {
+            state.tokens -= 1.;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1. - state.tokens) / limit.requests_per_second,
+            ))
+        }
+    }
+}
+
+/// The rate limiters of a chain's RPC endpoints. Clones share the same
+/// limiters, so that all the clients built from the same chain config draw
+/// from the same budget.
+#[derive(Debug, Clone, Default)]
+pub struct RpcRateLimiters {
+    limits: HashMap<String, RateLimitConf>,
+    limiters: Arc<Mutex<HashMap<String, Arc<RateLimiter>>>>,
+}
+
+impl RpcRateLimiters {
+    /// Create rate limiters with the given limits per url.
+    pub fn new(limits: HashMap<String, RateLimitConf>) -> Self {
+        Self {
+            limits,
+            limiters: Default::default(),
+        }
+    }
+
+    /// The rate limiter of the endpoint at `url`. If it doesn't exist yet, it's
+    /// created with the counter returned by `throttled_seconds`.
+    pub fn get(
+        &self,
+        url: &str,
+        throttled_seconds: impl FnOnce() -> Option<Counter>,
+    ) -> Arc<RateLimiter> {
+        self.limiters
+            .lock()
+            .unwrap()
+            .entry(url.to_owned())
+            .or_insert_with(|| {
+                Arc::new(RateLimiter::new(
+                    self.limits.get(url).copied(),
+                    throttled_seconds(),
+                ))
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits_rate() {
+        let limiter = RateLimiter::new(
+            Some(RateLimitConf {
+                requests_per_second: 2.,
+                burst: 2,
+            }),
+            None,
+        );
+        let now = Instant::now();
+        assert_eq!(limiter.try_acquire(now), None);
+        assert_eq!(limiter.try_acquire(now), None);
+        assert_eq!(limiter.try_acquire(now), Some(Duration::from_millis(500)));
+        assert_eq!(limiter.try_acquire(now + Duration::from_millis(500)), None);
+    }
+
+    #[test]
+    fn test_pause_holds_back_unlimited_endpoints() {
+        let limiter = RateLimiter::new(None, None);
+        let now = Instant::now();
+        assert_eq!(limiter.try_acquire(now), None);
+
+        limiter.pause_for(Duration::from_secs(10));
+        assert!(limiter.try_acquire(now).is_some());
+        assert_eq!(limiter.try_acquire(now + Duration::from_secs(11)), None);
+    }
+
+    #[test]
+    fn test_limiters_are_shared_between_clones() {
+        let limiters = RpcRateLimiters::default();
+        let clone = limiters.clone();
+        assert!(Arc::ptr_eq(
+            &limiters.get("http://localhost:8545", || None),
+            &clone.get("http://localhost:8545", || None)
+        ));
+    }
+}
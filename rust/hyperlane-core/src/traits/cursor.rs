@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use auto_impl::auto_impl;
 use eyre::Result;
 
-use crate::{Indexed, LogMeta};
+use crate::{ChainCommunicationError, Indexed, LogMeta};
 
 /// A cursor governs event indexing for a contract.
 #[async_trait]
@@ -29,6 +29,20 @@ pub trait ContractSyncCursor<T>: Send + Sync + 'static {
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
     ) -> Result<()>;
+
+    /// Ingests an error returned when querying the given range, e.g. because the
+    /// provider rejected the range as too large. Returns true if the cursor adjusted
+    /// the next range it will query in response, in which case it can be retried
+    /// immediately.
+    fn handle_query_error(
+        &mut self,
+        range: RangeInclusive<u32>,
+        err: &ChainCommunicationError,
+    ) -> bool;
+
+    /// The current max number of blocks, or sequences if indexing by sequence,
+    /// the cursor will query at once.
+    fn chunk_size(&self) -> u32;
 }
 
 /// The action that should be taken by the contract sync loop
@@ -1,10 +1,11 @@
 use std::fmt::Debug;
+use std::ops::RangeInclusive;
 
 use async_trait::async_trait;
 use auto_impl::auto_impl;
 use eyre::Result;
 
-use crate::{Indexed, LogMeta};
+use crate::{Indexed, LogMeta, H256};
 
 /// Interface for a HyperlaneLogStore that ingests logs.
 #[async_trait]
@@ -32,18 +33,46 @@ pub trait HyperlaneSequenceAwareIndexerStoreReader<T>: Send + Sync + Debug {
 
     /// Gets the block number at which the log occurred.
     async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>>;
+
+    /// Whether the log with the given sequence has been indexed. Stores which prune the
+    /// data of old logs must keep reporting them as indexed.
+    async fn is_sequence_indexed(&self, sequence: u32) -> Result<bool> {
+        Ok(self.retrieve_by_sequence(sequence).await?.is_some())
+    }
+}
+
+/// An interface for sequence-aware indexer stores that record the hash of the block
+/// each log occurred in, so that logs which were reorged out can be detected and
+/// rolled back.
+#[async_trait]
+#[auto_impl(&, Box, Arc)]
+pub trait HyperlaneReorgAwareIndexerStore<T>: Send + Sync + Debug {
+    /// Gets the hash of the block at which the log occurred.
+    /// Returns None if the log hasn't been indexed, or if its block hash wasn't recorded.
+    async fn retrieve_log_block_hash_by_sequence(&self, sequence: u32) -> Result<Option<H256>>;
+
+    /// Removes the logs with sequences in the given range, so they can be indexed again.
+    /// Returns the number of logs that were removed.
+    async fn rollback_logs(&self, sequences: RangeInclusive<u32>) -> Result<u32>;
 }
 
 /// Extension of HyperlaneLogStore trait for sequence-aware indexer stores.
 #[async_trait]
 pub trait HyperlaneSequenceAwareIndexerStore<T>:
-    HyperlaneLogStore<T> + HyperlaneSequenceAwareIndexerStoreReader<T>
+    HyperlaneLogStore<T>
+    + HyperlaneSequenceAwareIndexerStoreReader<T>
+    + HyperlaneReorgAwareIndexerStore<T>
 {
 }
 
 /// Auto-impl for HyperlaneSequenceAwareIndexerStore
 impl<T, U> HyperlaneSequenceAwareIndexerStore<T> for U where
-    U: HyperlaneLogStore<T> + HyperlaneSequenceAwareIndexerStoreReader<T> + Send + Sync + Debug
+    U: HyperlaneLogStore<T>
+        + HyperlaneSequenceAwareIndexerStoreReader<T>
+        + HyperlaneReorgAwareIndexerStore<T>
+        + Send
+        + Sync
+        + Debug
 {
 }
 
@@ -9,9 +9,11 @@ use std::ops::RangeInclusive;
 
 use async_trait::async_trait;
 use auto_impl::auto_impl;
+#[cfg(feature = "async")]
+use futures::stream::BoxStream;
 use serde::Deserialize;
 
-use crate::{ChainResult, Indexed, LogMeta};
+use crate::{ChainCommunicationError, ChainResult, Indexed, LogMeta, H256, H512};
 
 /// Indexing mode.
 #[derive(Copy, Debug, Default, Deserialize, Clone)]
@@ -24,6 +26,10 @@ pub enum IndexMode {
     Sequence,
 }
 
+/// A stream of logs pushed to an indexer as they are emitted on chain.
+#[cfg(feature = "async")]
+pub type LogStream<T> = BoxStream<'static, ChainResult<(Indexed<T>, LogMeta)>>;
+
 /// Interface for an indexer.
 #[async_trait]
 #[auto_impl(&, Box, Arc,)]
@@ -36,6 +42,40 @@ pub trait Indexer<T: Sized>: Send + Sync + Debug {
 
     /// Get the chain's latest block number that has reached finality
     async fn get_finalized_block_number(&self) -> ChainResult<u32>;
+
+    /// Subscribe to logs as they are emitted. Logs are pushed as soon as they
+    /// are included in a block, so they may not have reached finality yet and
+    /// are only used as a signal to query `fetch_logs` again early.
+    ///
+    /// Returns `None` if the indexer's connection does not support
+    /// subscriptions, in which case only `fetch_logs` is polled.
+    #[cfg(feature = "async")]
+    async fn subscribe_logs(&self) -> ChainResult<Option<LogStream<T>>> {
+        Ok(None)
+    }
+
+    /// Get the hash of the canonical block at the given height, used to check
+    /// whether indexed logs have since been reorged out.
+    ///
+    /// Returns `None` if the indexer can't look up blocks by height, in which
+    /// case indexed logs are never checked for reorgs.
+    async fn get_block_hash(&self, _block_number: u64) -> ChainResult<Option<H256>> {
+        Ok(None)
+    }
+
+    /// Fetch the logs emitted by the transaction `tx_hash`, e.g. to look up
+    /// events which haven't been indexed.
+    ///
+    /// Returns `ChainCommunicationError::Unsupported` if the indexer can't look
+    /// up logs by transaction.
+    async fn fetch_logs_by_tx_hash(
+        &self,
+        _tx_hash: H512,
+    ) -> ChainResult<Vec<(Indexed<T>, LogMeta)>> {
+        Err(ChainCommunicationError::Unsupported(
+            "Fetching logs by transaction hash",
+        ))
+    }
 }
 
 /// Interface for indexing data in sequence.
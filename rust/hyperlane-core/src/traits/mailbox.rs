@@ -4,8 +4,8 @@ use std::num::NonZeroU64;
 use async_trait::async_trait;
 
 use crate::{
-    traits::TxOutcome, utils::domain_hash, BatchItem, ChainCommunicationError, ChainResult,
-    HyperlaneContract, HyperlaneMessage, TxCostEstimate, H256, U256,
+    traits::TxOutcome, utils::domain_hash, BatchItem, BlockFinality, ChainCommunicationError,
+    ChainResult, HyperlaneContract, HyperlaneMessage, TxCostEstimate, H256, U256,
 };
 
 /// Interface for the Mailbox chain contract. Allows abstraction over different
@@ -26,6 +26,15 @@ pub trait Mailbox: HyperlaneContract + Send + Sync + Debug {
     /// Fetch the status of a message
     async fn delivered(&self, id: H256) -> ChainResult<bool>;
 
+    /// Fetch the status of a message as of the latest block which is final
+    /// according to `finality`.
+    ///
+    /// Chains which can't query past state fall back to the latest block, so
+    /// agents reject a delivery finality in their config.
+    async fn delivered_at_finality(&self, id: H256, _finality: BlockFinality) -> ChainResult<bool> {
+        self.delivered(id).await
+    }
+
     /// Fetch the current default interchain security module value
     async fn default_ism(&self) -> ChainResult<H256>;
 
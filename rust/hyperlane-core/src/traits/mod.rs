@@ -45,6 +45,9 @@ pub struct TxOutcome {
     pub gas_used: U256,
     /// Price paid for the gas
     pub gas_price: FixedPointNumber,
+    /// The fee paid for posting the transaction data to L1, if the chain is
+    /// a rollup that charges it on top of the gas used.
+    pub l1_data_fee: Option<U256>,
     // TODO: more? What can be abstracted across all chains?
 }
 
@@ -59,6 +62,22 @@ impl From<ethers_core::types::TransactionReceipt> for TxOutcome {
                 .effective_gas_price
                 .and_then(|price| U256::from(price).try_into().ok())
                 .unwrap_or(FixedPointNumber::zero()),
+            // OP Stack receipts report the L1 data fee in an `l1Fee` field
+            l1_data_fee: t
+                .other
+                .get_deserialized::<ethers_core::types::U256>("l1Fee")
+                .and_then(Result::ok)
+                .map(Into::into),
         }
     }
 }
+
+impl TxOutcome {
+    /// The L1 data fee expressed as an amount of gas at `gas_price`,
+    /// or zero if there is no L1 data fee.
+    pub fn l1_data_fee_as_gas(&self) -> U256 {
+        self.l1_data_fee
+            .map(|fee| crate::types::fee_as_gas(fee, &self.gas_price))
+            .unwrap_or_default()
+    }
+}
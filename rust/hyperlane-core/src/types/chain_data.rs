@@ -1,6 +1,8 @@
+use std::num::NonZeroU64;
+
 use derive_new::new;
 
-use crate::{H256, U256};
+use crate::{H256, H512, U256};
 
 /// Info about a given block in the chain.
 #[derive(Debug, Clone, Default)]
@@ -13,6 +15,17 @@ pub struct BlockInfo {
     pub number: u64,
 }
 
+/// When state read from a chain is considered final, i.e. safe from reorgs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFinality {
+    /// State is final once its block is this many blocks behind the latest
+    /// block
+    Confirmations(NonZeroU64),
+    /// State is final once its block is finalized, per the chain's `finalized`
+    /// block tag
+    Finalized,
+}
+
 /// Metrics about the chain.
 #[derive(Debug, Clone, Default, new)]
 pub struct ChainInfo {
@@ -27,7 +40,7 @@ pub struct ChainInfo {
 #[derive(Debug, Clone)]
 pub struct TxnInfo {
     /// Hash of this transaction
-    pub hash: H256,
+    pub hash: H512,
     /// Amount of gas which was allocated for running the transaction
     pub gas_limit: U256,
     /// Represents the maximum tx fee that will go to the miner as part of the
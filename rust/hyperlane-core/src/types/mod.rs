@@ -249,11 +249,42 @@ pub struct TxCostEstimate {
     /// is used to cover L1 and L2 costs. For details:
     /// `<https://medium.com/offchainlabs/understanding-arbitrum-2-dimensional-fees-fd1d582596c9>`
     pub l2_gas_limit: Option<U256>,
+    /// The fee for posting the transaction data to L1, in the smallest unit
+    /// of the native token. Only present for rollups: on OP Stack chains it is
+    /// charged on top of the gas used, while on Arbitrum Nitro chains it is
+    /// paid by the gas in `gas_limit` above `l2_gas_limit`.
+    pub l1_data_fee: Option<U256>,
 }
 
 impl TxCostEstimate {
-    /// The gas limit to be used by gas enforcement policies.
+    /// The gas limit to be used by gas enforcement policies. This is the L2
+    /// gas limit plus the L1 data fee expressed as gas at `gas_price`.
     pub fn enforceable_gas_limit(&self) -> U256 {
-        self.l2_gas_limit.unwrap_or(self.gas_limit)
+        self.l2_gas_limit
+            .unwrap_or(self.gas_limit)
+            .saturating_add(self.l1_data_fee_as_gas())
+    }
+
+    /// The L1 data fee expressed as an amount of gas at `gas_price`,
+    /// or zero if there is no L1 data fee.
+    pub fn l1_data_fee_as_gas(&self) -> U256 {
+        self.l1_data_fee
+            .map(|fee| fee_as_gas(fee, &self.gas_price))
+            .unwrap_or_default()
+    }
+}
+
+/// Converts a fee into the amount of gas that costs at least as much at
+/// `gas_price`. Returns zero if the gas price is zero.
+pub(crate) fn fee_as_gas(fee: U256, gas_price: &FixedPointNumber) -> U256 {
+    let gas_price: U256 = gas_price.ceil(0).try_into().unwrap_or_default();
+    if gas_price.is_zero() {
+        return U256::zero();
+    }
+    let (gas, remainder) = fee.div_mod(gas_price);
+    if remainder.is_zero() {
+        gas
+    } else {
+        gas.saturating_add(U256::one())
     }
 }
@@ -39,6 +39,8 @@ mock! {
 
         pub fn _delivered(&self, id: H256) -> ChainResult<bool> {}
 
+        pub fn _delivered_at_finality(&self, id: H256, finality: BlockFinality) -> ChainResult<bool> {}
+
         pub fn process(
             &self,
             message: &HyperlaneMessage,
@@ -84,6 +86,10 @@ impl Mailbox for MockMailboxContract {
         self._delivered(id)
     }
 
+    async fn delivered_at_finality(&self, id: H256, finality: BlockFinality) -> ChainResult<bool> {
+        self._delivered_at_finality(id, finality)
+    }
+
     async fn process(
         &self,
         message: &HyperlaneMessage,
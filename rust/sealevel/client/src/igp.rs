@@ -194,12 +194,27 @@ pub(crate) fn process_igp_cmd(mut ctx: Context, cmd: IgpCmd) {
                 hyperlane_sealevel_igp::overhead_igp_pda_seeds!(salt),
                 &payment_details.program_id,
             );
+
+            // The price feed account must be provided if the gas oracle reads from one.
+            let account = ctx
+                .client
+                .get_account_with_commitment(&igp_account, ctx.commitment)
+                .unwrap()
+                .value
+                .unwrap();
+            let price_feed = IgpAccount::fetch(&mut &account.data[..])
+                .unwrap()
+                .into_inner()
+                .price_feed(payment_details.destination_domain)
+                .copied();
+
             let (ixn, gas_payment_data_account) =
                 hyperlane_sealevel_igp::instruction::pay_for_gas_instruction(
                     payment_details.program_id,
                     ctx.payer_pubkey,
                     igp_account,
                     Some(overhead_igp_account),
+                    price_feed,
                     unique_gas_payment_keypair.pubkey(),
                     H256::from_str(&payment_details.message_id).unwrap(),
                     payment_details.destination_domain,
@@ -23,7 +23,7 @@ use account_utils::DiscriminatorEncode;
 use hyperlane_core::{H160, H256};
 use hyperlane_sealevel_connection_client::router::RemoteRouterConfig;
 use hyperlane_sealevel_igp::{
-    accounts::{InterchainGasPaymasterType, OverheadIgpAccount},
+    accounts::{IgpAccount, InterchainGasPaymasterType, OverheadIgpAccount},
     igp_gas_payment_pda_seeds, igp_program_data_pda_seeds,
 };
 use hyperlane_sealevel_mailbox::{
@@ -35,18 +35,23 @@ use hyperlane_sealevel_mailbox::{
 };
 
 use hyperlane_sealevel_token::{
-    hyperlane_token_ata_payer_pda_seeds, hyperlane_token_mint_pda_seeds,
+    hyperlane_token_ata_payer_pda_seeds, hyperlane_token_mint_pda_seeds, plugin::SyntheticPlugin,
     spl_associated_token_account::get_associated_token_address_with_program_id, spl_token_2022,
 };
 use hyperlane_sealevel_token_collateral::{
     hyperlane_token_escrow_pda_seeds, plugin::CollateralPlugin,
 };
 use hyperlane_sealevel_token_lib::{
-    accounts::HyperlaneTokenAccount,
-    hyperlane_token_pda_seeds,
+    accounts::{HyperlaneTokenAccount, PendingTransferQueueAccount},
+    hyperlane_token_pda_seeds, hyperlane_token_pending_transfer_queue_pda_seeds,
     instruction::{Instruction as HtInstruction, TransferRemote as HtTransferRemote},
+    message::TokenMessage,
+    processor::HyperlaneSealevelTokenPlugin,
+    rate_limit::{RateLimitConfig, RateLimitParams, TransferDirection},
+};
+use hyperlane_sealevel_token_native::{
+    hyperlane_token_native_collateral_pda_seeds, plugin::NativePlugin,
 };
-use hyperlane_sealevel_token_native::hyperlane_token_native_collateral_pda_seeds;
 use hyperlane_sealevel_validator_announce::{
     accounts::ValidatorStorageLocationsAccount,
     instruction::{
@@ -286,6 +291,8 @@ enum TokenSubCmd {
     TransferOwnership(TransferOwnership),
     SetInterchainSecurityModule(SetInterchainSecurityModule),
     Igp(Igp),
+    SetRateLimit(TokenSetRateLimit),
+    ClaimPendingTransfer(TokenClaimPendingTransfer),
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -342,6 +349,46 @@ struct TransferOwnership {
     new_owner: Pubkey,
 }
 
+#[derive(ValueEnum, Clone, Copy)]
+enum RateLimitDirection {
+    Outbound,
+    Inbound,
+}
+
+impl From<RateLimitDirection> for TransferDirection {
+    fn from(direction: RateLimitDirection) -> Self {
+        match direction {
+            RateLimitDirection::Outbound => TransferDirection::Outbound,
+            RateLimitDirection::Inbound => TransferDirection::Inbound,
+        }
+    }
+}
+
+#[derive(Args)]
+struct TokenSetRateLimit {
+    #[arg(long, short, default_value_t = HYPERLANE_TOKEN_PROG_ID)]
+    program_id: Pubkey,
+    domain: u32,
+    #[arg(value_enum)]
+    direction: RateLimitDirection,
+    /// The maximum amount, in local decimals, that can be transferred within a window.
+    /// If not provided, the rate limit is removed.
+    #[arg(long, requires = "window_seconds")]
+    capacity: Option<u64>,
+    /// The length of the rolling window, in seconds.
+    #[arg(long, requires = "capacity")]
+    window_seconds: Option<u64>,
+}
+
+#[derive(Args)]
+struct TokenClaimPendingTransfer {
+    #[arg(long, short, default_value_t = HYPERLANE_TOKEN_PROG_ID)]
+    program_id: Pubkey,
+    nonce: u64,
+    #[arg(value_enum)]
+    token_type: TokenType,
+}
+
 #[derive(Args)]
 struct Igp {
     #[arg(long, short, default_value_t = HYPERLANE_TOKEN_PROG_ID)]
@@ -1053,7 +1100,8 @@ fn process_token_cmd(ctx: Context, cmd: TokenCmd) {
             //
             // 0.    [executable] The system program.
             // 1.    [executable] The spl_noop program.
-            // 2.    [] The token PDA account.
+            // 2.    [writeable] The token PDA account. Only needs to be writeable if an
+            //       outbound rate limit is configured for the destination.
             // 3.    [executable] The mailbox program.
             // 4.    [writeable] The mailbox outbox account.
             // 5.    [] Message dispatch authority.
@@ -1066,12 +1114,14 @@ fn process_token_cmd(ctx: Context, cmd: TokenCmd) {
             // 11.   [writeable] Gas payment PDA.
             // 12.   [] OPTIONAL - The Overhead IGP program, if the configured IGP is an Overhead IGP.
             // 13.   [writeable] The IGP account.
+            // 14.   [] OPTIONAL - The gas oracle price feed account, if the IGP's gas oracle
+            //       for the destination is a price feed.
             //       ---- End if ----
-            // 14..N [??..??] Plugin-specific accounts.
+            // 15..N [??..??] Plugin-specific accounts.
             let mut accounts = vec![
                 AccountMeta::new_readonly(system_program::id(), false),
                 AccountMeta::new_readonly(spl_noop::id(), false),
-                AccountMeta::new_readonly(token_account, false),
+                AccountMeta::new(token_account, false),
                 AccountMeta::new_readonly(token.mailbox, false),
                 AccountMeta::new(mailbox_outbox_account, false),
                 AccountMeta::new_readonly(dispatch_authority_account, false),
@@ -1094,7 +1144,7 @@ fn process_token_cmd(ctx: Context, cmd: TokenCmd) {
                     AccountMeta::new(gas_payment_pda, false),
                 ]);
 
-                match igp_account_type {
+                let igp_account_id = match igp_account_type {
                     InterchainGasPaymasterType::OverheadIgp(overhead_igp_account_id) => {
                         let overhead_igp_account = ctx
                             .client
@@ -1110,10 +1160,25 @@ fn process_token_cmd(ctx: Context, cmd: TokenCmd) {
                             AccountMeta::new_readonly(overhead_igp_account_id, false),
                             AccountMeta::new(overhead_igp_account.inner, false),
                         ]);
+                        overhead_igp_account.inner
                     }
                     InterchainGasPaymasterType::Igp(igp_account_id) => {
                         accounts.push(AccountMeta::new(igp_account_id, false));
+                        igp_account_id
                     }
+                };
+
+                let igp_account = ctx
+                    .client
+                    .get_account_with_commitment(&igp_account_id, ctx.commitment)
+                    .unwrap()
+                    .value
+                    .unwrap();
+                let igp_account = IgpAccount::fetch(&mut &igp_account.data[..])
+                    .unwrap()
+                    .into_inner();
+                if let Some(price_feed) = igp_account.price_feed(xfer.destination_domain) {
+                    accounts.push(AccountMeta::new_readonly(*price_feed, false));
                 }
             }
 
@@ -1277,6 +1342,147 @@ fn process_token_cmd(ctx: Context, cmd: TokenCmd) {
                 parse_token_account_data(get_args.token_type, &mut &token_account.data[..]);
             }
         },
+        TokenSubCmd::SetRateLimit(set_rate_limit) => {
+            let rate_limit = set_rate_limit
+                .capacity
+                .zip(set_rate_limit.window_seconds)
+                .map(|(capacity, window_seconds)| RateLimitParams {
+                    capacity,
+                    window_seconds,
+                });
+            let config = RateLimitConfig {
+                domain: set_rate_limit.domain,
+                direction: set_rate_limit.direction.into(),
+                rate_limit,
+            };
+            let instruction =
+                hyperlane_sealevel_token_lib::instruction::set_rate_limits_instruction(
+                    set_rate_limit.program_id,
+                    ctx.payer_pubkey,
+                    vec![config.clone()],
+                )
+                .unwrap();
+
+            ctx.new_txn()
+                .add_with_description(
+                    instruction,
+                    format!(
+                        "Set {:?} rate limit of {} for domain {} to {:?}",
+                        config.direction,
+                        set_rate_limit.program_id,
+                        config.domain,
+                        config.rate_limit
+                    ),
+                )
+                .send_with_payer();
+        }
+        TokenSubCmd::ClaimPendingTransfer(claim) => {
+            let (token_account, _token_bump) =
+                Pubkey::find_program_address(hyperlane_token_pda_seeds!(), &claim.program_id);
+            let (pending_transfer_queue_account, _pending_transfer_queue_bump) =
+                Pubkey::find_program_address(
+                    hyperlane_token_pending_transfer_queue_pda_seeds!(),
+                    &claim.program_id,
+                );
+
+            let accounts = ctx
+                .client
+                .get_multiple_accounts_with_commitment(
+                    &[token_account, pending_transfer_queue_account],
+                    ctx.commitment,
+                )
+                .unwrap()
+                .value;
+            let token_account_data = accounts[0]
+                .as_ref()
+                .expect("Token account not found. Make sure you are connected to the right RPC.")
+                .data
+                .clone();
+            let pending_transfer_queue = PendingTransferQueueAccount::fetch(
+                &mut &accounts[1]
+                    .as_ref()
+                    .expect("Pending transfer queue not found. Are inbound rate limits set?")
+                    .data[..],
+            )
+            .unwrap()
+            .into_inner();
+            let pending_transfer = pending_transfer_queue
+                .transfers
+                .into_iter()
+                .find(|transfer| transfer.nonce == claim.nonce)
+                .expect("Pending transfer not found. Has it already been claimed?");
+            println!("Claiming pending transfer: {:#?}", pending_transfer);
+
+            // The plugin-specific accounts are the same as those required to
+            // handle an equivalent transfer from the remote.
+            let token_message = TokenMessage::new(
+                H256::from(pending_transfer.recipient.to_bytes()),
+                pending_transfer.amount.into(),
+                vec![],
+            );
+            let (transfer_out_account_metas, writeable_recipient) = match claim.token_type {
+                TokenType::Native => {
+                    let token =
+                        HyperlaneTokenAccount::<NativePlugin>::fetch(&mut &token_account_data[..])
+                            .unwrap()
+                            .into_inner();
+                    NativePlugin::transfer_out_account_metas(
+                        &claim.program_id,
+                        &token,
+                        &token_message,
+                    )
+                }
+                TokenType::Synthetic => {
+                    let token = HyperlaneTokenAccount::<SyntheticPlugin>::fetch(
+                        &mut &token_account_data[..],
+                    )
+                    .unwrap()
+                    .into_inner();
+                    SyntheticPlugin::transfer_out_account_metas(
+                        &claim.program_id,
+                        &token,
+                        &token_message,
+                    )
+                }
+                TokenType::Collateral => {
+                    let token = HyperlaneTokenAccount::<CollateralPlugin>::fetch(
+                        &mut &token_account_data[..],
+                    )
+                    .unwrap()
+                    .into_inner();
+                    CollateralPlugin::transfer_out_account_metas(
+                        &claim.program_id,
+                        &token,
+                        &token_message,
+                    )
+                }
+            }
+            .unwrap();
+
+            let mut instruction =
+                hyperlane_sealevel_token_lib::instruction::claim_pending_transfer_instruction(
+                    claim.program_id,
+                    claim.nonce,
+                    pending_transfer.recipient,
+                    writeable_recipient,
+                )
+                .unwrap();
+            instruction.accounts.extend(
+                transfer_out_account_metas
+                    .into_iter()
+                    .map(AccountMeta::from),
+            );
+
+            ctx.new_txn()
+                .add_with_description(
+                    instruction,
+                    format!(
+                        "Claim pending transfer {} of {}",
+                        claim.nonce, claim.program_id
+                    ),
+                )
+                .send_with_payer();
+        }
     }
 }
 
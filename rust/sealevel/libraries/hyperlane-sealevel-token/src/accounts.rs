@@ -14,7 +14,10 @@ use hyperlane_sealevel_igp::accounts::InterchainGasPaymasterType;
 use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
 use std::{cmp::Ordering, collections::HashMap, fmt::Debug};
 
-use crate::hyperlane_token_pda_seeds;
+use crate::{
+    hyperlane_token_pda_seeds,
+    rate_limit::{set_rate_limit, RateLimit, RateLimitConfig, TrailingRateLimits},
+};
 
 /// HyperlaneToken account data.
 pub type HyperlaneTokenAccount<T> = AccountData<HyperlaneToken<T>>;
@@ -47,6 +50,9 @@ pub struct HyperlaneToken<T> {
     pub remote_routers: HashMap<u32, H256>,
     /// Plugin-specific data.
     pub plugin_data: T,
+    /// Rate limits on transfers, if they have ever been configured.
+    /// This must remain the last field, see `TrailingRateLimits`.
+    pub rate_limits: TrailingRateLimits,
 }
 
 impl<T> HyperlaneToken<T>
@@ -86,6 +92,57 @@ where
             .as_u64();
         Ok(amount)
     }
+
+    /// Returns the rate limit on transfers to `destination`, if any.
+    pub fn outbound_rate_limit_mut(&mut self, destination: u32) -> Option<&mut RateLimit> {
+        self.rate_limits
+            .0
+            .as_mut()
+            .and_then(|rate_limits| rate_limits.outbound.get_mut(&destination))
+    }
+
+    /// Returns the rate limit on transfers from `origin`, if any.
+    pub fn inbound_rate_limit_mut(&mut self, origin: u32) -> Option<&mut RateLimit> {
+        self.rate_limits
+            .0
+            .as_mut()
+            .and_then(|rate_limits| rate_limits.inbound.get_mut(&origin))
+    }
+
+    /// Returns true if transfers from `origin` are rate limited.
+    pub fn is_inbound_rate_limited(&self, origin: u32) -> bool {
+        self.rate_limits
+            .0
+            .as_ref()
+            .map(|rate_limits| rate_limits.inbound.contains_key(&origin))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if transfers from any remote domain are rate limited.
+    pub fn has_inbound_rate_limits(&self) -> bool {
+        self.rate_limits
+            .0
+            .as_ref()
+            .map(|rate_limits| !rate_limits.inbound.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Removes the rate limit on transfers from `origin`, if any.
+    pub fn remove_inbound_rate_limit(&mut self, origin: u32) {
+        if let Some(rate_limits) = self.rate_limits.0.as_mut() {
+            rate_limits.inbound.remove(&origin);
+        }
+    }
+
+    /// Sets or removes a rate limit.
+    pub fn set_rate_limit(
+        &mut self,
+        config: &RateLimitConfig,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        let rate_limits = self.rate_limits.0.get_or_insert_with(Default::default);
+        set_rate_limit(rate_limits.get_mut(config.direction), config, now)
+    }
 }
 
 impl<T> SizedData for HyperlaneToken<T>
@@ -124,7 +181,9 @@ where
         // remote_routers keys & values
         (self.remote_routers.len() * (std::mem::size_of::<u32>() + 32)) +
         // plugin_data
-        self.plugin_data.size()
+        self.plugin_data.size() +
+        // rate_limits
+        self.rate_limits.size()
     }
 }
 
@@ -198,6 +257,91 @@ impl<T> HyperlaneGasRouter for HyperlaneToken<T> {
     }
 }
 
+/// The maximum number of pending transfers that can be queued at once.
+/// The pending transfer queue account is allocated with enough space for this
+/// many transfers when inbound rate limits are first configured.
+pub const MAX_PENDING_TRANSFERS: usize = 64;
+
+/// PendingTransferQueue account data.
+pub type PendingTransferQueueAccount = AccountData<PendingTransferQueue>;
+
+/// A PDA account containing inbound transfers that were queued because they
+/// exceeded the inbound rate limit for their origin, oldest first.
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, Default)]
+pub struct PendingTransferQueue {
+    /// The bump seed for this PDA.
+    pub bump: u8,
+    /// The nonce to assign to the next queued transfer.
+    pub next_nonce: u64,
+    /// The queued transfers, oldest first.
+    pub transfers: Vec<PendingTransfer>,
+}
+
+impl PendingTransferQueue {
+    /// The size of a queue holding `MAX_PENDING_TRANSFERS` transfers.
+    pub const MAX_SIZE: usize =
+        // bump
+        std::mem::size_of::<u8>() +
+        // next_nonce
+        std::mem::size_of::<u64>() +
+        // transfers length
+        std::mem::size_of::<u32>() +
+        // transfers
+        MAX_PENDING_TRANSFERS * PendingTransfer::SIZE;
+
+    /// Returns the oldest pending transfer from `origin`, if any.
+    /// Pending transfers from an origin can only be claimed in this order.
+    pub fn next_from(&self, origin: u32) -> Option<&PendingTransfer> {
+        self.transfers
+            .iter()
+            .find(|transfer| transfer.origin == origin)
+    }
+}
+
+impl SizedData for PendingTransferQueue {
+    fn size(&self) -> usize {
+        // bump
+        std::mem::size_of::<u8>() +
+        // next_nonce
+        std::mem::size_of::<u64>() +
+        // transfers length
+        std::mem::size_of::<u32>() +
+        // transfers
+        self.transfers.len() * PendingTransfer::SIZE
+    }
+}
+
+/// An inbound transfer that was queued due to a rate limit. It can be claimed
+/// once enough capacity is available.
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, Default, Clone)]
+pub struct PendingTransfer {
+    /// The nonce of the pending transfer.
+    pub nonce: u64,
+    /// The origin domain of the transfer.
+    pub origin: u32,
+    /// The recipient wallet.
+    pub recipient: Pubkey,
+    /// The amount to transfer, denominated in the local decimals.
+    pub amount: u64,
+    /// The unix timestamp the transfer was queued at.
+    pub queued_at: i64,
+}
+
+impl PendingTransfer {
+    /// The serialized size of a `PendingTransfer`.
+    pub const SIZE: usize =
+        // nonce
+        std::mem::size_of::<u64>() +
+        // origin
+        std::mem::size_of::<u32>() +
+        // recipient
+        32 +
+        // amount
+        std::mem::size_of::<u64>() +
+        // queued_at
+        std::mem::size_of::<i64>();
+}
+
 /// Converts an amount from one decimal representation to another.
 pub fn convert_decimals(amount: U256, from_decimals: u8, to_decimals: u8) -> Option<U256> {
     match from_decimals.cmp(&to_decimals) {
@@ -216,6 +360,7 @@ pub fn convert_decimals(amount: U256, from_decimals: u8, to_decimals: u8) -> Opt
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::rate_limit::RateLimits;
 
     #[test]
     fn test_convert_decimals() {
@@ -343,9 +488,100 @@ mod test {
             destination_gas: HashMap::from([(1000, 200000), (200, 400000)]),
             remote_routers: HashMap::from([(1000, H256::random()), (200, H256::random())]),
             plugin_data: Foo { bar: 69 },
+            rate_limits: TrailingRateLimits(None),
+        };
+        let serialized = hyperlane_token_foo.try_to_vec().unwrap();
+
+        assert_eq!(serialized.len(), hyperlane_token_foo.size());
+
+        let hyperlane_token_foo = HyperlaneToken::<Foo> {
+            rate_limits: TrailingRateLimits(Some(RateLimits {
+                outbound: HashMap::from([(1000, RateLimit::default())]),
+                inbound: HashMap::from([(1000, RateLimit::default()), (200, RateLimit::default())]),
+            })),
+            ..hyperlane_token_foo
         };
         let serialized = hyperlane_token_foo.try_to_vec().unwrap();
 
         assert_eq!(serialized.len(), hyperlane_token_foo.size());
     }
+
+    #[test]
+    fn test_hyperlane_token_without_rate_limits_keeps_layout() {
+        /// The token account data as it was before rate limits were introduced.
+        #[derive(BorshSerialize)]
+        struct LegacyHyperlaneToken {
+            bump: u8,
+            mailbox: Pubkey,
+            mailbox_process_authority: Pubkey,
+            dispatch_authority_bump: u8,
+            decimals: u8,
+            remote_decimals: u8,
+            owner: Option<Pubkey>,
+            interchain_security_module: Option<Pubkey>,
+            interchain_gas_paymaster: Option<(Pubkey, InterchainGasPaymasterType)>,
+            destination_gas: HashMap<u32, u64>,
+            remote_routers: HashMap<u32, H256>,
+            plugin_data: u64,
+        }
+
+        let remote_router = H256::random();
+        let legacy = LegacyHyperlaneToken {
+            bump: 1,
+            mailbox: Pubkey::new_unique(),
+            mailbox_process_authority: Pubkey::new_unique(),
+            dispatch_authority_bump: 2,
+            decimals: 3,
+            remote_decimals: 4,
+            owner: Some(Pubkey::new_unique()),
+            interchain_security_module: None,
+            interchain_gas_paymaster: None,
+            destination_gas: HashMap::new(),
+            remote_routers: HashMap::from([(1000, remote_router)]),
+            plugin_data: 69,
+        };
+        let mut legacy_serialized = legacy.try_to_vec().unwrap();
+        // Stale bytes, as if a second remote router had been unenrolled.
+        legacy_serialized.extend([200, 0, 0, 0]);
+        legacy_serialized.extend(H256::random().as_bytes());
+
+        let token = HyperlaneToken::<u64>::deserialize(&mut &legacy_serialized[..]).unwrap();
+        assert_eq!(token.remote_routers, HashMap::from([(1000, remote_router)]));
+        assert_eq!(token.plugin_data, 69);
+        assert_eq!(token.rate_limits, TrailingRateLimits(None));
+
+        // Without rate limits, the serialized token is unchanged.
+        assert_eq!(token.try_to_vec().unwrap(), legacy.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_pending_transfer_queue_size() {
+        let pending_transfer = PendingTransfer {
+            nonce: 2,
+            origin: 3,
+            recipient: Pubkey::new_unique(),
+            amount: 4,
+            queued_at: 5,
+        };
+        assert_eq!(
+            pending_transfer.try_to_vec().unwrap().len(),
+            PendingTransfer::SIZE
+        );
+
+        let queue = PendingTransferQueue {
+            bump: 1,
+            next_nonce: 3,
+            transfers: vec![pending_transfer.clone(), pending_transfer],
+        };
+        assert_eq!(queue.try_to_vec().unwrap().len(), queue.size());
+
+        let full_queue = PendingTransferQueue {
+            transfers: vec![PendingTransfer::default(); MAX_PENDING_TRANSFERS],
+            ..queue
+        };
+        assert_eq!(
+            full_queue.try_to_vec().unwrap().len(),
+            PendingTransferQueue::MAX_SIZE
+        );
+    }
 }
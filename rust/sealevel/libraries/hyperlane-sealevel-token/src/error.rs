@@ -17,6 +17,18 @@ pub enum Error {
     /// A message decoding error occurred.
     #[error("Message decoding error")]
     MessageDecodeError = 3,
+
+    /// A transfer exceeded the available rate limit capacity.
+    #[error("Rate limit exceeded")]
+    RateLimitExceeded = 4,
+
+    /// The pending transfer queue is full.
+    #[error("Pending transfer queue is full")]
+    PendingTransferQueueFull = 5,
+
+    /// A pending transfer was claimed before an older one from the same origin.
+    #[error("Pending transfer is not the next to be claimed from its origin")]
+    PendingTransferNotNext = 6,
 }
 
 impl From<Error> for ProgramError {
@@ -15,7 +15,10 @@ use solana_program::{
 
 use hyperlane_sealevel_mailbox::mailbox_message_dispatch_authority_pda_seeds;
 
-use crate::hyperlane_token_pda_seeds;
+use crate::{
+    hyperlane_token_pda_seeds, hyperlane_token_pending_transfer_queue_pda_seeds,
+    rate_limit::RateLimitConfig,
+};
 
 /// Instructions shared by all Hyperlane Sealevel Token programs.
 #[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq)]
@@ -36,6 +39,11 @@ pub enum Instruction {
     SetInterchainGasPaymaster(Option<(Pubkey, InterchainGasPaymasterType)>),
     /// Transfer ownership of the program. Only owner.
     TransferOwnership(Option<Pubkey>),
+    /// Set or remove rate limits on transfers. Only owner.
+    SetRateLimits(Vec<RateLimitConfig>),
+    /// Claim an inbound transfer that was queued due to a rate limit,
+    /// identified by its nonce.
+    ClaimPendingTransfer(u64),
 }
 
 impl DiscriminatorData for Instruction {
@@ -258,3 +266,91 @@ pub fn set_igp_instruction(
 
     Ok(instruction)
 }
+
+/// Sets rate limits.
+pub fn set_rate_limits_instruction(
+    program_id: Pubkey,
+    owner_payer: Pubkey,
+    configs: Vec<RateLimitConfig>,
+) -> Result<SolanaInstruction, ProgramError> {
+    let (token_key, _token_bump) =
+        Pubkey::try_find_program_address(hyperlane_token_pda_seeds!(), &program_id)
+            .ok_or(ProgramError::InvalidSeeds)?;
+
+    let (pending_transfer_queue_key, _pending_transfer_queue_bump) =
+        Pubkey::try_find_program_address(
+            hyperlane_token_pending_transfer_queue_pda_seeds!(),
+            &program_id,
+        )
+        .ok_or(ProgramError::InvalidSeeds)?;
+
+    let ixn = Instruction::SetRateLimits(configs);
+
+    // Accounts:
+    // 0. `[executable]` The system program.
+    // 1. `[writeable]` The token PDA account.
+    // 2. `[signer, writeable]` The owner.
+    // 3. `[writeable]` The pending transfer queue PDA account.
+    let accounts = vec![
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new(token_key, false),
+        AccountMeta::new(owner_payer, true),
+        AccountMeta::new(pending_transfer_queue_key, false),
+    ];
+
+    let instruction = SolanaInstruction {
+        program_id,
+        data: ixn.encode()?,
+        accounts,
+    };
+
+    Ok(instruction)
+}
+
+/// Gets an instruction to claim a pending transfer. This provides only the
+/// account metas required by the library, and consuming programs are expected
+/// to add the accounts required by their `transfer_out` implementation.
+pub fn claim_pending_transfer_instruction(
+    program_id: Pubkey,
+    nonce: u64,
+    recipient: Pubkey,
+    writeable_recipient: bool,
+) -> Result<SolanaInstruction, ProgramError> {
+    let (token_key, _token_bump) =
+        Pubkey::try_find_program_address(hyperlane_token_pda_seeds!(), &program_id)
+            .ok_or(ProgramError::InvalidSeeds)?;
+
+    let (pending_transfer_queue_key, _pending_transfer_queue_bump) =
+        Pubkey::try_find_program_address(
+            hyperlane_token_pending_transfer_queue_pda_seeds!(),
+            &program_id,
+        )
+        .ok_or(ProgramError::InvalidSeeds)?;
+
+    let ixn = Instruction::ClaimPendingTransfer(nonce);
+
+    // Accounts:
+    // 0.   `[executable]` The system program.
+    // 1.   `[writeable]` The token PDA account.
+    // 2.   `[writeable]` The pending transfer queue PDA account.
+    // 3.   `[depends on plugin]` The recipient wallet.
+    // 4..N `[??..??]` Plugin-specific accounts.
+    let accounts = vec![
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new(token_key, false),
+        AccountMeta::new(pending_transfer_queue_key, false),
+        AccountMeta {
+            pubkey: recipient,
+            is_signer: false,
+            is_writable: writeable_recipient,
+        },
+    ];
+
+    let instruction = SolanaInstruction {
+        program_id,
+        data: ixn.encode()?,
+        accounts,
+    };
+
+    Ok(instruction)
+}
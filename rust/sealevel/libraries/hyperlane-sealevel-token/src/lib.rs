@@ -9,6 +9,7 @@ pub mod error;
 pub mod instruction;
 pub mod message;
 pub mod processor;
+pub mod rate_limit;
 
 pub use spl_associated_token_account;
 pub use spl_noop;
@@ -12,7 +12,7 @@ use hyperlane_sealevel_connection_client::{
     },
     HyperlaneConnectionClient, HyperlaneConnectionClientSetterAccessControl,
 };
-use hyperlane_sealevel_igp::accounts::InterchainGasPaymasterType;
+use hyperlane_sealevel_igp::accounts::{IgpAccount, InterchainGasPaymasterType};
 use hyperlane_sealevel_mailbox::{
     mailbox_message_dispatch_authority_pda_seeds, mailbox_process_authority_pda_seeds,
 };
@@ -27,15 +27,19 @@ use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
-    sysvar::Sysvar,
+    sysvar::{clock::Clock, Sysvar},
 };
 use std::collections::HashMap;
 
 use crate::{
-    accounts::{HyperlaneToken, HyperlaneTokenAccount},
+    accounts::{
+        HyperlaneToken, HyperlaneTokenAccount, PendingTransfer, PendingTransferQueue,
+        PendingTransferQueueAccount, MAX_PENDING_TRANSFERS,
+    },
     error::Error,
     instruction::{Init, TransferRemote},
     message::TokenMessage,
+    rate_limit::{RateLimitConfig, RateLimitParams, TrailingRateLimits, TransferDirection},
 };
 
 /// Seeds relating to the PDA account with information about this warp route.
@@ -66,6 +70,24 @@ macro_rules! hyperlane_token_pda_seeds {
     }};
 }
 
+/// Seeds relating to the PDA account holding the inbound transfers that were
+/// queued due to a rate limit.
+#[macro_export]
+macro_rules! hyperlane_token_pending_transfer_queue_pda_seeds {
+    () => {{
+        &[b"hyperlane_token", b"-", b"pending_transfer_queue"]
+    }};
+
+    ($bump_seed:expr) => {{
+        &[
+            b"hyperlane_token",
+            b"-",
+            b"pending_transfer_queue",
+            &[$bump_seed],
+        ]
+    }};
+}
+
 /// A plugin that handles token transfers for a Hyperlane Sealevel Token program.
 pub trait HyperlaneSealevelTokenPlugin
 where
@@ -220,6 +242,7 @@ where
             remote_decimals: init.remote_decimals,
             remote_routers: HashMap::new(),
             plugin_data,
+            rate_limits: TrailingRateLimits(None),
         };
         let token_account_data = HyperlaneTokenAccount::<T>::from(token);
 
@@ -253,11 +276,13 @@ where
     /// Transfers tokens to a remote.
     /// Calls the plugin's `transfer_in` function to transfer tokens in,
     /// then dispatches a message to the remote recipient.
+    /// Errors if the transfer exceeds the outbound rate limit for the destination.
     ///
     /// Accounts:
     /// 0.    `[executable]` The system program.
     /// 1.    `[executable]` The spl_noop program.
-    /// 2.    `[]` The token PDA account.
+    /// 2.    `[writeable]` The token PDA account. Only needs to be writeable if an
+    ///       outbound rate limit is configured for the destination.
     /// 3.    `[executable]` The mailbox program.
     /// 4.    `[writeable]` The mailbox outbox account.
     /// 5.    `[]` Message dispatch authority.
@@ -270,8 +295,10 @@ where
     /// 11.   `[writeable]` Gas payment PDA.
     /// 12.   `[]` OPTIONAL - The Overhead IGP program, if the configured IGP is an Overhead IGP.
     /// 13.   `[writeable]` The IGP account.
+    /// 14.   `[]` OPTIONAL - The gas oracle price feed account, if the IGP's gas oracle
+    ///       for the destination is a price feed.
     ///      ---- End if ----
-    /// 14..N `[??..??]` Plugin-specific accounts.
+    /// 15..N `[??..??]` Plugin-specific accounts.
     pub fn transfer_remote(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -293,7 +320,7 @@ where
 
         // Account 2: Token storage account
         let token_account = next_account_info(accounts_iter)?;
-        let token =
+        let mut token =
             HyperlaneTokenAccount::fetch(&mut &token_account.data.borrow()[..])?.into_inner();
         let token_seeds: &[&[u8]] = hyperlane_token_pda_seeds!(token.bump);
         let expected_token_key = Pubkey::create_program_address(token_seeds, program_id)?;
@@ -369,6 +396,8 @@ where
                 // 4. `[writeable]` Gas payment PDA.
                 // 5. `[writeable]` The IGP account.
                 // 6. `[]` Overhead IGP account (optional).
+                // 7. `[]` The gas oracle price feed account, required if the destination
+                //    domain's gas oracle is a price feed.
 
                 let mut igp_payment_account_metas = vec![
                     AccountMeta::new_readonly(solana_program::system_program::id(), false),
@@ -385,11 +414,12 @@ where
                     igp_payment_pda_account.clone(),
                 ];
 
-                match igp_account_type {
+                let igp_account = match igp_account_type {
                     InterchainGasPaymasterType::Igp(_) => {
                         igp_payment_account_metas
                             .push(AccountMeta::new(*configured_igp_account.key, false));
                         igp_payment_account_infos.push(configured_igp_account.clone());
+                        configured_igp_account
                     }
                     InterchainGasPaymasterType::OverheadIgp(_) => {
                         // Account 13: The inner IGP account.
@@ -402,9 +432,24 @@ where
                         ]);
                         igp_payment_account_infos
                             .extend([inner_igp_account.clone(), configured_igp_account.clone()]);
+                        inner_igp_account
                     }
                 };
 
+                // Account 14: The gas oracle price feed account (optional).
+                // Only expected if the IGP reads the destination's gas data from a price feed.
+                // The IGP verifies the IGP account and the price feed account.
+                let igp = IgpAccount::fetch(&mut &igp_account.data.borrow()[..])?.into_inner();
+                if let Some(price_feed) = igp.price_feed(xfer.destination_domain) {
+                    let price_feed_account = next_account_info(accounts_iter)?;
+                    if price_feed_account.key != price_feed {
+                        return Err(ProgramError::InvalidArgument);
+                    }
+                    igp_payment_account_metas
+                        .push(AccountMeta::new_readonly(*price_feed_account.key, false));
+                    igp_payment_account_infos.push(price_feed_account.clone());
+                }
+
                 Some((igp_payment_account_metas, igp_payment_account_infos))
             } else {
                 None
@@ -419,6 +464,16 @@ where
         // by the remote routers as the number of decimals used by the message amount.
         let remote_amount = token.local_amount_to_remote_amount(local_amount)?;
 
+        // Consume the outbound rate limit capacity for the destination, if any.
+        // This errors if there is insufficient capacity.
+        let rate_limited = match token.outbound_rate_limit_mut(xfer.destination_domain) {
+            Some(rate_limit) => {
+                rate_limit.consume(local_amount, Clock::get()?.unix_timestamp)?;
+                true
+            }
+            None => false,
+        };
+
         // Transfer `local_amount` of tokens in...
         T::transfer_in(
             program_id,
@@ -487,15 +542,33 @@ where
             remote_amount
         );
 
+        if rate_limited {
+            // Store the updated rate limit. No need to realloc, the size is the same.
+            HyperlaneTokenAccount::<T>::from(token).store(token_account, false)?;
+        }
+
         Ok(())
     }
 
+    /// Transfers tokens from a remote.
+    /// Calls the plugin's `transfer_out` function to transfer tokens out, unless the
+    /// transfer exceeds the inbound rate limit for the origin, in which case the
+    /// transfer is queued in the pending transfer queue to be claimed later.
+    /// To preserve the order of transfers, a transfer is also queued if older
+    /// transfers from the same origin are still pending.
+    /// If the pending transfer queue is full, this errors and the message can be
+    /// processed again once pending transfers have been claimed.
+    ///
     /// Accounts:
     /// 0.   `[signer]` Mailbox processor authority specific to this program.
     /// 1.   `[executable]` system_program
-    /// 2.   `[]` hyperlane_token storage
+    /// 2.   `[writeable]` hyperlane_token storage. Only needs to be writeable if an
+    ///      inbound rate limit is configured for the origin.
     /// 3.   [depends on plugin] recipient wallet address
-    /// 4..N `[??..??]` Plugin-specific accounts.
+    ///      ---- If an inbound rate limit is configured for the origin ----
+    /// 4.   `[writeable]` The pending transfer queue PDA account.
+    ///      ---- End if ----
+    /// 5..N `[??..??]` Plugin-specific accounts.
     pub fn transfer_from_remote(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -519,7 +592,7 @@ where
 
         // Account 2: Token account
         let token_account = next_account_info(accounts_iter)?;
-        let token =
+        let mut token =
             HyperlaneTokenAccount::fetch(&mut &token_account.data.borrow()[..])?.into_inner();
         let token_seeds: &[&[u8]] = hyperlane_token_pda_seeds!(token.bump);
         let expected_token_key = Pubkey::create_program_address(token_seeds, program_id)?;
@@ -547,6 +620,55 @@ where
         // Convert to the local number of decimals.
         let local_amount: u64 = token.remote_amount_to_local_amount(remote_amount)?;
 
+        let rate_limited = token.is_inbound_rate_limited(xfer.origin);
+        if rate_limited {
+            // Account 4: Pending transfer queue PDA.
+            let pending_transfer_queue_account = next_account_info(accounts_iter)?;
+            let mut pending_transfer_queue =
+                Self::fetch_pending_transfer_queue(program_id, pending_transfer_queue_account)?;
+
+            let now = Clock::get()?.unix_timestamp;
+            // Transfers from the origin are released in order, so a transfer
+            // can't overtake older pending transfers from the same origin.
+            let must_queue = pending_transfer_queue.next_from(xfer.origin).is_some()
+                || token
+                    .inbound_rate_limit_mut(xfer.origin)
+                    .map(|rate_limit| rate_limit.consume(local_amount, now).is_err())
+                    .unwrap_or(false);
+            if must_queue {
+                // Queue the transfer to be claimed once there is enough capacity.
+                // Any plugin-specific accounts are intentionally left unused.
+                if pending_transfer_queue.transfers.len() >= MAX_PENDING_TRANSFERS {
+                    return Err(Error::PendingTransferQueueFull.into());
+                }
+                let nonce = pending_transfer_queue.next_nonce;
+                pending_transfer_queue.next_nonce = nonce
+                    .checked_add(1)
+                    .ok_or(ProgramError::from(Error::IntegerOverflow))?;
+                pending_transfer_queue.transfers.push(PendingTransfer {
+                    nonce,
+                    origin: xfer.origin,
+                    recipient: *recipient_wallet.key,
+                    amount: local_amount,
+                    queued_at: now,
+                });
+                // No need to realloc, the queue is allocated with space for
+                // `MAX_PENDING_TRANSFERS` transfers.
+                PendingTransferQueueAccount::from(pending_transfer_queue)
+                    .store(pending_transfer_queue_account, false)?;
+
+                msg!(
+                    "Warp route transfer from origin: {}, recipient: {}, remote_amount: {} is rate limited, queued as pending transfer {}",
+                    xfer.origin,
+                    recipient_wallet.key,
+                    remote_amount,
+                    nonce
+                );
+
+                return Ok(());
+            }
+        }
+
         // Transfer the `local_amount` of tokens out.
         T::transfer_out(
             program_id,
@@ -561,6 +683,11 @@ where
             return Err(ProgramError::from(Error::ExtraneousAccount));
         }
 
+        if rate_limited {
+            // Store the updated rate limit. No need to realloc, the size is the same.
+            HyperlaneTokenAccount::<T>::from(token).store(token_account, false)?;
+        }
+
         msg!(
             "Warp route transfer completed from origin: {}, recipient: {}, remote_amount: {}",
             xfer.origin,
@@ -571,6 +698,126 @@ where
         Ok(())
     }
 
+    /// Deserializes the pending transfer queue from the provided account and returns it.
+    /// Returns an Err if the provided account is not the pending transfer queue PDA for this program.
+    fn fetch_pending_transfer_queue(
+        program_id: &Pubkey,
+        pending_transfer_queue_account: &AccountInfo,
+    ) -> Result<PendingTransferQueue, ProgramError> {
+        if pending_transfer_queue_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pending_transfer_queue = PendingTransferQueueAccount::fetch_data(
+            &mut &pending_transfer_queue_account.data.borrow()[..],
+        )?
+        .ok_or(ProgramError::UninitializedAccount)?;
+        let expected_pending_transfer_queue_key = Pubkey::create_program_address(
+            hyperlane_token_pending_transfer_queue_pda_seeds!(pending_transfer_queue.bump),
+            program_id,
+        )?;
+        if pending_transfer_queue_account.key != &expected_pending_transfer_queue_key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(*pending_transfer_queue)
+    }
+
+    /// Claims an inbound transfer that was queued due to a rate limit, once
+    /// there is enough inbound capacity for its origin. Pending transfers from
+    /// an origin must be claimed in the order they were queued. Anyone may claim
+    /// a pending transfer on behalf of its recipient.
+    ///
+    /// Accounts:
+    /// 0.   `[executable]` The system program.
+    /// 1.   `[writeable]` The token PDA account.
+    /// 2.   `[writeable]` The pending transfer queue PDA account.
+    /// 3.   `[depends on plugin]` The recipient wallet.
+    /// 4..N `[??..??]` Plugin-specific accounts.
+    pub fn claim_pending_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        nonce: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        // Account 0: System program.
+        let system_program = next_account_info(accounts_iter)?;
+        if system_program.key != &solana_program::system_program::id() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Account 1: Token account.
+        let token_account = next_account_info(accounts_iter)?;
+        let mut token = HyperlaneToken::verify_account_and_fetch_inner(program_id, token_account)?;
+
+        // Account 2: Pending transfer queue PDA.
+        let pending_transfer_queue_account = next_account_info(accounts_iter)?;
+        let mut pending_transfer_queue =
+            Self::fetch_pending_transfer_queue(program_id, pending_transfer_queue_account)?;
+        let index = pending_transfer_queue
+            .transfers
+            .iter()
+            .position(|transfer| transfer.nonce == nonce)
+            .ok_or(ProgramError::InvalidArgument)?;
+        let pending_transfer = pending_transfer_queue.transfers.remove(index);
+        if pending_transfer_queue
+            .next_from(pending_transfer.origin)
+            .map(|next| next.nonce < nonce)
+            .unwrap_or(false)
+        {
+            return Err(Error::PendingTransferNotNext.into());
+        }
+
+        // Account 3: Recipient wallet.
+        let recipient_wallet = next_account_info(accounts_iter)?;
+        if recipient_wallet.key != &pending_transfer.recipient {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Consume the inbound rate limit capacity for the origin, if the
+        // rate limit still exists. This errors if there is insufficient capacity.
+        if let Some(rate_limit) = token.inbound_rate_limit_mut(pending_transfer.origin) {
+            rate_limit.consume(pending_transfer.amount, Clock::get()?.unix_timestamp)?;
+            // A rate limit that was lifted while transfers were pending is
+            // removed once the last of them is claimed.
+            if rate_limit.is_unlimited()
+                && pending_transfer_queue
+                    .next_from(pending_transfer.origin)
+                    .is_none()
+            {
+                token.remove_inbound_rate_limit(pending_transfer.origin);
+            }
+        }
+
+        // Transfer the amount of tokens out.
+        T::transfer_out(
+            program_id,
+            &token,
+            system_program,
+            recipient_wallet,
+            accounts_iter,
+            pending_transfer.amount,
+        )?;
+
+        if accounts_iter.next().is_some() {
+            return Err(ProgramError::from(Error::ExtraneousAccount));
+        }
+
+        // Store the updated queue and rate limit. No need to realloc, neither grew.
+        PendingTransferQueueAccount::from(pending_transfer_queue)
+            .store(pending_transfer_queue_account, false)?;
+        HyperlaneTokenAccount::<T>::from(token).store(token_account, false)?;
+
+        msg!(
+            "Claimed pending transfer {} from origin: {}, recipient: {}, amount: {}",
+            nonce,
+            pending_transfer.origin,
+            pending_transfer.recipient,
+            pending_transfer.amount
+        );
+
+        Ok(())
+    }
+
     /// Gets the account metas required by the `HandleInstruction` instruction,
     /// serializes them, and sets them as return data.
     ///
@@ -594,9 +841,18 @@ where
         let (transfer_out_account_metas, writeable_recipient) =
             T::transfer_out_account_metas(program_id, &token, &message)?;
 
+        // The token account is only written to if an inbound rate limit is
+        // configured for the origin.
+        let inbound_rate_limited = token.is_inbound_rate_limited(transfer.origin);
+
         let mut accounts: Vec<SerializableAccountMeta> = vec![
             AccountMeta::new_readonly(solana_program::system_program::id(), false).into(),
-            AccountMeta::new_readonly(*token_account_info.key, false).into(),
+            AccountMeta {
+                pubkey: *token_account_info.key,
+                is_signer: false,
+                is_writable: inbound_rate_limited,
+            }
+            .into(),
             AccountMeta {
                 pubkey: Pubkey::new_from_array(message.recipient().into()),
                 is_signer: false,
@@ -604,6 +860,14 @@ where
             }
             .into(),
         ];
+        if inbound_rate_limited {
+            let (pending_transfer_queue_key, _pending_transfer_queue_bump) =
+                Pubkey::find_program_address(
+                    hyperlane_token_pending_transfer_queue_pda_seeds!(),
+                    program_id,
+                );
+            accounts.push(AccountMeta::new(pending_transfer_queue_key, false).into());
+        }
         accounts.extend(transfer_out_account_metas);
 
         // Wrap it in the SimulationReturnData because serialized account_metas
@@ -884,4 +1148,116 @@ where
 
         Ok(())
     }
+
+    /// Lets the owner set or remove rate limits.
+    /// The pending transfer queue PDA is created, funded by the owner, the first
+    /// time an inbound rate limit is set. Removing an inbound rate limit while
+    /// transfers from the origin are pending only lifts it until they're claimed.
+    ///
+    /// Accounts:
+    /// 0. `[executable]` The system program.
+    /// 1. `[writeable]` The token PDA account.
+    /// 2. `[signer, writeable]` The access control owner.
+    /// 3. `[writeable]` The pending transfer queue PDA account.
+    pub fn set_rate_limits(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        configs: Vec<RateLimitConfig>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        // Account 0: System program. Only used if a realloc / rent exemption top up occurs.
+        let system_program = next_account_info(accounts_iter)?;
+        if system_program.key != &solana_program::system_program::id() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Account 1: Token account
+        let token_account = next_account_info(accounts_iter)?;
+        let mut token = HyperlaneToken::verify_account_and_fetch_inner(program_id, token_account)?;
+
+        // Account 2: Owner
+        let owner_account = next_account_info(accounts_iter)?;
+
+        // This errors if owner_account is not really the owner.
+        token.ensure_owner_signer(owner_account)?;
+
+        // Account 3: Pending transfer queue PDA
+        let pending_transfer_queue_account = next_account_info(accounts_iter)?;
+        let (pending_transfer_queue_key, pending_transfer_queue_bump) =
+            Pubkey::find_program_address(
+                hyperlane_token_pending_transfer_queue_pda_seeds!(),
+                program_id,
+            );
+        if pending_transfer_queue_account.key != &pending_transfer_queue_key {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let pending_transfer_queue = if pending_transfer_queue_account.data_is_empty() {
+            None
+        } else {
+            Some(Self::fetch_pending_transfer_queue(
+                program_id,
+                pending_transfer_queue_account,
+            )?)
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        for config in configs.iter() {
+            // Transfers from an origin are released in order. If its inbound rate
+            // limit is removed while transfers from it are still pending, the limit
+            // is lifted instead, so that new transfers keep queueing behind the
+            // pending ones. It's removed once they've all been claimed.
+            let has_pending_transfers = config.direction == TransferDirection::Inbound
+                && config.rate_limit.is_none()
+                && token.is_inbound_rate_limited(config.domain)
+                && pending_transfer_queue
+                    .as_ref()
+                    .map(|queue| queue.next_from(config.domain).is_some())
+                    .unwrap_or(false);
+            if has_pending_transfers {
+                token.set_rate_limit(
+                    &RateLimitConfig {
+                        rate_limit: Some(RateLimitParams::UNLIMITED),
+                        ..config.clone()
+                    },
+                    now,
+                )?;
+            } else {
+                token.set_rate_limit(config, now)?;
+            }
+        }
+
+        let rent = Rent::get()?;
+
+        // Create the pending transfer queue if inbound transfers can now be queued.
+        if token.has_inbound_rate_limits() && pending_transfer_queue_account.data_is_empty() {
+            let pending_transfer_queue_account_data =
+                PendingTransferQueueAccount::from(PendingTransferQueue {
+                    bump: pending_transfer_queue_bump,
+                    ..Default::default()
+                });
+            create_pda_account(
+                owner_account,
+                &rent,
+                // Add an extra byte for the initialized flag.
+                1 + PendingTransferQueue::MAX_SIZE,
+                program_id,
+                system_program,
+                pending_transfer_queue_account,
+                hyperlane_token_pending_transfer_queue_pda_seeds!(pending_transfer_queue_bump),
+            )?;
+            pending_transfer_queue_account_data.store(pending_transfer_queue_account, false)?;
+        }
+
+        // Store the updated token account and realloc if necessary.
+        HyperlaneTokenAccount::<T>::from(token).store_with_rent_exempt_realloc(
+            token_account,
+            &rent,
+            owner_account,
+            system_program,
+        )?;
+
+        Ok(())
+    }
 }
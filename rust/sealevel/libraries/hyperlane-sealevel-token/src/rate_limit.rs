@@ -0,0 +1,441 @@
+//! Rolling-window rate limits for transfers to and from remote domains.
+
+use account_utils::SizedData;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, program_error::ProgramError};
+use std::collections::HashMap;
+
+use crate::error::Error;
+
+/// The direction of a transfer, relative to this chain.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    /// Transfers from this chain to a remote domain.
+    Outbound,
+    /// Transfers from a remote domain to this chain.
+    Inbound,
+}
+
+/// The parameters of a rate limit.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitParams {
+    /// The maximum amount, in local decimals, that can be transferred within
+    /// any window of `window_seconds`.
+    pub capacity: u64,
+    /// The length of the rolling window, in seconds.
+    pub window_seconds: u64,
+}
+
+impl RateLimitParams {
+    /// Parameters that never limit transfers.
+    pub const UNLIMITED: Self = Self {
+        capacity: u64::MAX,
+        window_seconds: 1,
+    };
+}
+
+/// Rate limit configuration for a single remote domain and direction.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    /// The remote domain.
+    pub domain: u32,
+    /// The direction of transfers that are rate limited.
+    pub direction: TransferDirection,
+    /// The rate limit parameters, or None to remove the rate limit.
+    pub rate_limit: Option<RateLimitParams>,
+}
+
+/// A rolling-window rate limit.
+///
+/// The available capacity is consumed by transfers and refills linearly,
+/// reaching the full `capacity` after `window_seconds` without any transfers.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct RateLimit {
+    /// The maximum amount, in local decimals, that can be transferred within
+    /// any window of `window_seconds`.
+    pub capacity: u64,
+    /// The length of the rolling window, in seconds.
+    pub window_seconds: u64,
+    /// The capacity that was available as of `last_updated`.
+    pub available: u64,
+    /// The unix timestamp the available capacity was last updated at.
+    pub last_updated: i64,
+}
+
+impl RateLimit {
+    /// The serialized size of a `RateLimit`.
+    pub const SIZE: usize =
+        // capacity
+        std::mem::size_of::<u64>() +
+        // window_seconds
+        std::mem::size_of::<u64>() +
+        // available
+        std::mem::size_of::<u64>() +
+        // last_updated
+        std::mem::size_of::<i64>();
+
+    /// Creates a new rate limit with its full capacity available.
+    pub fn new(params: RateLimitParams, now: i64) -> Result<Self, ProgramError> {
+        if params.window_seconds == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(Self {
+            capacity: params.capacity,
+            window_seconds: params.window_seconds,
+            available: params.capacity,
+            last_updated: now,
+        })
+    }
+
+    /// Returns true if the rate limit never limits transfers.
+    pub fn is_unlimited(&self) -> bool {
+        self.capacity == u64::MAX
+    }
+
+    /// Returns the capacity available at the unix timestamp `now`.
+    pub fn available_at(&self, now: i64) -> u64 {
+        self.refilled(now).0
+    }
+
+    /// Consumes `amount` of the capacity available at the unix timestamp `now`.
+    /// Errors without consuming anything if the available capacity is insufficient.
+    pub fn consume(&mut self, amount: u64, now: i64) -> Result<(), ProgramError> {
+        let (available, last_updated) = self.refilled(now);
+        if amount > available {
+            return Err(Error::RateLimitExceeded.into());
+        }
+        self.available = available - amount;
+        self.last_updated = last_updated;
+        Ok(())
+    }
+
+    /// Updates the parameters of the rate limit, preserving the currently
+    /// consumed capacity where possible.
+    pub fn set_params(&mut self, params: RateLimitParams, now: i64) -> Result<(), ProgramError> {
+        if params.window_seconds == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let (available, _) = self.refilled(now);
+        let consumed = self.capacity.saturating_sub(available);
+        self.capacity = params.capacity;
+        self.window_seconds = params.window_seconds;
+        self.available = params.capacity.saturating_sub(consumed);
+        self.last_updated = now;
+        Ok(())
+    }
+
+    /// Returns the (available capacity, last updated timestamp) after refilling up to `now`.
+    /// Only the time that actually resulted in a refill is accounted for, so that
+    /// frequent updates don't round the refill rate down to zero.
+    fn refilled(&self, now: i64) -> (u64, i64) {
+        let elapsed = now.saturating_sub(self.last_updated).max(0) as u128;
+        let capacity = self.capacity as u128;
+        let window = self.window_seconds as u128;
+
+        let refill = capacity.saturating_mul(elapsed) / window;
+        let available = (self.available as u128).saturating_add(refill);
+        if available >= capacity {
+            return (self.capacity, now);
+        }
+        if refill == 0 {
+            return (self.available, self.last_updated);
+        }
+        // `refill * window / capacity <= elapsed`, which fits in an i64.
+        let accounted_for = (refill * window / capacity) as i64;
+        (available as u64, self.last_updated + accounted_for)
+    }
+}
+
+/// A discriminator that prefixes the rate limits stored in a token account.
+pub const RATE_LIMITS_DISCRIMINATOR: &[u8; 8] = b"RATELIMS";
+
+/// The rate limits on a token's transfers to and from remote domains.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct RateLimits {
+    /// Rate limits on transfers to remote domains.
+    pub outbound: HashMap<u32, RateLimit>,
+    /// Rate limits on transfers from remote domains.
+    pub inbound: HashMap<u32, RateLimit>,
+}
+
+impl RateLimits {
+    /// Returns the rate limits for transfers in the provided direction.
+    pub fn get(&self, direction: TransferDirection) -> &HashMap<u32, RateLimit> {
+        match direction {
+            TransferDirection::Outbound => &self.outbound,
+            TransferDirection::Inbound => &self.inbound,
+        }
+    }
+
+    /// Returns the rate limits for transfers in the provided direction, mutably.
+    pub fn get_mut(&mut self, direction: TransferDirection) -> &mut HashMap<u32, RateLimit> {
+        match direction {
+            TransferDirection::Outbound => &mut self.outbound,
+            TransferDirection::Inbound => &mut self.inbound,
+        }
+    }
+}
+
+impl SizedData for RateLimits {
+    fn size(&self) -> usize {
+        // outbound length
+        std::mem::size_of::<u32>() +
+        // outbound keys & values
+        (self.outbound.len() * (std::mem::size_of::<u32>() + RateLimit::SIZE)) +
+        // inbound length
+        std::mem::size_of::<u32>() +
+        // inbound keys & values
+        (self.inbound.len() * (std::mem::size_of::<u32>() + RateLimit::SIZE))
+    }
+}
+
+/// The rate limits stored at the end of a token account, after the plugin data.
+///
+/// Token accounts created before rate limits were introduced end with the plugin
+/// data, possibly followed by stale bytes left behind when the serialized data
+/// shrank, e.g. when a remote router was unenrolled. So that these accounts keep
+/// their layout and can't have stale bytes mistaken for rate limits:
+/// - nothing is serialized until rate limits are first configured, and
+/// - rate limits are only deserialized if prefixed by `RATE_LIMITS_DISCRIMINATOR`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TrailingRateLimits(pub Option<RateLimits>);
+
+impl BorshSerialize for TrailingRateLimits {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if let Some(rate_limits) = &self.0 {
+            writer.write_all(RATE_LIMITS_DISCRIMINATOR)?;
+            rate_limits.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for TrailingRateLimits {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        match buf.strip_prefix(RATE_LIMITS_DISCRIMINATOR) {
+            Some(rest) => {
+                *buf = rest;
+                Ok(Self(Some(RateLimits::deserialize(buf)?)))
+            }
+            None => Ok(Self(None)),
+        }
+    }
+}
+
+impl SizedData for TrailingRateLimits {
+    fn size(&self) -> usize {
+        self.0
+            .as_ref()
+            .map(|rate_limits| RATE_LIMITS_DISCRIMINATOR.len() + rate_limits.size())
+            .unwrap_or(0)
+    }
+}
+
+/// Sets or removes the rate limit in `rate_limits` described by `config`.
+pub fn set_rate_limit(
+    rate_limits: &mut HashMap<u32, RateLimit>,
+    config: &RateLimitConfig,
+    now: i64,
+) -> Result<(), ProgramError> {
+    match config.rate_limit {
+        Some(params) => match rate_limits.get_mut(&config.domain) {
+            Some(rate_limit) => rate_limit.set_params(params, now)?,
+            None => {
+                rate_limits.insert(config.domain, RateLimit::new(params, now)?);
+            }
+        },
+        None => {
+            rate_limits.remove(&config.domain);
+        }
+    }
+
+    msg!(
+        "Set {:?} rate limit for domain {} to: {:?}",
+        config.direction,
+        config.domain,
+        config.rate_limit
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DAY: u64 = 86400;
+
+    fn rate_limit(capacity: u64, window_seconds: u64) -> RateLimit {
+        RateLimit::new(
+            RateLimitParams {
+                capacity,
+                window_seconds,
+            },
+            1000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_new_errors_if_window_is_zero() {
+        assert_eq!(
+            RateLimit::new(
+                RateLimitParams {
+                    capacity: 100,
+                    window_seconds: 0,
+                },
+                1000,
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_consume() {
+        let mut limit = rate_limit(100, DAY);
+
+        assert_eq!(limit.consume(60, 1000), Ok(()));
+        assert_eq!(limit.available_at(1000), 40);
+
+        // Not enough capacity, and nothing is consumed.
+        assert_eq!(
+            limit.consume(41, 1000),
+            Err(Error::RateLimitExceeded.into())
+        );
+        assert_eq!(limit.available_at(1000), 40);
+
+        assert_eq!(limit.consume(40, 1000), Ok(()));
+        assert_eq!(limit.available_at(1000), 0);
+    }
+
+    #[test]
+    fn test_refill() {
+        let mut limit = rate_limit(100, 100);
+        limit.consume(100, 1000).unwrap();
+
+        // Refills linearly.
+        assert_eq!(limit.available_at(1025), 25);
+        assert_eq!(limit.available_at(1050), 50);
+        // And is capped at the capacity.
+        assert_eq!(limit.available_at(1100), 100);
+        assert_eq!(limit.available_at(5000), 100);
+    }
+
+    #[test]
+    fn test_refill_is_not_lost_to_rounding() {
+        // Refills at 1 token every 10 seconds.
+        let mut limit = rate_limit(10, 100);
+        limit.consume(10, 1000).unwrap();
+
+        // Frequent consumption of nothing must not prevent refills.
+        for now in (1000..=1100).step_by(3) {
+            limit.consume(0, now).unwrap();
+        }
+        assert_eq!(limit.available_at(1100), 10);
+    }
+
+    #[test]
+    fn test_set_params_preserves_consumed() {
+        let mut limit = rate_limit(100, DAY);
+        limit.consume(60, 1000).unwrap();
+
+        // Raising the capacity keeps the consumed amount.
+        limit
+            .set_params(
+                RateLimitParams {
+                    capacity: 200,
+                    window_seconds: DAY,
+                },
+                1000,
+            )
+            .unwrap();
+        assert_eq!(limit.available_at(1000), 140);
+
+        // Lowering the capacity below the consumed amount leaves nothing available.
+        limit
+            .set_params(
+                RateLimitParams {
+                    capacity: 50,
+                    window_seconds: DAY,
+                },
+                1000,
+            )
+            .unwrap();
+        assert_eq!(limit.available_at(1000), 0);
+    }
+
+    #[test]
+    fn test_trailing_rate_limits_serialization() {
+        // Nothing is serialized until rate limits are configured.
+        let unconfigured = TrailingRateLimits(None);
+        assert!(unconfigured.try_to_vec().unwrap().is_empty());
+        assert_eq!(unconfigured.size(), 0);
+
+        let configured = TrailingRateLimits(Some(RateLimits {
+            outbound: HashMap::from([(1234, rate_limit(100, DAY))]),
+            inbound: HashMap::new(),
+        }));
+        let mut serialized = configured.try_to_vec().unwrap();
+        assert_eq!(serialized.len(), configured.size());
+
+        // Bytes following the rate limits are ignored.
+        serialized.extend([1, 2, 3]);
+        assert_eq!(
+            TrailingRateLimits::deserialize(&mut &serialized[..]).unwrap(),
+            configured
+        );
+    }
+
+    #[test]
+    fn test_trailing_rate_limits_ignores_stale_bytes() {
+        // Stale bytes without the discriminator, e.g. left behind by an
+        // unenrolled remote router, aren't mistaken for rate limits.
+        let stale = [1u8, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut buf = &stale[..];
+        assert_eq!(
+            TrailingRateLimits::deserialize(&mut buf).unwrap(),
+            TrailingRateLimits(None)
+        );
+        assert_eq!(buf.len(), stale.len());
+
+        assert_eq!(
+            TrailingRateLimits::deserialize(&mut &[][..]).unwrap(),
+            TrailingRateLimits(None)
+        );
+    }
+
+    #[test]
+    fn test_set_rate_limit() {
+        let mut rate_limits = HashMap::new();
+        let config = RateLimitConfig {
+            domain: 1234,
+            direction: TransferDirection::Outbound,
+            rate_limit: Some(RateLimitParams {
+                capacity: 100,
+                window_seconds: DAY,
+            }),
+        };
+
+        set_rate_limit(&mut rate_limits, &config, 1000).unwrap();
+        assert_eq!(
+            rate_limits.get(&1234),
+            Some(&RateLimit {
+                capacity: 100,
+                window_seconds: DAY,
+                available: 100,
+                last_updated: 1000,
+            })
+        );
+
+        set_rate_limit(
+            &mut rate_limits,
+            &RateLimitConfig {
+                rate_limit: None,
+                ..config
+            },
+            1000,
+        )
+        .unwrap();
+        assert!(rate_limits.is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use hyperlane_core::H256;
 use std::collections::HashMap;
 
 use solana_program::{
+    clock::Clock,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     system_program,
@@ -10,23 +11,23 @@ use solana_program::{
 };
 use solana_program_test::*;
 use solana_sdk::{
-    instruction::InstructionError, signature::Signature, signature::Signer,
+    account::Account, instruction::InstructionError, signature::Signature, signature::Signer,
     signer::keypair::Keypair, transaction::TransactionError,
 };
 
 use hyperlane_test_utils::{
-    assert_transaction_error, igp_program_id, new_funded_keypair, process_instruction,
-    simulate_instruction, transfer_lamports,
+    assert_transaction_error, clone_keypair, igp_program_id, new_funded_keypair,
+    process_instruction, simulate_instruction, transfer_lamports,
 };
 use serializable_account_meta::SimulationReturnData;
 
 use access_control::AccessControl;
-use account_utils::{AccountData, DiscriminatorPrefixed, DiscriminatorPrefixedData};
+use account_utils::{AccountData, DiscriminatorPrefixed, DiscriminatorPrefixedData, SizedData};
 use hyperlane_sealevel_igp::{
     accounts::{
-        GasOracle, GasPaymentAccount, GasPaymentData, Igp, IgpAccount, OverheadIgp,
-        OverheadIgpAccount, ProgramData, ProgramDataAccount, RemoteGasData, SOL_DECIMALS,
-        TOKEN_EXCHANGE_RATE_SCALE,
+        GasOracle, GasPaymentAccount, GasPaymentData, GasPriceFeed, GasPriceFeedAccount, Igp,
+        IgpAccount, OverheadIgp, OverheadIgpAccount, PriceFeedGasOracle, ProgramData,
+        ProgramDataAccount, RemoteGasData, SOL_DECIMALS, TOKEN_EXCHANGE_RATE_SCALE,
     },
     error::Error as IgpError,
     igp_gas_payment_pda_seeds, igp_pda_seeds, igp_program_data_pda_seeds,
@@ -42,20 +43,60 @@ const TEST_DESTINATION_DOMAIN: u32 = 11111;
 const TEST_GAS_AMOUNT: u64 = 300000;
 const TEST_GAS_OVERHEAD_AMOUNT: u64 = 100000;
 const LOCAL_DECIMALS: u8 = SOL_DECIMALS;
+const TEST_PRICE_FEED_MAX_STALENESS_SECONDS: u64 = 60;
 
-async fn setup_client() -> (BanksClient, Keypair) {
+fn program_test() -> ProgramTest {
     let program_id = igp_program_id();
-    let program_test = ProgramTest::new(
+    ProgramTest::new(
         "hyperlane_sealevel_igp",
         program_id,
         processor!(igp_process_instruction),
-    );
+    )
+}
 
-    let (banks_client, payer, _recent_blockhash) = program_test.start().await;
+async fn setup_client() -> (BanksClient, Keypair) {
+    let (banks_client, payer, _recent_blockhash) = program_test().start().await;
 
     (banks_client, payer)
 }
 
+async fn setup_context() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+/// Writes a price feed account owned by `feed_owner` directly into the test bank.
+fn set_price_feed(
+    context: &mut ProgramTestContext,
+    feed_key: Pubkey,
+    feed_owner: Pubkey,
+    price_feed: GasPriceFeed,
+) {
+    let price_feed_account = GasPriceFeedAccount::new(price_feed.into());
+    let mut data = vec![0; price_feed_account.size()];
+    price_feed_account.store_in_slice(&mut data).unwrap();
+
+    let lamports = Rent::default().minimum_balance(data.len());
+    context.set_account(
+        &feed_key,
+        &Account {
+            lamports,
+            data,
+            owner: feed_owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+}
+
+async fn current_unix_timestamp(banks_client: &mut BanksClient) -> i64 {
+    banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp
+}
+
 async fn initialize(
     banks_client: &mut BanksClient,
     payer: &Keypair,
@@ -742,6 +783,7 @@ async fn quote_gas_payment(
     gas_amount: u64,
     igp_key: Pubkey,
     overhead_igp_key: Option<Pubkey>,
+    price_feed_key: Option<Pubkey>,
 ) -> Result<u64, BanksClientError> {
     let mut accounts = vec![
         AccountMeta::new_readonly(system_program::id(), false),
@@ -750,6 +792,9 @@ async fn quote_gas_payment(
     if let Some(overhead_igp_key) = overhead_igp_key {
         accounts.push(AccountMeta::new_readonly(overhead_igp_key, false));
     }
+    if let Some(price_feed_key) = price_feed_key {
+        accounts.push(AccountMeta::new_readonly(price_feed_key, false));
+    }
 
     let instruction = Instruction::new_with_borsh(
         igp_program_id(),
@@ -799,6 +844,7 @@ async fn run_quote_gas_payment_tests(gas_amount: u64, overhead_gas_amount: Optio
             TEST_GAS_AMOUNT,
             igp_key,
             None,
+            None,
         )
         .await
         .unwrap(),
@@ -834,6 +880,7 @@ async fn run_quote_gas_payment_tests(gas_amount: u64, overhead_gas_amount: Optio
             TEST_GAS_AMOUNT,
             igp_key,
             None,
+            None,
         )
         .await
         .unwrap(),
@@ -869,6 +916,7 @@ async fn run_quote_gas_payment_tests(gas_amount: u64, overhead_gas_amount: Optio
             TEST_GAS_AMOUNT,
             igp_key,
             None,
+            None,
         )
         .await
         .unwrap(),
@@ -904,6 +952,7 @@ async fn run_quote_gas_payment_tests(gas_amount: u64, overhead_gas_amount: Optio
             TEST_GAS_AMOUNT,
             igp_key,
             None,
+            None,
         )
         .await
         .unwrap(),
@@ -957,6 +1006,7 @@ async fn test_quote_gas_payment_errors_if_no_gas_oracle() {
             TEST_GAS_AMOUNT,
             igp_key,
             None,
+            None,
         )
         .await,
         TransactionError::InstructionError(
@@ -966,6 +1016,239 @@ async fn test_quote_gas_payment_errors_if_no_gas_oracle() {
     );
 }
 
+#[tokio::test]
+async fn test_quote_gas_payment_with_price_feed() {
+    let mut context = setup_context().await;
+    let price_feed_key = Pubkey::new_unique();
+    let price_feed_owner = Pubkey::new_unique();
+
+    let (igp_key, overhead_igp_key) = setup_test_igps(
+        &mut context.banks_client,
+        &context.payer,
+        TEST_DESTINATION_DOMAIN,
+        GasOracle::PriceFeed(PriceFeedGasOracle {
+            feed: price_feed_key,
+            feed_owner: price_feed_owner,
+            max_staleness_seconds: TEST_PRICE_FEED_MAX_STALENESS_SECONDS,
+            token_decimals: LOCAL_DECIMALS,
+        }),
+        Some(TEST_GAS_OVERHEAD_AMOUNT),
+    )
+    .await;
+
+    let now = current_unix_timestamp(&mut context.banks_client).await;
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        price_feed_owner,
+        GasPriceFeed {
+            // 0.2 exchange rate (remote token less valuable)
+            token_exchange_rate: (TOKEN_EXCHANGE_RATE_SCALE / 5),
+            gas_price: 150u128,
+            publish_time: now,
+        },
+    );
+
+    let payer = clone_keypair(&context.payer);
+
+    // 300,000 * 150 * 0.2 = 9000000, both without and with the overhead IGP.
+    assert_eq!(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            None,
+            Some(price_feed_key),
+        )
+        .await
+        .unwrap(),
+        9000000u64,
+    );
+    assert_eq!(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT - TEST_GAS_OVERHEAD_AMOUNT,
+            igp_key,
+            Some(overhead_igp_key),
+            Some(price_feed_key),
+        )
+        .await
+        .unwrap(),
+        9000000u64,
+    );
+
+    // Updates to the price feed are used without any change to the IGP.
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        price_feed_owner,
+        GasPriceFeed {
+            token_exchange_rate: (TOKEN_EXCHANGE_RATE_SCALE / 5),
+            gas_price: 300u128,
+            publish_time: now,
+        },
+    );
+
+    assert_eq!(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            None,
+            Some(price_feed_key),
+        )
+        .await
+        .unwrap(),
+        18000000u64,
+    );
+}
+
+#[tokio::test]
+async fn test_quote_gas_payment_errors_if_price_feed_is_stale() {
+    let mut context = setup_context().await;
+    let price_feed_key = Pubkey::new_unique();
+    let price_feed_owner = Pubkey::new_unique();
+
+    let (igp_key, _overhead_igp_key) = setup_test_igps(
+        &mut context.banks_client,
+        &context.payer,
+        TEST_DESTINATION_DOMAIN,
+        GasOracle::PriceFeed(PriceFeedGasOracle {
+            feed: price_feed_key,
+            feed_owner: price_feed_owner,
+            max_staleness_seconds: TEST_PRICE_FEED_MAX_STALENESS_SECONDS,
+            token_decimals: LOCAL_DECIMALS,
+        }),
+        None,
+    )
+    .await;
+
+    let now = current_unix_timestamp(&mut context.banks_client).await;
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        price_feed_owner,
+        GasPriceFeed {
+            token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE,
+            gas_price: 1u128,
+            publish_time: now - TEST_PRICE_FEED_MAX_STALENESS_SECONDS as i64 - 1,
+        },
+    );
+
+    let payer = clone_keypair(&context.payer);
+
+    assert_transaction_error(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            None,
+            Some(price_feed_key),
+        )
+        .await,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(IgpError::StalePriceFeed as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_quote_gas_payment_errors_if_price_feed_is_invalid() {
+    let mut context = setup_context().await;
+    let price_feed_key = Pubkey::new_unique();
+    let price_feed_owner = Pubkey::new_unique();
+
+    let (igp_key, overhead_igp_key) = setup_test_igps(
+        &mut context.banks_client,
+        &context.payer,
+        TEST_DESTINATION_DOMAIN,
+        GasOracle::PriceFeed(PriceFeedGasOracle {
+            feed: price_feed_key,
+            feed_owner: price_feed_owner,
+            max_staleness_seconds: TEST_PRICE_FEED_MAX_STALENESS_SECONDS,
+            token_decimals: LOCAL_DECIMALS,
+        }),
+        None,
+    )
+    .await;
+
+    let now = current_unix_timestamp(&mut context.banks_client).await;
+    let price_feed = GasPriceFeed {
+        token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE,
+        gas_price: 1u128,
+        publish_time: now,
+    };
+
+    let payer = clone_keypair(&context.payer);
+
+    // The price feed account isn't provided
+    assert_transaction_error(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            None,
+            None,
+        )
+        .await,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys),
+    );
+
+    // The price feed account isn't owned by the configured program
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        Pubkey::new_unique(),
+        price_feed.clone(),
+    );
+    assert_transaction_error(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            None,
+            Some(price_feed_key),
+        )
+        .await,
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId),
+    );
+
+    // A different, otherwise valid, price feed account is provided after the overhead IGP
+    let other_price_feed_key = Pubkey::new_unique();
+    set_price_feed(
+        &mut context,
+        other_price_feed_key,
+        price_feed_owner,
+        price_feed,
+    );
+    assert_transaction_error(
+        quote_gas_payment(
+            &mut context.banks_client,
+            &payer,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            igp_key,
+            Some(overhead_igp_key),
+            Some(other_price_feed_key),
+        )
+        .await,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument),
+    );
+}
+
 // ============ PayForGas ============
 
 async fn pay_for_gas(
@@ -973,6 +1256,7 @@ async fn pay_for_gas(
     payer: &Keypair,
     igp: Pubkey,
     overhead_igp: Option<Pubkey>,
+    price_feed: Option<Pubkey>,
     destination_domain: u32,
     gas_amount: u64,
     message_id: H256,
@@ -993,6 +1277,7 @@ async fn pay_for_gas(
     // 4. `[writeable]` Gas payment PDA.
     // 5. `[writeable]` The IGP account.
     // 6. `[]` Overhead IGP account (optional).
+    // 7. `[]` The gas oracle price feed account (optional).
     let mut accounts = vec![
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new(payer.pubkey(), true),
@@ -1004,6 +1289,9 @@ async fn pay_for_gas(
     if let Some(overhead_igp) = overhead_igp {
         accounts.push(AccountMeta::new_readonly(overhead_igp, false));
     }
+    if let Some(price_feed) = price_feed {
+        accounts.push(AccountMeta::new_readonly(price_feed, false));
+    }
 
     let instruction = Instruction::new_with_borsh(
         program_id,
@@ -1100,6 +1388,7 @@ async fn run_pay_for_gas_tests(gas_amount: u64, overhead_gas_amount: Option<u64>
         igp_key,
         // Only pass in the overhead igp key if there's an overhead amount
         overhead_gas_amount.map(|_| overhead_igp_key),
+        None,
     )
     .await
     .unwrap();
@@ -1112,6 +1401,7 @@ async fn run_pay_for_gas_tests(gas_amount: u64, overhead_gas_amount: Option<u64>
         igp_key,
         // Only pass in the overhead igp key if there's an overhead amount
         overhead_gas_amount.map(|_| overhead_igp_key),
+        None,
         TEST_DESTINATION_DOMAIN,
         gas_amount,
         message_id,
@@ -1145,6 +1435,7 @@ async fn run_pay_for_gas_tests(gas_amount: u64, overhead_gas_amount: Option<u64>
         igp_key,
         // Only pass in the overhead igp key if there's an overhead amount
         overhead_gas_amount.map(|_| overhead_igp_key),
+        None,
         TEST_DESTINATION_DOMAIN,
         gas_amount,
         message_id,
@@ -1177,6 +1468,105 @@ async fn test_pay_for_gas_with_overhead() {
     run_pay_for_gas_tests(TEST_GAS_AMOUNT, Some(TEST_GAS_OVERHEAD_AMOUNT)).await;
 }
 
+#[tokio::test]
+async fn test_pay_for_gas_with_price_feed() {
+    let mut context = setup_context().await;
+    let payer = clone_keypair(&context.payer);
+    let message_id = H256::random();
+    let price_feed_key = Pubkey::new_unique();
+    let price_feed_owner = Pubkey::new_unique();
+
+    initialize(&mut context.banks_client, &payer).await.unwrap();
+
+    let (igp_key, overhead_igp_key) = setup_test_igps(
+        &mut context.banks_client,
+        &payer,
+        TEST_DESTINATION_DOMAIN,
+        GasOracle::PriceFeed(PriceFeedGasOracle {
+            feed: price_feed_key,
+            feed_owner: price_feed_owner,
+            max_staleness_seconds: TEST_PRICE_FEED_MAX_STALENESS_SECONDS,
+            token_decimals: LOCAL_DECIMALS,
+        }),
+        Some(TEST_GAS_OVERHEAD_AMOUNT),
+    )
+    .await;
+
+    let now = current_unix_timestamp(&mut context.banks_client).await;
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        price_feed_owner,
+        GasPriceFeed {
+            token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE,
+            gas_price: 1u128,
+            publish_time: now,
+        },
+    );
+
+    let quote = quote_gas_payment(
+        &mut context.banks_client,
+        &payer,
+        TEST_DESTINATION_DOMAIN,
+        TEST_GAS_AMOUNT,
+        igp_key,
+        Some(overhead_igp_key),
+        Some(price_feed_key),
+    )
+    .await
+    .unwrap();
+
+    let igp_balance_before = context.banks_client.get_balance(igp_key).await.unwrap();
+
+    let (gas_payment_pda_key, unique_payment_account, payment_tx_signature) = pay_for_gas(
+        &mut context.banks_client,
+        &payer,
+        igp_key,
+        Some(overhead_igp_key),
+        Some(price_feed_key),
+        TEST_DESTINATION_DOMAIN,
+        TEST_GAS_AMOUNT,
+        message_id,
+    )
+    .await
+    .unwrap();
+
+    let igp_balance_after = context.banks_client.get_balance(igp_key).await.unwrap();
+
+    assert_eq!(igp_balance_after - igp_balance_before, quote);
+    assert_eq!(quote, TEST_GAS_AMOUNT + TEST_GAS_OVERHEAD_AMOUNT);
+
+    assert_gas_payment(
+        &mut context.banks_client,
+        igp_key,
+        payment_tx_signature,
+        unique_payment_account.pubkey(),
+        gas_payment_pda_key,
+        TEST_DESTINATION_DOMAIN,
+        TEST_GAS_AMOUNT + TEST_GAS_OVERHEAD_AMOUNT,
+        quote,
+        message_id,
+        0,
+    )
+    .await;
+
+    // Payments can't be made without the price feed account
+    assert_transaction_error(
+        pay_for_gas(
+            &mut context.banks_client,
+            &payer,
+            igp_key,
+            Some(overhead_igp_key),
+            None,
+            TEST_DESTINATION_DOMAIN,
+            TEST_GAS_AMOUNT,
+            message_id,
+        )
+        .await,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys),
+    );
+}
+
 #[tokio::test]
 async fn test_pay_for_gas_errors_if_payer_balance_is_insufficient() {
     let _program_id = igp_program_id();
@@ -1208,6 +1598,7 @@ async fn test_pay_for_gas_errors_if_payer_balance_is_insufficient() {
         TEST_GAS_AMOUNT,
         igp_key,
         None,
+        None,
     )
     .await
     .unwrap();
@@ -1220,6 +1611,7 @@ async fn test_pay_for_gas_errors_if_payer_balance_is_insufficient() {
             &low_balance_payer,
             igp_key,
             None,
+            None,
             TEST_DESTINATION_DOMAIN,
             TEST_GAS_AMOUNT,
             H256::random(),
@@ -1259,6 +1651,7 @@ async fn test_pay_for_gas_errors_if_no_gas_oracle() {
             &payer,
             igp_key,
             None,
+            None,
             TEST_DESTINATION_DOMAIN + 1,
             TEST_GAS_AMOUNT,
             H256::random(),
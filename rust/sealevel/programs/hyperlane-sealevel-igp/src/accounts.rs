@@ -5,7 +5,13 @@ use std::{cmp::Ordering, collections::HashMap};
 use access_control::AccessControl;
 use account_utils::{AccountData, DiscriminatorData, DiscriminatorPrefixed, SizedData};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{clock::Slot, program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, Slot, UnixTimestamp},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
 
 use hyperlane_core::{H256, U256};
 
@@ -45,7 +51,34 @@ impl InterchainGasPaymasterType {
 pub enum GasOracle {
     /// Remote gas data stored directly in the variant data.
     RemoteGasData(RemoteGasData),
-    // Future gas oracle variants could include a Pyth type, generalized CPI type, etc.
+    /// Remote gas data read from a price feed account at the time of quoting.
+    PriceFeed(PriceFeedGasOracle),
+    // Future gas oracle variants could include a generalized CPI type, etc.
+}
+
+impl GasOracle {
+    /// Returns the price feed account the gas oracle reads from, if any.
+    pub fn price_feed(&self) -> Option<&Pubkey> {
+        match self {
+            GasOracle::RemoteGasData(_) => None,
+            GasOracle::PriceFeed(oracle) => Some(&oracle.feed),
+        }
+    }
+
+    /// Gets the remote gas data provided by the gas oracle.
+    /// `price_feed_info` is required if the gas oracle is a price feed.
+    pub fn remote_gas_data(
+        &self,
+        price_feed_info: Option<&AccountInfo>,
+    ) -> Result<RemoteGasData, ProgramError> {
+        match self {
+            GasOracle::RemoteGasData(remote_gas_data) => Ok(remote_gas_data.clone()),
+            GasOracle::PriceFeed(oracle) => {
+                let price_feed_info = price_feed_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+                oracle.remote_gas_data(price_feed_info, Clock::get()?.unix_timestamp)
+            }
+        }
+    }
 }
 
 impl Default for GasOracle {
@@ -118,9 +151,10 @@ impl OverheadIgp {
         destination_domain: u32,
         gas_amount: u64,
         inner_igp: &Igp,
-    ) -> Result<u64, Error> {
+        price_feed_info: Option<&AccountInfo>,
+    ) -> Result<u64, ProgramError> {
         let total_gas_amount = self.gas_overhead(destination_domain) + gas_amount;
-        inner_igp.quote_gas_payment(destination_domain, total_gas_amount)
+        inner_igp.quote_gas_payment(destination_domain, total_gas_amount, price_feed_info)
     }
 }
 
@@ -182,37 +216,31 @@ impl SizedData for Igp {
 }
 
 impl Igp {
+    /// Returns the price feed account that the gas oracle for the destination
+    /// domain reads from, if any.
+    pub fn price_feed(&self, destination_domain: u32) -> Option<&Pubkey> {
+        self.gas_oracles
+            .get(&destination_domain)
+            .and_then(GasOracle::price_feed)
+    }
+
     /// Quotes a gas payment.
-    /// Returns an error if a gas oracle is not set for the destination domain.
+    /// Returns an error if a gas oracle is not set for the destination domain,
+    /// or if the gas oracle is a price feed and `price_feed_info` is not a
+    /// valid and fresh price feed account.
     pub fn quote_gas_payment(
         &self,
         destination_domain: u32,
         gas_amount: u64,
-    ) -> Result<u64, Error> {
+        price_feed_info: Option<&AccountInfo>,
+    ) -> Result<u64, ProgramError> {
         let oracle = self
             .gas_oracles
             .get(&destination_domain)
             .ok_or(Error::NoGasOracleSetForDestinationDomain)?;
-        let GasOracle::RemoteGasData(RemoteGasData {
-            token_exchange_rate,
-            gas_price,
-            token_decimals,
-        }) = oracle;
-
-        // Arithmetic is done using U256 to avoid overflows.
-
-        // The total cost quoted in the destination chain's native token.
-        let destination_gas_cost = U256::from(gas_amount) * U256::from(*gas_price);
+        let remote_gas_data = oracle.remote_gas_data(price_feed_info)?;
 
-        // Convert to the local native token (decimals not yet accounted for).
-        let origin_cost = (destination_gas_cost * U256::from(*token_exchange_rate))
-            / U256::from(TOKEN_EXCHANGE_RATE_SCALE);
-
-        // Convert from the remote token's decimals to the local token's decimals.
-        let origin_cost = convert_decimals(origin_cost, *token_decimals, SOL_DECIMALS);
-
-        // Panics if an overflow occurs.
-        Ok(origin_cost.as_u64())
+        Ok(remote_gas_data.quote_gas_payment(gas_amount))
     }
 }
 
@@ -244,6 +272,112 @@ pub struct RemoteGasData {
     pub token_decimals: u8,
 }
 
+impl RemoteGasData {
+    /// Quotes the payment, in lamports, for `gas_amount` gas on the remote chain.
+    pub fn quote_gas_payment(&self, gas_amount: u64) -> u64 {
+        let RemoteGasData {
+            token_exchange_rate,
+            gas_price,
+            token_decimals,
+        } = self;
+
+        // Arithmetic is done using U256 to avoid overflows.
+
+        // The total cost quoted in the destination chain's native token.
+        let destination_gas_cost = U256::from(gas_amount) * U256::from(*gas_price);
+
+        // Convert to the local native token (decimals not yet accounted for).
+        let origin_cost = (destination_gas_cost * U256::from(*token_exchange_rate))
+            / U256::from(TOKEN_EXCHANGE_RATE_SCALE);
+
+        // Convert from the remote token's decimals to the local token's decimals.
+        let origin_cost = convert_decimals(origin_cost, *token_decimals, SOL_DECIMALS);
+
+        // Panics if an overflow occurs.
+        origin_cost.as_u64()
+    }
+}
+
+/// A gas oracle that reads the token exchange rate and gas price from a
+/// price feed account, which is expected to be kept up to date by the
+/// program that owns it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct PriceFeedGasOracle {
+    /// The price feed account.
+    pub feed: Pubkey,
+    /// The program that must own the price feed account.
+    pub feed_owner: Pubkey,
+    /// The maximum age, in seconds, of the price feed's data for it to be used.
+    pub max_staleness_seconds: u64,
+    /// The number of decimals for the remote token.
+    pub token_decimals: u8,
+}
+
+impl PriceFeedGasOracle {
+    /// Reads the remote gas data from the price feed account.
+    /// Errors if `price_feed_info` is not the configured price feed account,
+    /// or if the price feed's data is older than `max_staleness_seconds` as of `now`.
+    pub fn remote_gas_data(
+        &self,
+        price_feed_info: &AccountInfo,
+        now: UnixTimestamp,
+    ) -> Result<RemoteGasData, ProgramError> {
+        if price_feed_info.key != &self.feed {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if price_feed_info.owner != &self.feed_owner {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let price_feed = GasPriceFeedAccount::fetch(&mut &price_feed_info.data.borrow()[..])?
+            .into_inner()
+            .data;
+
+        let max_staleness_seconds =
+            UnixTimestamp::try_from(self.max_staleness_seconds).unwrap_or(UnixTimestamp::MAX);
+        if now.saturating_sub(price_feed.publish_time) > max_staleness_seconds {
+            return Err(Error::StalePriceFeed.into());
+        }
+
+        Ok(RemoteGasData {
+            token_exchange_rate: price_feed.token_exchange_rate,
+            gas_price: price_feed.gas_price,
+            token_decimals: self.token_decimals,
+        })
+    }
+}
+
+/// A price feed account read by `GasOracle::PriceFeed` gas oracles.
+pub type GasPriceFeedAccount = AccountData<DiscriminatorPrefixed<GasPriceFeed>>;
+
+impl DiscriminatorData for GasPriceFeed {
+    const DISCRIMINATOR: [u8; 8] = *b"GASFEED_";
+}
+
+/// Price feed account data. Any program can own and update price feed
+/// accounts, so long as the data is stored in this layout.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Default, Clone)]
+pub struct GasPriceFeed {
+    /// The token exchange rate for the remote token, adjusted by the
+    /// TOKEN_EXCHANGE_RATE_SCALE.
+    pub token_exchange_rate: u128,
+    /// The gas price for the remote chain.
+    pub gas_price: u128,
+    /// The unix timestamp the data was published at.
+    pub publish_time: UnixTimestamp,
+}
+
+impl SizedData for GasPriceFeed {
+    fn size(&self) -> usize {
+        // 16 for token_exchange_rate
+        // 16 for gas_price
+        // 8 for publish_time
+        16 + 16 + 8
+    }
+}
+
 /// A discriminator used to easily identify gas payment accounts.
 /// This is the first 8 bytes of the account data.
 pub const GAS_PAYMENT_DISCRIMINATOR: &[u8; 8] = b"GASPAYMT";
@@ -339,4 +473,70 @@ mod test {
         let result = convert_decimals(num, from_decimals, to_decimals);
         assert_eq!(result, U256::from(0u128));
     }
+
+    #[test]
+    fn test_price_feed_remote_gas_data() {
+        let feed_key = Pubkey::new_unique();
+        let feed_owner = Pubkey::new_unique();
+        let oracle = PriceFeedGasOracle {
+            feed: feed_key,
+            feed_owner,
+            max_staleness_seconds: 60,
+            token_decimals: 18,
+        };
+
+        let price_feed = GasPriceFeed {
+            token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE / 5,
+            gas_price: 150,
+            publish_time: 1000,
+        };
+        let price_feed_account = GasPriceFeedAccount::new(price_feed.into());
+        let mut data = vec![0; price_feed_account.size()];
+        price_feed_account.store_in_slice(&mut data).unwrap();
+        let mut lamports = 0;
+        let price_feed_info = AccountInfo::new(
+            &feed_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &feed_owner,
+            false,
+            0,
+        );
+
+        let expected = RemoteGasData {
+            token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE / 5,
+            gas_price: 150,
+            token_decimals: 18,
+        };
+        assert_eq!(
+            oracle.remote_gas_data(&price_feed_info, 1000),
+            Ok(expected.clone())
+        );
+        // Exactly `max_staleness_seconds` old is still fresh.
+        assert_eq!(oracle.remote_gas_data(&price_feed_info, 1060), Ok(expected));
+        assert_eq!(
+            oracle.remote_gas_data(&price_feed_info, 1061),
+            Err(Error::StalePriceFeed.into())
+        );
+
+        // The price feed must be the configured account and owned by the configured program.
+        let wrong_oracle = PriceFeedGasOracle {
+            feed: Pubkey::new_unique(),
+            ..oracle.clone()
+        };
+        assert_eq!(
+            wrong_oracle.remote_gas_data(&price_feed_info, 1000),
+            Err(ProgramError::InvalidArgument)
+        );
+        let wrong_oracle = PriceFeedGasOracle {
+            feed_owner: Pubkey::new_unique(),
+            ..oracle
+        };
+        assert_eq!(
+            wrong_oracle.remote_gas_data(&price_feed_info, 1000),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
 }
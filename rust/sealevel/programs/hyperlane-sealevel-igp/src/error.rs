@@ -9,6 +9,9 @@ pub enum Error {
     /// No gas oracle set for destination domain.
     #[error("No gas oracle set for destination domain")]
     NoGasOracleSetForDestinationDomain = 1,
+    /// The price feed's data is too old to be used.
+    #[error("Price feed is stale")]
+    StalePriceFeed = 2,
 }
 
 impl From<Error> for ProgramError {
@@ -268,6 +268,7 @@ pub fn pay_for_gas_instruction(
     payer: Pubkey,
     igp: Pubkey,
     overhead_igp: Option<Pubkey>,
+    price_feed: Option<Pubkey>,
     unique_gas_payment_account_pubkey: Pubkey,
     message_id: H256,
     destination_domain: u32,
@@ -296,6 +297,8 @@ pub fn pay_for_gas_instruction(
     // 4. `[writeable]` Gas payment PDA.
     // 5. `[writeable]` The IGP account.
     // 6. `[]` Overhead IGP account (optional).
+    // 7. `[]` The gas oracle price feed account, required if the destination
+    //    domain's gas oracle is a price feed.
     let mut accounts = vec![
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
         AccountMeta::new(payer, true),
@@ -307,6 +310,9 @@ pub fn pay_for_gas_instruction(
     if let Some(overhead_igp) = overhead_igp {
         accounts.push(AccountMeta::new_readonly(overhead_igp, false));
     }
+    if let Some(price_feed) = price_feed {
+        accounts.push(AccountMeta::new_readonly(price_feed, false));
+    }
 
     let instruction = SolanaInstruction {
         program_id,
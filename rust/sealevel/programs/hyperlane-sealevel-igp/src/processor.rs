@@ -265,6 +265,8 @@ fn init_igp_variant<T: account_utils::DiscriminatorPrefixedData + SizedData>(
 /// 4. `[writeable]` Gas payment PDA.
 /// 5. `[writeable]` The IGP account.
 /// 6. `[]` Overhead IGP account (optional).
+/// 7. `[]` The gas oracle price feed account, required if the destination
+///    domain's gas oracle is a price feed.
 fn pay_for_gas(program_id: &Pubkey, accounts: &[AccountInfo], payment: PayForGas) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -330,9 +332,13 @@ fn pay_for_gas(program_id: &Pubkey, accounts: &[AccountInfo], payment: PayForGas
     }
 
     // Account 6: Overhead IGP account (optional).
+    // Account 7: Gas oracle price feed account (optional).
+    let (overhead_igp_info, price_feed_info) =
+        next_optional_igp_accounts(accounts_iter, igp.price_feed(payment.destination_domain));
+
     // The caller is expected to only provide an overhead IGP they are comfortable
     // with / have configured themselves.
-    let gas_amount = if let Some(overhead_igp_info) = accounts_iter.next() {
+    let gas_amount = if let Some(overhead_igp_info) = overhead_igp_info {
         if overhead_igp_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
@@ -352,7 +358,8 @@ fn pay_for_gas(program_id: &Pubkey, accounts: &[AccountInfo], payment: PayForGas
         payment.gas_amount
     };
 
-    let required_payment = igp.quote_gas_payment(payment.destination_domain, gas_amount)?;
+    let required_payment =
+        igp.quote_gas_payment(payment.destination_domain, gas_amount, price_feed_info)?;
 
     // Transfer the required payment to the IGP.
     invoke(
@@ -410,6 +417,8 @@ fn pay_for_gas(program_id: &Pubkey, accounts: &[AccountInfo], payment: PayForGas
 /// 0. `[executable]` The system program.
 /// 1. `[]` The IGP account.
 /// 2. `[]` The overhead IGP account (optional).
+/// 3. `[]` The gas oracle price feed account, required if the destination
+///    domain's gas oracle is a price feed.
 fn quote_gas_payment(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -430,11 +439,16 @@ fn quote_gas_payment(
     if igp_info.owner != program_id {
         return Err(ProgramError::IncorrectProgramId);
     }
+    let igp = IgpAccount::fetch(&mut &igp_info.data.borrow()[..])?.into_inner();
 
     // Account 2: Overhead IGP account (optional).
+    // Account 3: Gas oracle price feed account (optional).
+    let (overhead_igp_info, price_feed_info) =
+        next_optional_igp_accounts(accounts_iter, igp.price_feed(payment.destination_domain));
+
     // The caller is expected to only provide an overhead IGP they are comfortable
     // with / have configured themselves.
-    let gas_amount = if let Some(overhead_igp_info) = accounts_iter.next() {
+    let gas_amount = if let Some(overhead_igp_info) = overhead_igp_info {
         if overhead_igp_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
@@ -451,15 +465,38 @@ fn quote_gas_payment(
         payment.gas_amount
     };
 
-    let igp = IgpAccount::fetch(&mut &igp_info.data.borrow()[..])?.into_inner();
-
-    let required_payment = igp.quote_gas_payment(payment.destination_domain, gas_amount)?;
+    let required_payment =
+        igp.quote_gas_payment(payment.destination_domain, gas_amount, price_feed_info)?;
 
     set_return_data(&SimulationReturnData::new(required_payment).try_to_vec()?);
 
     Ok(())
 }
 
+/// Gets the optional accounts that follow the IGP account when paying for or
+/// quoting gas: the overhead IGP account, then the gas oracle price feed
+/// account if `price_feed` is Some. Because the overhead IGP account can be
+/// omitted, the price feed account is identified by its key.
+fn next_optional_igp_accounts<'a, 'b>(
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    price_feed: Option<&Pubkey>,
+) -> (Option<&'a AccountInfo<'b>>, Option<&'a AccountInfo<'b>>) {
+    let mut next = accounts_iter.next();
+
+    let overhead_igp_info = match next {
+        Some(account_info) if Some(account_info.key) != price_feed => {
+            let overhead_igp_info = next;
+            next = accounts_iter.next();
+            overhead_igp_info
+        }
+        _ => None,
+    };
+    // Any account provided for the price feed is verified when it is read.
+    let price_feed_info = price_feed.and(next);
+
+    (overhead_igp_info, price_feed_info)
+}
+
 /// Sets the beneficiary of an IGP.
 ///
 /// Accounts:
@@ -36,8 +36,13 @@ use hyperlane_sealevel_token_collateral::{
 use hyperlane_sealevel_token_lib::{
     accounts::{convert_decimals, HyperlaneToken, HyperlaneTokenAccount},
     hyperlane_token_pda_seeds,
-    instruction::{Init, Instruction as HyperlaneTokenInstruction, TransferRemote},
+    instruction::{
+        claim_pending_transfer_instruction, set_rate_limits_instruction, Init,
+        Instruction as HyperlaneTokenInstruction, TransferRemote,
+    },
     message::TokenMessage,
+    processor::HyperlaneSealevelTokenPlugin,
+    rate_limit::{RateLimitConfig, RateLimitParams, TrailingRateLimits, TransferDirection},
 };
 use hyperlane_test_utils::{
     assert_token_balance, assert_transaction_error, igp_program_id, initialize_igp_accounts,
@@ -488,6 +493,7 @@ async fn test_initialize() {
                 escrow_bump: hyperlane_token_accounts.escrow_bump,
                 ata_payer_bump: hyperlane_token_accounts.ata_payer_bump,
             },
+            rate_limits: TrailingRateLimits(None),
         }),
     );
 
@@ -1121,6 +1127,193 @@ async fn test_transfer_from_remote_errors_if_process_authority_not_signer() {
     );
 }
 
+#[tokio::test]
+async fn test_transfer_from_remote_queues_if_inbound_rate_limit_exceeded() {
+    let program_id = hyperlane_sealevel_token_collateral_id();
+    let mailbox_program_id = mailbox_id();
+    let spl_token_program_id = spl_token_2022::id();
+
+    let (mut banks_client, payer) = setup_client().await;
+
+    let mailbox_accounts =
+        initialize_mailbox(&mut banks_client, &mailbox_program_id, &payer, LOCAL_DOMAIN)
+            .await
+            .unwrap();
+
+    let (mint, mint_authority) = initialize_mint(
+        &mut banks_client,
+        &payer,
+        LOCAL_DECIMALS,
+        &spl_token_program_id,
+    )
+    .await;
+
+    let hyperlane_token_accounts = initialize_hyperlane_token(
+        &program_id,
+        &mut banks_client,
+        &payer,
+        None,
+        &mint,
+        &spl_token_program_id,
+    )
+    .await
+    .unwrap();
+    // ATA payer must have a balance to create new ATAs
+    transfer_lamports(
+        &mut banks_client,
+        &payer,
+        &hyperlane_token_accounts.ata_payer,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    let remote_router = H256::random();
+    enroll_remote_router(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        REMOTE_DOMAIN,
+        remote_router,
+    )
+    .await
+    .unwrap();
+
+    let initial_escrow_balance = 100 * 10u64.pow(LOCAL_DECIMALS_U32);
+    mint_to(
+        &mut banks_client,
+        &spl_token_program_id,
+        &mint,
+        &mint_authority,
+        &hyperlane_token_accounts.escrow,
+        initial_escrow_balance,
+    )
+    .await;
+
+    // Only allow 50 tokens to be received per day.
+    let capacity = 50 * 10u64.pow(LOCAL_DECIMALS_U32);
+    let set_rate_limit_instruction = |capacity: u64| {
+        set_rate_limits_instruction(
+            program_id,
+            payer.pubkey(),
+            vec![RateLimitConfig {
+                domain: REMOTE_DOMAIN,
+                direction: TransferDirection::Inbound,
+                rate_limit: Some(RateLimitParams {
+                    capacity,
+                    window_seconds: 86400,
+                }),
+            }],
+        )
+        .unwrap()
+    };
+    hyperlane_test_utils::process_instruction(
+        &mut banks_client,
+        set_rate_limit_instruction(capacity),
+        &payer,
+        &[&payer],
+    )
+    .await
+    .unwrap();
+
+    // Transfer 69 tokens, which exceeds the rate limit.
+    let recipient_pubkey = Pubkey::new_unique();
+    let recipient_associated_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &recipient_pubkey,
+            &mint,
+            &spl_token_program_id,
+        );
+    let local_transfer_amount = 69 * 10u64.pow(LOCAL_DECIMALS_U32);
+    let token_message = TokenMessage::new(
+        recipient_pubkey.to_bytes().into(),
+        convert_decimals(
+            local_transfer_amount.into(),
+            LOCAL_DECIMALS,
+            REMOTE_DECIMALS,
+        )
+        .unwrap(),
+        vec![],
+    );
+    let message = HyperlaneMessage {
+        version: 3,
+        nonce: 0,
+        origin: REMOTE_DOMAIN,
+        sender: remote_router,
+        destination: LOCAL_DOMAIN,
+        recipient: program_id.to_bytes().into(),
+        body: token_message.to_vec(),
+    };
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message,
+    )
+    .await
+    .unwrap();
+
+    // The recipient received nothing, and the escrow is untouched.
+    assert!(banks_client
+        .get_account(recipient_associated_token_account)
+        .await
+        .unwrap()
+        .is_none());
+    assert_token_balance(
+        &mut banks_client,
+        &hyperlane_token_accounts.escrow,
+        initial_escrow_balance,
+    )
+    .await;
+
+    // Raise the rate limit so the pending transfer can be claimed.
+    hyperlane_test_utils::process_instruction(
+        &mut banks_client,
+        set_rate_limit_instruction(2 * capacity),
+        &payer,
+        &[&payer],
+    )
+    .await
+    .unwrap();
+
+    let token_account_data = banks_client
+        .get_account(hyperlane_token_accounts.token)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let token = HyperlaneTokenAccount::<CollateralPlugin>::fetch(&mut &token_account_data[..])
+        .unwrap()
+        .into_inner();
+    let (transfer_out_account_metas, writeable_recipient) =
+        CollateralPlugin::transfer_out_account_metas(&program_id, &token, &token_message).unwrap();
+    let mut instruction =
+        claim_pending_transfer_instruction(program_id, 0, recipient_pubkey, writeable_recipient)
+            .unwrap();
+    instruction.accounts.extend(
+        transfer_out_account_metas
+            .into_iter()
+            .map(AccountMeta::from),
+    );
+    hyperlane_test_utils::process_instruction(&mut banks_client, instruction, &payer, &[&payer])
+        .await
+        .unwrap();
+
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        local_transfer_amount,
+    )
+    .await;
+    assert_token_balance(
+        &mut banks_client,
+        &hyperlane_token_accounts.escrow,
+        initial_escrow_balance - local_transfer_amount,
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_enroll_remote_router() {
     let program_id = hyperlane_sealevel_token_collateral_id();
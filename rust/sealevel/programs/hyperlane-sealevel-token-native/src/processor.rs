@@ -11,6 +11,7 @@ use hyperlane_sealevel_message_recipient_interface::{
 use hyperlane_sealevel_token_lib::{
     instruction::{Init, Instruction as TokenIxn, TransferRemote},
     processor::HyperlaneSealevelToken,
+    rate_limit::RateLimitConfig,
 };
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
 
@@ -79,6 +80,10 @@ pub fn process_instruction(
         TokenIxn::SetInterchainGasPaymaster(new_igp) => {
             set_interchain_gas_paymaster(program_id, accounts, new_igp)
         }
+        TokenIxn::SetRateLimits(configs) => set_rate_limits(program_id, accounts, configs),
+        TokenIxn::ClaimPendingTransfer(nonce) => {
+            claim_pending_transfer(program_id, accounts, nonce)
+        }
     }
     .map_err(|err| {
         msg!("{}", err);
@@ -105,7 +110,8 @@ fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], init: Init) -> Prog
 /// Accounts:
 /// 0.   `[executable]` The system program.
 /// 1.   `[executable]` The spl_noop program.
-/// 2.   `[]` The token PDA account.
+/// 2.   `[writeable]` The token PDA account. Only needs to be writeable if an
+///      outbound rate limit is configured for the destination.
 /// 3.   `[executable]` The mailbox program.
 /// 4.   `[writeable]` The mailbox outbox account.
 /// 5.   `[]` Message dispatch authority.
@@ -132,10 +138,15 @@ fn transfer_remote(
 /// Accounts:
 /// 0.   `[signer]` Mailbox processor authority specific to this program.
 /// 1.   `[executable]` system_program
-/// 2.   `[]` hyperlane_token storage
+/// 2.   `[writeable]` hyperlane_token storage. Only needs to be writeable if an
+///      inbound rate limit is configured for the origin.
 /// 3.   `[writeable]` recipient wallet address
-/// 4.   `[executable]` The system program.
-/// 5.   `[writeable]` The native token collateral PDA account.
+///      ---- If an inbound rate limit is configured for the origin ----
+/// 4.   `[writeable]` The pending transfer payer PDA account.
+/// 5.   `[writeable]` The pending transfer PDA account.
+///      ---- End if ----
+/// 6.   `[executable]` The system program.
+/// 7.   `[writeable]` The native token collateral PDA account.
 fn transfer_from_remote(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -260,3 +271,35 @@ fn set_interchain_gas_paymaster(
         program_id, accounts, new_igp,
     )
 }
+
+/// Lets the owner set or remove rate limits.
+///
+/// Accounts:
+/// 0. `[executable]` The system program.
+/// 1. `[writeable]` The token PDA account.
+/// 2. `[signer]` The access control owner.
+fn set_rate_limits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    configs: Vec<RateLimitConfig>,
+) -> ProgramResult {
+    HyperlaneSealevelToken::<NativePlugin>::set_rate_limits(program_id, accounts, configs)
+}
+
+/// Claims an inbound transfer that was queued due to a rate limit.
+///
+/// Accounts:
+/// 0. `[executable]` The system program.
+/// 1. `[writeable]` The token PDA account.
+/// 2. `[writeable]` The pending transfer PDA account.
+/// 3. `[writeable]` The pending transfer payer PDA account.
+/// 4. `[writeable]` The recipient wallet.
+/// 5. `[executable]` The system program.
+/// 6. `[writeable]` The native token collateral PDA account.
+fn claim_pending_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    nonce: u64,
+) -> ProgramResult {
+    HyperlaneSealevelToken::<NativePlugin>::claim_pending_transfer(program_id, accounts, nonce)
+}
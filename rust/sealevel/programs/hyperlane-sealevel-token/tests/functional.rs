@@ -2,13 +2,16 @@
 //! strictly in unit tests. This includes CPIs, like creating
 //! new PDA accounts.
 
-use account_utils::DiscriminatorEncode;
+use account_utils::{DiscriminatorEncode, SizedData};
 use hyperlane_core::{Encode, HyperlaneMessage, H256, U256};
 use hyperlane_sealevel_connection_client::{
     gas_router::GasRouterConfig, router::RemoteRouterConfig,
 };
 use hyperlane_sealevel_igp::{
-    accounts::{GasPaymentAccount, GasPaymentData, InterchainGasPaymasterType},
+    accounts::{
+        GasOracle, GasPaymentAccount, GasPaymentData, GasPriceFeed, GasPriceFeedAccount,
+        InterchainGasPaymasterType, PriceFeedGasOracle, SOL_DECIMALS, TOKEN_EXCHANGE_RATE_SCALE,
+    },
     igp_gas_payment_pda_seeds,
 };
 use hyperlane_sealevel_mailbox::{
@@ -24,23 +27,33 @@ use hyperlane_sealevel_token::{
     processor::process_instruction,
 };
 use hyperlane_sealevel_token_lib::{
-    accounts::{convert_decimals, HyperlaneToken, HyperlaneTokenAccount},
-    hyperlane_token_pda_seeds,
+    accounts::{
+        convert_decimals, HyperlaneToken, HyperlaneTokenAccount, PendingTransfer,
+        PendingTransferQueue, PendingTransferQueueAccount,
+    },
+    error::Error as HyperlaneTokenError,
+    hyperlane_token_pda_seeds, hyperlane_token_pending_transfer_queue_pda_seeds,
     instruction::{Init, Instruction as HyperlaneTokenInstruction, TransferRemote},
     message::TokenMessage,
+    rate_limit::{
+        RateLimitConfig, RateLimitParams, RateLimits, TrailingRateLimits, TransferDirection,
+    },
 };
 use hyperlane_test_utils::{
-    assert_token_balance, assert_transaction_error, igp_program_id, initialize_igp_accounts,
-    initialize_mailbox, mailbox_id, new_funded_keypair, process, transfer_lamports, IgpAccounts,
-    MailboxAccounts,
+    assert_token_balance, assert_transaction_error, clone_keypair, igp_program_id,
+    initialize_igp_accounts, initialize_igp_program, initialize_mailbox, mailbox_id,
+    new_funded_keypair, process, setup_test_igps, transfer_lamports, IgpAccounts, MailboxAccounts,
 };
 use solana_program::{
+    clock::Clock,
     instruction::{AccountMeta, Instruction},
     pubkey,
     pubkey::Pubkey,
+    sysvar::rent::Rent,
 };
 use solana_program_test::*;
 use solana_sdk::{
+    account::Account,
     instruction::InstructionError,
     signature::Signer,
     signer::keypair::Keypair,
@@ -62,7 +75,7 @@ fn hyperlane_sealevel_token_id() -> Pubkey {
     pubkey!("3MzUPjP5LEkiHH82nEAe28Xtz9ztuMqWc8UmuKxrpVQH")
 }
 
-async fn setup_client() -> (BanksClient, Keypair) {
+fn program_test() -> ProgramTest {
     let program_id = hyperlane_sealevel_token_id();
     let mut program_test = ProgramTest::new(
         "hyperlane_sealevel_token",
@@ -104,11 +117,44 @@ async fn setup_client() -> (BanksClient, Keypair) {
         processor!(hyperlane_sealevel_test_ism::program::process_instruction),
     );
 
-    let (banks_client, payer, _recent_blockhash) = program_test.start().await;
+    program_test
+}
+
+async fn setup_client() -> (BanksClient, Keypair) {
+    let (banks_client, payer, _recent_blockhash) = program_test().start().await;
 
     (banks_client, payer)
 }
 
+async fn setup_context() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+/// Writes a price feed account owned by `feed_owner` directly into the test bank.
+fn set_price_feed(
+    context: &mut ProgramTestContext,
+    feed_key: Pubkey,
+    feed_owner: Pubkey,
+    price_feed: GasPriceFeed,
+) {
+    let price_feed_account = GasPriceFeedAccount::new(price_feed.into());
+    let mut data = vec![0; price_feed_account.size()];
+    price_feed_account.store_in_slice(&mut data).unwrap();
+
+    let lamports = Rent::default().minimum_balance(data.len());
+    context.set_account(
+        &feed_key,
+        &Account {
+            lamports,
+            data,
+            owner: feed_owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+}
+
 struct HyperlaneTokenAccounts {
     token: Pubkey,
     token_bump: u8,
@@ -340,6 +386,7 @@ async fn test_initialize() {
                 mint_bump: hyperlane_token_accounts.mint_bump,
                 ata_payer_bump: hyperlane_token_accounts.ata_payer_bump,
             },
+            rate_limits: TrailingRateLimits(None),
         }),
     );
 
@@ -830,6 +877,246 @@ async fn test_transfer_remote() {
     );
 }
 
+#[tokio::test]
+async fn test_transfer_remote_with_price_feed_gas_oracle() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let mut context = setup_context().await;
+    let payer = clone_keypair(&context.payer);
+
+    let mailbox_accounts = initialize_mailbox(
+        &mut context.banks_client,
+        &mailbox_program_id,
+        &payer,
+        LOCAL_DOMAIN,
+    )
+    .await
+    .unwrap();
+
+    // The IGP reads the gas data for the remote domain from a price feed.
+    let price_feed_key = Pubkey::new_unique();
+    let price_feed_owner = Pubkey::new_unique();
+    let (igp_program_data, _igp_program_data_bump) =
+        initialize_igp_program(&mut context.banks_client, &payer)
+            .await
+            .unwrap();
+    let (igp, overhead_igp) = setup_test_igps(
+        &mut context.banks_client,
+        &payer,
+        H256::zero(),
+        REMOTE_DOMAIN,
+        GasOracle::PriceFeed(PriceFeedGasOracle {
+            feed: price_feed_key,
+            feed_owner: price_feed_owner,
+            max_staleness_seconds: 60,
+            token_decimals: SOL_DECIMALS,
+        }),
+        None,
+    )
+    .await;
+    let igp_accounts = IgpAccounts {
+        program: igp_program_id(),
+        program_data: igp_program_data,
+        igp,
+        overhead_igp,
+    };
+    let now = context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    set_price_feed(
+        &mut context,
+        price_feed_key,
+        price_feed_owner,
+        GasPriceFeed {
+            // 2x exchange rate (remote token more valuable)
+            token_exchange_rate: TOKEN_EXCHANGE_RATE_SCALE * 2,
+            gas_price: 1u128,
+            publish_time: now,
+        },
+    );
+
+    let hyperlane_token_accounts = initialize_hyperlane_token(
+        &program_id,
+        &mut context.banks_client,
+        &payer,
+        Some(&igp_accounts),
+    )
+    .await
+    .unwrap();
+    // ATA payer must have a balance to create new ATAs
+    transfer_lamports(
+        &mut context.banks_client,
+        &payer,
+        &hyperlane_token_accounts.ata_payer,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    let remote_router = H256::random();
+    enroll_remote_router(
+        &mut context.banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        REMOTE_DOMAIN,
+        remote_router,
+    )
+    .await
+    .unwrap();
+
+    // Mint 100 tokens to the token sender by faking a transfer from remote.
+    let token_sender = Keypair::new();
+    let token_sender_pubkey = token_sender.pubkey();
+    let sender_initial_balance = 100 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut context.banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &HyperlaneMessage {
+            version: 3,
+            nonce: 0,
+            origin: REMOTE_DOMAIN,
+            sender: remote_router,
+            destination: LOCAL_DOMAIN,
+            recipient: program_id.to_bytes().into(),
+            body: TokenMessage::new(
+                token_sender_pubkey.to_bytes().into(),
+                convert_decimals(
+                    sender_initial_balance.into(),
+                    LOCAL_DECIMALS,
+                    REMOTE_DECIMALS,
+                )
+                .unwrap(),
+                vec![],
+            )
+            .to_vec(),
+        },
+    )
+    .await
+    .unwrap();
+    let token_sender_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &token_sender_pubkey,
+            &hyperlane_token_accounts.mint,
+            &spl_token_2022::id(),
+        );
+
+    // Give the token_sender a SOL balance to pay tx fees and gas payments.
+    transfer_lamports(
+        &mut context.banks_client,
+        &payer,
+        &token_sender_pubkey,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    let transfer_amount = 69 * 10u64.pow(LOCAL_DECIMALS_U32);
+    let transfer_remote_instruction = |include_price_feed: bool| {
+        let unique_message_account_keypair = Keypair::new();
+        let (dispatched_message_key, _dispatched_message_bump) = Pubkey::find_program_address(
+            mailbox_dispatched_message_pda_seeds!(&unique_message_account_keypair.pubkey()),
+            &mailbox_program_id,
+        );
+        let (gas_payment_pda_key, _gas_payment_pda_bump) = Pubkey::find_program_address(
+            igp_gas_payment_pda_seeds!(&unique_message_account_keypair.pubkey()),
+            &igp_program_id(),
+        );
+        let mut accounts = vec![
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(spl_noop::id(), false),
+            AccountMeta::new_readonly(hyperlane_token_accounts.token, false),
+            AccountMeta::new_readonly(mailbox_accounts.program, false),
+            AccountMeta::new(mailbox_accounts.outbox, false),
+            AccountMeta::new_readonly(hyperlane_token_accounts.dispatch_authority, false),
+            AccountMeta::new_readonly(token_sender_pubkey, true),
+            AccountMeta::new_readonly(unique_message_account_keypair.pubkey(), true),
+            AccountMeta::new(dispatched_message_key, false),
+            AccountMeta::new_readonly(igp_accounts.program, false),
+            AccountMeta::new(igp_accounts.program_data, false),
+            AccountMeta::new(gas_payment_pda_key, false),
+            AccountMeta::new_readonly(igp_accounts.overhead_igp, false),
+            AccountMeta::new(igp_accounts.igp, false),
+        ];
+        // 14. `[]` The gas oracle price feed account.
+        if include_price_feed {
+            accounts.push(AccountMeta::new_readonly(price_feed_key, false));
+        }
+        accounts.extend([
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new(hyperlane_token_accounts.mint, false),
+            AccountMeta::new(token_sender_ata, false),
+        ]);
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &HyperlaneTokenInstruction::TransferRemote(TransferRemote {
+                destination_domain: REMOTE_DOMAIN,
+                recipient: H256::random(),
+                amount_or_id: transfer_amount.into(),
+            })
+            .encode()
+            .unwrap(),
+            accounts,
+        );
+        (
+            instruction,
+            unique_message_account_keypair,
+            gas_payment_pda_key,
+        )
+    };
+
+    // Omitting the price feed account fails.
+    let (instruction, unique_message_account_keypair, _gas_payment_pda_key) =
+        transfer_remote_instruction(false);
+    let result = hyperlane_test_utils::process_instruction(
+        &mut context.banks_client,
+        instruction,
+        &token_sender,
+        &[&token_sender, &unique_message_account_keypair],
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument),
+    );
+
+    let (instruction, unique_message_account_keypair, gas_payment_pda_key) =
+        transfer_remote_instruction(true);
+    hyperlane_test_utils::process_instruction(
+        &mut context.banks_client,
+        instruction,
+        &token_sender,
+        &[&token_sender, &unique_message_account_keypair],
+    )
+    .await
+    .unwrap();
+
+    assert_token_balance(
+        &mut context.banks_client,
+        &token_sender_ata,
+        sender_initial_balance - transfer_amount,
+    )
+    .await;
+
+    // The gas payment was quoted using the price feed.
+    let gas_payment_account_data = context
+        .banks_client
+        .get_account(gas_payment_pda_key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let gas_payment = GasPaymentAccount::fetch(&mut &gas_payment_account_data[..])
+        .unwrap()
+        .into_inner();
+    assert_eq!(gas_payment.gas_amount, REMOTE_GAS_AMOUNT);
+    assert_eq!(gas_payment.payment, REMOTE_GAS_AMOUNT * 2);
+}
+
 #[tokio::test]
 async fn test_enroll_remote_router() {
     let program_id = hyperlane_sealevel_token_id();
@@ -1336,3 +1623,855 @@ async fn test_set_interchain_gas_paymaster_errors_if_owner_not_signer() {
         TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature),
     );
 }
+
+async fn set_rate_limit(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    owner: &Keypair,
+    token_account: &Pubkey,
+    config: RateLimitConfig,
+) -> Result<(), BanksClientError> {
+    let (pending_transfer_queue_key, _pending_transfer_queue_bump) = Pubkey::find_program_address(
+        hyperlane_token_pending_transfer_queue_pda_seeds!(),
+        program_id,
+    );
+
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(
+            *program_id,
+            &HyperlaneTokenInstruction::SetRateLimits(vec![config])
+                .encode()
+                .unwrap(),
+            vec![
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new(*token_account, false),
+                AccountMeta::new(owner.pubkey(), true),
+                AccountMeta::new(pending_transfer_queue_key, false),
+            ],
+        )],
+        Some(&owner.pubkey()),
+        &[owner],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(())
+}
+
+async fn fetch_pending_transfer_queue(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+) -> PendingTransferQueue {
+    let (pending_transfer_queue_key, _pending_transfer_queue_bump) = Pubkey::find_program_address(
+        hyperlane_token_pending_transfer_queue_pda_seeds!(),
+        program_id,
+    );
+    let pending_transfer_queue_account_data = banks_client
+        .get_account(pending_transfer_queue_key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    *PendingTransferQueueAccount::fetch(&mut &pending_transfer_queue_account_data[..])
+        .unwrap()
+        .into_inner()
+}
+
+async fn claim_pending_transfer(
+    banks_client: &mut BanksClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    hyperlane_token_accounts: &HyperlaneTokenAccounts,
+    nonce: u64,
+    recipient: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let (pending_transfer_queue_key, _pending_transfer_queue_bump) = Pubkey::find_program_address(
+        hyperlane_token_pending_transfer_queue_pda_seeds!(),
+        program_id,
+    );
+    let recipient_associated_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            recipient,
+            &hyperlane_token_accounts.mint,
+            &spl_token_2022::id(),
+        );
+
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(
+            *program_id,
+            &HyperlaneTokenInstruction::ClaimPendingTransfer(nonce)
+                .encode()
+                .unwrap(),
+            // 0. `[executable]` The system program.
+            // 1. `[writeable]` The token PDA account.
+            // 2. `[writeable]` The pending transfer queue PDA account.
+            // 3. `[]` The recipient wallet.
+            // 4. `[executable]` SPL token 2022 program
+            // 5. `[executable]` SPL associated token account
+            // 6. `[writeable]` Mint account
+            // 7. `[writeable]` Recipient associated token account
+            // 8. `[writeable]` ATA payer PDA account.
+            vec![
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new(hyperlane_token_accounts.token, false),
+                AccountMeta::new(pending_transfer_queue_key, false),
+                AccountMeta::new_readonly(*recipient, false),
+                AccountMeta::new_readonly(spl_token_2022::id(), false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                AccountMeta::new(hyperlane_token_accounts.mint, false),
+                AccountMeta::new(recipient_associated_token_account, false),
+                AccountMeta::new(hyperlane_token_accounts.ata_payer, false),
+            ],
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_rate_limits() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let (mut banks_client, payer) = setup_client().await;
+
+    initialize_mailbox(&mut banks_client, &mailbox_program_id, &payer, LOCAL_DOMAIN)
+        .await
+        .unwrap();
+
+    let hyperlane_token_accounts =
+        initialize_hyperlane_token(&program_id, &mut banks_client, &payer, None)
+            .await
+            .unwrap();
+
+    let rate_limit = RateLimitParams {
+        capacity: 100 * 10u64.pow(LOCAL_DECIMALS_U32),
+        window_seconds: 86400,
+    };
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: Some(rate_limit),
+        },
+    )
+    .await
+    .unwrap();
+
+    let token_account_data = banks_client
+        .get_account(hyperlane_token_accounts.token)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let token = HyperlaneTokenAccount::<SyntheticPlugin>::fetch(&mut &token_account_data[..])
+        .unwrap()
+        .into_inner();
+    let rate_limits = token.rate_limits.0.as_ref().unwrap();
+    assert!(rate_limits.outbound.is_empty());
+    let inbound_rate_limit = rate_limits.inbound.get(&REMOTE_DOMAIN).unwrap();
+    assert_eq!(inbound_rate_limit.capacity, rate_limit.capacity);
+    assert_eq!(inbound_rate_limit.window_seconds, rate_limit.window_seconds);
+    assert_eq!(inbound_rate_limit.available, rate_limit.capacity);
+
+    // The pending transfer queue was created, funded by the owner.
+    assert_eq!(
+        fetch_pending_transfer_queue(&mut banks_client, &program_id).await,
+        PendingTransferQueue {
+            bump: Pubkey::find_program_address(
+                hyperlane_token_pending_transfer_queue_pda_seeds!(),
+                &program_id,
+            )
+            .1,
+            next_nonce: 0,
+            transfers: vec![],
+        },
+    );
+
+    // And remove it
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let token_account_data = banks_client
+        .get_account(hyperlane_token_accounts.token)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let token = HyperlaneTokenAccount::<SyntheticPlugin>::fetch(&mut &token_account_data[..])
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        token.rate_limits,
+        TrailingRateLimits(Some(RateLimits::default()))
+    );
+}
+
+#[tokio::test]
+async fn test_set_rate_limits_errors_if_not_signed_by_owner() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let (mut banks_client, payer) = setup_client().await;
+
+    initialize_mailbox(&mut banks_client, &mailbox_program_id, &payer, LOCAL_DOMAIN)
+        .await
+        .unwrap();
+
+    let hyperlane_token_accounts =
+        initialize_hyperlane_token(&program_id, &mut banks_client, &payer, None)
+            .await
+            .unwrap();
+
+    let non_owner = new_funded_keypair(&mut banks_client, &payer, 1000000000).await;
+
+    let result = set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &non_owner,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Outbound,
+            rate_limit: Some(RateLimitParams {
+                capacity: 1,
+                window_seconds: 86400,
+            }),
+        },
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument),
+    );
+}
+
+#[tokio::test]
+async fn test_transfer_remote_errors_if_outbound_rate_limit_exceeded() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let token_sender = Keypair::new();
+    let token_sender_pubkey = token_sender.pubkey();
+
+    // Mint 100 tokens to the token sender's ATA.
+    let sender_initial_balance = 100 * 10u64.pow(LOCAL_DECIMALS_U32);
+    let (
+        mut banks_client,
+        payer,
+        mailbox_accounts,
+        igp_accounts,
+        hyperlane_token_accounts,
+        token_sender_ata,
+    ) = transfer_from_remote(
+        convert_decimals(
+            sender_initial_balance.into(),
+            LOCAL_DECIMALS,
+            REMOTE_DECIMALS,
+        )
+        .unwrap(),
+        None,
+        None,
+        Some(token_sender_pubkey),
+    )
+    .await
+    .unwrap();
+
+    transfer_lamports(
+        &mut banks_client,
+        &payer,
+        &token_sender_pubkey,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    // Only allow 50 tokens to be sent per day.
+    let capacity = 50 * 10u64.pow(LOCAL_DECIMALS_U32);
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Outbound,
+            rate_limit: Some(RateLimitParams {
+                capacity,
+                window_seconds: 86400,
+            }),
+        },
+    )
+    .await
+    .unwrap();
+
+    let transfer_remote_instruction = |transfer_amount: u64| {
+        let unique_message_account_keypair = Keypair::new();
+        let (dispatched_message_key, _dispatched_message_bump) = Pubkey::find_program_address(
+            mailbox_dispatched_message_pda_seeds!(&unique_message_account_keypair.pubkey()),
+            &mailbox_program_id,
+        );
+        let (gas_payment_pda_key, _gas_payment_pda_bump) = Pubkey::find_program_address(
+            igp_gas_payment_pda_seeds!(&unique_message_account_keypair.pubkey()),
+            &igp_program_id(),
+        );
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &HyperlaneTokenInstruction::TransferRemote(TransferRemote {
+                destination_domain: REMOTE_DOMAIN,
+                recipient: H256::random(),
+                amount_or_id: transfer_amount.into(),
+            })
+            .encode()
+            .unwrap(),
+            vec![
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(spl_noop::id(), false),
+                AccountMeta::new(hyperlane_token_accounts.token, false),
+                AccountMeta::new_readonly(mailbox_accounts.program, false),
+                AccountMeta::new(mailbox_accounts.outbox, false),
+                AccountMeta::new_readonly(hyperlane_token_accounts.dispatch_authority, false),
+                AccountMeta::new_readonly(token_sender_pubkey, true),
+                AccountMeta::new_readonly(unique_message_account_keypair.pubkey(), true),
+                AccountMeta::new(dispatched_message_key, false),
+                AccountMeta::new_readonly(igp_accounts.program, false),
+                AccountMeta::new(igp_accounts.program_data, false),
+                AccountMeta::new(gas_payment_pda_key, false),
+                AccountMeta::new_readonly(igp_accounts.overhead_igp, false),
+                AccountMeta::new(igp_accounts.igp, false),
+                AccountMeta::new_readonly(spl_token_2022::id(), false),
+                AccountMeta::new(hyperlane_token_accounts.mint, false),
+                AccountMeta::new(token_sender_ata, false),
+            ],
+        );
+        (instruction, unique_message_account_keypair)
+    };
+
+    // Transferring 30 tokens is within the limit.
+    let first_amount = 30 * 10u64.pow(LOCAL_DECIMALS_U32);
+    let (instruction, unique_message_account_keypair) = transfer_remote_instruction(first_amount);
+    hyperlane_test_utils::process_instruction(
+        &mut banks_client,
+        instruction,
+        &token_sender,
+        &[&token_sender, &unique_message_account_keypair],
+    )
+    .await
+    .unwrap();
+
+    // Another 30 tokens exceeds it.
+    let (instruction, unique_message_account_keypair) = transfer_remote_instruction(first_amount);
+    let result = hyperlane_test_utils::process_instruction(
+        &mut banks_client,
+        instruction,
+        &token_sender,
+        &[&token_sender, &unique_message_account_keypair],
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HyperlaneTokenError::RateLimitExceeded as u32),
+        ),
+    );
+
+    // The remaining 20 tokens can still be sent.
+    let (instruction, unique_message_account_keypair) =
+        transfer_remote_instruction(capacity - first_amount);
+    hyperlane_test_utils::process_instruction(
+        &mut banks_client,
+        instruction,
+        &token_sender,
+        &[&token_sender, &unique_message_account_keypair],
+    )
+    .await
+    .unwrap();
+
+    assert_token_balance(
+        &mut banks_client,
+        &token_sender_ata,
+        sender_initial_balance - capacity,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_transfer_from_remote_queues_if_inbound_rate_limit_exceeded() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let (mut banks_client, payer) = setup_client().await;
+
+    let mailbox_accounts =
+        initialize_mailbox(&mut banks_client, &mailbox_program_id, &payer, LOCAL_DOMAIN)
+            .await
+            .unwrap();
+
+    let hyperlane_token_accounts =
+        initialize_hyperlane_token(&program_id, &mut banks_client, &payer, None)
+            .await
+            .unwrap();
+    // ATA payer must have a balance to create new ATAs
+    transfer_lamports(
+        &mut banks_client,
+        &payer,
+        &hyperlane_token_accounts.ata_payer,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    let remote_router = H256::random();
+    enroll_remote_router(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        REMOTE_DOMAIN,
+        remote_router,
+    )
+    .await
+    .unwrap();
+
+    // Only allow 50 tokens to be received per day.
+    let capacity = 50 * 10u64.pow(LOCAL_DECIMALS_U32);
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: Some(RateLimitParams {
+                capacity,
+                window_seconds: 86400,
+            }),
+        },
+    )
+    .await
+    .unwrap();
+
+    let recipient_pubkey = Pubkey::new_unique();
+    let recipient_associated_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &recipient_pubkey,
+            &hyperlane_token_accounts.mint,
+            &spl_token_2022::id(),
+        );
+
+    let message = |nonce: u32, local_amount: u64| HyperlaneMessage {
+        version: 3,
+        nonce,
+        origin: REMOTE_DOMAIN,
+        sender: remote_router,
+        destination: LOCAL_DOMAIN,
+        recipient: program_id.to_bytes().into(),
+        body: TokenMessage::new(
+            recipient_pubkey.to_bytes().into(),
+            convert_decimals(local_amount.into(), LOCAL_DECIMALS, REMOTE_DECIMALS).unwrap(),
+            vec![],
+        )
+        .to_vec(),
+    };
+
+    // Transfer 69 tokens, which exceeds the rate limit.
+    let first_amount = 69 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(0, first_amount),
+    )
+    .await
+    .unwrap();
+
+    // Then transfer 10 tokens. This is within the rate limit, but it's queued
+    // behind the first transfer so it can't overtake it.
+    let second_amount = 10 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(1, second_amount),
+    )
+    .await
+    .unwrap();
+
+    // The recipient received nothing, and both transfers are pending.
+    assert!(banks_client
+        .get_account(recipient_associated_token_account)
+        .await
+        .unwrap()
+        .is_none());
+
+    let pending_transfer_queue = fetch_pending_transfer_queue(&mut banks_client, &program_id).await;
+    assert_eq!(pending_transfer_queue.next_nonce, 2);
+    assert_eq!(
+        pending_transfer_queue.transfers,
+        vec![
+            PendingTransfer {
+                nonce: 0,
+                origin: REMOTE_DOMAIN,
+                recipient: recipient_pubkey,
+                amount: first_amount,
+                queued_at: pending_transfer_queue.transfers[0].queued_at,
+            },
+            PendingTransfer {
+                nonce: 1,
+                origin: REMOTE_DOMAIN,
+                recipient: recipient_pubkey,
+                amount: second_amount,
+                queued_at: pending_transfer_queue.transfers[1].queued_at,
+            },
+        ],
+    );
+
+    let token_account_data = banks_client
+        .get_account(hyperlane_token_accounts.token)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let mut token = HyperlaneTokenAccount::<SyntheticPlugin>::fetch(&mut &token_account_data[..])
+        .unwrap()
+        .into_inner();
+    // No capacity was consumed.
+    assert_eq!(
+        token
+            .inbound_rate_limit_mut(REMOTE_DOMAIN)
+            .unwrap()
+            .available,
+        capacity
+    );
+
+    // Pending transfers must be claimed in order.
+    let result = claim_pending_transfer(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts,
+        1,
+        &recipient_pubkey,
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HyperlaneTokenError::PendingTransferNotNext as u32),
+        ),
+    );
+
+    // The first pending transfer can't be claimed while it exceeds the rate limit.
+    let result = claim_pending_transfer(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts,
+        0,
+        &recipient_pubkey,
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HyperlaneTokenError::RateLimitExceeded as u32),
+        ),
+    );
+
+    // Raise the rate limit, and now both can be claimed in order.
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: Some(RateLimitParams {
+                capacity: 2 * capacity,
+                window_seconds: 86400,
+            }),
+        },
+    )
+    .await
+    .unwrap();
+
+    claim_pending_transfer(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts,
+        0,
+        &recipient_pubkey,
+    )
+    .await
+    .unwrap();
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        first_amount,
+    )
+    .await;
+
+    claim_pending_transfer(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts,
+        1,
+        &recipient_pubkey,
+    )
+    .await
+    .unwrap();
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        first_amount + second_amount,
+    )
+    .await;
+
+    // The queue is empty again.
+    let pending_transfer_queue = fetch_pending_transfer_queue(&mut banks_client, &program_id).await;
+    assert!(pending_transfer_queue.transfers.is_empty());
+    assert_eq!(pending_transfer_queue.next_nonce, 2);
+
+    // And a pending transfer can't be claimed twice.
+    let result = claim_pending_transfer(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts,
+        0,
+        &recipient_pubkey,
+    )
+    .await;
+    assert_transaction_error(
+        result,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument),
+    );
+
+    // With the queue empty, transfers within the rate limit are delivered directly.
+    let third_amount = 5 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(2, third_amount),
+    )
+    .await
+    .unwrap();
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        first_amount + second_amount + third_amount,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_transfer_from_remote_keeps_queueing_if_inbound_rate_limit_removed() {
+    let program_id = hyperlane_sealevel_token_id();
+    let mailbox_program_id = mailbox_id();
+
+    let (mut banks_client, payer) = setup_client().await;
+
+    let mailbox_accounts =
+        initialize_mailbox(&mut banks_client, &mailbox_program_id, &payer, LOCAL_DOMAIN)
+            .await
+            .unwrap();
+
+    let hyperlane_token_accounts =
+        initialize_hyperlane_token(&program_id, &mut banks_client, &payer, None)
+            .await
+            .unwrap();
+    // ATA payer must have a balance to create new ATAs
+    transfer_lamports(
+        &mut banks_client,
+        &payer,
+        &hyperlane_token_accounts.ata_payer,
+        ONE_SOL_IN_LAMPORTS,
+    )
+    .await;
+
+    let remote_router = H256::random();
+    enroll_remote_router(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        REMOTE_DOMAIN,
+        remote_router,
+    )
+    .await
+    .unwrap();
+
+    // Only allow 50 tokens to be received per day.
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: Some(RateLimitParams {
+                capacity: 50 * 10u64.pow(LOCAL_DECIMALS_U32),
+                window_seconds: 86400,
+            }),
+        },
+    )
+    .await
+    .unwrap();
+
+    let recipient_pubkey = Pubkey::new_unique();
+    let recipient_associated_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &recipient_pubkey,
+            &hyperlane_token_accounts.mint,
+            &spl_token_2022::id(),
+        );
+
+    let message = |nonce: u32, local_amount: u64| HyperlaneMessage {
+        version: 3,
+        nonce,
+        origin: REMOTE_DOMAIN,
+        sender: remote_router,
+        destination: LOCAL_DOMAIN,
+        recipient: program_id.to_bytes().into(),
+        body: TokenMessage::new(
+            recipient_pubkey.to_bytes().into(),
+            convert_decimals(local_amount.into(), LOCAL_DECIMALS, REMOTE_DECIMALS).unwrap(),
+            vec![],
+        )
+        .to_vec(),
+    };
+
+    // Transfer 69 tokens, which exceeds the rate limit and is queued.
+    let first_amount = 69 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(0, first_amount),
+    )
+    .await
+    .unwrap();
+
+    // Remove the rate limit while the transfer is still pending.
+    set_rate_limit(
+        &mut banks_client,
+        &program_id,
+        &payer,
+        &hyperlane_token_accounts.token,
+        RateLimitConfig {
+            domain: REMOTE_DOMAIN,
+            direction: TransferDirection::Inbound,
+            rate_limit: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // A new transfer still can't overtake the pending one.
+    let second_amount = 10 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(1, second_amount),
+    )
+    .await
+    .unwrap();
+    assert!(banks_client
+        .get_account(recipient_associated_token_account)
+        .await
+        .unwrap()
+        .is_none());
+    let pending_transfer_queue = fetch_pending_transfer_queue(&mut banks_client, &program_id).await;
+    assert_eq!(
+        pending_transfer_queue
+            .transfers
+            .iter()
+            .map(|transfer| transfer.nonce)
+            .collect::<Vec<_>>(),
+        vec![0, 1],
+    );
+
+    // Both can be claimed in order without any limit.
+    for nonce in [0, 1] {
+        claim_pending_transfer(
+            &mut banks_client,
+            &program_id,
+            &payer,
+            &hyperlane_token_accounts,
+            nonce,
+            &recipient_pubkey,
+        )
+        .await
+        .unwrap();
+    }
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        first_amount + second_amount,
+    )
+    .await;
+
+    // Once the queue is drained, the rate limit is removed.
+    let token_account_data = banks_client
+        .get_account(hyperlane_token_accounts.token)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let token = HyperlaneTokenAccount::<SyntheticPlugin>::fetch(&mut &token_account_data[..])
+        .unwrap()
+        .into_inner();
+    assert!(!token.is_inbound_rate_limited(REMOTE_DOMAIN));
+
+    // And transfers are delivered directly again.
+    let third_amount = 5 * 10u64.pow(LOCAL_DECIMALS_U32);
+    process(
+        &mut banks_client,
+        &payer,
+        &mailbox_accounts,
+        vec![],
+        &message(2, third_amount),
+    )
+    .await
+    .unwrap();
+    assert_token_balance(
+        &mut banks_client,
+        &recipient_associated_token_account,
+        first_amount + second_amount + third_amount,
+    )
+    .await;
+}
@@ -0,0 +1,329 @@
+//! Inspect and repair the RocksDB database of a Hyperlane agent.
+//!
+//! The database is opened read-only unless `--write` is passed, so it can be
+//! inspected while an agent is running. Commands which modify the database
+//! require `--write`, and the agent using the database must be stopped first.
+//!
+//! Backups made by an agent can be restored into a new database directory with
+//! the `restore` command, which validates the backup before it's used.
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use std::{collections::BTreeMap, ops::RangeInclusive, path::PathBuf, str::FromStr};
+
+use clap::{Args, Parser, Subcommand};
+use eyre::{bail, eyre, Result};
+use hyperlane_base::{
+    db::{restore_db_backup, HyperlaneRocksDB, TypedDB, DB},
+    settings::CheckpointSyncerConf,
+};
+use hyperlane_core::{GasPaymentKey, HyperlaneMessage, KnownHyperlaneDomain, H256};
+use serde_json::{json, Value};
+use strum::IntoEnumIterator;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Path to the agent's database, which is required by all commands except
+    /// `restore`
+    #[arg(long)]
+    db: Option<PathBuf>,
+    /// Open the database for writing, which is required to modify it
+    #[arg(long, default_value_t = false)]
+    write: bool,
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// List the domains with data in the database
+    Domains(DomainsArgs),
+    /// Show a message with its processing state and gas data
+    Message(MessageArgs),
+    /// Show a merkle tree insertion
+    MerkleInsertion(MerkleInsertionArgs),
+    /// Export a range of messages, by nonce, to JSON
+    ExportMessages(RangeArgs),
+    /// Export a range of merkle tree insertions, by leaf index, to JSON
+    ExportMerkleInsertions(RangeArgs),
+    /// Mark a message as not processed, so that the relayer delivers it again
+    ResetProcessed(MessageArgs),
+    /// Reset the retry count of a message, so that the relayer stops backing off
+    ResetRetryCount(MessageArgs),
+    /// Restore a backup of a database into a new directory
+    Restore(RestoreArgs),
+}
+
+#[derive(Args)]
+struct DomainsArgs {
+    /// Comma separated names of the domains to look for. Defaults to the known
+    /// Hyperlane domains, so the names of any other domains must be given
+    #[arg(long, value_delimiter = ',')]
+    domains: Vec<String>,
+}
+
+#[derive(Args)]
+struct MessageArgs {
+    /// Name of the domain the message was dispatched from
+    #[arg(long)]
+    domain: String,
+    /// Nonce of the message
+    #[arg(long, required_unless_present = "id", conflicts_with = "id")]
+    nonce: Option<u32>,
+    /// Id of the message
+    #[arg(long)]
+    id: Option<String>,
+}
+
+#[derive(Args)]
+struct MerkleInsertionArgs {
+    /// Name of the domain of the merkle tree hook
+    #[arg(long)]
+    domain: String,
+    /// Leaf index of the insertion
+    #[arg(long)]
+    leaf_index: u32,
+}
+
+#[derive(Args)]
+struct RangeArgs {
+    /// Name of the domain to export data of
+    #[arg(long)]
+    domain: String,
+    /// First nonce or leaf index to export
+    #[arg(long)]
+    from: u32,
+    /// Last nonce or leaf index to export
+    #[arg(long)]
+    to: u32,
+}
+
+#[derive(Args)]
+struct RestoreArgs {
+    /// Where the backups are stored, e.g. `file://path`, `s3://bucket/region/folder`
+    /// or `gs://bucket/folder`
+    #[arg(long)]
+    location: String,
+    /// Name of the backup to restore. Defaults to the most recent backup
+    #[arg(long)]
+    name: Option<String>,
+    /// Path of the restored database, which must not exist yet
+    #[arg(long)]
+    target: PathBuf,
+    /// Comma separated names of the domains the backup must have data for
+    #[arg(long, value_delimiter = ',')]
+    domains: Vec<String>,
+}
+
+impl RangeArgs {
+    fn range(&self) -> Result<RangeInclusive<u32>> {
+        if self.from > self.to {
+            bail!("`--from` must not be greater than `--to`");
+        }
+        Ok(self.from..=self.to)
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let output = match &cli.cmd {
+        Cmd::Restore(args) => restore(args).await?,
+        _ => inspect(&cli)?,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+async fn restore(args: &RestoreArgs) -> Result<Value> {
+    let storage = CheckpointSyncerConf::from_str(&args.location)?
+        .build_backup_storage()
+        .await?;
+    let domains = args.domains.iter().map(String::as_str).collect::<Vec<_>>();
+    let manifest = restore_db_backup(
+        storage.as_ref(),
+        args.name.as_deref(),
+        &args.target,
+        &domains,
+    )
+    .await?;
+    Ok(serde_json::to_value(manifest)?)
+}
+
+/// The domains among `args.domains`, or else the known domains, with data in
+/// the database. Keys don't delimit domain names unambiguously, so the names
+/// must be known up front.
+fn domain_names(db: &DB, args: &DomainsArgs) -> Result<BTreeMap<String, u64>> {
+    let names = if args.domains.is_empty() {
+        KnownHyperlaneDomain::iter()
+            .map(|domain| domain.to_string())
+            .collect()
+    } else {
+        args.domains.clone()
+    };
+    Ok(TypedDB::domain_names(db, names.iter().map(String::as_str))?)
+}
+
+fn inspect(cli: &Cli) -> Result<Value> {
+    let path = cli.db.as_ref().ok_or_else(|| eyre!("`--db` is required"))?;
+    let db = if cli.write {
+        DB::from_path(path)?
+    } else {
+        DB::from_path_read_only(path)?
+    };
+
+    let output = match &cli.cmd {
+        Cmd::Domains(args) => json!(domain_names(&db, args)?
+            .into_iter()
+            .map(|(domain, keys)| json!({ "domain": domain, "keys": keys }))
+            .collect::<Vec<_>>()),
+        Cmd::Message(args) => {
+            let db = domain_db(&args.domain, &db);
+            let (nonce, id) = resolve_message(&db, args)?;
+            message_record(&db, nonce, id)?
+        }
+        Cmd::MerkleInsertion(args) => {
+            let db = domain_db(&args.domain, &db);
+            merkle_insertion_record(&db, args.leaf_index)?.ok_or_else(|| {
+                eyre!("No merkle tree insertion at leaf index {}", args.leaf_index)
+            })?
+        }
+        Cmd::ExportMessages(args) => {
+            let db = domain_db(&args.domain, &db);
+            let mut records = vec![];
+            for nonce in args.range()? {
+                if let Some(id) = db.retrieve_message_id_by_nonce(&nonce)? {
+                    records.push(message_record(&db, Some(nonce), id)?);
+                }
+            }
+            Value::Array(records)
+        }
+        Cmd::ExportMerkleInsertions(args) => {
+            let db = domain_db(&args.domain, &db);
+            let mut records = vec![];
+            for leaf_index in args.range()? {
+                records.extend(merkle_insertion_record(&db, leaf_index)?);
+            }
+            Value::Array(records)
+        }
+        Cmd::ResetProcessed(args) => {
+            ensure_writable(cli)?;
+            let db = domain_db(&args.domain, &db);
+            let (nonce, id) = resolve_message(&db, args)?;
+            let nonce = nonce.ok_or_else(|| eyre!("Unknown nonce for message {id:?}"))?;
+            if db.is_message_pruned(nonce)? {
+                bail!("Message {id:?} was pruned from the database and can't be delivered again");
+            }
+            db.store_processed_by_nonce(&nonce, &false)?;
+            message_record(&db, Some(nonce), id)?
+        }
+        Cmd::ResetRetryCount(args) => {
+            ensure_writable(cli)?;
+            let db = domain_db(&args.domain, &db);
+            let (nonce, id) = resolve_message(&db, args)?;
+            db.store_pending_message_retry_count_by_message_id(&id, &0)?;
+            message_record(&db, nonce, id)?
+        }
+        Cmd::Restore(_) => unreachable!("Restoring doesn't use an existing database"),
+    };
+    Ok(output)
+}
+
+fn ensure_writable(cli: &Cli) -> Result<()> {
+    if !cli.write {
+        bail!("Refusing to modify the database without `--write`");
+    }
+    Ok(())
+}
+
+fn domain_db(name: &str, db: &DB) -> HyperlaneRocksDB {
+    HyperlaneRocksDB::from_domain_name(name, db.clone())
+}
+
+/// The nonce and id of the message selected by `args`. The nonce of a message
+/// selected by id is unknown if its body was pruned.
+fn resolve_message(db: &HyperlaneRocksDB, args: &MessageArgs) -> Result<(Option<u32>, H256)> {
+    match (args.nonce, &args.id) {
+        (Some(nonce), _) => {
+            let id = db
+                .retrieve_message_id_by_nonce(&nonce)?
+                .ok_or_else(|| eyre!("No message with nonce {nonce}"))?;
+            Ok((Some(nonce), id))
+        }
+        (None, Some(id)) => {
+            let id = H256::from_str(id).map_err(|e| eyre!("Invalid message id `{id}`: {e}"))?;
+            let nonce = db.retrieve_message_by_id(&id)?.map(|m| m.nonce);
+            Ok((nonce, id))
+        }
+        (None, None) => bail!("Either `--nonce` or `--id` is required"),
+    }
+}
+
+fn message_record(db: &HyperlaneRocksDB, nonce: Option<u32>, id: H256) -> Result<Value> {
+    let message = db.retrieve_message_by_id(&id)?;
+    let gas_payment = message
+        .as_ref()
+        .map(|message| {
+            db.retrieve_gas_payment_by_gas_payment_key(GasPaymentKey {
+                message_id: id,
+                destination: message.destination,
+            })
+        })
+        .transpose()?;
+    let gas_expenditure = db.retrieve_gas_expenditure_by_message_id(id)?;
+    let (processed, processed_at, dispatched_block_number, dispatch_tx) = match nonce {
+        Some(nonce) => (
+            db.retrieve_processed_by_nonce(&nonce)?,
+            db.retrieve_processed_at_by_nonce(&nonce)?,
+            db.retrieve_dispatched_block_number_by_nonce(&nonce)?,
+            db.retrieve_dispatch_tx_by_nonce(&nonce)?,
+        ),
+        None => (None, None, None, None),
+    };
+
+    Ok(json!({
+        "id": format!("{id:?}"),
+        "nonce": nonce,
+        "message": message.as_ref().map(message_json),
+        "dispatchedBlockNumber": dispatched_block_number,
+        "dispatchTx": dispatch_tx.map(|tx| format!("{tx:?}")),
+        "processed": processed.unwrap_or(false),
+        "processedAt": processed_at,
+        "deliveryTx": db.retrieve_delivery_tx_by_message_id(&id)?.map(|tx| format!("{tx:?}")),
+        "retryCount": db.retrieve_pending_message_retry_count_by_message_id(&id)?,
+        "gasPayment": gas_payment.map(|payment| json!({
+            "payment": payment.payment.to_string(),
+            "gasAmount": payment.gas_amount.to_string(),
+        })),
+        "gasExpenditure": {
+            "tokensUsed": gas_expenditure.tokens_used.to_string(),
+            "gasUsed": gas_expenditure.gas_used.to_string(),
+        },
+        "merkleLeafIndex": db.retrieve_merkle_leaf_index_by_message_id(&id)?,
+    }))
+}
+
+fn message_json(message: &HyperlaneMessage) -> Value {
+    json!({
+        "version": message.version,
+        "nonce": message.nonce,
+        "origin": message.origin,
+        "sender": format!("{:?}", message.sender),
+        "destination": message.destination,
+        "recipient": format!("{:?}", message.recipient),
+        "body": format!("0x{}", hex::encode(&message.body)),
+    })
+}
+
+fn merkle_insertion_record(db: &HyperlaneRocksDB, leaf_index: u32) -> Result<Option<Value>> {
+    let Some(insertion) = db.retrieve_merkle_tree_insertion_by_leaf_index(&leaf_index)? else {
+        return Ok(None);
+    };
+    Ok(Some(json!({
+        "leafIndex": leaf_index,
+        "messageId": format!("{:?}", insertion.message_id()),
+        "blockNumber": db.retrieve_merkle_tree_insertion_block_number_by_leaf_index(&leaf_index)?,
+    })))
+}
@@ -23,6 +23,18 @@ export enum RpcConsensusType {
   Quorum = 'quorum',
 }
 
+export enum QuorumPolicyType {
+  Majority = 'majority',
+  All = 'all',
+  First = 'first',
+}
+
+const QuorumPolicySchema = z
+  .union([z.nativeEnum(QuorumPolicyType), ZNzUint])
+  .describe(
+    'The agreement required between RPCs: a majority of the total weight, all of them, the first successful response in order of weight, or a minimum combined weight.',
+  );
+
 export enum AgentLogLevel {
   Off = 'off',
   Error = 'error',
@@ -114,6 +126,13 @@ const AgentCosmosChainMetadataSchema = z.object({
     .positive()
     .lte(32)
     .describe('The number of bytes used to represent a contract address.'),
+  websocketUrl: z
+    .string()
+    .url()
+    .optional()
+    .describe(
+      'The Tendermint websocket url to subscribe to events with. If not set, agents only poll for events.',
+    ),
 });
 
 export type AgentCosmosGasPrice = z.infer<
@@ -134,9 +153,50 @@ export const AgentChainMetadataSchema = ChainMetadataSchemaObject.merge(
       .nativeEnum(RpcConsensusType)
       .describe('The consensus type to use when multiple RPCs are configured.')
       .optional(),
+    rpcQuorum: z
+      .object({
+        policy: QuorumPolicySchema.optional().describe(
+          'The policy of methods without a policy of their own. Defaults to majority.',
+        ),
+        methods: z
+          .array(
+            z.object({
+              method: z.string().describe('The JSON-RPC method, e.g. eth_call.'),
+              selector: z
+                .string()
+                .regex(/^0x[0-9a-fA-F]{8}$/)
+                .optional()
+                .describe(
+                  'Only apply the policy to eth_calls of the function with this 4 byte selector.',
+                ),
+              policy: QuorumPolicySchema,
+            }),
+          )
+          .optional()
+          .describe('Per-method quorum policies.'),
+      })
+      .optional()
+      .describe(
+        'The quorum policies to use when the rpcConsensusType is quorum.',
+      ),
     signer: AgentSignerSchema.optional().describe(
       'The signer to use for this chain',
     ),
+    additionalSigners: z
+      .array(AgentSignerSchema)
+      .optional()
+      .describe(
+        'Further signers the relayer submits transactions with in parallel to the signer, as a pool of wallets. Each must be distinct from the signer and from each other.',
+      ),
+    minSignerBalance: ZUWei.optional().describe(
+      'The balance below which a signer is considered out of funds, in the lowest denomination of the native token. Relayer wallets below it are retired from their pool and get no new apps until topped up.',
+    ),
+    deliveryFinality: z
+      .union([ZNzUint, z.literal('finalized')])
+      .optional()
+      .describe(
+        'When the relayer considers deliveries of messages to the chain final: once they are this many blocks deep, or once their block is finalized. If not specified, deliveries are checked at the latest block a minute after submission. Only supported on EVM chains.',
+      ),
     index: z
       .object({
         from: ZUint.optional().describe(
@@ -151,6 +211,12 @@ export const AgentChainMetadataSchema = ChainMetadataSchemaObject.merge(
           .describe(
             'The indexing method to use for this chain; will attempt to choose a suitable default if not specified.',
           ),
+        subscribe: z
+          .boolean()
+          .optional()
+          .describe(
+            'Whether to subscribe to dispatched messages and merkle tree insertions over a websocket connection to index them as soon as they are emitted, rather than only polling for them once they are final. Defaults to false.',
+          ),
       })
       .optional(),
   })
@@ -212,6 +278,12 @@ export const AgentConfigSchema = z.object({
     .describe(
       'The port to expose prometheus metrics on. Accessible via `GET /metrics`.',
     ),
+  shutdownDeadline: ZUint.optional().describe(
+    'How long to wait in seconds for work in flight to finish when the agent is asked to shut down. Defaults to 60.',
+  ),
+  healthMaxIndexingLag: ZUint.optional().describe(
+    'How many blocks beyond the reorg period an indexer may lag behind the tip of its chain before the agent is reported as not ready via `GET /health/ready`. Defaults to 100.',
+  ),
   chains: z
     .record(AgentChainMetadataSchema)
     .describe('Chain metadata for all chains that the agent will index.')
@@ -280,6 +352,29 @@ const GasPaymentEnforcementSchema = z.union([
 ]);
 export type GasPaymentEnforcement = z.infer<typeof GasPaymentEnforcementSchema>;
 
+const DbBackupConfigSchema = z.object({
+  dbBackupLocation: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, where to back up the database to, e.g. file://path, s3://bucket/region/folder or gs://bucket/folder.',
+    ),
+  dbBackupInterval: ZUint.optional().describe(
+    'How often to back up the database in seconds. If not specified, backups are only made when triggered.',
+  ),
+  dbBackupRetention: ZNzUint.optional().describe(
+    'How many of the most recent database backups to keep. Defaults to 7.',
+  ),
+  dbBackupApiToken: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, backups can be triggered with POST /db_backup, authenticated with this bearer token.',
+    ),
+});
+
 const MetricAppContextSchema = z.object({
   name: z.string().min(1),
   matchingList: MatchingListSchema.describe(
@@ -287,12 +382,48 @@ const MetricAppContextSchema = z.object({
   ),
 });
 
-export const RelayerAgentConfigSchema = AgentConfigSchema.extend({
+const PriorityLaneSchema = z.object({
+  name: z.string().min(1),
+  weight: ZNzUint.optional().describe(
+    'The share of the submitters the lane gets, relative to the weights of the other lanes with pending messages. Defaults to 1, which is also the weight of the lane of messages matching no lane.',
+  ),
+  matchingList: MatchingListSchema.optional().describe(
+    'A matching list, only messages that match are in the lane. By default all messages match.',
+  ),
+  appContext: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, only messages classified as this app context are in the lane.',
+    ),
+  minGasOverpaymentRatio: z
+    .number()
+    .nonnegative()
+    .optional()
+    .describe(
+      'If specified, only messages whose unspent gas payment covers at least this many times their estimated gas limit are in the lane.',
+    ),
+});
+
+const LifecycleEventWebhookSchema = z.object({
+  url: z.string().url(),
+  matchingList: MatchingListSchema.optional().describe(
+    'A matching list, only events of messages that match are posted to the webhook. By default all messages match.',
+  ),
+});
+
+export const RelayerAgentConfigSchema = AgentConfigSchema.merge(
+  DbBackupConfigSchema,
+).extend({
   db: z
     .string()
     .min(1)
     .optional()
     .describe('The path to the relayer database.'),
+  deliveredMessageRetentionDays: ZUint.optional().describe(
+    'If specified, the data of messages delivered more than this many days ago is pruned from the relayer database.',
+  ),
   relayChains: CommaSeperatedChainList.describe(
     'Comma separated list of chains to relay messages between.',
   ),
@@ -332,6 +463,46 @@ export const RelayerAgentConfigSchema = AgentConfigSchema.extend({
     .describe(
       'A list of app contexts and their matching lists to use for metrics. A message will be classified as the first matching app context.',
     ),
+  priorityLanes: z
+    .union([z.array(PriorityLaneSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'An ordered list of priority lanes for the queues of the submitters, which share the submitters by weight. A message is in the first lane it matches.',
+    ),
+  lifecycleEventsFile: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, the path of a file to append the lifecycle events of messages to as JSON lines.',
+    ),
+  lifecycleEventWebhooks: z
+    .union([z.array(LifecycleEventWebhookSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'A list of webhooks to post the lifecycle events of messages to as JSON, with optional matching lists.',
+    ),
+  lifecycleEventsApiToken: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, enables GET /message_events, which streams the lifecycle events of messages as server-sent events. Requests must carry this token as a bearer token.',
+    ),
+  manualRelayToken: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'If specified, enables POST /manual_relay, which delivers a message regardless of the whitelist, blacklist and gas payment enforcement. Requests must carry this token as a bearer token.',
+    ),
+  manualRelayAuditLog: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'The path of a file to append a record of each manual relay, and of each unauthorized attempt, to as JSON lines. Defaults to manual_relay_audit.jsonl in the working directory.',
+    ),
 });
 
 export type RelayerConfig = z.infer<typeof RelayerAgentConfigSchema>;
@@ -345,7 +516,9 @@ export const ScraperAgentConfigSchema = AgentConfigSchema.extend({
 
 export type ScraperConfig = z.infer<typeof ScraperAgentConfigSchema>;
 
-export const ValidatorAgentConfigSchema = AgentConfigSchema.extend({
+export const ValidatorAgentConfigSchema = AgentConfigSchema.merge(
+  DbBackupConfigSchema,
+).extend({
   db: z
     .string()
     .min(1)
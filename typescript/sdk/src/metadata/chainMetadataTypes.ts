@@ -17,6 +17,7 @@ export enum ExplorerFamily {
 
 export enum ChainTechnicalStack {
   ArbitrumNitro = 'arbitrumnitro',
+  OpStack = 'opstack',
   Other = 'other',
 }
 
@@ -38,6 +39,23 @@ export const RpcUrlSchema = z.object({
     .string()
     .optional()
     .describe('The WSS URL if the endpoint also supports websockets.'),
+  weight: ZNzUint.optional().describe(
+    'The weight of responses from this RPC when agents use a quorum of RPCs. Defaults to 1.',
+  ),
+  rateLimit: z
+    .object({
+      requestsPerSecond: z
+        .number()
+        .positive()
+        .describe('The sustained number of requests per second.'),
+      burst: ZNzUint.optional().describe(
+        'The number of requests that may be sent at once after a quiet period. Defaults to the requests per second.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Client-side rate limit of requests to this RPC, shared by all of an agent\'s clients of it.',
+    ),
   pagination: z
     .object({
       maxBlockRange: ZNzUint.optional().describe(